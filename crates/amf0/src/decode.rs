@@ -28,6 +28,11 @@ impl<'a> Amf0Decoder<'a> {
         self.cursor.get_ref().len() == self.cursor.position() as usize
     }
 
+    /// Returns the number of bytes consumed so far.
+    pub const fn position(&self) -> usize {
+        self.cursor.position() as usize
+    }
+
     fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Amf0ReadError> {
         let pos = self.cursor.position();
         self.cursor.seek(SeekFrom::Current(len as i64))?;
@@ -184,6 +189,7 @@ mod tests {
         let mut amf_reader = Amf0Decoder::new(&amf0_number);
         let value = amf_reader.decode_with_type(Amf0Marker::Number).unwrap();
         assert_eq!(value, Amf0Value::Number(772.161));
+        assert_eq!(amf_reader.position(), 9);
     }
 
     #[test]
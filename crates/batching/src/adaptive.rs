@@ -0,0 +1,148 @@
+//! Adaptive batching delays which follow the observed load.
+
+use std::time::{Duration, Instant};
+
+/// The weight of a new observation in the moving averages.
+const SMOOTHING: f64 = 0.2;
+
+/// The settings of an adaptive delay.
+///
+/// Instead of always waiting the configured delay before a batch is flushed,
+/// the delay follows the load:
+///
+/// - Under high load batches fill quickly, waiting longer than it takes to
+///   fill a batch only adds latency, so the delay shrinks towards the time a
+///   batch needs to fill up.
+/// - Under low load the delay grows so that more requests are accumulated,
+///   but never past the time the executor takes for a batch, after which
+///   waiting costs more than executing another batch would.
+///
+/// The delay always stays between `min` and `max`. Until requests and batches
+/// have been observed the configured delay of the builder is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdaptiveDelay {
+    /// The shortest delay.
+    pub min: Duration,
+    /// The longest delay.
+    pub max: Duration,
+}
+
+impl AdaptiveDelay {
+    /// Create a new adaptive delay configuration
+    pub const fn new(min: Duration, max: Duration) -> Self {
+        Self { min, max }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Estimates {
+    last_arrival: Option<Instant>,
+    /// The moving average of the time between two requests, in seconds.
+    interarrival: Option<f64>,
+    /// The moving average of the time the executor takes for a batch, in
+    /// seconds.
+    latency: Option<f64>,
+}
+
+/// The delay of a batch loop, either fixed or adaptive.
+#[derive(Debug)]
+pub(crate) struct BatchDelay {
+    delay: Duration,
+    adaptive: Option<(AdaptiveDelay, usize, std::sync::Mutex<Estimates>)>,
+}
+
+impl BatchDelay {
+    pub(crate) const fn fixed(delay: Duration) -> Self {
+        Self { delay, adaptive: None }
+    }
+
+    pub(crate) fn new(delay: Duration, adaptive: Option<AdaptiveDelay>, batch_size: usize) -> Self {
+        Self {
+            delay,
+            adaptive: adaptive.map(|config| (config, batch_size.max(1), std::sync::Mutex::default())),
+        }
+    }
+
+    fn estimates(&self) -> Option<std::sync::MutexGuard<'_, Estimates>> {
+        let (_, _, estimates) = self.adaptive.as_ref()?;
+        Some(estimates.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// The delay to wait before a batch is flushed.
+    pub(crate) fn current(&self) -> Duration {
+        let Some((config, batch_size, estimates)) = &self.adaptive else {
+            return self.delay;
+        };
+
+        let estimates = estimates.lock().unwrap_or_else(|e| e.into_inner());
+        let fill = estimates.interarrival.map(|interarrival| interarrival * *batch_size as f64);
+
+        let delay = match (fill, estimates.latency) {
+            (Some(fill), Some(latency)) => Duration::from_secs_f64(fill.min(latency)),
+            (Some(fill), None) => Duration::from_secs_f64(fill).min(self.delay),
+            _ => self.delay,
+        };
+
+        delay.clamp(config.min, config.max.max(config.min))
+    }
+
+    /// Observe `count` requests which just arrived.
+    pub(crate) fn record_arrivals(&self, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        let Some(mut estimates) = self.estimates() else {
+            return;
+        };
+
+        let now = Instant::now();
+        if let Some(last) = estimates.last_arrival.replace(now) {
+            let interarrival = now.duration_since(last).as_secs_f64() / count as f64;
+            estimates.interarrival = Some(smooth(estimates.interarrival, interarrival));
+        }
+    }
+
+    /// Observe a batch which took `latency` to execute.
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        if let Some(mut estimates) = self.estimates() {
+            estimates.latency = Some(smooth(estimates.latency, latency.as_secs_f64()));
+        }
+    }
+}
+
+fn smooth(average: Option<f64>, value: f64) -> f64 {
+    average.map_or(value, |average| average + SMOOTHING * (value - average))
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{AdaptiveDelay, BatchDelay};
+
+    const CONFIG: AdaptiveDelay = AdaptiveDelay::new(Duration::from_millis(1), Duration::from_millis(50));
+
+    #[test]
+    fn fixed() {
+        let delay = BatchDelay::fixed(Duration::from_millis(5));
+        delay.record_arrivals(10);
+        delay.record_latency(Duration::from_millis(100));
+        assert_eq!(delay.current(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn bounds() {
+        let delay = BatchDelay::new(Duration::from_millis(5), Some(CONFIG), 10);
+        assert_eq!(delay.current(), Duration::from_millis(5));
+
+        // Requests arriving all at once fill a batch right away.
+        delay.record_arrivals(100);
+        delay.record_arrivals(100);
+        assert_eq!(delay.current(), CONFIG.min);
+
+        let delay = BatchDelay::new(Duration::from_millis(100), Some(CONFIG), 10);
+        assert_eq!(delay.current(), CONFIG.max);
+    }
+}
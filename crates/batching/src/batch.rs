@@ -1,23 +1,40 @@
 use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use tokio::sync::oneshot;
 
+use crate::adaptive::{AdaptiveDelay, BatchDelay};
+use crate::circuit_breaker::{Circuit, CircuitBreaker, CircuitState};
+
 /// A response to a batch request
 pub struct BatchResponse<Resp> {
     send: oneshot::Sender<Resp>,
+    outcome: Option<BatchOutcome<Resp>>,
+}
+
+/// Marks the batch as failed when a failing response is sent.
+struct BatchOutcome<Resp> {
+    failed: Arc<AtomicBool>,
+    is_failure: fn(&Resp) -> bool,
 }
 
 impl<Resp> BatchResponse<Resp> {
     /// Create a new batch response
     #[must_use]
     pub fn new(send: oneshot::Sender<Resp>) -> Self {
-        Self { send }
+        Self { send, outcome: None }
     }
 
     /// Send a response back to the requester
     #[inline(always)]
     pub fn send(self, response: Resp) {
+        if let Some(outcome) = self.outcome {
+            if (outcome.is_failure)(&response) {
+                outcome.failed.store(true, Ordering::Relaxed);
+            }
+        }
+
         let _ = self.send.send(response);
     }
 
@@ -56,6 +73,46 @@ impl<Resp> BatchResponse<Resp> {
     {
         self.send(Some(value).into())
     }
+
+    /// Send each result to the response at the same index
+    ///
+    /// This is useful when the batch is executed with a single call which
+    /// reports an outcome for each request, such as a bulk write where some
+    /// rows can fail while others succeed.
+    ///
+    /// If the number of results does not match the number of responses nothing
+    /// is sent and the responses are returned, so they can still be answered.
+    ///
+    /// ```rust
+    /// # use scuffle_batching::batch::BatchResponse;
+    /// # async fn insert_users(users: Vec<i64>) -> Vec<Result<(), String>> {
+    /// #     users.into_iter().map(|_| Ok(())).collect()
+    /// # }
+    /// # async fn execute(requests: Vec<(i64, BatchResponse<Result<(), String>>)>) {
+    /// let (users, responses): (Vec<_>, Vec<_>) = requests.into_iter().unzip();
+    /// let results = insert_users(users).await;
+    ///
+    /// if let Err(responses) = BatchResponse::send_all(responses, results) {
+    ///     for response in responses {
+    ///         response.send_err("unexpected number of results".to_owned());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn send_all<T>(responses: Vec<Self>, results: Vec<T>) -> Result<(), Vec<Self>>
+    where
+        Resp: From<T>,
+    {
+        if responses.len() != results.len() {
+            return Err(responses);
+        }
+
+        for (response, result) in responses.into_iter().zip(results) {
+            response.send(result.into());
+        }
+
+        Ok(())
+    }
 }
 
 /// A trait for executing batches
@@ -68,7 +125,21 @@ pub trait BatchExecutor {
     /// Execute a batch of requests
     /// You must call `send` on the `BatchResponse` to send the response back to
     /// the client
+    ///
+    /// Every request has its own response, so the requests of a batch can
+    /// have different outcomes, for example by using a `Result` as the
+    /// response type. See [`BatchResponse::send_all`] to answer a batch with
+    /// a result per request.
     fn execute(&self, requests: Vec<(Self::Request, BatchResponse<Self::Response>)>) -> impl Future<Output = ()> + Send;
+
+    /// Returns true if the response means the batch failed, used by the
+    /// circuit breaker. A batch fails if any of its responses is a failure.
+    ///
+    /// By default no response is a failure.
+    fn is_failure(response: &Self::Response) -> bool {
+        let _ = response;
+        false
+    }
 }
 
 /// A builder for a [`Batcher`]
@@ -78,6 +149,8 @@ pub struct BatcherBuilder<E> {
     batch_size: usize,
     concurrency: usize,
     delay: std::time::Duration,
+    circuit_breaker: Option<CircuitBreaker>,
+    adaptive_delay: Option<AdaptiveDelay>,
     _marker: std::marker::PhantomData<E>,
 }
 
@@ -94,6 +167,8 @@ impl<E> BatcherBuilder<E> {
             batch_size: 1000,
             concurrency: 50,
             delay: std::time::Duration::from_millis(5),
+            circuit_breaker: None,
+            adaptive_delay: None,
             _marker: std::marker::PhantomData,
         }
     }
@@ -119,6 +194,14 @@ impl<E> BatcherBuilder<E> {
         self
     }
 
+    /// Set the circuit breaker, a batch fails when any of its responses is a
+    /// failure according to [`BatchExecutor::is_failure`]
+    #[inline]
+    pub const fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.with_circuit_breaker(circuit_breaker);
+        self
+    }
+
     /// Set the concurrency
     #[inline]
     pub const fn with_concurrency(&mut self, concurrency: usize) -> &mut Self {
@@ -126,6 +209,13 @@ impl<E> BatcherBuilder<E> {
         self
     }
 
+    /// Adapt the delay to the observed load, see [`AdaptiveDelay`]
+    #[inline]
+    pub const fn adaptive_delay(mut self, adaptive_delay: AdaptiveDelay) -> Self {
+        self.with_adaptive_delay(adaptive_delay);
+        self
+    }
+
     /// Set the batch size
     #[inline]
     pub const fn with_batch_size(&mut self, batch_size: usize) -> &mut Self {
@@ -140,13 +230,34 @@ impl<E> BatcherBuilder<E> {
         self
     }
 
+    /// Set the circuit breaker, a batch fails when any of its responses is a
+    /// failure according to [`BatchExecutor::is_failure`]
+    #[inline]
+    pub const fn with_circuit_breaker(&mut self, circuit_breaker: CircuitBreaker) -> &mut Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Adapt the delay to the observed load, see [`AdaptiveDelay`]
+    #[inline]
+    pub const fn with_adaptive_delay(&mut self, adaptive_delay: AdaptiveDelay) -> &mut Self {
+        self.adaptive_delay = Some(adaptive_delay);
+        self
+    }
+
+    fn batch_delay(&self) -> BatchDelay {
+        BatchDelay::new(self.delay, self.adaptive_delay, self.batch_size)
+    }
+
     /// Build the batcher
     #[inline]
     pub fn build(self, executor: E) -> Batcher<E>
     where
         E: BatchExecutor + Send + Sync + 'static,
     {
-        Batcher::new(executor, self.batch_size, self.concurrency, self.delay)
+        let mut batcher = Batcher::with_batch_delay(executor, self.batch_size, self.concurrency, self.batch_delay());
+        batcher.circuit = self.circuit_breaker.map(Circuit::new).map(Arc::new);
+        batcher
     }
 }
 
@@ -161,6 +272,8 @@ where
     semaphore: Arc<tokio::sync::Semaphore>,
     current_batch: Arc<tokio::sync::Mutex<Option<Batch<E>>>>,
     batch_size: usize,
+    circuit: Option<Arc<Circuit>>,
+    delay: Arc<BatchDelay>,
 }
 
 struct Batch<E>
@@ -169,7 +282,12 @@ where
 {
     items: Vec<(E::Request, BatchResponse<E::Response>)>,
     semaphore: Arc<tokio::sync::Semaphore>,
+    circuit: Option<Arc<Circuit>>,
+    delay: Arc<BatchDelay>,
+    failed: Arc<AtomicBool>,
     created_at: std::time::Instant,
+    #[cfg(feature = "tracing")]
+    started_at: Arc<std::sync::OnceLock<std::time::Instant>>,
 }
 
 impl<E> Batcher<E>
@@ -178,11 +296,16 @@ where
 {
     /// Create a new batcher
     pub fn new(executor: E, batch_size: usize, concurrency: usize, delay: std::time::Duration) -> Self {
+        Self::with_batch_delay(executor, batch_size, concurrency, BatchDelay::fixed(delay))
+    }
+
+    fn with_batch_delay(executor: E, batch_size: usize, concurrency: usize, delay: BatchDelay) -> Self {
         let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
         let current_batch = Arc::new(tokio::sync::Mutex::new(None));
         let executor = Arc::new(executor);
+        let delay = Arc::new(delay);
 
-        let join_handle = tokio::spawn(batch_loop(executor.clone(), current_batch.clone(), delay));
+        let join_handle = tokio::spawn(batch_loop(executor.clone(), current_batch.clone(), delay.clone()));
 
         Self {
             executor,
@@ -190,6 +313,8 @@ where
             semaphore,
             current_batch,
             batch_size: batch_size.max(1),
+            circuit: None,
+            delay,
         }
     }
 
@@ -198,16 +323,62 @@ where
         BatcherBuilder::new()
     }
 
+    /// The state of the circuit breaker, `None` if there is none
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit.as_ref().map(|circuit| circuit.state())
+    }
+
+    /// The delay a new batch currently waits before it is flushed, which only
+    /// changes with an [`AdaptiveDelay`]
+    pub fn effective_delay(&self) -> std::time::Duration {
+        self.delay.current()
+    }
+
     /// Execute a single request
+    ///
+    /// Returns `None` without executing the request if the circuit breaker is
+    /// open
     pub async fn execute(&self, items: E::Request) -> Option<E::Response> {
         self.execute_many(std::iter::once(items)).await.pop()?
     }
 
     /// Execute many requests
+    ///
+    /// Returns `None` for every request without executing them if the circuit
+    /// breaker is open
     pub async fn execute_many<I>(&self, items: I) -> Vec<Option<E::Response>>
     where
         I: IntoIterator<Item = E::Request>,
     {
+        if !self.circuit.as_ref().is_none_or(|circuit| circuit.allow()) {
+            return items.into_iter().map(|_| None).collect();
+        }
+
+        let fut = self.execute_many_inner(items);
+
+        #[cfg(feature = "tracing")]
+        let fut = tracing::Instrument::instrument(
+            fut,
+            tracing::info_span!(
+                "execute",
+                requests = tracing::field::Empty,
+                buffering_ms = tracing::field::Empty,
+                execution_ms = tracing::field::Empty,
+            ),
+        );
+
+        fut.await
+    }
+
+    async fn execute_many_inner<I>(&self, items: I) -> Vec<Option<E::Response>>
+    where
+        I: IntoIterator<Item = E::Request>,
+    {
+        #[cfg(feature = "tracing")]
+        let mut timings = crate::timings::Timings::new();
+        #[cfg(feature = "tracing")]
+        let mut started_at = Vec::new();
+
         let mut responses = Vec::new();
 
         {
@@ -215,12 +386,18 @@ where
 
             for item in items {
                 if batch.is_none() {
-                    batch.replace(Batch::new(self.semaphore.clone()));
+                    batch.replace(Batch::new(self.semaphore.clone(), self.circuit.clone(), self.delay.clone()));
                 }
 
                 let batch_mut = batch.as_mut().unwrap();
+
+                #[cfg(feature = "tracing")]
+                if !started_at.last().is_some_and(|last| Arc::ptr_eq(last, &batch_mut.started_at)) {
+                    started_at.push(batch_mut.started_at.clone());
+                }
+
                 let (tx, rx) = oneshot::channel();
-                batch_mut.items.push((item, BatchResponse::new(tx)));
+                batch_mut.items.push((item, batch_mut.response(tx)));
                 responses.push(rx);
 
                 if batch_mut.items.len() >= self.batch_size {
@@ -229,36 +406,47 @@ where
             }
         }
 
+        self.delay.record_arrivals(responses.len());
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("requests", responses.len());
+
         let mut results = Vec::with_capacity(responses.len());
         for response in responses {
             results.push(response.await.ok());
         }
 
+        #[cfg(feature = "tracing")]
+        {
+            for started_at in started_at {
+                timings.observe(started_at.get().copied());
+            }
+
+            timings.record(&tracing::Span::current());
+        }
+
         results
     }
 }
 
-async fn batch_loop<E>(
-    executor: Arc<E>,
-    current_batch: Arc<tokio::sync::Mutex<Option<Batch<E>>>>,
-    delay: std::time::Duration,
-) where
+async fn batch_loop<E>(executor: Arc<E>, current_batch: Arc<tokio::sync::Mutex<Option<Batch<E>>>>, delay: Arc<BatchDelay>)
+where
     E: BatchExecutor + Send + Sync + 'static,
 {
-    let mut delay_delta = delay;
+    let mut delay_delta = delay.current();
     loop {
         tokio::time::sleep(delay_delta).await;
 
         let mut batch = current_batch.lock().await;
         let Some(created_at) = batch.as_ref().map(|b| b.created_at) else {
-            delay_delta = delay;
+            delay_delta = delay.current();
             continue;
         };
 
-        let remaining = delay.saturating_sub(created_at.elapsed());
+        let remaining = delay.current().saturating_sub(created_at.elapsed());
         if remaining == std::time::Duration::ZERO {
             tokio::spawn(batch.take().unwrap().spawn(executor.clone()));
-            delay_delta = delay;
+            delay_delta = delay.current();
         } else {
             delay_delta = remaining;
         }
@@ -269,17 +457,42 @@ impl<E> Batch<E>
 where
     E: BatchExecutor + Send + Sync + 'static,
 {
-    fn new(semaphore: Arc<tokio::sync::Semaphore>) -> Self {
+    fn new(semaphore: Arc<tokio::sync::Semaphore>, circuit: Option<Arc<Circuit>>, delay: Arc<BatchDelay>) -> Self {
         Self {
             created_at: std::time::Instant::now(),
             items: Vec::new(),
             semaphore,
+            circuit,
+            delay,
+            failed: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "tracing")]
+            started_at: Arc::new(std::sync::OnceLock::new()),
+        }
+    }
+
+    fn response(&self, send: oneshot::Sender<E::Response>) -> BatchResponse<E::Response> {
+        let mut response = BatchResponse::new(send);
+        if self.circuit.is_some() {
+            response.outcome = Some(BatchOutcome {
+                failed: self.failed.clone(),
+                is_failure: E::is_failure,
+            });
         }
+
+        response
     }
 
     async fn spawn(self, executor: Arc<E>) {
         let _ticket = self.semaphore.acquire_owned().await;
+        #[cfg(feature = "tracing")]
+        let _ = self.started_at.set(std::time::Instant::now());
+        let executed_at = std::time::Instant::now();
         executor.execute(self.items).await;
+        self.delay.record_latency(executed_at.elapsed());
+
+        if let Some(circuit) = &self.circuit {
+            circuit.record(!self.failed.load(Ordering::Relaxed));
+        }
     }
 }
 
@@ -597,6 +810,112 @@ mod tests {
         assert!(start.elapsed() < std::time::Duration::from_millis(20));
     }
 
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn partial_results() {
+        struct BulkExecutor;
+
+        impl BatchExecutor for BulkExecutor {
+            type Request = i64;
+            type Response = Result<i64, String>;
+
+            async fn execute(&self, requests: Vec<(Self::Request, BatchResponse<Self::Response>)>) {
+                let (rows, responses): (Vec<_>, Vec<_>) = requests.into_iter().unzip();
+                let results = rows
+                    .into_iter()
+                    .map(|row| if row < 0 { Err(format!("invalid row {row}")) } else { Ok(row) })
+                    .collect::<Vec<_>>();
+
+                assert!(BatchResponse::send_all(responses, results).is_ok());
+            }
+        }
+
+        let batcher = BatcherBuilder::default()
+            .batch_size(4)
+            .delay(std::time::Duration::from_millis(10))
+            .build(BulkExecutor);
+
+        let (a, b, c) = tokio::join!(batcher.execute(1), batcher.execute(-2), batcher.execute(3));
+        assert_eq!(a, Some(Ok(1)));
+        assert_eq!(b, Some(Err("invalid row -2".to_owned())));
+        assert_eq!(c, Some(Ok(3)));
+    }
+
+    #[test]
+    fn send_all_mismatch() {
+        let (tx1, mut rx1) = oneshot::channel::<Result<i64, ()>>();
+        let (tx2, mut rx2) = oneshot::channel::<Result<i64, ()>>();
+
+        let responses = BatchResponse::send_all(vec![BatchResponse::new(tx1), BatchResponse::new(tx2)], vec![Ok(1)])
+            .expect_err("expected the responses back");
+        assert_eq!(responses.len(), 2);
+        assert!(rx1.try_recv().is_err());
+
+        for response in responses {
+            response.send_err(());
+        }
+
+        assert_eq!(rx1.try_recv(), Ok(Err(())));
+        assert_eq!(rx2.try_recv(), Ok(Err(())));
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn circuit_breaker() {
+        struct FlakyExecutor {
+            failing: Arc<std::sync::atomic::AtomicBool>,
+            requests: Arc<AtomicUsize>,
+        }
+
+        impl BatchExecutor for FlakyExecutor {
+            type Request = usize;
+            type Response = Result<usize, ()>;
+
+            async fn execute(&self, requests: Vec<(Self::Request, BatchResponse<Self::Response>)>) {
+                self.requests.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let failing = self.failing.load(std::sync::atomic::Ordering::Relaxed);
+                for (request, response) in requests {
+                    if failing {
+                        response.send_err(());
+                    } else {
+                        response.send_ok(request);
+                    }
+                }
+            }
+
+            fn is_failure(response: &Self::Response) -> bool {
+                response.is_err()
+            }
+        }
+
+        let failing = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let batcher = BatcherBuilder::default()
+            .delay(std::time::Duration::from_millis(1))
+            .circuit_breaker(CircuitBreaker::new(2, std::time::Duration::from_millis(50)))
+            .build(FlakyExecutor {
+                failing: failing.clone(),
+                requests: requests.clone(),
+            });
+
+        assert_eq!(batcher.execute(1).await, Some(Err(())));
+        assert_eq!(batcher.execute(2).await, Some(Err(())));
+        assert_eq!(batcher.circuit_state(), Some(CircuitState::Open));
+
+        // The circuit is open, so requests fail without reaching the executor.
+        assert_eq!(batcher.execute(3).await, None);
+        assert_eq!(batcher.execute_many([4, 5]).await, vec![None, None]);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        failing.store(false, std::sync::atomic::Ordering::Relaxed);
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        assert_eq!(batcher.circuit_state(), Some(CircuitState::HalfOpen));
+        assert_eq!(batcher.execute(6).await, Some(Ok(6)));
+        assert_eq!(batcher.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 3);
+    }
+
     #[cfg(not(valgrind))] // test is time-sensitive
     #[tokio::test]
     async fn option() {
@@ -643,4 +962,44 @@ mod tests {
         assert!(start.elapsed() >= std::time::Duration::from_millis(5));
         assert!(start.elapsed() < std::time::Duration::from_millis(20));
     }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn adaptive_delay() {
+        const DELAY: std::time::Duration = std::time::Duration::from_millis(10);
+
+        let batcher = || {
+            Batcher::builder()
+                .batch_size(10)
+                .delay(DELAY)
+                .adaptive_delay(crate::AdaptiveDelay::new(
+                    std::time::Duration::from_millis(1),
+                    std::time::Duration::from_millis(50),
+                ))
+                .build(TestExecutor {
+                    values: HashMap::from_iter((0..200).map(|i| (i, i))),
+                    delay: std::time::Duration::from_millis(20),
+                    requests: Arc::new(AtomicUsize::new(0)),
+                    capacity: 10,
+                })
+        };
+
+        // High load, the requests arrive all at once and fill the batches right away.
+        let high = batcher();
+        assert_eq!(high.effective_delay(), DELAY);
+        let results = futures::future::join_all((0..200).map(|i| high.execute(i))).await;
+        assert!(results.iter().all(Option::is_some));
+
+        // Low load, a request every 15ms.
+        let low = batcher();
+        for i in 0..8 {
+            assert_eq!(low.execute(i).await, Some(i));
+            tokio::time::sleep(std::time::Duration::from_millis(15)).await;
+        }
+
+        let high = high.effective_delay();
+        let low = low.effective_delay();
+        assert!(high < DELAY, "high load delay: {high:?}");
+        assert!(low > DELAY, "low load delay: {low:?}");
+    }
 }
@@ -0,0 +1,112 @@
+//! A circuit breaker which stops dispatching batches to a failing backend.
+
+use std::time::{Duration, Instant};
+
+/// The settings of a circuit breaker.
+///
+/// After `failure_threshold` consecutive batches have failed the circuit
+/// opens, and for the next `cooldown` every request fails fast without being
+/// batched. Once the cooldown has passed the circuit is half-open, requests
+/// are batched again and the outcome of the next batch decides whether the
+/// circuit closes (on success) or opens for another cooldown (on failure).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    /// The number of consecutive failed batches which open the circuit.
+    pub failure_threshold: usize,
+    /// How long the circuit stays open before recovery is tested.
+    pub cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    /// Create a new circuit breaker configuration
+    pub const fn new(failure_threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+        }
+    }
+}
+
+/// The state of a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Batches are dispatched as normal.
+    Closed,
+    /// Requests fail fast until the cooldown has passed.
+    Open,
+    /// The cooldown has passed, the next batch decides the new state.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: usize },
+    Open { until: Instant },
+    HalfOpen,
+}
+
+/// The shared state of a circuit breaker.
+#[derive(Debug)]
+pub(crate) struct Circuit {
+    config: CircuitBreaker,
+    state: std::sync::Mutex<State>,
+}
+
+impl Circuit {
+    pub(crate) fn new(config: CircuitBreaker) -> Self {
+        Self {
+            config,
+            state: std::sync::Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The current state of the circuit.
+    pub(crate) fn state(&self) -> CircuitState {
+        match *self.lock() {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { until } if Instant::now() < until => CircuitState::Open,
+            State::Open { .. } | State::HalfOpen => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Returns false if requests should fail fast.
+    pub(crate) fn allow(&self) -> bool {
+        let mut state = self.lock();
+        match *state {
+            State::Open { until } if Instant::now() < until => false,
+            State::Open { .. } => {
+                *state = State::HalfOpen;
+                true
+            }
+            State::Closed { .. } | State::HalfOpen => true,
+        }
+    }
+
+    /// Record the outcome of a batch.
+    pub(crate) fn record(&self, success: bool) {
+        let mut state = self.lock();
+        if success {
+            *state = State::Closed { failures: 0 };
+            return;
+        }
+
+        let failures = match *state {
+            State::Closed { failures } => failures + 1,
+            // A batch which was dispatched before the circuit opened does not extend the cooldown.
+            State::Open { .. } => return,
+            State::HalfOpen => self.config.failure_threshold,
+        };
+
+        *state = if failures >= self.config.failure_threshold {
+            State::Open {
+                until: Instant::now() + self.config.cooldown,
+            }
+        } else {
+            State::Closed { failures }
+        };
+    }
+}
@@ -2,6 +2,9 @@ use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::sync::Arc;
 
+use crate::adaptive::{AdaptiveDelay, BatchDelay};
+use crate::circuit_breaker::{Circuit, CircuitBreaker, CircuitState};
+
 /// A trait for fetching data in batches
 pub trait DataLoaderFetcher {
     /// The incoming key type
@@ -20,6 +23,8 @@ pub struct DataLoaderBuilder<E> {
     batch_size: usize,
     concurrency: usize,
     delay: std::time::Duration,
+    circuit_breaker: Option<CircuitBreaker>,
+    adaptive_delay: Option<AdaptiveDelay>,
     _phantom: std::marker::PhantomData<E>,
 }
 
@@ -36,6 +41,8 @@ impl<E> DataLoaderBuilder<E> {
             batch_size: 1000,
             concurrency: 50,
             delay: std::time::Duration::from_millis(5),
+            circuit_breaker: None,
+            adaptive_delay: None,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -61,6 +68,21 @@ impl<E> DataLoaderBuilder<E> {
         self
     }
 
+    /// Set the circuit breaker, a batch fails when the
+    /// [`DataLoaderFetcher`] returns `None`
+    #[inline]
+    pub const fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.with_circuit_breaker(circuit_breaker);
+        self
+    }
+
+    /// Adapt the delay to the observed load, see [`AdaptiveDelay`]
+    #[inline]
+    pub const fn adaptive_delay(mut self, adaptive_delay: AdaptiveDelay) -> Self {
+        self.with_adaptive_delay(adaptive_delay);
+        self
+    }
+
     /// Set the batch size
     #[inline]
     pub const fn with_batch_size(&mut self, batch_size: usize) -> &mut Self {
@@ -82,13 +104,61 @@ impl<E> DataLoaderBuilder<E> {
         self
     }
 
+    /// Set the circuit breaker, a batch fails when the
+    /// [`DataLoaderFetcher`] returns `None`
+    #[inline]
+    pub const fn with_circuit_breaker(&mut self, circuit_breaker: CircuitBreaker) -> &mut Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Adapt the delay to the observed load, see [`AdaptiveDelay`]
+    #[inline]
+    pub const fn with_adaptive_delay(&mut self, adaptive_delay: AdaptiveDelay) -> &mut Self {
+        self.adaptive_delay = Some(adaptive_delay);
+        self
+    }
+
+    fn batch_delay(&self) -> BatchDelay {
+        BatchDelay::new(self.delay, self.adaptive_delay, self.batch_size)
+    }
+
     /// Build the dataloader
     #[inline]
     pub fn build(self, executor: E) -> DataLoader<E>
     where
         E: DataLoaderFetcher + Send + Sync + 'static,
     {
-        DataLoader::new(executor, self.batch_size, self.concurrency, self.delay)
+        let mut loader = DataLoader::with_batch_delay(executor, self.batch_size, self.concurrency, self.batch_delay());
+        loader.circuit = self.circuit_breaker.map(Circuit::new).map(Arc::new);
+        loader
+    }
+
+    /// Build a [`ShardedDataLoader`] with the given number of shards, each
+    /// shard uses the settings of this builder.
+    ///
+    /// The shards share a single circuit breaker, as they share the same
+    /// backend.
+    #[inline]
+    pub fn build_sharded(self, shards: usize, executor: E) -> ShardedDataLoader<E>
+    where
+        E: DataLoaderFetcher + Clone + Send + Sync + 'static,
+    {
+        // Every shard adapts its delay to its own load.
+        let mut loader = ShardedDataLoader {
+            shards: (0..shards.max(1))
+                .map(|_| {
+                    DataLoader::with_batch_delay(executor.clone(), self.batch_size, self.concurrency, self.batch_delay())
+                })
+                .collect(),
+            hasher: std::hash::RandomState::new(),
+        };
+        let circuit = self.circuit_breaker.map(Circuit::new).map(Arc::new);
+        for shard in &mut loader.shards {
+            shard.circuit = circuit.clone();
+        }
+
+        loader
     }
 }
 
@@ -103,6 +173,25 @@ where
     semaphore: Arc<tokio::sync::Semaphore>,
     current_batch: Arc<tokio::sync::Mutex<Option<Batch<E>>>>,
     batch_size: usize,
+    circuit: Option<Arc<Circuit>>,
+    delay: Arc<BatchDelay>,
+    primed: Arc<std::sync::Mutex<PrimedKeys<E::Key, E::Value>>>,
+}
+
+type PrimedKeys<K, V> = HashMap<K, Primed<K, V>>;
+
+fn lock_primed<K, V>(primed: &std::sync::Mutex<PrimedKeys<K, V>>) -> std::sync::MutexGuard<'_, PrimedKeys<K, V>> {
+    primed.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A key which was primed with [`DataLoader::prime`] or
+/// [`DataLoader::prime_value`].
+enum Primed<K, V> {
+    /// A known value, returned without fetching.
+    Value(V),
+    /// A batch the key was eagerly enqueued in, removed once the batch has
+    /// completed.
+    Pending(Arc<BatchResult<K, V>>),
 }
 
 impl<E> DataLoader<E>
@@ -111,11 +200,16 @@ where
 {
     /// Create a new dataloader
     pub fn new(executor: E, batch_size: usize, concurrency: usize, delay: std::time::Duration) -> Self {
+        Self::with_batch_delay(executor, batch_size, concurrency, BatchDelay::fixed(delay))
+    }
+
+    fn with_batch_delay(executor: E, batch_size: usize, concurrency: usize, delay: BatchDelay) -> Self {
         let semaphore = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
         let current_batch = Arc::new(tokio::sync::Mutex::new(None));
         let executor = Arc::new(executor);
+        let delay = Arc::new(delay);
 
-        let join_handle = tokio::spawn(batch_loop(executor.clone(), current_batch.clone(), delay));
+        let join_handle = tokio::spawn(batch_loop(executor.clone(), current_batch.clone(), delay.clone()));
 
         Self {
             executor,
@@ -123,6 +217,9 @@ where
             semaphore,
             current_batch,
             batch_size: batch_size.max(1),
+            circuit: None,
+            delay,
+            primed: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
@@ -132,9 +229,20 @@ where
         DataLoaderBuilder::new()
     }
 
+    /// The state of the circuit breaker, `None` if there is none
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit.as_ref().map(|circuit| circuit.state())
+    }
+
+    /// The delay a new batch currently waits before it is flushed, which only
+    /// changes with an [`AdaptiveDelay`]
+    pub fn effective_delay(&self) -> std::time::Duration {
+        self.delay.current()
+    }
+
     /// Load a single key
     /// Can return an error if the underlying [`DataLoaderFetcher`] returns an
-    /// error
+    /// error, or if the circuit breaker is open
     ///
     /// Returns `None` if the key is not found
     pub async fn load(&self, items: E::Key) -> Result<Option<E::Value>, ()> {
@@ -143,7 +251,7 @@ where
 
     /// Load many keys
     /// Can return an error if the underlying [`DataLoaderFetcher`] returns an
-    /// error
+    /// error, or if the circuit breaker is open
     ///
     /// Returns a map of keys to values which may be incomplete if any of the
     /// keys were not found
@@ -151,11 +259,152 @@ where
     where
         I: IntoIterator<Item = E::Key> + Send,
     {
-        struct BatchWaiting<K, V> {
-            keys: HashSet<K>,
-            result: Arc<BatchResult<K, V>>,
+        if !self.allow() {
+            return Err(());
+        }
+
+        let fut = async {
+            let mut results = HashMap::new();
+            let (items, pending) = self.take_primed(items, &mut results);
+            let mut waiters = self.enqueue(items).await;
+
+            let primed = pending
+                .iter()
+                .flat_map(|waiting| waiting.keys.iter().map(|key| (key.clone(), waiting.result.clone())))
+                .collect::<Vec<_>>();
+            waiters.count += primed.len();
+            waiters.waiters.extend(pending);
+
+            let result = waiters.wait(&mut results).await;
+            self.forget_primed(primed);
+            result?;
+
+            Ok(results)
+        };
+
+        #[cfg(feature = "tracing")]
+        let fut = tracing::Instrument::instrument(fut, load_span());
+
+        fut.await
+    }
+
+    /// Start loading keys without waiting for them
+    ///
+    /// The keys are added to the current batch, the next [`load`](Self::load)
+    /// or [`load_many`](Self::load_many) of a primed key waits on that batch
+    /// instead of fetching the key again, even if the batch has already been
+    /// dispatched. This only waits until the keys have been enqueued.
+    ///
+    /// Primed keys are forgotten once their batch has completed, so keys which
+    /// are never loaded do not keep the batch alive. A load after that fetches
+    /// the key again.
+    pub async fn prime<I>(&self, items: I)
+    where
+        I: IntoIterator<Item = E::Key> + Send,
+    {
+        if !self.allow() {
+            return;
+        }
+
+        let items = {
+            let primed = self.lock_primed();
+            items.into_iter().filter(|key| !primed.contains_key(key)).collect::<Vec<_>>()
+        };
+
+        let waiters = self.enqueue(items).await;
+
+        let mut primed = self.lock_primed();
+        for waiting in waiters.waiters {
+            // The batch may have completed already, it only forgets the keys primed before.
+            if waiting.result.token.is_cancelled() {
+                continue;
+            }
+
+            waiting.result.primed.store(true, std::sync::atomic::Ordering::Relaxed);
+            for key in waiting.keys {
+                primed.entry(key).or_insert_with(|| Primed::Pending(waiting.result.clone()));
+            }
+        }
+    }
+
+    /// Set the value of a key, loads of the key return it without calling
+    /// the [`DataLoaderFetcher`] until it is removed with [`clear`](Self::clear)
+    pub fn prime_value(&self, key: E::Key, value: E::Value) {
+        self.lock_primed().insert(key, Primed::Value(value));
+    }
+
+    /// Remove a key primed with [`prime`](Self::prime) or
+    /// [`prime_value`](Self::prime_value)
+    pub fn clear(&self, key: &E::Key) {
+        self.lock_primed().remove(key);
+    }
+
+    fn allow(&self) -> bool {
+        self.circuit.as_ref().is_none_or(|circuit| circuit.allow())
+    }
+
+    fn lock_primed(&self) -> std::sync::MutexGuard<'_, PrimedKeys<E::Key, E::Value>> {
+        lock_primed(&self.primed)
+    }
+
+    /// Split off the primed keys, primed values are added to `results` and
+    /// keys with a pending batch are returned grouped by that batch.
+    fn take_primed<I>(&self, items: I, results: &mut HashMap<E::Key, E::Value>) -> SplitKeys<E::Key, E::Value>
+    where
+        I: IntoIterator<Item = E::Key>,
+    {
+        let primed = self.lock_primed();
+        if primed.is_empty() {
+            return (items.into_iter().collect(), Vec::new());
         }
 
+        let mut rest = Vec::new();
+        let mut pending = Vec::<BatchWaiting<E::Key, E::Value>>::new();
+        for key in items {
+            match primed.get(&key) {
+                Some(Primed::Value(value)) => {
+                    results.insert(key, value.clone());
+                }
+                Some(Primed::Pending(result)) => match pending.iter_mut().find(|w| Arc::ptr_eq(&w.result, result)) {
+                    Some(waiting) => {
+                        waiting.keys.insert(key);
+                    }
+                    None => pending.push(BatchWaiting {
+                        keys: HashSet::from([key]),
+                        result: result.clone(),
+                    }),
+                },
+                None => rest.push(key),
+            }
+        }
+
+        (rest, pending)
+    }
+
+    /// Forget primed keys once a load has waited on their batch, unless they
+    /// have been primed again since.
+    fn forget_primed(&self, keys: Vec<PendingKey<E::Key, E::Value>>) {
+        if keys.is_empty() {
+            return;
+        }
+
+        let mut primed = self.lock_primed();
+        for (key, result) in keys {
+            if matches!(primed.get(&key), Some(Primed::Pending(pending)) if Arc::ptr_eq(pending, &result)) {
+                primed.remove(&key);
+            }
+        }
+    }
+
+    /// Add the keys to the current batch, returning the batches we need to
+    /// wait on for their results.
+    async fn enqueue<I>(&self, items: I) -> BatchWaiters<E::Key, E::Value>
+    where
+        I: IntoIterator<Item = E::Key> + Send,
+    {
+        #[cfg(feature = "tracing")]
+        let timings = crate::timings::Timings::new();
+
         let mut waiters = Vec::<BatchWaiting<E::Key, E::Value>>::new();
 
         let mut count = 0;
@@ -166,7 +415,12 @@ where
 
             for item in items {
                 if batch.is_none() {
-                    batch.replace(Batch::new(self.semaphore.clone()));
+                    batch.replace(Batch::new(
+                        self.semaphore.clone(),
+                        self.circuit.clone(),
+                        self.delay.clone(),
+                        self.primed.clone(),
+                    ));
                     new_batch = true;
                 }
 
@@ -192,40 +446,245 @@ where
             }
         }
 
-        let mut results = HashMap::with_capacity(count);
-        for waiting in waiters {
-            let result = waiting.result.wait().await?;
+        self.delay.record_arrivals(count);
+
+        BatchWaiters {
+            waiters,
+            count,
+            #[cfg(feature = "tracing")]
+            timings,
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn load_span() -> tracing::Span {
+    tracing::info_span!(
+        "load",
+        keys = tracing::field::Empty,
+        buffering_ms = tracing::field::Empty,
+        execution_ms = tracing::field::Empty,
+    )
+}
+
+struct BatchWaiting<K, V> {
+    keys: HashSet<K>,
+    result: Arc<BatchResult<K, V>>,
+}
+
+/// Keys which still need to be enqueued, and primed keys waiting on their batch.
+type SplitKeys<K, V> = (Vec<K>, Vec<BatchWaiting<K, V>>);
+type PendingKey<K, V> = (K, Arc<BatchResult<K, V>>);
+
+struct BatchWaiters<K, V> {
+    waiters: Vec<BatchWaiting<K, V>>,
+    count: usize,
+    #[cfg(feature = "tracing")]
+    timings: crate::timings::Timings,
+}
+
+impl<K, V> BatchWaiters<K, V>
+where
+    K: Eq + std::hash::Hash,
+    V: Clone,
+{
+    /// Wait for all the batches to finish, collecting the results.
+    async fn wait(self, results: &mut HashMap<K, V>) -> Result<(), ()> {
+        #[cfg(feature = "tracing")]
+        let mut timings = self.timings;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("keys", self.count);
+
+        results.reserve(self.count);
+        for waiting in self.waiters {
+            let result = waiting.result.wait().await;
+
+            #[cfg(feature = "tracing")]
+            {
+                timings.observe(waiting.result.started_at.get().copied());
+                timings.record(&tracing::Span::current());
+            }
+
+            let result = result?;
             results.extend(waiting.keys.into_iter().filter_map(|key| {
                 let value = result.get(&key)?.clone();
                 Some((key, value))
             }));
         }
 
-        Ok(results)
+        Ok(())
+    }
+
+    /// Merge the batches of `other` into these, so they can all be waited on
+    /// at once.
+    fn merge(&mut self, other: Self) {
+        self.waiters.extend(other.waiters);
+        self.count += other.count;
     }
 }
 
-async fn batch_loop<E>(
-    executor: Arc<E>,
-    current_batch: Arc<tokio::sync::Mutex<Option<Batch<E>>>>,
-    delay: std::time::Duration,
-) where
+/// A dataloader which spreads keys over multiple [`DataLoader`] shards.
+///
+/// Every shard has its own batch, batching window and worker, so loads for
+/// keys on different shards do not contend on the same lock. Keys are
+/// assigned to a shard by their hash, so the same key always ends up in the
+/// same shard and is still deduplicated.
+///
+/// The tradeoff is that keys are split over more (smaller) batches than they
+/// would be with a single [`DataLoader`].
+#[must_use = "dataloaders must be used to load data"]
+pub struct ShardedDataLoader<E>
+where
     E: DataLoaderFetcher + Send + Sync + 'static,
 {
-    let mut delay_delta = delay;
+    shards: Box<[DataLoader<E>]>,
+    hasher: std::hash::RandomState,
+}
+
+impl<E> ShardedDataLoader<E>
+where
+    E: DataLoaderFetcher + Clone + Send + Sync + 'static,
+{
+    /// Create a new sharded dataloader, the executor is cloned for every
+    /// shard and every shard uses the given batch size, concurrency and delay.
+    pub fn new(executor: E, shards: usize, batch_size: usize, concurrency: usize, delay: std::time::Duration) -> Self {
+        let shards = (0..shards.max(1))
+            .map(|_| DataLoader::new(executor.clone(), batch_size, concurrency, delay))
+            .collect();
+
+        Self {
+            shards,
+            hasher: std::hash::RandomState::new(),
+        }
+    }
+}
+
+impl<E> ShardedDataLoader<E>
+where
+    E: DataLoaderFetcher + Send + Sync + 'static,
+{
+    /// The number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The state of the circuit breaker, `None` if there is none
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.shards[0].circuit_state()
+    }
+
+    fn shard(&self, key: &E::Key) -> usize {
+        (std::hash::BuildHasher::hash_one(&self.hasher, key) % self.shards.len() as u64) as usize
+    }
+
+    /// Load a single key
+    /// Can return an error if the underlying [`DataLoaderFetcher`] returns an
+    /// error, or if the circuit breaker is open
+    ///
+    /// Returns `None` if the key is not found
+    pub async fn load(&self, item: E::Key) -> Result<Option<E::Value>, ()> {
+        self.shards[self.shard(&item)].load(item).await
+    }
+
+    /// Load many keys
+    /// Can return an error if the underlying [`DataLoaderFetcher`] returns an
+    /// error, or if the circuit breaker is open
+    ///
+    /// Returns a map of keys to values which may be incomplete if any of the
+    /// keys were not found
+    pub async fn load_many<I>(&self, items: I) -> Result<HashMap<E::Key, E::Value>, ()>
+    where
+        I: IntoIterator<Item = E::Key> + Send,
+    {
+        if !self.shards[0].allow() {
+            return Err(());
+        }
+
+        let mut sharded = (0..self.shards.len()).map(|_| Vec::new()).collect::<Vec<_>>();
+        for item in items {
+            sharded[self.shard(&item)].push(item);
+        }
+
+        let fut = async {
+            // Enqueue on every shard before waiting, so the shards are batched
+            // concurrently.
+            let mut waiters: Option<BatchWaiters<_, _>> = None;
+            for (shard, items) in self.shards.iter().zip(sharded) {
+                if !items.is_empty() {
+                    let shard_waiters = shard.enqueue(items).await;
+                    match &mut waiters {
+                        Some(waiters) => waiters.merge(shard_waiters),
+                        None => waiters = Some(shard_waiters),
+                    }
+                }
+            }
+
+            let mut results = HashMap::new();
+            if let Some(waiters) = waiters {
+                waiters.wait(&mut results).await?;
+            }
+
+            Ok(results)
+        };
+
+        #[cfg(feature = "tracing")]
+        let fut = tracing::Instrument::instrument(fut, load_span());
+
+        fut.await
+    }
+
+    /// Start loading keys without waiting for them
+    ///
+    /// See [`DataLoader::prime`].
+    pub async fn prime<I>(&self, items: I)
+    where
+        I: IntoIterator<Item = E::Key> + Send,
+    {
+        let mut sharded = (0..self.shards.len()).map(|_| Vec::new()).collect::<Vec<_>>();
+        for item in items {
+            sharded[self.shard(&item)].push(item);
+        }
+
+        for (shard, items) in self.shards.iter().zip(sharded) {
+            if !items.is_empty() {
+                shard.prime(items).await;
+            }
+        }
+    }
+
+    /// Set the value of a key
+    ///
+    /// See [`DataLoader::prime_value`].
+    pub fn prime_value(&self, key: E::Key, value: E::Value) {
+        self.shards[self.shard(&key)].prime_value(key, value);
+    }
+
+    /// Remove a primed key
+    ///
+    /// See [`DataLoader::clear`].
+    pub fn clear(&self, key: &E::Key) {
+        self.shards[self.shard(key)].clear(key);
+    }
+}
+
+async fn batch_loop<E>(executor: Arc<E>, current_batch: Arc<tokio::sync::Mutex<Option<Batch<E>>>>, delay: Arc<BatchDelay>)
+where
+    E: DataLoaderFetcher + Send + Sync + 'static,
+{
+    let mut delay_delta = delay.current();
     loop {
         tokio::time::sleep(delay_delta).await;
 
         let mut batch = current_batch.lock().await;
         let Some(created_at) = batch.as_ref().map(|b| b.created_at) else {
-            delay_delta = delay;
+            delay_delta = delay.current();
             continue;
         };
 
-        let remaining = delay.saturating_sub(created_at.elapsed());
+        let remaining = delay.current().saturating_sub(created_at.elapsed());
         if remaining == std::time::Duration::ZERO {
             tokio::spawn(batch.take().unwrap().spawn(executor.clone()));
-            delay_delta = delay;
+            delay_delta = delay.current();
         } else {
             delay_delta = remaining;
         }
@@ -235,6 +694,10 @@ async fn batch_loop<E>(
 struct BatchResult<K, V> {
     values: tokio::sync::OnceCell<Option<HashMap<K, V>>>,
     token: tokio_util::sync::CancellationToken,
+    /// Set if any key was primed with this batch.
+    primed: std::sync::atomic::AtomicBool,
+    #[cfg(feature = "tracing")]
+    started_at: std::sync::OnceLock<std::time::Instant>,
 }
 
 impl<K, V> BatchResult<K, V> {
@@ -242,6 +705,9 @@ impl<K, V> BatchResult<K, V> {
         Self {
             values: tokio::sync::OnceCell::new(),
             token: tokio_util::sync::CancellationToken::new(),
+            primed: std::sync::atomic::AtomicBool::new(false),
+            #[cfg(feature = "tracing")]
+            started_at: std::sync::OnceLock::new(),
         }
     }
 
@@ -261,26 +727,66 @@ where
     items: HashSet<E::Key>,
     result: Arc<BatchResult<E::Key, E::Value>>,
     semaphore: Arc<tokio::sync::Semaphore>,
+    circuit: Option<Arc<Circuit>>,
+    delay: Arc<BatchDelay>,
+    primed: Arc<std::sync::Mutex<PrimedKeys<E::Key, E::Value>>>,
     created_at: std::time::Instant,
 }
 
+/// Forgets the keys primed with a batch once it has completed, see
+/// [`DataLoader::prime`].
+struct ForgetPrimed<K, V> {
+    result: Arc<BatchResult<K, V>>,
+    primed: Arc<std::sync::Mutex<PrimedKeys<K, V>>>,
+}
+
+impl<K, V> Drop for ForgetPrimed<K, V> {
+    fn drop(&mut self) {
+        // The flag is set under the lock, so it has to be read under it as well.
+        let mut primed = lock_primed(&self.primed);
+        if self.result.primed.load(std::sync::atomic::Ordering::Relaxed) {
+            primed.retain(|_, primed| !matches!(primed, Primed::Pending(result) if Arc::ptr_eq(result, &self.result)));
+        }
+    }
+}
+
 impl<E> Batch<E>
 where
     E: DataLoaderFetcher + Send + Sync + 'static,
 {
-    fn new(semaphore: Arc<tokio::sync::Semaphore>) -> Self {
+    fn new(
+        semaphore: Arc<tokio::sync::Semaphore>,
+        circuit: Option<Arc<Circuit>>,
+        delay: Arc<BatchDelay>,
+        primed: Arc<std::sync::Mutex<PrimedKeys<E::Key, E::Value>>>,
+    ) -> Self {
         Self {
             items: HashSet::new(),
             result: Arc::new(BatchResult::new()),
             semaphore,
+            circuit,
+            delay,
+            primed,
             created_at: std::time::Instant::now(),
         }
     }
 
     async fn spawn(self, executor: Arc<E>) {
+        // Dropped after the token is cancelled, `prime` does not add keys to a cancelled batch.
+        let _forget_primed = ForgetPrimed {
+            result: self.result.clone(),
+            primed: self.primed.clone(),
+        };
         let _drop_guard = self.result.token.clone().drop_guard();
         let _ticket = self.semaphore.acquire_owned().await.unwrap();
+        #[cfg(feature = "tracing")]
+        let _ = self.result.started_at.set(std::time::Instant::now());
+        let executed_at = std::time::Instant::now();
         let result = executor.load(self.items).await;
+        self.delay.record_latency(executed_at.elapsed());
+        if let Some(circuit) = &self.circuit {
+            circuit.record(result.is_some());
+        }
 
         #[cfg_attr(all(coverage_nightly, test), coverage(off))]
         fn unknwown_error<E>(_: E) -> ! {
@@ -347,30 +853,30 @@ mod tests {
         let start = std::time::Instant::now();
         let a = loader.load("a").await.unwrap();
         assert_eq!(a, Some(1));
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 1);
 
         let start = std::time::Instant::now();
         let b = loader.load("b").await.unwrap();
         assert_eq!(b, Some(2));
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
         let start = std::time::Instant::now();
         let c = loader.load("c").await.unwrap();
         assert_eq!(c, Some(3));
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 3);
 
         let start = std::time::Instant::now();
         let ab = loader.load_many(vec!["a", "b"]).await.unwrap();
         assert_eq!(ab, HashMap::from_iter(vec![("a", 1), ("b", 2)]));
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 4);
 
         let start = std::time::Instant::now();
         let unknown = loader.load("unknown").await.unwrap();
         assert_eq!(unknown, None);
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 5);
     }
 
@@ -394,7 +900,7 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(ab, HashMap::from_iter(vec![("a", 1), ("b", 2), ("c", 3)]));
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 5);
     }
 
@@ -582,7 +1088,246 @@ mod tests {
         let (a, b) = tokio::join!(loader.load("a"), loader.load("b"));
         assert_eq!(a, Ok(Some(1)));
         assert_eq!(b, Ok(Some(2)));
-        assert!(start.elapsed() < std::time::Duration::from_millis(15));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
+    #[derive(Clone)]
+    struct ShardFetcher {
+        requests: Arc<std::sync::Mutex<Vec<HashSet<u64>>>>,
+    }
+
+    impl DataLoaderFetcher for ShardFetcher {
+        type Key = u64;
+        type Value = u64;
+
+        async fn load(&self, keys: HashSet<Self::Key>) -> Option<HashMap<Self::Key, Self::Value>> {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            let values = keys.iter().map(|&k| (k, k * 2)).collect();
+            self.requests.lock().unwrap().push(keys);
+            Some(values)
+        }
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sharded() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let fetcher = ShardFetcher {
+            requests: requests.clone(),
+        };
+
+        let loader = Arc::new(
+            DataLoader::builder()
+                .batch_size(1000)
+                .concurrency(10)
+                .delay(std::time::Duration::from_millis(10))
+                .build_sharded(4, fetcher),
+        );
+        assert_eq!(loader.shards(), 4);
+
+        let tasks = (0..500)
+            .map(|i| {
+                let loader = loader.clone();
+                tokio::spawn(async move { loader.load(i).await })
+            })
+            .collect::<Vec<_>>();
+
+        for (i, task) in tasks.into_iter().enumerate() {
+            assert_eq!(task.await.unwrap(), Ok(Some(i as u64 * 2)));
+        }
+
+        let many = loader.load_many(0..100).await.unwrap();
+        assert_eq!(many, HashMap::from_iter((0..100).map(|i| (i, i * 2))));
+
+        let requests = requests.lock().unwrap();
+        let shards = requests
+            .iter()
+            .map(|keys| loader.shard(keys.iter().next().unwrap()))
+            .collect::<HashSet<_>>();
+
+        // Every batch only contains keys from a single shard.
+        for keys in requests.iter() {
+            let shard = loader.shard(keys.iter().next().unwrap());
+            assert!(keys.iter().all(|key| loader.shard(key) == shard));
+        }
+
+        assert!(shards.len() > 1, "expected multiple shards to be used: {shards:?}");
+    }
+
+    struct FlakyFetcher {
+        failing: Arc<std::sync::atomic::AtomicBool>,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl DataLoaderFetcher for FlakyFetcher {
+        type Key = u64;
+        type Value = u64;
+
+        async fn load(&self, keys: HashSet<Self::Key>) -> Option<HashMap<Self::Key, Self::Value>> {
+            self.requests.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            if self.failing.load(std::sync::atomic::Ordering::Relaxed) {
+                return None;
+            }
+
+            Some(keys.into_iter().map(|k| (k, k)).collect())
+        }
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn circuit_breaker() {
+        let failing = Arc::new(std::sync::atomic::AtomicBool::new(true));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let fetcher = FlakyFetcher {
+            failing: failing.clone(),
+            requests: requests.clone(),
+        };
+
+        let loader = DataLoader::builder()
+            .delay(std::time::Duration::from_millis(1))
+            .circuit_breaker(CircuitBreaker::new(2, std::time::Duration::from_millis(50)))
+            .build(fetcher);
+
+        assert_eq!(loader.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(loader.load(1).await, Err(()));
+        assert_eq!(loader.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(loader.load(2).await, Err(()));
+        assert_eq!(loader.circuit_state(), Some(CircuitState::Open));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        // The circuit is open, so loads fail without reaching the fetcher.
+        assert_eq!(loader.load(3).await, Err(()));
+        assert_eq!(loader.load_many([4, 5]).await, Err(()));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        // A failure while half-open opens the circuit again.
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        assert_eq!(loader.circuit_state(), Some(CircuitState::HalfOpen));
+        assert_eq!(loader.load(6).await, Err(()));
+        assert_eq!(loader.circuit_state(), Some(CircuitState::Open));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 3);
+
+        // Once the backend recovers a successful batch closes the circuit.
+        failing.store(false, std::sync::atomic::Ordering::Relaxed);
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        assert_eq!(loader.load(7).await, Ok(Some(7)));
+        assert_eq!(loader.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(loader.load(8).await, Ok(Some(8)));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 5);
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn prime() {
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let fetcher = TestFetcher {
+            values: HashMap::from_iter(vec![("a", 1), ("b", 2), ("c", 3)]),
+            delay: std::time::Duration::from_millis(20),
+            requests: requests.clone(),
+            capacity: 2,
+        };
+
+        let loader = DataLoader::builder()
+            .batch_size(2)
+            .concurrency(1)
+            .delay(std::time::Duration::from_millis(5))
+            .build(fetcher);
+
+        loader.prime(vec!["a", "b"]).await;
+        // The batch is full, so it is fetched while we do other work.
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        let start = std::time::Instant::now();
+        let ab = loader.load_many(vec!["a", "b"]).await.unwrap();
+        assert_eq!(ab, HashMap::from_iter(vec![("a", 1), ("b", 2)]));
+        assert!(start.elapsed() < std::time::Duration::from_millis(20));
         assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        // A primed key is only used by the next load.
+        let start = std::time::Instant::now();
+        assert_eq!(loader.load("a").await, Ok(Some(1)));
+        assert!(start.elapsed() >= std::time::Duration::from_millis(20));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        // Loads of keys whose batch is still running wait on it.
+        loader.prime(vec!["c"]).await;
+        let (c, other) = tokio::join!(loader.load("c"), loader.load("c"));
+        assert_eq!(c, Ok(Some(3)));
+        assert_eq!(other, Ok(Some(3)));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 3);
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn prime_forgotten() {
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let fetcher = TestFetcher {
+            values: HashMap::from_iter(vec![("a", 1), ("b", 2), ("c", 3)]),
+            delay: std::time::Duration::from_millis(5),
+            requests: requests.clone(),
+            capacity: 2,
+        };
+
+        let loader = DataLoader::builder()
+            .batch_size(2)
+            .concurrency(1)
+            .delay(std::time::Duration::from_millis(5))
+            .build(fetcher);
+
+        loader.prime(vec!["a", "b"]).await;
+        let result = match loader.lock_primed().get("a") {
+            Some(Primed::Pending(result)) => Arc::downgrade(result),
+            _ => panic!("expected a pending prime"),
+        };
+
+        // The keys are never loaded, the batch result is freed once it completes.
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 1);
+        assert!(result.upgrade().is_none(), "batch result was not freed");
+        assert!(loader.lock_primed().is_empty());
+
+        // A load after that fetches the key again.
+        assert_eq!(loader.load("a").await, Ok(Some(1)));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        // Values primed with `prime_value` are kept.
+        loader.prime_value("c", 30);
+        loader.prime(vec!["b"]).await;
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        assert_eq!(loader.lock_primed().len(), 1);
+        assert_eq!(loader.load("c").await, Ok(Some(30)));
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn prime_value() {
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let fetcher = TestFetcher {
+            values: HashMap::from_iter(vec![("a", 1), ("b", 2), ("c", 3)]),
+            delay: std::time::Duration::from_millis(5),
+            requests: requests.clone(),
+            capacity: 2,
+        };
+
+        let loader = DataLoader::builder().batch_size(2).concurrency(1).build(fetcher);
+
+        loader.prime_value("a", 10);
+        loader.prime_value("unknown", 20);
+
+        assert_eq!(loader.load("a").await, Ok(Some(10)));
+        assert_eq!(loader.load("unknown").await, Ok(Some(20)));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+        let abc = loader.load_many(vec!["a", "b", "c"]).await.unwrap();
+        assert_eq!(abc, HashMap::from_iter(vec![("a", 10), ("b", 2), ("c", 3)]));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        loader.clear(&"a");
+        assert_eq!(loader.load("a").await, Ok(Some(1)));
+        assert_eq!(requests.load(std::sync::atomic::Ordering::Relaxed), 2);
     }
 }
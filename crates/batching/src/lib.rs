@@ -21,6 +21,12 @@
 //! Latency is also more consistent as we are doing fewer requests to the
 //! external resource.
 //!
+//! With the `tracing` feature enabled every `load`/`execute` call is wrapped
+//! in a span which records how long the call waited for its batch to be
+//! flushed (`buffering_ms`) and how long the batch then took to execute
+//! (`execution_ms`), which makes it possible to see which side of this
+//! tradeoff a workload is on.
+//!
 //! ## Usage
 //!
 //! Here is an example of how to use the `DataLoader` interface to batch
@@ -147,8 +153,14 @@
 //! `SPDX-License-Identifier: MIT OR Apache-2.0`
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
 
+pub mod adaptive;
 pub mod batch;
+pub mod circuit_breaker;
 pub mod dataloader;
+#[cfg(feature = "tracing")]
+mod timings;
 
+pub use adaptive::AdaptiveDelay;
 pub use batch::{BatchExecutor, Batcher};
-pub use dataloader::{DataLoader, DataLoaderFetcher};
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use dataloader::{DataLoader, DataLoaderFetcher, ShardedDataLoader};
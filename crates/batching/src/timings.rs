@@ -0,0 +1,164 @@
+use std::time::{Duration, Instant};
+
+/// Tracks how long a single `load`/`execute` call spent waiting for its
+/// batches to be flushed and how long the batches then spent executing.
+///
+/// A call can span multiple batches, in which case the longest buffering and
+/// execution times are recorded since those are what the caller waited on.
+pub(crate) struct Timings {
+    enqueued_at: Instant,
+    buffering: Option<Duration>,
+    execution: Option<Duration>,
+}
+
+impl Timings {
+    pub(crate) fn new() -> Self {
+        Self {
+            enqueued_at: Instant::now(),
+            buffering: None,
+            execution: None,
+        }
+    }
+
+    /// Observe a batch which started executing at `started_at` and whose
+    /// result was just received, `None` if the batch never started executing.
+    pub(crate) fn observe(&mut self, started_at: Option<Instant>) {
+        let Some(started_at) = started_at else {
+            return;
+        };
+
+        let buffering = started_at.saturating_duration_since(self.enqueued_at);
+        let execution = started_at.elapsed();
+
+        self.buffering = Some(self.buffering.map_or(buffering, |d| d.max(buffering)));
+        self.execution = Some(self.execution.map_or(execution, |d| d.max(execution)));
+    }
+
+    /// Record the timings on the `buffering_ms` and `execution_ms` fields of
+    /// the span.
+    pub(crate) fn record(&self, span: &tracing::Span) {
+        if let Some(buffering) = self.buffering {
+            span.record("buffering_ms", buffering.as_secs_f64() * 1000.0);
+        }
+
+        if let Some(execution) = self.execution {
+            span.record("execution_ms", execution.as_secs_f64() * 1000.0);
+        }
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    use crate::batch::BatchResponse;
+    use crate::{BatchExecutor, Batcher, DataLoader, DataLoaderFetcher};
+
+    /// Collects the f64 fields recorded on every span.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<HashMap<(&'static str, &'static str), f64>>>);
+
+    struct Visitor<'a>(&'static str, &'a mut HashMap<(&'static str, &'static str), f64>);
+
+    impl Visit for Visitor<'_> {
+        fn record_f64(&mut self, field: &Field, value: f64) {
+            self.1.insert((self.0, field.name()), value);
+        }
+
+        fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+    }
+
+    impl<S> Layer<S> for Capture
+    where
+        S: Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            attrs.record(&mut Visitor(attrs.metadata().name(), &mut self.0.lock().unwrap()));
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            let name = ctx.span(id).unwrap().name();
+            values.record(&mut Visitor(name, &mut self.0.lock().unwrap()));
+        }
+    }
+
+    impl Capture {
+        fn get(&self, span: &'static str, field: &'static str) -> f64 {
+            *self
+                .0
+                .lock()
+                .unwrap()
+                .get(&(span, field))
+                .unwrap_or_else(|| panic!("{span}.{field} was not recorded"))
+        }
+    }
+
+    const DELAY: std::time::Duration = std::time::Duration::from_millis(20);
+    const WORK: std::time::Duration = std::time::Duration::from_millis(30);
+
+    struct SlowFetcher;
+
+    impl DataLoaderFetcher for SlowFetcher {
+        type Key = u64;
+        type Value = u64;
+
+        async fn load(&self, keys: HashSet<Self::Key>) -> Option<HashMap<Self::Key, Self::Value>> {
+            tokio::time::sleep(WORK).await;
+            Some(keys.into_iter().map(|k| (k, k)).collect())
+        }
+    }
+
+    struct SlowExecutor;
+
+    impl BatchExecutor for SlowExecutor {
+        type Request = u64;
+        type Response = u64;
+
+        async fn execute(&self, requests: Vec<(Self::Request, BatchResponse<Self::Response>)>) {
+            tokio::time::sleep(WORK).await;
+            for (request, response) in requests {
+                response.send(request);
+            }
+        }
+    }
+
+    fn assert_timings(capture: &Capture, span: &'static str) {
+        let buffering = capture.get(span, "buffering_ms");
+        let execution = capture.get(span, "execution_ms");
+
+        assert!(buffering >= DELAY.as_secs_f64() * 1000.0 * 0.9, "buffering: {buffering}");
+        assert!(execution >= WORK.as_secs_f64() * 1000.0 * 0.9, "execution: {execution}");
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn load_span() {
+        let capture = Capture::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let loader = DataLoader::builder().delay(DELAY).build(SlowFetcher);
+        assert_eq!(loader.load(1).await, Ok(Some(1)));
+
+        assert_timings(&capture, "load");
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn execute_span() {
+        let capture = Capture::default();
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let batcher = Batcher::builder().delay(DELAY).build(SlowExecutor);
+        assert_eq!(batcher.execute(1).await, Some(1));
+
+        assert_timings(&capture, "execute");
+    }
+}
@@ -0,0 +1,439 @@
+use std::io;
+
+/// An error returned by [`SliceBitReader`] and [`SliceBitWriter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitSliceError {
+    /// The access would go past the end of the slice.
+    OutOfBounds,
+    /// The value does not fit in the number of bits to write.
+    ValueTooLarge,
+}
+
+impl std::fmt::Display for BitSliceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBounds => f.write_str("bit access out of bounds"),
+            Self::ValueTooLarge => f.write_str("bits too large to write"),
+        }
+    }
+}
+
+impl std::error::Error for BitSliceError {}
+
+impl From<BitSliceError> for io::Error {
+    fn from(err: BitSliceError) -> Self {
+        match err {
+            BitSliceError::OutOfBounds => io::Error::new(io::ErrorKind::UnexpectedEof, err),
+            BitSliceError::ValueTooLarge => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+/// Checks that `count` bits starting at bit `pos` are inside a slice of `len`
+/// bytes.
+#[inline(always)]
+const fn check_bounds(pos: usize, count: usize, len: usize) -> Result<(), BitSliceError> {
+    match pos.checked_add(count) {
+        Some(end) if end <= len * 8 => Ok(()),
+        _ => Err(BitSliceError::OutOfBounds),
+    }
+}
+
+/// A reader that reads individual bits directly from a byte slice.
+///
+/// Unlike [`BitReader`](crate::BitReader) this does not go through
+/// [`io::Read`], every access is bounds checked against the slice and a failed
+/// read does not advance the reader.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct SliceBitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SliceBitReader<'a> {
+    /// Creates a new reader at the start of the slice
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Reads a single bit
+    pub fn read_bit(&mut self) -> Result<bool, BitSliceError> {
+        check_bounds(self.pos, 1, self.data.len())?;
+
+        let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+
+        Ok(bit == 1)
+    }
+
+    /// Reads multiple bits (the most significant bit is read first)
+    pub fn read_bits(&mut self, count: u8) -> Result<u64, BitSliceError> {
+        let mut remaining = count.min(64) as usize;
+        check_bounds(self.pos, remaining, self.data.len())?;
+
+        let mut bits = 0;
+        while remaining > 0 {
+            let offset = self.pos % 8;
+            let available = 8 - offset;
+            let take = available.min(remaining);
+
+            let byte = self.data[self.pos / 8] as u64;
+            let chunk = (byte >> (available - take)) & ((1 << take) - 1);
+            bits = (bits << take) | chunk;
+
+            self.pos += take;
+            remaining -= take;
+        }
+
+        Ok(bits)
+    }
+
+    /// Skips a number of bits
+    pub fn skip_bits(&mut self, count: usize) -> Result<(), BitSliceError> {
+        check_bounds(self.pos, count, self.data.len())?;
+        self.pos += count;
+        Ok(())
+    }
+
+    /// Reads `count` whole bytes, the reader must be aligned to the byte
+    /// boundary
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], BitSliceError> {
+        debug_assert!(self.is_aligned(), "read_bytes called on an unaligned reader");
+        let start = self.pos.div_ceil(8);
+        let bytes = self.data.get(start..start + count).ok_or(BitSliceError::OutOfBounds)?;
+        self.pos = (start + count) * 8;
+        Ok(bytes)
+    }
+
+    /// Aligns the reader to the next byte boundary
+    #[inline(always)]
+    pub fn align(&mut self) {
+        self.pos = self.pos.next_multiple_of(8).min(self.data.len() * 8);
+    }
+
+    /// Moves the reader to the given position in bits from the start of the
+    /// slice
+    pub fn set_bit_position(&mut self, pos: usize) -> Result<(), BitSliceError> {
+        check_bounds(pos, 0, self.data.len())?;
+        self.pos = pos;
+        Ok(())
+    }
+
+    /// Returns the current position in bits from the start of the slice
+    #[inline(always)]
+    #[must_use]
+    pub const fn bit_position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the current bit position in the current byte (0-7)
+    #[inline(always)]
+    #[must_use]
+    pub const fn bit_pos(&self) -> u8 {
+        (self.pos % 8) as u8
+    }
+
+    /// Checks if the reader is aligned to the byte boundary
+    #[inline(always)]
+    #[must_use]
+    pub const fn is_aligned(&self) -> bool {
+        self.pos.is_multiple_of(8)
+    }
+
+    /// Returns the number of bits left to read
+    #[inline(always)]
+    #[must_use]
+    pub const fn remaining_bits(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    /// Returns the underlying slice
+    #[inline(always)]
+    #[must_use]
+    pub const fn get_ref(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// A writer that writes individual bits directly into a mutable byte slice.
+///
+/// Unlike [`BitWriter`](crate::BitWriter) this writes in place, bits which are
+/// not written keep their value, so it can be used to patch fields in an
+/// existing buffer. Every access is bounds checked against the slice and a
+/// failed write does not modify the slice or advance the writer.
+#[derive(Debug)]
+#[must_use]
+pub struct SliceBitWriter<'a> {
+    data: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> SliceBitWriter<'a> {
+    /// Creates a new writer at the start of the slice
+    pub const fn new(data: &'a mut [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Writes a single bit
+    pub fn write_bit(&mut self, bit: bool) -> Result<(), BitSliceError> {
+        check_bounds(self.pos, 1, self.data.len())?;
+
+        let mask = 1 << (7 - self.pos % 8);
+        if bit {
+            self.data[self.pos / 8] |= mask;
+        } else {
+            self.data[self.pos / 8] &= !mask;
+        }
+        self.pos += 1;
+
+        Ok(())
+    }
+
+    /// Writes a number of bits (the most significant bit is written first)
+    pub fn write_bits(&mut self, bits: u64, count: u8) -> Result<(), BitSliceError> {
+        let count = count.min(64) as usize;
+
+        if count != 64 && bits > (1 << count) - 1 {
+            return Err(BitSliceError::ValueTooLarge);
+        }
+
+        check_bounds(self.pos, count, self.data.len())?;
+
+        let mut remaining = count;
+        while remaining > 0 {
+            let offset = self.pos % 8;
+            let available = 8 - offset;
+            let take = available.min(remaining);
+            let shift = available - take;
+
+            let mask = (((1u16 << take) - 1) as u8) << shift;
+            let chunk = ((bits >> (remaining - take)) as u8) << shift;
+
+            let byte = &mut self.data[self.pos / 8];
+            *byte = (*byte & !mask) | (chunk & mask);
+
+            self.pos += take;
+            remaining -= take;
+        }
+
+        Ok(())
+    }
+
+    /// Writes whole bytes, the writer must be aligned to the byte boundary
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), BitSliceError> {
+        debug_assert!(self.is_aligned(), "write_bytes called on an unaligned writer");
+        let start = self.pos.div_ceil(8);
+        self.data
+            .get_mut(start..start + bytes.len())
+            .ok_or(BitSliceError::OutOfBounds)?
+            .copy_from_slice(bytes);
+        self.pos = (start + bytes.len()) * 8;
+        Ok(())
+    }
+
+    /// Aligns the writer to the next byte boundary
+    ///
+    /// The skipped bits keep their value.
+    #[inline(always)]
+    pub fn align(&mut self) {
+        self.pos = self.pos.next_multiple_of(8).min(self.data.len() * 8);
+    }
+
+    /// Moves the writer to the given position in bits from the start of the
+    /// slice
+    pub fn set_bit_position(&mut self, pos: usize) -> Result<(), BitSliceError> {
+        check_bounds(pos, 0, self.data.len())?;
+        self.pos = pos;
+        Ok(())
+    }
+
+    /// Returns the current position in bits from the start of the slice
+    #[inline(always)]
+    #[must_use]
+    pub const fn bit_position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the current bit position in the current byte (0-7)
+    #[inline(always)]
+    #[must_use]
+    pub const fn bit_pos(&self) -> u8 {
+        (self.pos % 8) as u8
+    }
+
+    /// Checks if the writer is aligned to the byte boundary
+    #[inline(always)]
+    #[must_use]
+    pub const fn is_aligned(&self) -> bool {
+        self.pos.is_multiple_of(8)
+    }
+
+    /// Returns the number of bits left to write
+    #[inline(always)]
+    #[must_use]
+    pub const fn remaining_bits(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    /// Returns a reference to the underlying slice
+    #[inline(always)]
+    #[must_use]
+    pub const fn get_ref(&self) -> &[u8] {
+        self.data
+    }
+
+    /// Returns the underlying slice
+    #[inline(always)]
+    #[must_use]
+    pub fn into_inner(self) -> &'a mut [u8] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_bit_reader() {
+        let data = 0b10101010110011001111000101010101u32.to_be_bytes();
+        let mut reader = SliceBitReader::new(&data);
+
+        let cases = [
+            (3, 0b101),
+            (4, 0b0101),
+            (3, 0b011),
+            (3, 0b001),
+            (3, 0b100),
+            (3, 0b111),
+            (5, 0b10001),
+            (1, 0b0),
+            (7, 0b1010101),
+        ];
+
+        let mut pos = 0;
+        for (i, (count, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(
+                reader.read_bits(count),
+                Ok(expected),
+                "reading {count} bits ({i}) are not correct"
+            );
+            pos += count as usize;
+            assert_eq!(reader.bit_position(), pos);
+            assert_eq!(reader.bit_pos(), (pos % 8) as u8);
+            assert_eq!(reader.remaining_bits(), 32 - pos);
+        }
+
+        assert_eq!(reader.read_bit(), Err(BitSliceError::OutOfBounds));
+        assert_eq!(reader.bit_position(), 32);
+    }
+
+    #[test]
+    fn test_slice_bit_reader_bounds() {
+        let data = [0xAB, 0xCD, 0xEF];
+        let mut reader = SliceBitReader::new(&data);
+
+        assert_eq!(reader.read_bits(4), Ok(0xA));
+        assert_eq!(reader.read_bits(24), Err(BitSliceError::OutOfBounds));
+        // A failed read does not advance the reader.
+        assert_eq!(reader.bit_position(), 4);
+        assert_eq!(reader.read_bits(20), Ok(0xBCDEF));
+
+        reader.set_bit_position(8).unwrap();
+        assert_eq!(reader.read_bit(), Ok(true));
+        reader.align();
+        assert_eq!(reader.bit_position(), 16);
+        assert_eq!(reader.read_bytes(1), Ok(&[0xEF][..]));
+        assert_eq!(reader.read_bytes(1), Err(BitSliceError::OutOfBounds));
+
+        assert_eq!(reader.set_bit_position(25), Err(BitSliceError::OutOfBounds));
+        reader.set_bit_position(2).unwrap();
+        reader.skip_bits(6).unwrap();
+        assert_eq!(reader.read_bits(8), Ok(0xCD));
+        assert_eq!(reader.skip_bits(9), Err(BitSliceError::OutOfBounds));
+
+        let data = u64::MAX.to_be_bytes();
+        let mut reader = SliceBitReader::new(&data);
+        assert_eq!(reader.read_bits(64), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_slice_bit_writer() {
+        let mut data = [0; 5];
+        let mut writer = SliceBitWriter::new(&mut data);
+
+        writer.write_bits(0b11111111, 8).unwrap();
+        assert_eq!(writer.bit_position(), 8);
+        assert!(writer.is_aligned());
+
+        writer.write_bits(0b0000, 4).unwrap();
+        assert_eq!(writer.bit_pos(), 4);
+        writer.align();
+        assert_eq!(writer.bit_position(), 16);
+
+        writer.write_bits(0b1010, 4).unwrap();
+        writer.write_bits(0b101010101010, 12).unwrap();
+        assert_eq!(writer.bit_position(), 32);
+
+        writer.write_bit(true).unwrap();
+        assert_eq!(writer.bit_position(), 33);
+        assert_eq!(writer.remaining_bits(), 7);
+
+        assert_eq!(writer.write_bits(0b10000, 4), Err(BitSliceError::ValueTooLarge));
+        assert_eq!(writer.write_bits(0, 8), Err(BitSliceError::OutOfBounds));
+        assert_eq!(writer.bit_position(), 33);
+
+        assert_eq!(data, [0b11111111, 0b00000000, 0b10101010, 0b10101010, 0b10000000]);
+    }
+
+    #[test]
+    fn test_slice_bit_writer_in_place() {
+        let mut data = [0xFF, 0xFF, 0xFF];
+        let mut writer = SliceBitWriter::new(&mut data);
+
+        // Patch a 6 bit field spanning the first two bytes.
+        writer.set_bit_position(5).unwrap();
+        writer.write_bits(0b010101, 6).unwrap();
+        assert_eq!(writer.bit_position(), 11);
+
+        writer.align();
+        writer.write_bytes(&[0x12]).unwrap();
+        assert_eq!(writer.write_bytes(&[0x34]), Err(BitSliceError::OutOfBounds));
+        assert_eq!(writer.get_ref(), &[0b11111010, 0b10111111, 0x12]);
+
+        writer.set_bit_position(0).unwrap();
+        writer.write_bit(false).unwrap();
+        assert_eq!(writer.into_inner(), &[0b01111010, 0b10111111, 0x12]);
+
+        let mut data = [0; 8];
+        SliceBitWriter::new(&mut data).write_bits(u64::MAX, 64).unwrap();
+        assert_eq!(data, u64::MAX.to_be_bytes());
+    }
+
+    #[test]
+    fn test_slice_bit_round_trip() {
+        let mut data = [0; 4];
+        let mut writer = SliceBitWriter::new(&mut data);
+        writer.write_bits(0b101, 3).unwrap();
+        writer.write_bits(0x1234, 16).unwrap();
+        writer.write_bits(0b1, 1).unwrap();
+
+        let mut reader = SliceBitReader::new(&data);
+        assert_eq!(reader.read_bits(3), Ok(0b101));
+        assert_eq!(reader.read_bits(16), Ok(0x1234));
+        assert_eq!(reader.read_bit(), Ok(true));
+        assert_eq!(reader.bit_position(), 20);
+    }
+
+    #[test]
+    fn test_slice_bit_error_into_io() {
+        let err: io::Error = BitSliceError::OutOfBounds.into();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let err: io::Error = BitSliceError::ValueTooLarge.into();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "bits too large to write");
+    }
+}
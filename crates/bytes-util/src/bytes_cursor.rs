@@ -28,6 +28,16 @@ pub trait BytesCursorExt {
     /// buffer, however this is more efficient as it does not copy the
     /// bytes.
     fn extract_bytes(&mut self, size: usize) -> io::Result<Bytes>;
+
+    /// Splits off the next `n` bytes of the cursor, leaving the cursor
+    /// positioned at the remainder.
+    ///
+    /// This mirrors [`Bytes::split_to`] and is the same as
+    /// [`extract_bytes`](BytesCursorExt::extract_bytes). It does not copy the
+    /// bytes and returns an error if `n` is greater than the remaining bytes.
+    fn split_to(&mut self, n: usize) -> io::Result<Bytes> {
+        self.extract_bytes(n)
+    }
 }
 
 fn remaining(cursor: &BytesCursor) -> usize {
@@ -102,6 +112,34 @@ mod tests {
         assert_eq!(remaining(&cursor), 0);
     }
 
+    #[test]
+    fn test_bytes_cursor_split_to() {
+        let data = Bytes::from_static(&[1, 2, 3, 4, 5]);
+        let mut cursor = io::Cursor::new(data.clone());
+
+        let bytes = cursor.split_to(2).unwrap();
+        assert_eq!(bytes, Bytes::from_static(&[1, 2]));
+        // The split shares the underlying buffer.
+        assert_eq!(bytes.as_ptr(), data.as_ptr());
+        assert_eq!(remaining(&cursor), 3);
+
+        let bytes = cursor.split_to(3).unwrap();
+        assert_eq!(bytes, Bytes::from_static(&[3, 4, 5]));
+        assert_eq!(remaining(&cursor), 0);
+    }
+
+    #[test]
+    fn test_bytes_cursor_split_to_too_long() {
+        let mut cursor = io::Cursor::new(Bytes::from_static(&[1, 2, 3]));
+        cursor.set_position(1);
+
+        let err = cursor.split_to(3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        // The cursor is not advanced on error.
+        assert_eq!(cursor.position(), 1);
+        assert_eq!(cursor.split_to(2).unwrap(), Bytes::from_static(&[2, 3]));
+    }
+
     #[test]
     fn seek_out_of_bounds() {
         let mut cursor = io::Cursor::new(Bytes::from_static(&[1, 2, 3, 4, 5]));
@@ -0,0 +1,162 @@
+use std::io;
+
+/// A writer which inserts emulation prevention bytes into the data written to
+/// it, turning a raw byte sequence payload (RBSP) into the form used inside
+/// H.264 / H.265 NAL units.
+///
+/// Whenever two zero bytes are followed by a byte less than or equal to `0x03`
+/// an `0x03` byte is inserted before it, so that the sequences `00 00 00`,
+/// `00 00 01` and `00 00 02` (which would be mistaken for start codes) never
+/// appear in the output.
+///
+/// This is typically wrapped by a [`BitWriter`](crate::BitWriter) when writing
+/// parameter sets:
+///
+/// ```rust
+/// use scuffle_bytes_util::{BitWriter, EmulationPreventionWriter};
+///
+/// let mut writer = BitWriter::new(EmulationPreventionWriter::new(Vec::new()));
+/// writer.write_bits(0x000001, 24).unwrap();
+/// let data = writer.finish().unwrap().finish().unwrap();
+///
+/// assert_eq!(data, [0x00, 0x00, 0x03, 0x01]);
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct EmulationPreventionWriter<W> {
+    writer: W,
+    zero_count: u8,
+}
+
+impl<W> EmulationPreventionWriter<W> {
+    /// Creates a new writer wrapping the given writer.
+    pub const fn new(writer: W) -> Self {
+        Self { writer, zero_count: 0 }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub const fn get_ref(&self) -> &W {
+        &self.writer
+    }
+}
+
+impl<W: io::Write> EmulationPreventionWriter<W> {
+    /// Finishes the payload and returns the underlying writer.
+    ///
+    /// A NAL unit must not end with a zero byte, so if the payload ended with
+    /// two zero bytes a final `0x03` is written.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.zero_count == 2 {
+            self.writer.write_all(&[0x03])?;
+        }
+
+        Ok(self.writer)
+    }
+}
+
+impl<W: io::Write> io::Write for EmulationPreventionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if self.zero_count == 2 && byte <= 0x03 {
+                self.writer.write_all(&[0x03])?;
+                self.zero_count = 0;
+            }
+
+            self.writer.write_all(&[byte])?;
+            self.zero_count = if byte == 0x00 { self.zero_count + 1 } else { 0 };
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Inserts emulation prevention bytes into the given payload.
+///
+/// See [`EmulationPreventionWriter`] for details, this is the inverse of
+/// [`strip_emulation_prevention`].
+pub fn add_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut writer = EmulationPreventionWriter::new(Vec::with_capacity(data.len() + data.len() / 2));
+    // Writing to a Vec never fails.
+    io::Write::write_all(&mut writer, data).expect("write to vec");
+    writer.finish().expect("write to vec")
+}
+
+/// Removes the emulation prevention bytes from the given NAL unit data.
+///
+/// Every `0x03` byte which follows two zero bytes is removed.
+/// ISO/IEC-14496-10-2022 - 3.1.48
+pub fn strip_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut vec = Vec::with_capacity(data.len());
+    let mut zero_count = 0;
+
+    for &byte in data {
+        if zero_count == 2 && byte == 0x03 {
+            zero_count = 0;
+            continue;
+        }
+
+        vec.push(byte);
+        zero_count = if byte == 0x00 { zero_count + 1 } else { 0 };
+    }
+
+    vec
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_emulation_prevention() {
+        let cases: [(&[u8], &[u8]); 6] = [
+            (&[0x00, 0x00, 0x00], &[0x00, 0x00, 0x03, 0x00]),
+            (&[0x00, 0x00, 0x01], &[0x00, 0x00, 0x03, 0x01]),
+            (&[0x00, 0x00, 0x03], &[0x00, 0x00, 0x03, 0x03]),
+            (&[0x00, 0x00, 0x04], &[0x00, 0x00, 0x04]),
+            (&[0x67, 0x00, 0x00], &[0x67, 0x00, 0x00, 0x03]),
+            (
+                &[0x00, 0x00, 0x00, 0x00, 0x00, 0x01],
+                &[0x00, 0x00, 0x03, 0x00, 0x00, 0x03, 0x00, 0x01],
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(add_emulation_prevention(input), expected, "input: {input:02x?}");
+        }
+    }
+
+    #[test]
+    fn test_strip_emulation_prevention() {
+        assert_eq!(
+            strip_emulation_prevention(&[0x67, 0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03]),
+            [0x67, 0x00, 0x00, 0x01, 0x00, 0x00]
+        );
+        // Only the first 0x03 after two zeros is an emulation prevention byte.
+        assert_eq!(strip_emulation_prevention(&[0x00, 0x00, 0x03, 0x03]), [0x00, 0x00, 0x03]);
+    }
+
+    #[test]
+    fn test_emulation_prevention_round_trip() {
+        let cases: [&[u8]; 5] = [
+            &[0x00, 0x00, 0x00],
+            &[0x00, 0x00, 0x01],
+            &[0x42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x02, 0x11],
+            &[0x00, 0x00, 0x03, 0x00, 0x00],
+            &[],
+        ];
+
+        for data in cases {
+            let escaped = add_emulation_prevention(data);
+            assert!(
+                !escaped.windows(3).any(|w| w[0] == 0 && w[1] == 0 && w[2] <= 0x02),
+                "start code emulation in {escaped:02x?}"
+            );
+            assert_eq!(strip_emulation_prevention(&escaped), data);
+        }
+    }
+}
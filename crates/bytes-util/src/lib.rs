@@ -1,9 +1,13 @@
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
 
 mod bit_read;
+mod bit_slice;
 mod bit_write;
 mod bytes_cursor;
+mod emulation_prevention;
 
 pub use bit_read::BitReader;
+pub use bit_slice::{BitSliceError, SliceBitReader, SliceBitWriter};
 pub use bit_write::BitWriter;
 pub use bytes_cursor::{BytesCursor, BytesCursorExt};
+pub use emulation_prevention::{add_emulation_prevention, strip_emulation_prevention, EmulationPreventionWriter};
@@ -87,6 +87,36 @@ impl<F: Future> Future for FutureWithContext<'_, F> {
     }
 }
 
+/// The error of a [`FutureWithContextResult`] whose context was done before
+/// the future completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the context was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+pin_project_lite::pin_project! {
+    /// A future with a context attached to it, which fails with [`Cancelled`]
+    /// when the context is done, see [`ContextFutExt::with_context_result`].
+    pub struct FutureWithContextResult<'a, F> {
+        #[pin]
+        inner: FutureWithContext<'a, F>,
+    }
+}
+
+impl<F: Future> Future for FutureWithContextResult<'_, F> {
+    type Output = Result<F::Output, Cancelled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+        self.project().inner.poll(cx).map(|v| v.ok_or(Cancelled))
+    }
+}
+
 pub trait ContextFutExt<Fut> {
     /// Wraps a future with a context and cancels the future when the context is
     /// done.
@@ -110,6 +140,30 @@ pub trait ContextFutExt<Fut> {
     fn with_context<'a>(self, ctx: impl Into<ContextRef<'a>>) -> FutureWithContext<'a, Fut>
     where
         Self: Sized;
+
+    /// Like [`ContextFutExt::with_context`], but a cancelled future fails with
+    /// [`Cancelled`] instead of returning `None`, so it can be used with `?`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use scuffle_context::{Context, ContextFutExt};
+    /// # tokio_test::block_on(async {
+    /// async fn work(ctx: &Context) -> Result<(), Box<dyn std::error::Error>> {
+    ///     // Some work which never finishes by itself.
+    ///     std::future::pending::<()>().with_context_result(ctx).await?;
+    ///     Ok(())
+    /// }
+    ///
+    /// let (ctx, handler) = Context::new();
+    /// handler.cancel();
+    ///
+    /// assert_eq!(work(&ctx).await.unwrap_err().to_string(), "the context was cancelled");
+    /// # });
+    /// ```
+    fn with_context_result<'a>(self, ctx: impl Into<ContextRef<'a>>) -> FutureWithContextResult<'a, Fut>
+    where
+        Self: Sized;
 }
 
 impl<F: IntoFuture> ContextFutExt<F::IntoFuture> for F {
@@ -123,6 +177,12 @@ impl<F: IntoFuture> ContextFutExt<F::IntoFuture> for F {
             _marker: std::marker::PhantomData,
         }
     }
+
+    fn with_context_result<'a>(self, ctx: impl Into<ContextRef<'a>>) -> FutureWithContextResult<'a, F::IntoFuture> {
+        FutureWithContextResult {
+            inner: self.with_context(ctx),
+        }
+    }
 }
 
 pin_project_lite::pin_project! {
@@ -203,7 +263,7 @@ mod tests {
     use futures_lite::{Stream, StreamExt};
     use scuffle_future_ext::FutureExt;
 
-    use super::{Context, ContextFutExt, ContextStreamExt};
+    use super::{Cancelled, Context, ContextFutExt, ContextStreamExt};
 
     #[tokio::test]
     async fn future() {
@@ -238,6 +298,18 @@ mod tests {
         assert_eq!(task.await.unwrap(), Some(1));
     }
 
+    #[tokio::test]
+    async fn future_with_context_result() {
+        let (ctx, handler) = Context::new();
+
+        assert_eq!(async { 1 }.with_context_result(&ctx).await, Ok(1));
+
+        let task = tokio::spawn(std::future::pending::<()>().with_context_result(ctx));
+        handler.shutdown().await;
+
+        assert_eq!(task.await.unwrap(), Err(Cancelled));
+    }
+
     #[tokio::test]
     async fn future_ctx_by_ref() {
         let (ctx, handler) = Context::new();
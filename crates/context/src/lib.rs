@@ -27,6 +27,20 @@
 //! # });
 //! ```
 //!
+//! ## Feature flags
+//!
+//! - `time`: Enables deadlines and timeouts, such as
+//!   [`Context::new_child_with_deadline`] and
+//!   [`Handler::with_shutdown_timeout`]. This pulls in the tokio time driver
+//!   and runtime (deadlines are enforced by a spawned task), which the base
+//!   crate does not depend on.
+//! - `rt`: Enables [`Context::spawn`] and [`Context::linked`], which spawn
+//!   tasks on the tokio runtime.
+//! - `metrics`: Enables [`Handler::with_metrics`].
+//! - `tracing`: Enables warnings for handlers dropped without a shutdown.
+//! - `test-util`: Enables [`Handler::scoped_global`], which isolates the
+//!   global handler between tests.
+//!
 //! ## License
 //!
 //! This project is licensed under the [MIT](./LICENSE.MIT) or
@@ -36,13 +50,20 @@
 //! `SPDX-License-Identifier: MIT OR Apache-2.0`
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
 
+use std::any::{Any, TypeId};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicUsize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+use std::time::Duration;
 
 use tokio_util::sync::CancellationToken;
 
 /// For extending types.
 mod ext;
+/// The gauge of [`Handler::with_metrics`].
+#[cfg(feature = "metrics")]
+mod metrics;
 
 pub use ext::*;
 
@@ -68,6 +89,9 @@ struct ContextTrackerInner {
     /// this `ContextTrackerInner`.
     active_count: AtomicUsize,
     notify: tokio::sync::Notify,
+    /// See [`Handler::with_metrics`].
+    #[cfg(feature = "metrics")]
+    metrics: Mutex<Option<metrics::Registration>>,
 }
 
 impl ContextTrackerInner {
@@ -76,6 +100,8 @@ impl ContextTrackerInner {
             stopped: AtomicBool::new(false),
             active_count: AtomicUsize::new(0),
             notify: tokio::sync::Notify::new(),
+            #[cfg(feature = "metrics")]
+            metrics: Mutex::new(None),
         })
     }
 
@@ -104,6 +130,26 @@ impl ContextTrackerInner {
     }
 }
 
+/// A value attached to a context with [`Context::with_value`].
+///
+/// The values form a linked list, so attaching a value never copies the values
+/// of the parent and a lookup walks from the most recently attached value
+/// towards the root.
+struct ContextValue {
+    type_id: TypeId,
+    value: Box<dyn Any + Send + Sync>,
+    parent: Option<Arc<ContextValue>>,
+}
+
+impl std::fmt::Debug for ContextValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextValue")
+            .field("type_id", &self.type_id)
+            .field("parent", &self.parent)
+            .finish_non_exhaustive()
+    }
+}
+
 /// A context for cancelling futures and waiting for shutdown.
 ///
 /// A context can be created from a handler by calling [`Handler::context`] or
@@ -119,6 +165,11 @@ impl ContextTrackerInner {
 pub struct Context {
     token: CancellationToken,
     tracker: ContextTracker,
+    /// See [`Context::with_value`].
+    values: Option<Arc<ContextValue>>,
+    /// The reason of the handler this context belongs to, see
+    /// [`Context::cancellation_reason`].
+    reason: Arc<OnceLock<CancellationReason>>,
 }
 
 impl Clone for Context {
@@ -126,6 +177,8 @@ impl Clone for Context {
         Self {
             token: self.token.clone(),
             tracker: self.tracker.0.child(),
+            values: self.values.clone(),
+            reason: self.reason.clone(),
         }
     }
 }
@@ -153,19 +206,201 @@ impl Context {
     pub fn new_child(&self) -> (Self, Handler) {
         let token = self.token.child_token();
         let tracker = ContextTrackerInner::new();
+        let reason = Arc::new(OnceLock::new());
 
         (
             Self {
                 tracker: tracker.child(),
                 token: token.clone(),
+                values: self.values.clone(),
+                reason: reason.clone(),
             },
             Handler {
-                token: Arc::new(TokenDropGuard(token)),
+                token: Arc::new(TokenDropGuard(token, reason)),
                 tracker,
+                links: Default::default(),
+                finalizers: Default::default(),
+                shutdown_timeout: None,
             },
         )
     }
 
+    #[cfg(feature = "time")]
+    #[must_use]
+    /// Create a new child context which is cancelled once `deadline` has
+    /// passed.
+    ///
+    /// Otherwise the child behaves like one created with
+    /// [`Context::new_child`], it is also cancelled when this context is done
+    /// or the returned handler is cancelled. The deadline is enforced by a task
+    /// on the current tokio runtime, which ends as soon as the child is
+    /// cancelled for any reason, so dropping the returned handler does not
+    /// leak it.
+    ///
+    /// This requires the `time` feature and must be called from within a
+    /// tokio runtime with the time driver enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// use scuffle_context::Context;
+    ///
+    /// let (parent, parent_handler) = Context::new();
+    /// let deadline = std::time::Instant::now() + std::time::Duration::from_millis(10);
+    /// let (child, child_handler) = parent.new_child_with_deadline(deadline);
+    ///
+    /// child.done().await;
+    /// assert!(child_handler.is_done());
+    /// assert!(!parent.is_done());
+    /// # });
+    /// ```
+    pub fn new_child_with_deadline(&self, deadline: std::time::Instant) -> (Self, Handler) {
+        let (ctx, handler) = self.new_child();
+        cancel_at(handler.token.0.clone(), handler.token.1.clone(), deadline);
+        (ctx, handler)
+    }
+
+    #[cfg(feature = "time")]
+    #[must_use]
+    /// Create a new child context which is cancelled once `timeout` has
+    /// elapsed.
+    ///
+    /// See [`Context::new_child_with_deadline`], this requires the `time`
+    /// feature.
+    pub fn new_child_with_timeout(&self, timeout: Duration) -> (Self, Handler) {
+        match std::time::Instant::now().checked_add(timeout) {
+            Some(deadline) => self.new_child_with_deadline(deadline),
+            // The deadline is so far in the future it never passes.
+            None => self.new_child(),
+        }
+    }
+
+    #[cfg(feature = "rt")]
+    #[must_use]
+    /// Create a new context which is cancelled as soon as any of the given
+    /// contexts is done.
+    ///
+    /// This is useful for work which depends on several subsystems and should
+    /// stop if any of them shuts down. Otherwise the context behaves like one
+    /// created with [`Context::new_child`] on the first parent, it inherits
+    /// the values of the first parent and the returned handler tracks it, so
+    /// [`Handler::shutdown`] waits for it. Without any parents the context is
+    /// only cancelled through the returned handler.
+    ///
+    /// The parents after the first are watched by a task on the current tokio
+    /// runtime, which ends as soon as the context is cancelled for any reason.
+    /// This requires the `rt` feature and must be called from within a tokio
+    /// runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// use scuffle_context::Context;
+    ///
+    /// let (db, db_handler) = Context::new();
+    /// let (http, http_handler) = Context::new();
+    /// let (ctx, handler) = Context::linked(&[&db, &http]);
+    ///
+    /// http_handler.cancel();
+    /// ctx.done().await;
+    /// assert!(!db.is_done());
+    /// # });
+    /// ```
+    pub fn linked(parents: &[&Context]) -> (Self, Handler) {
+        let Some((first, rest)) = parents.split_first() else {
+            let handler = Handler::new();
+            return (handler.context(), handler);
+        };
+
+        let (ctx, handler) = first.new_child();
+        if rest.iter().any(|parent| parent.is_done()) {
+            handler.token.0.cancel();
+        } else if !rest.is_empty() {
+            cancel_on_any(
+                handler.token.0.clone(),
+                rest.iter().map(|parent| parent.token.clone()).collect(),
+            );
+        }
+
+        (ctx, handler)
+    }
+
+    #[must_use]
+    /// Create a detached context from this context.
+    ///
+    /// The detached context is cancelled together with this context, but it is
+    /// not tracked by any handler. This means [`Handler::wait`],
+    /// [`Handler::done`] and [`Handler::shutdown`] will not wait for it (or any
+    /// of its clones) to be dropped.
+    ///
+    /// This is useful for best-effort, fire-and-forget work (such as flushing
+    /// telemetry) which should not hold up a graceful shutdown.
+    ///
+    /// Be careful: work attached to a detached context may still be running
+    /// after [`Handler::shutdown`] returns, and will simply be dropped if the
+    /// runtime is shut down before it completes. Do not use this for work that
+    /// must finish before the process exits.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Context;
+    ///
+    /// let (ctx, handler) = Context::new();
+    /// let detached = ctx.detached();
+    /// ```
+    pub fn detached(&self) -> Self {
+        Self {
+            token: self.token.child_token(),
+            tracker: ContextTrackerInner::new().child(),
+            values: self.values.clone(),
+            reason: self.reason.clone(),
+        }
+    }
+
+    #[must_use]
+    /// Create a context from an existing [`CancellationToken`].
+    ///
+    /// The context is done once the token is cancelled. Like a
+    /// [`detached`](Context::detached) context it is not tracked by any
+    /// handler, so nothing waits for it to be dropped. To have work on an
+    /// imported token counted by a [`Handler`], create a tracked child of it
+    /// with [`Context::new_child`] and wait on the returned handler.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Context;
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// let token = CancellationToken::new();
+    /// let ctx = Context::from_token(token.clone());
+    ///
+    /// token.cancel();
+    /// assert!(ctx.is_done());
+    /// ```
+    pub fn from_token(token: CancellationToken) -> Self {
+        Self {
+            token,
+            tracker: ContextTrackerInner::new().child(),
+            values: None,
+            reason: Arc::default(),
+        }
+    }
+
+    #[must_use]
+    /// Returns a [`CancellationToken`] which is cancelled when this context is
+    /// done.
+    ///
+    /// The token is a child of the context, cancelling it does not cancel the
+    /// context. Holding the token does not count as holding the context, so
+    /// [`Handler::shutdown`] does not wait for it to be dropped.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.child_token()
+    }
+
     #[must_use]
     /// Returns the global context
     pub fn global() -> Self {
@@ -187,12 +422,153 @@ impl Context {
     pub fn is_done(&self) -> bool {
         self.token.is_cancelled()
     }
+
+    /// Returns why the context is done, or `None` if it is not done yet.
+    ///
+    /// Contexts which were cancelled by a parent context (or the token given
+    /// to [`Context::from_token`]) report
+    /// [`CancellationReason::ParentCancelled`], even if the parent itself hit
+    /// a deadline. A context created with [`Context::detached`] reports the
+    /// reason of the context it was detached from.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::{CancellationReason, Context};
+    ///
+    /// let (parent, parent_handler) = Context::new();
+    /// let (child, child_handler) = parent.new_child();
+    /// assert_eq!(child.cancellation_reason(), None);
+    ///
+    /// parent_handler.cancel();
+    /// assert_eq!(parent.cancellation_reason(), Some(CancellationReason::Manual));
+    /// assert_eq!(child.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+    /// ```
+    #[must_use]
+    pub fn cancellation_reason(&self) -> Option<CancellationReason> {
+        reason_of(&self.token, &self.reason)
+    }
+
+    #[must_use]
+    /// Attach a value to this context.
+    ///
+    /// The value can be read with [`Context::value`] from this context, its
+    /// clones and any context derived from it (with [`Context::new_child`] or
+    /// [`Context::detached`]). Attaching a value of a type which is already
+    /// attached shadows the previous value for this context and its
+    /// descendants, the parent context still sees the old value.
+    ///
+    /// Contexts created from a [`Handler`] start without any values.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Context;
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// struct RequestId(u64);
+    ///
+    /// let (ctx, handler) = Context::new();
+    /// let ctx = ctx.with_value(RequestId(42));
+    /// let (child, child_handler) = ctx.new_child();
+    ///
+    /// assert_eq!(child.value::<RequestId>(), Some(RequestId(42)));
+    /// ```
+    pub fn with_value<T: Clone + Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.values = Some(Arc::new(ContextValue {
+            type_id: TypeId::of::<T>(),
+            value: Box::new(value),
+            parent: self.values.take(),
+        }));
+        self
+    }
+
+    /// Returns the most recently attached value of type `T`, looking through
+    /// the values inherited from parent contexts.
+    ///
+    /// See [`Context::with_value`].
+    #[must_use]
+    pub fn value<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        let mut current = self.values.as_deref();
+        while let Some(node) = current {
+            if node.type_id == TypeId::of::<T>() {
+                return node.value.downcast_ref::<T>().cloned();
+            }
+
+            current = node.parent.as_deref();
+        }
+
+        None
+    }
+
+    #[cfg(feature = "rt")]
+    /// Spawns the future on the current tokio runtime, cancelled when this
+    /// context is done.
+    ///
+    /// The task holds a clone of this context until it finishes, so
+    /// [`Handler::shutdown`] waits for it, even if the future panics. The
+    /// returned handle resolves to `None` if the context was cancelled
+    /// before the future completed.
+    ///
+    /// This requires the `rt` feature and must be called from within a tokio
+    /// runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// use scuffle_context::Context;
+    ///
+    /// let (ctx, handler) = Context::new();
+    ///
+    /// let task = ctx.spawn(async { 42 });
+    /// assert_eq!(task.await.unwrap(), Some(42));
+    ///
+    /// let task = ctx.spawn(std::future::pending::<()>());
+    /// drop(ctx);
+    /// handler.shutdown().await;
+    /// assert_eq!(task.await.unwrap(), None);
+    /// # });
+    /// ```
+    pub fn spawn<F>(&self, fut: F) -> tokio::task::JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        tokio::spawn(fut.with_context(self.clone()))
+    }
+}
+
+/// Why a [`Context`] or [`Handler`] is done, see
+/// [`Context::cancellation_reason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CancellationReason {
+    /// The handler was cancelled with [`Handler::cancel`] (or
+    /// [`Handler::shutdown`]), through a linked handler, or dropped.
+    Manual,
+    /// The deadline of a context created with
+    /// [`Context::new_child_with_deadline`] passed.
+    Deadline,
+    /// A parent context was cancelled, for any reason.
+    ParentCancelled,
+}
+
+/// Returns the reason of a token which uses the given reason cell, an unset
+/// reason of a cancelled token means it was cancelled by its parent.
+fn reason_of(token: &CancellationToken, reason: &OnceLock<CancellationReason>) -> Option<CancellationReason> {
+    token
+        .is_cancelled()
+        .then(|| reason.get().copied().unwrap_or(CancellationReason::ParentCancelled))
 }
 
 /// A wrapper type around [`CancellationToken`] that will cancel the token as
 /// soon as it is dropped.
+///
+/// The reason is set before the token is cancelled, so it is visible as soon
+/// as the token is.
 #[derive(Debug)]
-struct TokenDropGuard(CancellationToken);
+struct TokenDropGuard(CancellationToken, Arc<OnceLock<CancellationReason>>);
 
 impl TokenDropGuard {
     #[must_use]
@@ -201,6 +577,11 @@ impl TokenDropGuard {
     }
 
     fn cancel(&self) {
+        // A token which was already cancelled by its parent keeps that reason.
+        if !self.0.is_cancelled() {
+            let _ = self.1.set(CancellationReason::Manual);
+        }
+
         self.0.cancel();
     }
 }
@@ -215,6 +596,48 @@ impl Drop for TokenDropGuard {
 pub struct Handler {
     token: Arc<TokenDropGuard>,
     tracker: Arc<ContextTrackerInner>,
+    /// Handlers which are cancelled when this handler is cancelled, see
+    /// [`Handler::link`].
+    links: Arc<Mutex<Vec<WeakHandler>>>,
+    /// See [`Handler::on_shutdown`].
+    finalizers: Arc<Finalizers>,
+    /// See [`Handler::with_shutdown_timeout`].
+    shutdown_timeout: Option<Duration>,
+}
+
+/// A weak reference to a [`Handler`], used so that linked handlers do not keep
+/// each other alive.
+#[derive(Debug)]
+struct WeakHandler {
+    token: Weak<TokenDropGuard>,
+    tracker: Weak<ContextTrackerInner>,
+    links: Weak<Mutex<Vec<WeakHandler>>>,
+    finalizers: Weak<Finalizers>,
+    shutdown_timeout: Option<Duration>,
+}
+
+/// The finalizers registered with [`Handler::on_shutdown`], in registration
+/// order.
+#[derive(Default)]
+struct Finalizers(Mutex<Vec<Pin<Box<dyn Future<Output = ()> + Send>>>>);
+
+impl std::fmt::Debug for Finalizers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = self.0.lock().unwrap_or_else(PoisonError::into_inner).len();
+        f.debug_tuple("Finalizers").field(&len).finish()
+    }
+}
+
+impl WeakHandler {
+    fn upgrade(&self) -> Option<Handler> {
+        Some(Handler {
+            token: self.token.upgrade()?,
+            tracker: self.tracker.upgrade()?,
+            links: self.links.upgrade()?,
+            finalizers: self.finalizers.upgrade()?,
+            shutdown_timeout: self.shutdown_timeout,
+        })
+    }
 }
 
 impl Default for Handler {
@@ -231,23 +654,246 @@ impl Handler {
         let tracker = ContextTrackerInner::new();
 
         Handler {
-            token: Arc::new(TokenDropGuard(token)),
+            token: Arc::new(TokenDropGuard(token, Arc::default())),
             tracker,
+            links: Default::default(),
+            finalizers: Default::default(),
+            shutdown_timeout: None,
         }
     }
 
     #[must_use]
     /// Returns the global handler.
+    ///
+    /// The first call initializes the global handler with the default
+    /// [`GlobalConfig`], unless [`Handler::init_global`] was called before.
     pub fn global() -> &'static Self {
-        static GLOBAL: std::sync::OnceLock<Handler> = std::sync::OnceLock::new();
+        #[cfg(any(test, feature = "test-util"))]
+        if let Some(handler) = SCOPED_GLOBAL.get() {
+            return handler;
+        }
+
+        global_in(&GLOBAL)
+    }
+
+    /// Replace the global handler on the current thread with a new handler,
+    /// until the returned guard is dropped.
+    ///
+    /// Tests in the same process share the global handler, so a test which
+    /// cancels it also cancels the contexts of every other test. Taking a
+    /// scoped global at the start of the test keeps [`Handler::global`],
+    /// [`Context::global`] and [`Context::new`] on that thread separate from
+    /// other tests. This works with `#[tokio::test]`, which runs the test on a
+    /// single thread by default, tasks on other threads still see the real
+    /// global handler.
+    ///
+    /// Scopes can be nested, dropping a guard restores the previous global
+    /// handler. Each scope leaks its handler, this is only meant for tests.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Handler;
+    ///
+    /// {
+    ///     let scope = Handler::scoped_global();
+    ///     scope.cancel();
+    ///     assert!(Handler::global().is_done());
+    /// }
+    ///
+    /// assert!(!Handler::global().is_done());
+    /// ```
+    #[cfg(feature = "test-util")]
+    pub fn scoped_global() -> ScopedGlobal {
+        Self::scoped_global_inner()
+    }
+
+    #[cfg(any(test, feature = "test-util"))]
+    fn scoped_global_inner() -> ScopedGlobal {
+        let handler: &'static Handler = Box::leak(Box::new(Handler::new()));
+
+        ScopedGlobal {
+            handler,
+            previous: SCOPED_GLOBAL.replace(Some(handler)),
+        }
+    }
+
+    /// Initialize the global handler with the given config.
+    ///
+    /// This must be called before the first call to [`Handler::global`] (or
+    /// anything using the global handler, like [`Context::new`]), otherwise
+    /// the global handler has already been created with the default config and
+    /// [`GlobalAlreadyInitialized`] is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::{GlobalConfig, Handler};
+    ///
+    /// let handler = Handler::init_global(GlobalConfig::new().with_cancel_on_panic(true))
+    ///     .expect("global handler already initialized");
+    ///
+    /// assert!(!handler.is_done());
+    /// ```
+    pub fn init_global(config: GlobalConfig) -> Result<&'static Self, GlobalAlreadyInitialized> {
+        init_global_in(&GLOBAL, config)
+    }
+
+    /// Set the maximum time [`Handler::shutdown`] waits for contexts to be
+    /// dropped.
+    ///
+    /// Once the timeout elapses `shutdown` returns even though some contexts
+    /// are still alive. This requires a tokio runtime with the time driver
+    /// enabled.
+    ///
+    /// Child handlers do not inherit the timeout. This requires the `time`
+    /// feature.
+    #[cfg(feature = "time")]
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = Some(timeout);
+        self
+    }
 
-        GLOBAL.get_or_init(Handler::new)
+    /// Returns the timeout set by [`Handler::with_shutdown_timeout`].
+    pub fn shutdown_timeout(&self) -> Option<Duration> {
+        self.shutdown_timeout
     }
 
-    /// Shutdown the handler and wait for all contexts to be done.
+    /// Shutdown the handler and wait for all contexts to be done, then run the
+    /// finalizers registered with [`Handler::on_shutdown`].
+    ///
+    /// If a [shutdown timeout](Handler::with_shutdown_timeout) is set, this
+    /// waits at most that long, including the time spent in finalizers.
     pub async fn shutdown(&self) {
         self.cancel();
-        self.done().await;
+
+        let shutdown = async {
+            self.done().await;
+            self.run_finalizers().await;
+        };
+
+        #[cfg(feature = "time")]
+        if let Some(timeout) = self.shutdown_timeout {
+            tokio::time::timeout(timeout, shutdown).await.ok();
+            return;
+        }
+
+        shutdown.await;
+    }
+
+    /// Shutdown the handler giving its contexts a grace period to finish, and
+    /// call `force` if they did not.
+    ///
+    /// This is the usual sequence after the first shutdown signal: cancel
+    /// everything, wait for in-flight work to drain, and give up on the
+    /// stragglers (typically by exiting the process) once the grace period is
+    /// over. `force` is called with the number of contexts still alive.
+    ///
+    /// The grace period covers the whole [`Handler::shutdown`], including the
+    /// [finalizers](Handler::on_shutdown). Returns `true` if the shutdown
+    /// completed within the grace period.
+    ///
+    /// With the `tracing` feature a warning with the number of stragglers is
+    /// logged before `force` is called. This requires the `time` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # tokio_test::block_on(async {
+    /// use std::time::Duration;
+    ///
+    /// use scuffle_context::Handler;
+    ///
+    /// let handler = Handler::new();
+    ///
+    /// // Wait for the shutdown signal, e.g. with `scuffle_signal`.
+    ///
+    /// handler
+    ///     .shutdown_with_grace(Duration::from_secs(30), |_| std::process::exit(1))
+    ///     .await;
+    /// # });
+    /// ```
+    #[cfg(feature = "time")]
+    pub async fn shutdown_with_grace(&self, grace: Duration, force: impl FnOnce(usize)) -> bool {
+        if tokio::time::timeout(grace, self.shutdown()).await.is_ok() {
+            return true;
+        }
+
+        let active_count = self.active_count();
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            active_count,
+            grace = ?grace,
+            "scuffle_context::Handler did not shut down within the grace period, forcing shutdown"
+        );
+
+        force(active_count);
+        false
+    }
+
+    /// Register a future to run during [`Handler::shutdown`], after all
+    /// contexts of this handler are done.
+    ///
+    /// This is the place for async cleanup which must happen after the work
+    /// using the contexts has stopped, such as flushing buffers, closing
+    /// connections or releasing a distributed lock.
+    ///
+    /// - Finalizers run one after the other in reverse registration order
+    ///   (last registered runs first), like drops of local variables.
+    /// - Each finalizer runs at most once. Calling `shutdown` again (or from
+    ///   several clones of the handler) only runs finalizers registered since
+    ///   they were last run.
+    /// - Finalizers cannot fail, errors have to be handled inside the future.
+    ///   If a finalizer panics the remaining finalizers still run and the
+    ///   first panic is resumed once they are done.
+    /// - Finalizers only run in `shutdown`. They do not run when the handler is
+    ///   merely cancelled or dropped, nor when a parent or linked handler is
+    ///   shut down. If the [shutdown timeout](Handler::with_shutdown_timeout)
+    ///   elapses, the finalizers which have not completed yet are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// use scuffle_context::Handler;
+    ///
+    /// let handler = Handler::new();
+    /// let ctx = handler.context();
+    ///
+    /// handler.on_shutdown(async {
+    ///     // Runs after `ctx` has been dropped.
+    ///     println!("flushed");
+    /// });
+    ///
+    /// drop(ctx);
+    /// handler.shutdown().await;
+    /// # });
+    /// ```
+    pub fn on_shutdown(&self, finalizer: impl Future<Output = ()> + Send + 'static) {
+        self.finalizers
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::pin(finalizer));
+    }
+
+    /// Runs the registered finalizers, see [`Handler::on_shutdown`].
+    async fn run_finalizers(&self) {
+        use futures_lite::FutureExt;
+
+        let finalizers = std::mem::take(&mut *self.finalizers.0.lock().unwrap_or_else(PoisonError::into_inner));
+
+        let mut panic = None;
+        for finalizer in finalizers.into_iter().rev() {
+            if let Err(payload) = std::panic::AssertUnwindSafe(finalizer).catch_unwind().await {
+                panic.get_or_insert(payload);
+            }
+        }
+
+        if let Some(payload) = panic {
+            std::panic::resume_unwind(payload);
+        }
     }
 
     /// Waits for the handler to be done (waiting for all contexts to be done).
@@ -269,6 +915,8 @@ impl Handler {
         Context {
             token: self.token.child(),
             tracker: self.tracker.child(),
+            values: None,
+            reason: self.token.1.clone(),
         }
     }
 
@@ -278,37 +926,400 @@ impl Handler {
         self.context().new_child()
     }
 
+    #[cfg(feature = "time")]
+    #[must_use]
+    /// Create a new child context from this handler which is cancelled once
+    /// `deadline` has passed, see [`Context::new_child_with_deadline`].
+    pub fn new_child_with_deadline(&self, deadline: std::time::Instant) -> (Context, Handler) {
+        self.context().new_child_with_deadline(deadline)
+    }
+
+    #[cfg(feature = "time")]
+    #[must_use]
+    /// Create a new child context from this handler which is cancelled once
+    /// `timeout` has elapsed, see [`Context::new_child_with_timeout`].
+    pub fn new_child_with_timeout(&self, timeout: Duration) -> (Context, Handler) {
+        self.context().new_child_with_timeout(timeout)
+    }
+
     /// Cancel the handler.
+    ///
+    /// This also cancels all handlers linked to this handler, see
+    /// [`Handler::link`].
     pub fn cancel(&self) {
         self.tracker.stop();
         self.token.cancel();
-    }
 
-    /// Returns true if the handler is done.
-    pub fn is_done(&self) -> bool {
-        self.token.0.is_cancelled()
+        // Take the links so that cancelling a linked handler (which links back to
+        // us) does not recurse forever.
+        let links = std::mem::take(&mut *self.links.lock().unwrap_or_else(PoisonError::into_inner));
+        for link in links.iter().filter_map(WeakHandler::upgrade) {
+            link.cancel();
+        }
     }
-}
-
-#[cfg_attr(all(coverage_nightly, test), coverage(off))]
-#[cfg(test)]
-mod tests {
-    use scuffle_future_ext::FutureExt;
-
-    use crate::{Context, Handler};
-
-    #[tokio::test]
-    async fn new() {
-        let (ctx, handler) = Context::new();
-        assert!(!handler.is_done());
-        assert!(!ctx.is_done());
 
-        let handler = Handler::default();
-        assert!(!handler.is_done());
+    /// Returns a guard which cancels this handler when it is dropped.
+    ///
+    /// This makes sure the handler is cancelled however the scope holding the
+    /// guard is left, including early returns and panics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Handler;
+    ///
+    /// let handler = Handler::new();
+    ///
+    /// {
+    ///     let _guard = handler.cancel_guard();
+    ///     assert!(!handler.is_done());
+    /// }
+    ///
+    /// assert!(handler.is_done());
+    /// ```
+    pub fn cancel_guard(&self) -> CancelGuard {
+        CancelGuard(self.clone())
     }
 
-    #[tokio::test]
-    async fn cancel() {
+    /// Link this handler with another handler, so that calling
+    /// [`Handler::cancel`] (or [`Handler::shutdown`]) on either of them
+    /// cancels both.
+    ///
+    /// The handlers are still tracked independently, so [`Handler::wait`] only
+    /// waits for the contexts of the handler it is called on.
+    ///
+    /// Only explicit cancellation is propagated, a handler which is cancelled
+    /// because its parent was cancelled or because it was dropped does not
+    /// cancel the handlers linked to it.
+    ///
+    /// If either handler is already cancelled, the other is cancelled
+    /// immediately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scuffle_context::Handler;
+    ///
+    /// let db = Handler::new();
+    /// let http = Handler::new();
+    /// db.link(&http);
+    ///
+    /// db.cancel();
+    /// assert!(http.is_done());
+    /// ```
+    pub fn link(&self, other: &Handler) {
+        if Arc::ptr_eq(&self.token, &other.token) {
+            return;
+        }
+
+        if self.is_done() {
+            other.cancel();
+            return;
+        }
+
+        if other.is_done() {
+            self.cancel();
+            return;
+        }
+
+        self.links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(other.downgrade());
+        other
+            .links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(self.downgrade());
+    }
+
+    fn downgrade(&self) -> WeakHandler {
+        WeakHandler {
+            token: Arc::downgrade(&self.token),
+            tracker: Arc::downgrade(&self.tracker),
+            links: Arc::downgrade(&self.links),
+            finalizers: Arc::downgrade(&self.finalizers),
+            shutdown_timeout: self.shutdown_timeout,
+        }
+    }
+
+    /// Returns true if the handler is done.
+    pub fn is_done(&self) -> bool {
+        self.token.0.is_cancelled()
+    }
+
+    /// Returns why the handler is done, or `None` if it is not done yet.
+    ///
+    /// See [`Context::cancellation_reason`].
+    pub fn cancellation_reason(&self) -> Option<CancellationReason> {
+        reason_of(&self.token.0, &self.token.1)
+    }
+
+    /// Returns the number of contexts created from this handler which are
+    /// still alive.
+    ///
+    /// Contexts of child handlers (created with [`Handler::new_child`] or
+    /// [`Context::new_child`]) are tracked by the child handler and are not
+    /// included, however the parent context they were created from is.
+    pub fn active_count(&self) -> usize {
+        self.tracker.active_count.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Export the number of active contexts of this handler as an observable
+    /// gauge named `scuffle_context_active`, with the given name as the
+    /// `handler` attribute.
+    ///
+    /// The gauge is registered with the global meter provider the first time
+    /// this is called, so the provider must be set before that. It stops
+    /// reporting the handler once the handler and all of its contexts are
+    /// dropped, calling this again replaces the name.
+    ///
+    /// See [`Handler::active_count`].
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(self, name: impl Into<std::borrow::Cow<'static, str>>) -> Self {
+        let registration = metrics::register(name.into(), &self.tracker);
+        // Replacing a previous registration removes it from the gauge.
+        *self.tracker.metrics.lock().unwrap_or_else(PoisonError::into_inner) = Some(registration);
+
+        self
+    }
+}
+
+/// In debug builds, warn when the last clone of a handler is dropped without
+/// being cancelled while contexts created from it are still alive. Those
+/// contexts are only cancelled because the handler was dropped, which usually
+/// means a [`Handler::shutdown`] was forgotten and nothing waited for them.
+#[cfg(all(feature = "tracing", debug_assertions))]
+impl Drop for Handler {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.token) != 1 || self.is_done() {
+            return;
+        }
+
+        let active_count = self.active_count();
+        if active_count > 0 {
+            tracing::warn!(
+                active_count,
+                "scuffle_context::Handler dropped without being cancelled while it still has active contexts, \
+                 did you forget to call `shutdown`?"
+            );
+        }
+    }
+}
+
+/// A guard which cancels a [`Handler`] when dropped, see
+/// [`Handler::cancel_guard`].
+#[derive(Debug)]
+#[must_use = "the handler is cancelled as soon as the guard is dropped"]
+pub struct CancelGuard(Handler);
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+static GLOBAL: OnceLock<Handler> = OnceLock::new();
+
+#[cfg(any(test, feature = "test-util"))]
+thread_local! {
+    /// The handler of the innermost [`ScopedGlobal`] on this thread.
+    static SCOPED_GLOBAL: std::cell::Cell<Option<&'static Handler>> = const { std::cell::Cell::new(None) };
+}
+
+/// Replaces the global handler on the current thread until dropped, see
+/// [`Handler::scoped_global`].
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug)]
+#[must_use = "the global handler is restored as soon as the guard is dropped"]
+pub struct ScopedGlobal {
+    handler: &'static Handler,
+    previous: Option<&'static Handler>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl std::ops::Deref for ScopedGlobal {
+    type Target = Handler;
+
+    fn deref(&self) -> &Self::Target {
+        self.handler
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Drop for ScopedGlobal {
+    fn drop(&mut self) {
+        SCOPED_GLOBAL.set(self.previous);
+    }
+}
+
+fn global_in(global: &'static OnceLock<Handler>) -> &'static Handler {
+    global.get_or_init(|| GlobalConfig::default().build(global))
+}
+
+fn init_global_in(
+    global: &'static OnceLock<Handler>,
+    config: GlobalConfig,
+) -> Result<&'static Handler, GlobalAlreadyInitialized> {
+    let mut config = Some(config);
+    let handler = global.get_or_init(|| config.take().unwrap().build(global));
+
+    if config.is_some() {
+        return Err(GlobalAlreadyInitialized);
+    }
+
+    Ok(handler)
+}
+
+/// The configuration of the global handler, see [`Handler::init_global`].
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct GlobalConfig {
+    shutdown_timeout: Option<Duration>,
+    cancel_on_panic: bool,
+}
+
+impl GlobalConfig {
+    /// Create a new config with the defaults, no shutdown timeout and panics
+    /// do not cancel the global handler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the shutdown timeout of the global handler, see
+    /// [`Handler::with_shutdown_timeout`]. This requires the `time` feature.
+    #[cfg(feature = "time")]
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = Some(timeout);
+        self
+    }
+
+    /// Install a panic hook which cancels the global handler whenever any
+    /// thread panics, so the rest of the application shuts down.
+    ///
+    /// The previously installed hook is still called.
+    pub fn with_cancel_on_panic(mut self, cancel_on_panic: bool) -> Self {
+        self.cancel_on_panic = cancel_on_panic;
+        self
+    }
+
+    fn build(self, global: &'static OnceLock<Handler>) -> Handler {
+        let mut handler = Handler::new();
+        handler.shutdown_timeout = self.shutdown_timeout;
+
+        if self.cancel_on_panic {
+            let hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                // The hook is installed while the handler is being initialized,
+                // so a panic during initialization finds the lock empty.
+                if let Some(handler) = global.get() {
+                    handler.cancel();
+                }
+
+                hook(info);
+            }));
+        }
+
+        handler
+    }
+}
+
+/// Spawns a task which cancels the token at the deadline, the task ends early
+/// if the token is cancelled before.
+#[cfg(feature = "time")]
+fn cancel_at(token: CancellationToken, reason: Arc<OnceLock<CancellationReason>>, deadline: std::time::Instant) {
+    tokio::spawn(async move {
+        let deadline = tokio::time::Instant::from_std(deadline);
+        let elapsed = futures_lite::future::or(
+            async {
+                tokio::time::sleep_until(deadline).await;
+                true
+            },
+            async {
+                token.cancelled().await;
+                false
+            },
+        )
+        .await;
+
+        if elapsed {
+            let _ = reason.set(CancellationReason::Deadline);
+            token.cancel();
+        }
+    });
+}
+
+/// Spawns a task which cancels the token once any of the parents is
+/// cancelled, the task ends early if the token is cancelled before.
+#[cfg(feature = "rt")]
+fn cancel_on_any(token: CancellationToken, parents: Vec<CancellationToken>) {
+    tokio::spawn(async move {
+        let mut parents = parents
+            .into_iter()
+            .map(|parent| Box::pin(parent.cancelled_owned()))
+            .collect::<Vec<_>>();
+        let any_parent = std::future::poll_fn(|cx| {
+            if parents.iter_mut().any(|parent| parent.as_mut().poll(cx).is_ready()) {
+                std::task::Poll::Ready(())
+            } else {
+                std::task::Poll::Pending
+            }
+        });
+
+        let cancelled = futures_lite::future::or(
+            async {
+                any_parent.await;
+                true
+            },
+            async {
+                token.cancelled().await;
+                false
+            },
+        )
+        .await;
+
+        if cancelled {
+            token.cancel();
+        }
+    });
+}
+
+/// Returned by [`Handler::init_global`] when the global handler has already
+/// been initialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalAlreadyInitialized;
+
+impl std::fmt::Display for GlobalAlreadyInitialized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the global handler has already been initialized")
+    }
+}
+
+impl std::error::Error for GlobalAlreadyInitialized {}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use scuffle_future_ext::FutureExt;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CancellationReason, Context, GlobalAlreadyInitialized, GlobalConfig, Handler};
+
+    #[tokio::test]
+    async fn new() {
+        let (ctx, handler) = Context::new();
+        assert!(!handler.is_done());
+        assert!(!ctx.is_done());
+
+        let handler = Handler::default();
+        assert!(!handler.is_done());
+    }
+
+    #[tokio::test]
+    async fn cancel() {
         let (ctx, handler) = Context::new();
         let (child_ctx, child_handler) = ctx.new_child();
         let child_ctx2 = ctx.clone();
@@ -385,8 +1396,579 @@ mod tests {
         assert!(handler.is_done());
     }
 
+    #[tokio::test]
+    async fn on_shutdown() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 0..3 {
+            let order = order.clone();
+            handler.on_shutdown(async move {
+                tokio::task::yield_now().await;
+                order.lock().unwrap().push(i);
+            });
+        }
+
+        // Finalizers wait for the contexts to be dropped.
+        assert!(handler
+            .shutdown()
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .is_err());
+        assert!(order.lock().unwrap().is_empty());
+
+        drop(ctx);
+        handler.shutdown().await;
+        assert_eq!(*order.lock().unwrap(), [2, 1, 0]);
+
+        // Each finalizer runs exactly once.
+        handler.clone().shutdown().await;
+        assert_eq!(*order.lock().unwrap(), [2, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn on_shutdown_panic() {
+        let handler = Handler::new();
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        let counter = ran.clone();
+        handler.on_shutdown(async move {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        handler.on_shutdown(async { panic!("finalizer panicked") });
+
+        let task = tokio::spawn(async move { handler.shutdown().await });
+        assert!(task.await.unwrap_err().is_panic());
+        assert_eq!(ran.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn values() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct RequestId(u64);
+
+        let handler = Handler::new();
+        let parent = handler.context().with_value(RequestId(1)).with_value("trace");
+        let (child, _child_handler) = parent.new_child();
+        let (grandchild, _grandchild_handler) = child.new_child();
+
+        assert_eq!(grandchild.value::<RequestId>(), Some(RequestId(1)));
+        assert_eq!(grandchild.value::<&str>(), Some("trace"));
+        assert_eq!(grandchild.clone().value::<RequestId>(), Some(RequestId(1)));
+        assert_eq!(grandchild.detached().value::<RequestId>(), Some(RequestId(1)));
+        assert_eq!(grandchild.value::<u32>(), None);
+
+        // Shadowing a value does not affect the parent.
+        let grandchild = grandchild.with_value(RequestId(2));
+        assert_eq!(grandchild.value::<RequestId>(), Some(RequestId(2)));
+        assert_eq!(child.value::<RequestId>(), Some(RequestId(1)));
+
+        // Contexts created from a handler start empty.
+        assert_eq!(handler.context().value::<RequestId>(), None);
+    }
+
+    #[tokio::test]
+    async fn detached() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let detached = ctx.detached();
+        drop(ctx);
+
+        let task = tokio::spawn(async move {
+            let detached2 = detached.clone();
+            detached.done().await;
+            // Keep working after the context is done, this must not block shutdown.
+            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            drop(detached2);
+        });
+
+        assert!(handler
+            .shutdown()
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .is_ok());
+        assert!(!task.is_finished());
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn from_token() {
+        let token = CancellationToken::new();
+        let ctx = Context::from_token(token.clone());
+        let (child, child_handler) = ctx.new_child();
+        assert!(!ctx.is_done());
+        assert!(!child.is_done());
+
+        let task = tokio::spawn(async move {
+            child.done().await;
+        });
+
+        token.cancel();
+        assert!(ctx.is_done());
+        ctx.done().with_timeout(std::time::Duration::from_millis(200)).await.unwrap();
+
+        // The child is tracked by its handler, so waiting on it waits for the task.
+        child_handler
+            .shutdown()
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .unwrap();
+        task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancellation_token() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let token = ctx.cancellation_token();
+
+        // Cancelling the exported token does not cancel the context.
+        ctx.cancellation_token().cancel();
+        assert!(!ctx.is_done());
+        assert!(!token.is_cancelled());
+
+        // Holding the token does not block shutdown.
+        drop(ctx);
+        handler
+            .shutdown()
+            .with_timeout(std::time::Duration::from_millis(200))
+            .await
+            .unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn detached_cancel() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let detached = ctx.detached();
+
+        assert!(!detached.is_done());
+        handler.cancel();
+        assert!(detached.is_done());
+        assert!(detached.clone().is_done());
+    }
+
+    #[tokio::test]
+    async fn link() {
+        let a = Handler::new();
+        let b = Handler::new();
+        let c = Handler::new();
+        a.link(&b);
+        b.link(&c);
+
+        let a_ctx = a.context();
+
+        b.cancel();
+
+        assert!(a.is_done());
+        assert!(b.is_done());
+        assert!(c.is_done());
+        assert!(a_ctx.is_done());
+
+        // The tracking is still independent.
+        assert!(b.wait().with_timeout(std::time::Duration::from_millis(200)).await.is_ok());
+        assert!(a.wait().with_timeout(std::time::Duration::from_millis(200)).await.is_err());
+        drop(a_ctx);
+        assert!(a.wait().with_timeout(std::time::Duration::from_millis(200)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn link_cancelled() {
+        let a = Handler::new();
+        let b = Handler::new();
+        a.cancel();
+        b.link(&a);
+        assert!(b.is_done());
+
+        // Linked handlers do not keep each other alive.
+        let strong_counts = |handler: &Handler| {
+            (
+                Arc::strong_count(&handler.token),
+                Arc::strong_count(&handler.tracker),
+                Arc::strong_count(&handler.links),
+            )
+        };
+        let a = Handler::new();
+        let b = Handler::new();
+        let (a_counts, b_counts) = (strong_counts(&a), strong_counts(&b));
+        a.link(&b);
+        assert_eq!(strong_counts(&a), a_counts);
+        assert_eq!(strong_counts(&b), b_counts);
+
+        let b_token = Arc::downgrade(&b.token);
+        drop(b);
+        assert!(b_token.upgrade().is_none());
+        a.cancel();
+    }
+
+    #[test]
+    fn cancel_guard() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        {
+            let _guard = handler.cancel_guard();
+            assert!(!ctx.is_done());
+        }
+
+        assert!(handler.is_done());
+        assert!(ctx.is_done());
+
+        fn early_return(handler: &Handler, exit: bool) -> bool {
+            let _guard = handler.cancel_guard();
+            if exit {
+                return true;
+            }
+
+            unreachable!()
+        }
+
+        let handler = Handler::new();
+        assert!(early_return(&handler, true));
+        assert!(handler.is_done());
+
+        let handler = Handler::new();
+        std::panic::catch_unwind(|| {
+            let _guard = handler.cancel_guard();
+            panic!("test panic");
+        })
+        .unwrap_err();
+        assert!(handler.is_done());
+    }
+
+    #[tokio::test]
+    async fn active_count() {
+        let handler = Handler::new();
+        assert_eq!(handler.active_count(), 0);
+
+        let ctx = handler.context();
+        let ctx2 = ctx.clone();
+        let (_child_ctx, _child_handler) = ctx.new_child();
+        assert_eq!(handler.active_count(), 2);
+
+        drop(ctx);
+        drop(ctx2);
+        assert_eq!(handler.active_count(), 0);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn with_metrics() {
+        use opentelemetry_sdk::metrics::data::Gauge;
+        use opentelemetry_sdk::metrics::{SdkMeterProvider, Temporality};
+        use scuffle_metrics::opentelemetry::KeyValue;
+
+        // With delta temporality a collection only contains what the callback
+        // observed, handlers which are no longer reported are not kept around.
+        let exporter = scuffle_metrics::prometheus::exporter()
+            .with_temporality(Temporality::Delta)
+            .build();
+        let provider = SdkMeterProvider::builder().with_reader(exporter.clone()).build();
+        scuffle_metrics::opentelemetry::global::set_meter_provider(provider);
+
+        let read = |name: &'static str| {
+            let metrics = exporter.snapshot().unwrap();
+            metrics
+                .scope_metrics
+                .iter()
+                .flat_map(|scope| scope.metrics.iter())
+                .find(|metric| metric.name == "scuffle_context_active")
+                .and_then(|metric| metric.data.as_any().downcast_ref::<Gauge<u64>>())
+                .and_then(|gauge| {
+                    gauge
+                        .data_points
+                        .iter()
+                        .find(|point| point.attributes.contains(&KeyValue::new("handler", name)))
+                })
+                .map(|point| point.value)
+        };
+
+        let handler = Handler::new().with_metrics("test");
+        assert_eq!(read("test"), Some(0));
+
+        let ctx = handler.context();
+        let ctx2 = ctx.clone();
+        assert_eq!(read("test"), Some(2));
+
+        drop(ctx);
+        assert_eq!(read("test"), Some(1));
+
+        // Registering again replaces the previous registration.
+        let handler = handler.with_metrics("renamed");
+        assert_eq!(read("test"), None);
+        assert_eq!(read("renamed"), Some(1));
+
+        // The registration lives as long as the contexts of the handler.
+        drop(handler);
+        assert_eq!(read("renamed"), Some(1));
+
+        drop(ctx2);
+        assert_eq!(read("renamed"), None);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn init_global() {
+        static GLOBAL: std::sync::OnceLock<Handler> = std::sync::OnceLock::new();
+
+        let timeout = std::time::Duration::from_secs(5);
+        let handler = crate::init_global_in(&GLOBAL, GlobalConfig::new().with_shutdown_timeout(timeout)).unwrap();
+        assert_eq!(handler.shutdown_timeout(), Some(timeout));
+        assert_eq!(crate::global_in(&GLOBAL).shutdown_timeout(), Some(timeout));
+
+        let err = crate::init_global_in(&GLOBAL, GlobalConfig::new()).unwrap_err();
+        assert_eq!(err, GlobalAlreadyInitialized);
+        assert_eq!(err.to_string(), "the global handler has already been initialized");
+        assert_eq!(crate::global_in(&GLOBAL).shutdown_timeout(), Some(timeout));
+    }
+
+    #[test]
+    fn init_global_too_late() {
+        static GLOBAL: std::sync::OnceLock<Handler> = std::sync::OnceLock::new();
+
+        assert_eq!(crate::global_in(&GLOBAL).shutdown_timeout(), None);
+        assert_eq!(
+            crate::init_global_in(&GLOBAL, GlobalConfig::new()).unwrap_err(),
+            GlobalAlreadyInitialized
+        );
+    }
+
+    #[test]
+    fn init_global_cancel_on_panic() {
+        static GLOBAL: std::sync::OnceLock<Handler> = std::sync::OnceLock::new();
+
+        let handler = crate::init_global_in(&GLOBAL, GlobalConfig::new().with_cancel_on_panic(true)).unwrap();
+        assert!(!handler.is_done());
+
+        std::thread::spawn(|| panic!("test panic")).join().unwrap_err();
+
+        assert!(handler.is_done());
+    }
+
+    #[cfg(feature = "time")]
+    #[tokio::test]
+    async fn shutdown_timeout() {
+        let handler = Handler::new().with_shutdown_timeout(std::time::Duration::from_millis(50));
+        let ctx = handler.context();
+
+        handler
+            .shutdown()
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .expect("shutdown did not respect the timeout");
+
+        assert!(ctx.is_done());
+    }
+
+    #[cfg(feature = "time")]
+    #[tokio::test]
+    async fn shutdown_with_grace() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        tokio::spawn(async move {
+            ctx.done().await;
+            // Drains well within the grace period.
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            drop(ctx);
+        });
+
+        let forced = std::sync::atomic::AtomicBool::new(false);
+        let drained = handler
+            .shutdown_with_grace(std::time::Duration::from_secs(1), |_| {
+                forced.store(true, std::sync::atomic::Ordering::Relaxed)
+            })
+            .await;
+        assert!(drained);
+        assert!(!forced.load(std::sync::atomic::Ordering::Relaxed));
+
+        let handler = Handler::new();
+        let _stuck = handler.context();
+        let _also_stuck = handler.context();
+
+        let mut stragglers = None;
+        let drained = handler
+            .shutdown_with_grace(std::time::Duration::from_millis(50), |active_count| {
+                stragglers = Some(active_count)
+            })
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .expect("shutdown did not respect the grace period");
+        assert!(!drained);
+        assert_eq!(stragglers, Some(2));
+    }
+
+    #[cfg(feature = "time")]
+    #[tokio::test]
+    async fn deadline() {
+        let (parent, parent_handler) = Context::new();
+        let (child, child_handler) = parent.new_child_with_timeout(std::time::Duration::from_millis(50));
+        let (grandchild, _grandchild_handler) = child.new_child();
+
+        assert!(!child.is_done());
+        child
+            .done()
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .expect("deadline did not cancel the context");
+
+        assert!(child_handler.is_done());
+        assert!(grandchild.is_done());
+        assert!(!parent.is_done());
+        assert!(!parent_handler.is_done());
+
+        // The child is still cancelled with its parent before the deadline.
+        let (child, _child_handler) = parent.new_child_with_timeout(std::time::Duration::from_secs(60));
+        parent_handler.cancel();
+        assert!(child.is_done());
+
+        // A deadline in the past cancels right away.
+        let handler = Handler::new();
+        let (child, _child_handler) = handler.new_child_with_deadline(std::time::Instant::now());
+        child
+            .done()
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .expect("past deadline did not cancel the context");
+
+        let (child, _child_handler) = handler.new_child_with_timeout(std::time::Duration::MAX);
+        assert!(!child.is_done());
+    }
+
+    #[test]
+    fn cancellation_reason() {
+        let handler = Handler::new();
+        let ctx = handler.context();
+        let (child, child_handler) = ctx.new_child();
+        let detached = child.detached();
+
+        assert_eq!(handler.cancellation_reason(), None);
+        assert_eq!(ctx.cancellation_reason(), None);
+
+        handler.cancel();
+        assert_eq!(handler.cancellation_reason(), Some(CancellationReason::Manual));
+        assert_eq!(ctx.cancellation_reason(), Some(CancellationReason::Manual));
+        assert_eq!(child.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+        assert_eq!(detached.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+
+        // Cancelling a handler which its parent already cancelled keeps the reason.
+        child_handler.cancel();
+        assert_eq!(child_handler.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+
+        // Dropping a handler cancels it manually.
+        let parent = Handler::new();
+        let (child, child_handler) = parent.new_child();
+        drop(child_handler);
+        assert_eq!(child.cancellation_reason(), Some(CancellationReason::Manual));
+
+        let token = CancellationToken::new();
+        let ctx = Context::from_token(token.clone());
+        token.cancel();
+        assert_eq!(ctx.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+    }
+
+    #[cfg(feature = "time")]
+    #[tokio::test]
+    async fn cancellation_reason_deadline() {
+        let handler = Handler::new();
+        let (child, child_handler) = handler.new_child_with_timeout(std::time::Duration::from_millis(10));
+        let (grandchild, _grandchild_handler) = child.new_child();
+
+        child
+            .done()
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .expect("deadline did not cancel the context");
+
+        assert_eq!(child.cancellation_reason(), Some(CancellationReason::Deadline));
+        assert_eq!(child_handler.cancellation_reason(), Some(CancellationReason::Deadline));
+        assert_eq!(grandchild.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+        assert_eq!(handler.cancellation_reason(), None);
+
+        // A manual cancel before the deadline is not reported as a deadline.
+        let (child, child_handler) = handler.new_child_with_timeout(std::time::Duration::from_millis(10));
+        child_handler.cancel();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(child.cancellation_reason(), Some(CancellationReason::Manual));
+    }
+
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn linked() {
+        for cancelled in 0..2 {
+            let parents = [Handler::new(), Handler::new()];
+            let contexts = [parents[0].context(), parents[1].context()];
+            let (ctx, handler) = Context::linked(&[&contexts[0], &contexts[1]]);
+            assert!(!ctx.is_done());
+
+            parents[cancelled].cancel();
+            ctx.done()
+                .with_timeout(std::time::Duration::from_secs(1))
+                .await
+                .expect("cancelling a parent did not cancel the linked context");
+
+            assert!(handler.is_done());
+            assert_eq!(ctx.cancellation_reason(), Some(CancellationReason::ParentCancelled));
+            assert!(!parents[1 - cancelled].is_done());
+
+            // The linked context is tracked by its own handler.
+            let shutdown = handler.shutdown();
+            let mut shutdown = std::pin::pin!(shutdown);
+            assert!(futures_lite::future::poll_once(&mut shutdown).await.is_none());
+            drop(ctx);
+            shutdown.await;
+        }
+
+        // A cancelled parent cancels the linked context right away.
+        let (other, parent) = (Handler::new(), Handler::new());
+        parent.cancel();
+        let (ctx, _handler) = Context::linked(&[&other.context(), &parent.context()]);
+        assert!(ctx.is_done());
+    }
+
+    #[cfg(feature = "rt")]
+    #[tokio::test]
+    async fn spawn() {
+        struct SlowDrop(Arc<std::sync::atomic::AtomicBool>);
+
+        impl Drop for SlowDrop {
+            fn drop(&mut self) {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+
+        let handler = Handler::new();
+        let ctx = handler.context();
+
+        assert_eq!(ctx.spawn(async { 42 }).await.unwrap(), Some(42));
+
+        // A panicking task still releases the context.
+        assert!(ctx.spawn(async { panic!("task panicked") }).await.unwrap_err().is_panic());
+
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let guard = SlowDrop(dropped.clone());
+        let task = ctx.spawn(async move {
+            let _guard = guard;
+            std::future::pending::<()>().await
+        });
+        drop(ctx);
+
+        handler
+            .shutdown()
+            .with_timeout(std::time::Duration::from_secs(1))
+            .await
+            .expect("shutdown did not finish");
+        assert!(dropped.load(std::sync::atomic::Ordering::Relaxed));
+        assert_eq!(task.await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn global_handler() {
+        // Cancelling the real global handler would cancel the other tests.
+        let _scope = Handler::scoped_global_inner();
         let handler = Handler::global();
 
         assert!(!handler.is_done());
@@ -401,4 +1983,93 @@ mod tests {
         assert!(child_handler.is_done());
         assert!(child_ctx.is_done());
     }
+
+    #[test]
+    fn scoped_global() {
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+
+        let threads: Vec<_> = [true, false]
+            .into_iter()
+            .map(|cancel| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    let scope = Handler::scoped_global_inner();
+                    let ctx = Context::global();
+                    assert!(std::ptr::eq(Handler::global(), &*scope));
+
+                    if cancel {
+                        Handler::global().cancel();
+                    }
+
+                    // Both threads have cancelled their handler, if they wanted to.
+                    barrier.wait();
+                    assert_eq!(ctx.is_done(), cancel);
+                    assert_eq!(Context::new().0.is_done(), cancel);
+
+                    // Nested scopes restore the outer scope once dropped.
+                    let inner = Handler::scoped_global_inner();
+                    assert!(!Handler::global().is_done());
+                    drop(inner);
+                    assert!(std::ptr::eq(Handler::global(), &*scope));
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert!(std::ptr::eq(Handler::global(), crate::global_in(&crate::GLOBAL)));
+    }
+
+    #[cfg(all(feature = "tracing", debug_assertions))]
+    #[test]
+    fn forgotten_shutdown_warning() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let output = || String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+
+        // Cancelled handlers and handlers without live contexts are fine.
+        let handler = Handler::new();
+        let ctx = handler.context();
+        handler.cancel();
+        drop(handler);
+        drop(ctx);
+        drop(Handler::new());
+
+        // Dropping one of multiple clones is fine as well.
+        let handler = Handler::new();
+        let ctx = handler.context();
+        drop(handler.clone());
+        assert!(output().is_empty(), "{}", output());
+
+        drop(handler);
+        assert!(ctx.is_done());
+
+        let output = output();
+        assert!(output.contains("WARN"), "{output}");
+        assert!(output.contains("did you forget to call `shutdown`?"), "{output}");
+        assert!(output.contains("active_count=1"), "{output}");
+    }
 }
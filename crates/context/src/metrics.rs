@@ -0,0 +1,82 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, Weak};
+
+use scuffle_metrics::opentelemetry::metrics::ObservableGauge;
+use scuffle_metrics::opentelemetry::{global, InstrumentationScope, KeyValue};
+
+use crate::ContextTrackerInner;
+
+/// A handler reported by the gauge.
+struct Entry {
+    id: u64,
+    attributes: [KeyValue; 1],
+    tracker: Weak<ContextTrackerInner>,
+}
+
+/// The handlers reported by the gauge, see [`register`].
+static ENTRIES: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// Keeps a handler reported by the `scuffle_context_active` gauge, it is
+/// removed from the gauge when this is dropped.
+///
+/// The SDK has no way to unregister the callback of an instrument, so the
+/// gauge is created once with a callback which reports every registered
+/// handler.
+#[derive(Debug)]
+pub(crate) struct Registration(u64);
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        ENTRIES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|entry| entry.id != self.0);
+    }
+}
+
+/// Reports the number of active contexts of the tracker with the given name
+/// as the `handler` attribute until the returned registration is dropped.
+pub(crate) fn register(name: Cow<'static, str>, tracker: &Arc<ContextTrackerInner>) -> Registration {
+    static GAUGE: OnceLock<ObservableGauge<u64>> = OnceLock::new();
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    GAUGE.get_or_init(|| {
+        let meter = global::meter_with_scope(
+            InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
+                .with_version(env!("CARGO_PKG_VERSION"))
+                .build(),
+        );
+
+        meter
+            .u64_observable_gauge("scuffle_context_active")
+            .with_description("The number of active contexts of a handler")
+            .with_callback(|observer| {
+                // The trackers are upgraded after the lock is released, dropping the last
+                // reference to one removes its entry which needs the lock.
+                let entries = ENTRIES
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .iter()
+                    .map(|entry| (entry.attributes.clone(), entry.tracker.clone()))
+                    .collect::<Vec<_>>();
+
+                for (attributes, tracker) in entries {
+                    if let Some(tracker) = tracker.upgrade() {
+                        let count = tracker.active_count.load(Ordering::Relaxed);
+                        observer.observe(count as u64, &attributes);
+                    }
+                }
+            })
+            .build()
+    });
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    ENTRIES.lock().unwrap_or_else(PoisonError::into_inner).push(Entry {
+        id,
+        attributes: [KeyValue::new("handler", name)],
+        tracker: Arc::downgrade(tracker),
+    });
+
+    Registration(id)
+}
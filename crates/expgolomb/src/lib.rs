@@ -63,6 +63,31 @@ pub trait BitReaderExpGolombExt {
             Ok((exp_glob / 2) as i64 + 1)
         }
     }
+
+    /// Reads a mapped Exp-Golomb encoded number (`me(v)` in H.264)
+    ///
+    /// The Exp-Golomb encoded code number is used as an index into
+    /// `mapping_table`, for example one of the `coded_block_pattern` tables
+    /// from Table 9-4 of the H.264 specification. Returns an
+    /// [`InvalidData`](io::ErrorKind::InvalidData) error if the code number is
+    /// out of range for the table.
+    fn read_mapped_exp_golomb(&mut self, mapping_table: &[u64]) -> io::Result<u64> {
+        let code_num = self.read_exp_golomb()?;
+
+        usize::try_from(code_num)
+            .ok()
+            .and_then(|code_num| mapping_table.get(code_num))
+            .copied()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "mapped exp-golomb code number {code_num} is out of range for a table of {} entries",
+                        mapping_table.len()
+                    ),
+                )
+            })
+    }
 }
 
 impl<R: io::Read> BitReaderExpGolombExt for BitReader<R> {
@@ -337,4 +362,29 @@ mod tests {
         assert_eq!(result, i64::MAX);
         assert_eq!(get_remaining_bits(&bit_reader), remaining_bits - 154);
     }
+
+    #[test]
+    fn test_mapped_exp_glob_decode() {
+        // The first entries of the intra 4:2:0 coded_block_pattern mapping
+        // (H.264 Table 9-4).
+        const MAPPING: [u64; 4] = [47, 31, 15, 0];
+
+        let mut bit_writer = BitWriter::<Vec<u8>>::default();
+
+        bit_writer.write_bits(0b1, 1).unwrap(); // 0
+        bit_writer.write_bits(0b011, 3).unwrap(); // 2
+        bit_writer.write_bits(0b00100, 5).unwrap(); // 3
+        bit_writer.write_bits(0b00101, 5).unwrap(); // 4
+
+        let data = bit_writer.finish().unwrap();
+
+        let mut bit_reader = BitReader::new(std::io::Cursor::new(data));
+
+        assert_eq!(bit_reader.read_mapped_exp_golomb(&MAPPING).unwrap(), 47);
+        assert_eq!(bit_reader.read_mapped_exp_golomb(&MAPPING).unwrap(), 15);
+        assert_eq!(bit_reader.read_mapped_exp_golomb(&MAPPING).unwrap(), 0);
+
+        let err = bit_reader.read_mapped_exp_golomb(&MAPPING).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
 }
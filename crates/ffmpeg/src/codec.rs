@@ -1,7 +1,21 @@
 use rusty_ffmpeg::ffi::*;
 
+use crate::error::FfmpegError;
+use crate::option::{class_options, OptionInfo};
 use crate::AVCodecID;
 
+/// Returns the name ffmpeg knows the codec by, or [`FfmpegError::UnknownCodec`]
+/// if there is no such codec.
+fn codec_name(codec_id: AVCodecID) -> Result<&'static str, FfmpegError> {
+    // Safety: `avcodec_descriptor_get` is safe to call with any codec id.
+    let descriptor = unsafe { avcodec_descriptor_get(codec_id.0 as crate::ffi::AVCodecID) };
+    // Safety: The descriptor is either null or points to a static descriptor table entry.
+    let descriptor = unsafe { descriptor.as_ref() }.ok_or(FfmpegError::UnknownCodec(codec_id))?;
+    // Safety: The name of a descriptor is a static, nul-terminated string.
+    let name = unsafe { std::ffi::CStr::from_ptr(descriptor.name) };
+    Ok(name.to_str().unwrap_or("unknown"))
+}
+
 /// A wrapper around an [`AVCodec`] pointer.
 ///
 /// This is specifically used for decoders. The most typical way to use this is to create it from a [`AVCodecID`] or to search for it by name.
@@ -50,6 +64,18 @@ impl DecoderCodec {
         }
     }
 
+    /// Creates a [`DecoderCodec`] from a [`AVCodecID`], reporting why no decoder could be found.
+    ///
+    /// Unlike [`DecoderCodec::new`] this tells an unknown codec id
+    /// ([`FfmpegError::UnknownCodec`]) apart from a codec this ffmpeg build has
+    /// no decoder for ([`FfmpegError::DecoderNotCompiled`]).
+    pub fn find(codec_id: AVCodecID) -> Result<Self, FfmpegError> {
+        match Self::new(codec_id) {
+            Some(codec) => Ok(codec),
+            None => Err(FfmpegError::DecoderNotCompiled(codec_name(codec_id)?)),
+        }
+    }
+
     /// Creates a [`DecoderCodec`] from a codec name.
     pub fn by_name(name: &str) -> Option<Self> {
         let c_name = std::ffi::CString::new(name).ok()?;
@@ -63,6 +89,26 @@ impl DecoderCodec {
         }
     }
 
+    /// Returns the options accepted by the decoder, which can be set with
+    /// [`DecoderOptions::codec_options`](crate::decoder::DecoderOptions::codec_options).
+    ///
+    /// These are the generic decoding options shared by all decoders (such as
+    /// `skip_frame`), followed by the options specific to this decoder.
+    pub fn options(&self) -> Vec<OptionInfo> {
+        // Safety: `avcodec_get_class` is safe to call.
+        let generic = unsafe { avcodec_get_class() };
+        // Safety: The class returned by `avcodec_get_class` is a valid pointer.
+        let mut options = unsafe { class_options(generic, AV_OPT_FLAG_DECODING_PARAM) };
+
+        // Safety: The pointer here is valid.
+        if let Some(codec) = unsafe { self.0.as_ref() } {
+            // Safety: The class is null or a valid pointer to a class.
+            options.extend(unsafe { class_options(codec.priv_class, AV_OPT_FLAG_DECODING_PARAM) });
+        }
+
+        options
+    }
+
     /// Returns the raw pointer to the [`AVCodec`].
     pub const fn as_ptr(&self) -> *const AVCodec {
         self.0
@@ -125,6 +171,18 @@ impl EncoderCodec {
         }
     }
 
+    /// Creates an [`EncoderCodec`] from a [`AVCodecID`], reporting why no encoder could be found.
+    ///
+    /// Unlike [`EncoderCodec::new`] this tells an unknown codec id
+    /// ([`FfmpegError::UnknownCodec`]) apart from a codec this ffmpeg build has
+    /// no encoder for ([`FfmpegError::EncoderNotCompiled`]).
+    pub fn find(codec_id: AVCodecID) -> Result<Self, FfmpegError> {
+        match Self::new(codec_id) {
+            Some(codec) => Ok(codec),
+            None => Err(FfmpegError::EncoderNotCompiled(codec_name(codec_id)?)),
+        }
+    }
+
     /// Creates an [`EncoderCodec`] from a codec name.
     pub fn by_name(name: &str) -> Option<Self> {
         let c_name = std::ffi::CString::new(name).ok()?;
@@ -167,6 +225,7 @@ impl From<DecoderCodec> for *const AVCodec {
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
     use crate::codec::{AVCodecID, DecoderCodec, EncoderCodec};
+    use crate::error::FfmpegError;
     use crate::ffi::{avcodec_find_decoder, avcodec_find_encoder, AVCodec};
 
     #[test]
@@ -343,4 +402,29 @@ mod tests {
         let raw_ptr: *const AVCodec = empty_decoder_codec.into();
         assert!(raw_ptr.is_null(), "The raw pointer should be null for an empty DecoderCodec");
     }
+
+    #[test]
+    fn test_codec_find() {
+        assert!(DecoderCodec::find(AVCodecID::H264).is_ok());
+        assert!(EncoderCodec::find(AVCodecID::Mpeg4).is_ok());
+
+        // VP6 can only be decoded and A64 multicolor can only be encoded.
+        assert_eq!(
+            EncoderCodec::find(AVCodecID::Vp6),
+            Err(FfmpegError::EncoderNotCompiled("vp6"))
+        );
+        assert_eq!(
+            DecoderCodec::find(AVCodecID::A64Multi),
+            Err(FfmpegError::DecoderNotCompiled("a64_multi"))
+        );
+
+        assert_eq!(
+            DecoderCodec::find(AVCodecID(999_999)),
+            Err(FfmpegError::UnknownCodec(AVCodecID(999_999)))
+        );
+        assert_eq!(
+            EncoderCodec::find(AVCodecID::None),
+            Err(FfmpegError::UnknownCodec(AVCodecID::None))
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use crate::codec::DecoderCodec;
+use crate::dict::Dictionary;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
 use crate::frame::{AudioFrame, GenericFrame, VideoFrame};
@@ -72,6 +73,11 @@ pub struct DecoderOptions {
     pub codec: Option<DecoderCodec>,
     /// The number of threads to use for decoding.
     pub thread_count: i32,
+    /// Options passed to the decoder when it is opened, such as
+    /// `skip_frame=nonkey` to only decode keyframes.
+    ///
+    /// The accepted options can be listed with [`DecoderCodec::options`].
+    pub codec_options: Option<Dictionary>,
 }
 
 /// The default options for a [`Decoder`].
@@ -80,6 +86,7 @@ impl Default for DecoderOptions {
         Self {
             codec: None,
             thread_count: 1,
+            codec_options: None,
         }
     }
 }
@@ -96,10 +103,10 @@ impl Decoder {
             return Err(FfmpegError::NoDecoder);
         };
 
-        let codec = options
-            .codec
-            .or_else(|| DecoderCodec::new(AVCodecID(codec_params.codec_id as i32)))
-            .ok_or(FfmpegError::NoDecoder)?;
+        let codec = match options.codec {
+            Some(codec) => codec,
+            None => DecoderCodec::find(AVCodecID(codec_params.codec_id as i32))?,
+        };
 
         if codec.is_empty() {
             return Err(FfmpegError::NoDecoder);
@@ -139,8 +146,15 @@ impl Decoder {
         }
 
         if matches!(AVMediaType(decoder_mut.codec_type), AVMediaType::Video | AVMediaType::Audio) {
-            // Safety: `codec` is a valid pointer, and `decoder` is a valid pointer.
-            FfmpegErrorCode(unsafe { avcodec_open2(decoder_mut, codec.as_ptr(), std::ptr::null_mut()) }).result()?;
+            let mut codec_options = options.codec_options;
+            let codec_options_ptr = codec_options
+                .as_mut()
+                .map(|options| options.as_mut_ptr_ref() as *mut *mut _)
+                .unwrap_or(std::ptr::null_mut());
+
+            // Safety: `codec` is a valid pointer, `decoder` is a valid pointer and
+            // `codec_options_ptr` is null or a valid pointer.
+            FfmpegErrorCode(unsafe { avcodec_open2(decoder_mut, codec.as_ptr(), codec_options_ptr) }).result()?;
         }
 
         Ok(match AVMediaType(decoder_mut.codec_type) {
@@ -208,6 +222,34 @@ impl GenericDecoder {
             code => Err(FfmpegError::Code(code)),
         }
     }
+
+    /// Sends an end-of-file packet to the decoder and returns an iterator over
+    /// all the remaining frames.
+    ///
+    /// The iterator stops after the last frame or after the first error.
+    pub fn finish(&mut self) -> impl Iterator<Item = Result<GenericFrame, FfmpegError>> + '_ {
+        // Once draining has started ffmpeg answers another EOF with `Eof`, the
+        // remaining frames can still be received in that case.
+        let mut eof = Some(match self.send_eof() {
+            Err(FfmpegError::Code(FfmpegErrorCode::Eof)) => Ok(()),
+            result => result,
+        });
+        let mut done = false;
+
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            let result = match eof.take() {
+                Some(Err(err)) => Err(err),
+                _ => self.receive_frame(),
+            };
+
+            done = !matches!(result, Ok(Some(_)));
+            result.transpose()
+        })
+    }
 }
 
 impl VideoDecoder {
@@ -240,6 +282,12 @@ impl VideoDecoder {
     pub fn receive_frame(&mut self) -> Result<Option<VideoFrame>, FfmpegError> {
         Ok(self.0.receive_frame()?.map(|frame| frame.video()))
     }
+
+    /// Sends an end-of-file packet to the decoder and returns an iterator over
+    /// all the remaining video frames.
+    pub fn finish(&mut self) -> impl Iterator<Item = Result<VideoFrame, FfmpegError>> + '_ {
+        self.0.finish().map(|frame| frame.map(|frame| frame.video()))
+    }
 }
 
 impl std::ops::Deref for VideoDecoder {
@@ -276,6 +324,12 @@ impl AudioDecoder {
     pub fn receive_frame(&mut self) -> Result<Option<AudioFrame>, FfmpegError> {
         Ok(self.0.receive_frame()?.map(|frame| frame.audio()))
     }
+
+    /// Sends an end-of-file packet to the decoder and returns an iterator over
+    /// all the remaining audio frames.
+    pub fn finish(&mut self) -> impl Iterator<Item = Result<AudioFrame, FfmpegError>> + '_ {
+        self.0.finish().map(|frame| frame.map(|frame| frame.audio()))
+    }
 }
 
 impl std::ops::Deref for AudioDecoder {
@@ -297,8 +351,10 @@ impl std::ops::DerefMut for AudioDecoder {
 mod tests {
     use crate::codec::DecoderCodec;
     use crate::decoder::{Decoder, DecoderOptions};
+    use crate::dict::Dictionary;
+    use crate::frame::VideoFrame;
     use crate::io::Input;
-    use crate::{AVCodecID, AVMediaType};
+    use crate::{AVCodecID, AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic, AVMediaType};
 
     #[test]
     fn test_generic_decoder_debug() {
@@ -322,6 +378,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: Some(DecoderCodec::new(AVCodecID::H264).expect("Failed to find H264 codec")),
             thread_count: 2,
+            codec_options: None,
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let generic_decoder = match decoder {
@@ -363,6 +420,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: Some(DecoderCodec::new(AVCodecID::H264).expect("Failed to find H264 codec")),
             thread_count: 2,
+            codec_options: None,
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
 
@@ -414,6 +472,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: Some(DecoderCodec::new(AVCodecID::Aac).expect("Failed to find AAC codec")),
             thread_count: 2,
+            codec_options: None,
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let audio_decoder = match decoder {
@@ -537,6 +596,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: None,
             thread_count: 2,
+            codec_options: None,
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let mut video_decoder = match decoder {
@@ -573,6 +633,7 @@ mod tests {
         let decoder_options = DecoderOptions {
             codec: None,
             thread_count: 2,
+            codec_options: None,
         };
         let decoder = Decoder::with_options(&stream, decoder_options).expect("Failed to create Decoder");
         let mut audio_decoder = match decoder {
@@ -641,4 +702,122 @@ mod tests {
         insta::assert_debug_snapshot!("test_decoder_video", video_frames);
         insta::assert_debug_snapshot!("test_decoder_audio", audio_frames);
     }
+
+    #[test]
+    fn test_decoder_color_metadata() {
+        let valid_file_path = "../../assets/avc_aac_large.mp4";
+        let mut input = Input::open(valid_file_path).expect("Failed to open valid file");
+        let streams = input.streams();
+        let video_stream = streams.best(AVMediaType::Video).expect("No video stream found");
+        let codec_parameters = video_stream.codec_parameters().expect("Missing codec parameters");
+        let primaries = AVColorPrimaries::from(codec_parameters.color_primaries);
+        let range = AVColorRange::from(codec_parameters.color_range);
+        let mut video_decoder = Decoder::new(&video_stream)
+            .expect("Failed to create decoder")
+            .video()
+            .expect("Failed to get video decoder");
+
+        let video_stream_index = video_stream.index();
+        let frame = loop {
+            let packet = input
+                .receive_packet()
+                .expect("Failed to receive packet")
+                .expect("No video frame decoded");
+            if packet.stream_index() != video_stream_index {
+                continue;
+            }
+
+            video_decoder.send_packet(&packet).expect("Failed to send packet");
+            if let Some(frame) = video_decoder.receive_frame().expect("Failed to receive frame") {
+                break frame;
+            }
+        };
+
+        assert_eq!(frame.color_primaries(), primaries);
+        assert_eq!(frame.color_range(), range);
+
+        let mut new_frame = VideoFrame::new().expect("Failed to create frame");
+        assert_eq!(new_frame.color_range(), AVColorRange::Unspecified);
+
+        new_frame.set_colorspace(frame.colorspace());
+        new_frame.set_color_range(frame.color_range());
+        new_frame.set_color_primaries(frame.color_primaries());
+        new_frame.set_color_trc(frame.color_trc());
+        assert_eq!(new_frame.colorspace(), frame.colorspace());
+        assert_eq!(new_frame.color_range(), range);
+        assert_eq!(new_frame.color_primaries(), primaries);
+        assert_eq!(new_frame.color_trc(), frame.color_trc());
+
+        new_frame.set_colorspace(AVColorSpace::Bt2020Ncl);
+        new_frame.set_color_range(AVColorRange::Jpeg);
+        new_frame.set_color_primaries(AVColorPrimaries::Bt2020);
+        new_frame.set_color_trc(AVColorTransferCharacteristic::Smpte2084);
+        assert_eq!(new_frame.colorspace(), AVColorSpace::Bt2020Ncl);
+        assert_eq!(new_frame.color_range(), AVColorRange::Jpeg);
+        assert_eq!(new_frame.color_primaries(), AVColorPrimaries::Bt2020);
+        assert_eq!(new_frame.color_trc(), AVColorTransferCharacteristic::Smpte2084);
+    }
+
+    #[test]
+    fn test_decoder_codec_options() {
+        let codec = DecoderCodec::new(AVCodecID::H264).expect("Failed to find H264 codec");
+        let options = codec.options();
+
+        let skip_frame = options
+            .iter()
+            .find(|o| o.name == "skip_frame")
+            .expect("Expected a skip_frame option");
+        assert!(skip_frame.constants.iter().any(|c| c == "nonkey"));
+        // The private options of the h264 decoder follow the generic ones.
+        assert!(options.iter().any(|o| o.name == "is_avc"));
+        // Encoding only options are not listed.
+        assert!(!options.iter().any(|o| o.name == "b"));
+    }
+
+    #[test]
+    fn test_decoder_skip_frame() {
+        fn decode_video_frames(codec_options: Option<Dictionary>) -> usize {
+            let mut input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open valid file");
+            let streams = input.streams();
+            let video_stream = streams.best(AVMediaType::Video).expect("No video stream found");
+            let video_stream_index = video_stream.index();
+            let options = DecoderOptions {
+                codec_options,
+                ..Default::default()
+            };
+            let mut decoder = Decoder::with_options(&video_stream, options)
+                .expect("Failed to create decoder")
+                .video()
+                .expect("Failed to get video decoder");
+
+            let mut frames = 0;
+            while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+                if packet.stream_index() == video_stream_index {
+                    decoder.send_packet(&packet).expect("Failed to send packet");
+                    while decoder.receive_frame().expect("Failed to receive frame").is_some() {
+                        frames += 1;
+                    }
+                }
+            }
+
+            decoder.send_eof().expect("Failed to send eof");
+            while decoder.receive_frame().expect("Failed to receive frame").is_some() {
+                frames += 1;
+            }
+
+            frames
+        }
+
+        let mut codec_options = Dictionary::new();
+        codec_options.set(c"skip_frame", c"nonkey").expect("Failed to set skip_frame");
+
+        let all_frames = decode_video_frames(None);
+        let key_frames = decode_video_frames(Some(codec_options));
+
+        assert!(key_frames > 0, "Expected keyframes to be decoded");
+        assert!(
+            key_frames < all_frames,
+            "Expected fewer frames with skip_frame=nonkey: {key_frames} >= {all_frames}"
+        );
+    }
 }
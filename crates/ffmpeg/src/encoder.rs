@@ -30,16 +30,24 @@ pub struct VideoEncoderSettings {
     height: i32,
     frame_rate: Rational,
     pixel_format: AVPixelFormat,
+    /// The maximum number of frames between keyframes, e.g. the frame rate
+    /// times the segment duration to start every HLS segment with a keyframe.
+    /// Encoders may insert extra keyframes on scene changes.
     gop_size: Option<i32>,
     qmax: Option<i32>,
     qmin: Option<i32>,
+    /// The number of threads the encoder may use, `0` lets FFmpeg pick.
     thread_count: Option<i32>,
+    /// The threading methods the encoder may use, see [`AVThreadType`](crate::AVThreadType).
+    #[builder(into)]
     thread_type: Option<i32>,
     sample_aspect_ratio: Option<Rational>,
     bitrate: Option<i64>,
     rc_min_rate: Option<i64>,
     rc_max_rate: Option<i64>,
     rc_buffer_size: Option<i32>,
+    /// The maximum number of consecutive B-frames, `0` disables B-frames so
+    /// packets come out in presentation order.
     max_b_frames: Option<i32>,
     codec_specific_options: Option<Dictionary>,
     flags: Option<i32>,
@@ -86,7 +94,10 @@ pub struct AudioEncoderSettings {
     sample_rate: i32,
     ch_layout: AudioChannelLayout,
     sample_fmt: AVSampleFormat,
+    /// The number of threads the encoder may use, `0` lets FFmpeg pick.
     thread_count: Option<i32>,
+    /// The threading methods the encoder may use, see [`AVThreadType`](crate::AVThreadType).
+    #[builder(into)]
     thread_type: Option<i32>,
     bitrate: Option<i64>,
     rc_min_rate: Option<i64>,
@@ -278,6 +289,34 @@ impl Encoder {
         }
     }
 
+    /// Sends an EOF frame to the encoder and returns an iterator over all the
+    /// remaining packets.
+    ///
+    /// The iterator stops after the last packet or after the first error.
+    pub fn finish(&mut self) -> impl Iterator<Item = Result<Packet, FfmpegError>> + '_ {
+        // Once draining has started ffmpeg answers another EOF with `Eof`, the
+        // remaining packets can still be received in that case.
+        let mut eof = Some(match self.send_eof() {
+            Err(FfmpegError::Code(FfmpegErrorCode::Eof)) => Ok(()),
+            result => result,
+        });
+        let mut done = false;
+
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            let result = match eof.take() {
+                Some(Err(err)) => Err(err),
+                _ => self.receive_packet(),
+            };
+
+            done = !matches!(result, Ok(Some(_)));
+            result.transpose()
+        })
+    }
+
     /// Returns the stream index of the encoder.
     pub const fn stream_index(&self) -> i32 {
         self.stream_index
@@ -311,7 +350,8 @@ mod tests {
     use crate::ffi::AVCodecContext;
     use crate::io::{Input, Output, OutputOptions};
     use crate::rational::Rational;
-    use crate::{AVChannelOrder, AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat};
+    use crate::test_util::encode_video;
+    use crate::{AVChannelOrder, AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat, AVThreadType};
 
     #[test]
     fn test_video_encoder_apply() {
@@ -754,6 +794,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encoder_thread_settings() {
+        let codec = EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder");
+        let data = std::io::Cursor::new(Vec::new());
+        let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+        let mut output = Output::new(data, options).expect("Failed to create Output");
+        let video_settings = VideoEncoderSettings::builder()
+            .width(640)
+            .height(480)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .thread_count(2)
+            .thread_type(AVThreadType::Slice)
+            .build();
+        let time_base = AVRational { num: 1, den: 30 };
+        let encoder =
+            Encoder::new(codec, &mut output, time_base, time_base, video_settings).expect("Failed to create encoder");
+
+        let context = encoder.encoder.as_deref_except();
+        assert_eq!(context.thread_count, 2);
+        assert_eq!(AVThreadType(context.thread_type), AVThreadType::Slice);
+    }
+
     #[test]
     fn test_encoder_encode_video() {
         let mut input = Input::open("../../assets/avc_aac.mp4").expect("Failed to open input file");
@@ -834,6 +897,79 @@ mod tests {
         insta::assert_debug_snapshot!("test_encoder_encode_video", &boxes);
     }
 
+    #[test]
+    fn test_encoder_finish() {
+        let mut packets = 0;
+        let (mut encoder, frames) = encode_video(
+            |decoder| {
+                VideoEncoderSettings::builder()
+                    .width(decoder.width())
+                    .height(decoder.height())
+                    .frame_rate(decoder.frame_rate())
+                    .pixel_format(decoder.pixel_format())
+                    .max_b_frames(2)
+                    .build()
+            },
+            |_| packets += 1,
+        );
+
+        let remaining = encoder
+            .finish()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("Failed to drain encoder");
+
+        assert!(!remaining.is_empty(), "expected trailing packets after EOF");
+        assert_eq!(packets + remaining.len(), frames);
+        assert!(encoder.finish().next().is_none());
+    }
+
+    #[test]
+    fn test_encoder_gop_size() {
+        const GOP_SIZE: usize = 30;
+
+        let mut keyframes = Vec::new();
+        let mut packets = 0;
+        let (mut encoder, _) = encode_video(
+            |decoder| {
+                // Keyframes on scene changes would make the interval irregular.
+                let mut options = Dictionary::new();
+                options
+                    .set(c"sc_threshold", c"1000000000")
+                    .expect("Failed to set sc_threshold");
+
+                VideoEncoderSettings::builder()
+                    .width(decoder.width())
+                    .height(decoder.height())
+                    .frame_rate(decoder.frame_rate())
+                    .pixel_format(decoder.pixel_format())
+                    .gop_size(GOP_SIZE as i32)
+                    .max_b_frames(0)
+                    .codec_specific_options(options)
+                    .build()
+            },
+            |packet| {
+                if packet.is_key() {
+                    keyframes.push(packets);
+                }
+                packets += 1;
+            },
+        );
+
+        for packet in encoder.finish() {
+            if packet.expect("Failed to drain encoder").is_key() {
+                keyframes.push(packets);
+            }
+            packets += 1;
+        }
+
+        assert!(packets > GOP_SIZE * 2, "the input is too short: {packets} packets");
+        assert_eq!(keyframes.first(), Some(&0));
+        for interval in keyframes.windows(2).map(|w| w[1] - w[0]) {
+            assert!(interval <= GOP_SIZE, "keyframes {keyframes:?}");
+        }
+        assert!(keyframes.len() >= packets.div_ceil(GOP_SIZE), "keyframes {keyframes:?}");
+    }
+
     /// make sure [#248](https://github.com/ScuffleCloud/scuffle/pull/248) doesn't happen again
     #[test]
     fn test_pr_248() {
@@ -1,4 +1,4 @@
-use nutype_enum::nutype_enum;
+use nutype_enum::{display_enum, nutype_enum};
 
 use crate::ffi::*;
 
@@ -2037,6 +2037,8 @@ nutype_enum! {
     }
 }
 
+display_enum!(AVCodecID);
+
 impl PartialEq<i32> for AVCodecID {
     fn eq(&self, other: &i32) -> bool {
         self.0 == *other
@@ -2054,3 +2056,16 @@ impl From<AVCodecID> for crate::ffi::AVCodecID {
         value.0 as crate::ffi::AVCodecID
     }
 }
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::AVCodecID;
+
+    #[test]
+    fn test_codec_id_display() {
+        assert_eq!(format!("{}", AVCodecID::H264), "H264");
+        assert_eq!(format!("{}", AVCodecID(-1234)), "-1234");
+        assert_eq!(format!("{:?}", AVCodecID::H264), "AVCodecID::H264");
+    }
+}
@@ -0,0 +1,92 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// Color primaries used in FFmpeg's `AVColorPrimaries`.
+    ///
+    /// The primaries define the chromaticity of the red, green and blue
+    /// components and the white point, matching ISO/IEC 23091-2 (H.273).
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorPrimaries(i32) {
+        /// Reserved value.
+        /// - **Equivalent to**: `AVCOL_PRI_RESERVED0`
+        Reserved0 = AVCOL_PRI_RESERVED0 as i32,
+
+        /// **BT.709**, also used by sRGB and most HD content.
+        /// - **Equivalent to**: `AVCOL_PRI_BT709`
+        Bt709 = AVCOL_PRI_BT709 as i32,
+
+        /// The primaries are not specified.
+        /// - **Equivalent to**: `AVCOL_PRI_UNSPECIFIED`
+        Unspecified = AVCOL_PRI_UNSPECIFIED as i32,
+
+        /// Reserved value.
+        /// - **Equivalent to**: `AVCOL_PRI_RESERVED`
+        Reserved = AVCOL_PRI_RESERVED as i32,
+
+        /// **BT.470 System M** (FCC NTSC).
+        /// - **Equivalent to**: `AVCOL_PRI_BT470M`
+        Bt470M = AVCOL_PRI_BT470M as i32,
+
+        /// **BT.470 System B, G** (PAL / SECAM, BT.601-625).
+        /// - **Equivalent to**: `AVCOL_PRI_BT470BG`
+        Bt470Bg = AVCOL_PRI_BT470BG as i32,
+
+        /// **SMPTE 170M** (NTSC, BT.601-525).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE170M`
+        Smpte170M = AVCOL_PRI_SMPTE170M as i32,
+
+        /// **SMPTE 240M**, functionally identical to SMPTE 170M.
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE240M`
+        Smpte240M = AVCOL_PRI_SMPTE240M as i32,
+
+        /// Generic film with a colour filter using Illuminant C.
+        /// - **Equivalent to**: `AVCOL_PRI_FILM`
+        Film = AVCOL_PRI_FILM as i32,
+
+        /// **BT.2020** / BT.2100, used for UHD and HDR content.
+        /// - **Equivalent to**: `AVCOL_PRI_BT2020`
+        Bt2020 = AVCOL_PRI_BT2020 as i32,
+
+        /// **SMPTE ST 428-1** (CIE 1931 XYZ).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE428`
+        Smpte428 = AVCOL_PRI_SMPTE428 as i32,
+
+        /// **SMPTE ST 431-2** (DCI-P3).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE431`
+        Smpte431 = AVCOL_PRI_SMPTE431 as i32,
+
+        /// **SMPTE ST 432-1** (Display P3).
+        /// - **Equivalent to**: `AVCOL_PRI_SMPTE432`
+        Smpte432 = AVCOL_PRI_SMPTE432 as i32,
+
+        /// **EBU Tech. 3213-E**.
+        /// - **Equivalent to**: `AVCOL_PRI_EBU3213`
+        Ebu3213 = AVCOL_PRI_EBU3213 as i32,
+
+        /// Not part of the ABI, the number of primaries.
+        /// - **Equivalent to**: `AVCOL_PRI_NB`
+        Nb = AVCOL_PRI_NB as i32,
+    }
+}
+
+impl PartialEq<i32> for AVColorPrimaries {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorPrimaries {
+    fn from(value: u32) -> Self {
+        AVColorPrimaries(value as i32)
+    }
+}
+
+impl From<AVColorPrimaries> for u32 {
+    fn from(value: AVColorPrimaries) -> Self {
+        value.0 as u32
+    }
+}
@@ -0,0 +1,48 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// Color ranges used in FFmpeg's `AVColorRange`.
+    ///
+    /// The range defines which sample values are used to represent black and
+    /// white.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorRange(i32) {
+        /// The range is not specified.
+        /// - **Equivalent to**: `AVCOL_RANGE_UNSPECIFIED`
+        Unspecified = AVCOL_RANGE_UNSPECIFIED as i32,
+
+        /// **Limited range** ("TV"), e.g. 16-235 for 8-bit luma.
+        /// - **Equivalent to**: `AVCOL_RANGE_MPEG`
+        Mpeg = AVCOL_RANGE_MPEG as i32,
+
+        /// **Full range** ("PC"), e.g. 0-255 for 8-bit samples.
+        /// - **Equivalent to**: `AVCOL_RANGE_JPEG`
+        Jpeg = AVCOL_RANGE_JPEG as i32,
+
+        /// Not part of the ABI, the number of ranges.
+        /// - **Equivalent to**: `AVCOL_RANGE_NB`
+        Nb = AVCOL_RANGE_NB as i32,
+    }
+}
+
+impl PartialEq<i32> for AVColorRange {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorRange {
+    fn from(value: u32) -> Self {
+        AVColorRange(value as i32)
+    }
+}
+
+impl From<AVColorRange> for u32 {
+    fn from(value: AVColorRange) -> Self {
+        value.0 as u32
+    }
+}
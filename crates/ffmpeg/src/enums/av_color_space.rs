@@ -0,0 +1,96 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// Color spaces used in FFmpeg's `AVColorSpace`.
+    ///
+    /// The color space defines the matrix coefficients used to derive luma and
+    /// chroma from the RGB primaries, matching ISO/IEC 23091-2 (H.273).
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorSpace(i32) {
+        /// **RGB** / GBR, the identity matrix (also used for XYZ).
+        /// - **Equivalent to**: `AVCOL_SPC_RGB`
+        Rgb = AVCOL_SPC_RGB as i32,
+
+        /// **BT.709**.
+        /// - **Equivalent to**: `AVCOL_SPC_BT709`
+        Bt709 = AVCOL_SPC_BT709 as i32,
+
+        /// The color space is not specified.
+        /// - **Equivalent to**: `AVCOL_SPC_UNSPECIFIED`
+        Unspecified = AVCOL_SPC_UNSPECIFIED as i32,
+
+        /// Reserved value.
+        /// - **Equivalent to**: `AVCOL_SPC_RESERVED`
+        Reserved = AVCOL_SPC_RESERVED as i32,
+
+        /// **FCC Title 47 Code of Federal Regulations 73.682 (a)(20)**.
+        /// - **Equivalent to**: `AVCOL_SPC_FCC`
+        Fcc = AVCOL_SPC_FCC as i32,
+
+        /// **BT.470 System B, G** (PAL / SECAM, BT.601-625).
+        /// - **Equivalent to**: `AVCOL_SPC_BT470BG`
+        Bt470Bg = AVCOL_SPC_BT470BG as i32,
+
+        /// **SMPTE 170M** (NTSC, BT.601-525).
+        /// - **Equivalent to**: `AVCOL_SPC_SMPTE170M`
+        Smpte170M = AVCOL_SPC_SMPTE170M as i32,
+
+        /// **SMPTE 240M**.
+        /// - **Equivalent to**: `AVCOL_SPC_SMPTE240M`
+        Smpte240M = AVCOL_SPC_SMPTE240M as i32,
+
+        /// **YCgCo**.
+        /// - **Equivalent to**: `AVCOL_SPC_YCGCO`
+        YCgCo = AVCOL_SPC_YCGCO as i32,
+
+        /// **BT.2020** non-constant luminance.
+        /// - **Equivalent to**: `AVCOL_SPC_BT2020_NCL`
+        Bt2020Ncl = AVCOL_SPC_BT2020_NCL as i32,
+
+        /// **BT.2020** constant luminance.
+        /// - **Equivalent to**: `AVCOL_SPC_BT2020_CL`
+        Bt2020Cl = AVCOL_SPC_BT2020_CL as i32,
+
+        /// **SMPTE ST 2085** (Y'D'zD'x).
+        /// - **Equivalent to**: `AVCOL_SPC_SMPTE2085`
+        Smpte2085 = AVCOL_SPC_SMPTE2085 as i32,
+
+        /// Chromaticity-derived non-constant luminance.
+        /// - **Equivalent to**: `AVCOL_SPC_CHROMA_DERIVED_NCL`
+        ChromaDerivedNcl = AVCOL_SPC_CHROMA_DERIVED_NCL as i32,
+
+        /// Chromaticity-derived constant luminance.
+        /// - **Equivalent to**: `AVCOL_SPC_CHROMA_DERIVED_CL`
+        ChromaDerivedCl = AVCOL_SPC_CHROMA_DERIVED_CL as i32,
+
+        /// **ICtCp** from BT.2100.
+        /// - **Equivalent to**: `AVCOL_SPC_ICTCP`
+        Ictcp = AVCOL_SPC_ICTCP as i32,
+
+        /// Not part of the ABI, the number of color spaces.
+        /// - **Equivalent to**: `AVCOL_SPC_NB`
+        Nb = AVCOL_SPC_NB as i32,
+    }
+}
+
+impl PartialEq<i32> for AVColorSpace {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorSpace {
+    fn from(value: u32) -> Self {
+        AVColorSpace(value as i32)
+    }
+}
+
+impl From<AVColorSpace> for u32 {
+    fn from(value: AVColorSpace) -> Self {
+        value.0 as u32
+    }
+}
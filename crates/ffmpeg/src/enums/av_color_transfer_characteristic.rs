@@ -0,0 +1,112 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// Transfer characteristics used in FFmpeg's `AVColorTransferCharacteristic`.
+    ///
+    /// The transfer characteristic is the opto-electronic transfer function used
+    /// to encode the samples, matching ISO/IEC 23091-2 (H.273).
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/pixfmt_8h.html>
+    pub enum AVColorTransferCharacteristic(i32) {
+        /// Reserved value.
+        /// - **Equivalent to**: `AVCOL_TRC_RESERVED0`
+        Reserved0 = AVCOL_TRC_RESERVED0 as i32,
+
+        /// **BT.709**.
+        /// - **Equivalent to**: `AVCOL_TRC_BT709`
+        Bt709 = AVCOL_TRC_BT709 as i32,
+
+        /// The transfer characteristic is not specified.
+        /// - **Equivalent to**: `AVCOL_TRC_UNSPECIFIED`
+        Unspecified = AVCOL_TRC_UNSPECIFIED as i32,
+
+        /// Reserved value.
+        /// - **Equivalent to**: `AVCOL_TRC_RESERVED`
+        Reserved = AVCOL_TRC_RESERVED as i32,
+
+        /// **BT.470 System M** (assumed display gamma 2.2).
+        /// - **Equivalent to**: `AVCOL_TRC_GAMMA22`
+        Gamma22 = AVCOL_TRC_GAMMA22 as i32,
+
+        /// **BT.470 System B, G** (assumed display gamma 2.8).
+        /// - **Equivalent to**: `AVCOL_TRC_GAMMA28`
+        Gamma28 = AVCOL_TRC_GAMMA28 as i32,
+
+        /// **SMPTE 170M**, functionally identical to BT.709.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE170M`
+        Smpte170M = AVCOL_TRC_SMPTE170M as i32,
+
+        /// **SMPTE 240M**.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE240M`
+        Smpte240M = AVCOL_TRC_SMPTE240M as i32,
+
+        /// Linear transfer characteristic.
+        /// - **Equivalent to**: `AVCOL_TRC_LINEAR`
+        Linear = AVCOL_TRC_LINEAR as i32,
+
+        /// Logarithmic transfer characteristic (100:1 range).
+        /// - **Equivalent to**: `AVCOL_TRC_LOG`
+        Log = AVCOL_TRC_LOG as i32,
+
+        /// Logarithmic transfer characteristic (100 * Sqrt(10) : 1 range).
+        /// - **Equivalent to**: `AVCOL_TRC_LOG_SQRT`
+        LogSqrt = AVCOL_TRC_LOG_SQRT as i32,
+
+        /// **IEC 61966-2-4** (xvYCC).
+        /// - **Equivalent to**: `AVCOL_TRC_IEC61966_2_4`
+        Iec61966_2_4 = AVCOL_TRC_IEC61966_2_4 as i32,
+
+        /// **BT.1361** extended colour gamut system.
+        /// - **Equivalent to**: `AVCOL_TRC_BT1361_ECG`
+        Bt1361Ecg = AVCOL_TRC_BT1361_ECG as i32,
+
+        /// **IEC 61966-2-1** (sRGB / sYCC).
+        /// - **Equivalent to**: `AVCOL_TRC_IEC61966_2_1`
+        Iec61966_2_1 = AVCOL_TRC_IEC61966_2_1 as i32,
+
+        /// **BT.2020** for 10-bit systems.
+        /// - **Equivalent to**: `AVCOL_TRC_BT2020_10`
+        Bt2020_10 = AVCOL_TRC_BT2020_10 as i32,
+
+        /// **BT.2020** for 12-bit systems.
+        /// - **Equivalent to**: `AVCOL_TRC_BT2020_12`
+        Bt2020_12 = AVCOL_TRC_BT2020_12 as i32,
+
+        /// **SMPTE ST 2084** (PQ), used for HDR10.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE2084`
+        Smpte2084 = AVCOL_TRC_SMPTE2084 as i32,
+
+        /// **SMPTE ST 428-1**.
+        /// - **Equivalent to**: `AVCOL_TRC_SMPTE428`
+        Smpte428 = AVCOL_TRC_SMPTE428 as i32,
+
+        /// **ARIB STD-B67** (HLG).
+        /// - **Equivalent to**: `AVCOL_TRC_ARIB_STD_B67`
+        AribStdB67 = AVCOL_TRC_ARIB_STD_B67 as i32,
+
+        /// Not part of the ABI, the number of transfer characteristics.
+        /// - **Equivalent to**: `AVCOL_TRC_NB`
+        Nb = AVCOL_TRC_NB as i32,
+    }
+}
+
+impl PartialEq<i32> for AVColorTransferCharacteristic {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
+
+impl From<u32> for AVColorTransferCharacteristic {
+    fn from(value: u32) -> Self {
+        AVColorTransferCharacteristic(value as i32)
+    }
+}
+
+impl From<AVColorTransferCharacteristic> for u32 {
+    fn from(value: AVColorTransferCharacteristic) -> Self {
+        value.0 as u32
+    }
+}
@@ -0,0 +1,78 @@
+use nutype_enum::nutype_enum;
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// Value types of FFmpeg's `AVOption`.
+    ///
+    /// The type decides how the string value of an option is parsed when it is
+    /// set through a [`Dictionary`](crate::dict::Dictionary).
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/group__avoptions.html>
+    pub enum AVOptionType(u32) {
+        /// A set of flags, combined with `+` (e.g. `frag_keyframe+empty_moov`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_FLAGS`
+        Flags = AV_OPT_TYPE_FLAGS,
+        /// A 32-bit integer.
+        /// - **Equivalent to**: `AV_OPT_TYPE_INT`
+        Int = AV_OPT_TYPE_INT,
+        /// A 64-bit integer.
+        /// - **Equivalent to**: `AV_OPT_TYPE_INT64`
+        Int64 = AV_OPT_TYPE_INT64,
+        /// A double precision float.
+        /// - **Equivalent to**: `AV_OPT_TYPE_DOUBLE`
+        Double = AV_OPT_TYPE_DOUBLE,
+        /// A single precision float.
+        /// - **Equivalent to**: `AV_OPT_TYPE_FLOAT`
+        Float = AV_OPT_TYPE_FLOAT,
+        /// A string.
+        /// - **Equivalent to**: `AV_OPT_TYPE_STRING`
+        String = AV_OPT_TYPE_STRING,
+        /// A rational number (e.g. `30000/1001`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_RATIONAL`
+        Rational = AV_OPT_TYPE_RATIONAL,
+        /// Binary data, set as a hex string.
+        /// - **Equivalent to**: `AV_OPT_TYPE_BINARY`
+        Binary = AV_OPT_TYPE_BINARY,
+        /// A dictionary, set as `key=value:key=value`.
+        /// - **Equivalent to**: `AV_OPT_TYPE_DICT`
+        Dict = AV_OPT_TYPE_DICT,
+        /// An unsigned 64-bit integer.
+        /// - **Equivalent to**: `AV_OPT_TYPE_UINT64`
+        UInt64 = AV_OPT_TYPE_UINT64,
+        /// A named constant for another option.
+        /// - **Equivalent to**: `AV_OPT_TYPE_CONST`
+        Const = AV_OPT_TYPE_CONST,
+        /// An image size (e.g. `1280x720` or `hd720`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_IMAGE_SIZE`
+        ImageSize = AV_OPT_TYPE_IMAGE_SIZE,
+        /// A pixel format name.
+        /// - **Equivalent to**: `AV_OPT_TYPE_PIXEL_FMT`
+        PixelFmt = AV_OPT_TYPE_PIXEL_FMT,
+        /// A sample format name.
+        /// - **Equivalent to**: `AV_OPT_TYPE_SAMPLE_FMT`
+        SampleFmt = AV_OPT_TYPE_SAMPLE_FMT,
+        /// A video frame rate (e.g. `30` or `ntsc`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_VIDEO_RATE`
+        VideoRate = AV_OPT_TYPE_VIDEO_RATE,
+        /// A duration (e.g. `2.5` or `00:00:02.500`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_DURATION`
+        Duration = AV_OPT_TYPE_DURATION,
+        /// A color (e.g. `red` or `0xff0000`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_COLOR`
+        Color = AV_OPT_TYPE_COLOR,
+        /// A boolean.
+        /// - **Equivalent to**: `AV_OPT_TYPE_BOOL`
+        Bool = AV_OPT_TYPE_BOOL,
+        /// A channel layout (e.g. `stereo`).
+        /// - **Equivalent to**: `AV_OPT_TYPE_CHLAYOUT`
+        ChLayout = AV_OPT_TYPE_CHLAYOUT,
+    }
+}
+
+impl PartialEq<u32> for AVOptionType {
+    fn eq(&self, other: &u32) -> bool {
+        self.0 == *other
+    }
+}
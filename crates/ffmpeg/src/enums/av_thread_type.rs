@@ -0,0 +1,34 @@
+use nutype_enum::{bitwise_enum, nutype_enum};
+
+use crate::ffi::*;
+
+nutype_enum! {
+    /// Threading methods used in FFmpeg's `AVCodecContext::thread_type`.
+    ///
+    /// These flags select how a codec may split work across threads, they can be
+    /// combined to let the codec pick the method it supports.
+    ///
+    /// See the official FFmpeg documentation:
+    /// <https://ffmpeg.org/doxygen/trunk/avcodec_8h.html>
+    pub enum AVThreadType(i32) {
+        /// Decode or encode **more than one frame at once**.
+        /// - **Used for**: Higher throughput at the cost of one frame of delay per thread.
+        /// - **Binary representation**: `0b01`
+        /// - **Equivalent to**: `FF_THREAD_FRAME`
+        Frame = FF_THREAD_FRAME as i32,
+
+        /// Decode or encode **more than one part of a single frame at once**.
+        /// - **Used for**: Multithreading without adding delay.
+        /// - **Binary representation**: `0b10`
+        /// - **Equivalent to**: `FF_THREAD_SLICE`
+        Slice = FF_THREAD_SLICE as i32,
+    }
+}
+
+bitwise_enum!(AVThreadType);
+
+impl PartialEq<i32> for AVThreadType {
+    fn eq(&self, other: &i32) -> bool {
+        self.0 == *other
+    }
+}
@@ -36,3 +36,21 @@ pub use av_pkt_flags::*;
 
 mod av_discard;
 pub use av_discard::*;
+
+mod av_thread_type;
+pub use av_thread_type::*;
+
+mod av_color_primaries;
+pub use av_color_primaries::*;
+
+mod av_color_transfer_characteristic;
+pub use av_color_transfer_characteristic::*;
+
+mod av_color_space;
+pub use av_color_space::*;
+
+mod av_color_range;
+pub use av_color_range::*;
+
+mod av_option_type;
+pub use av_option_type::*;
@@ -29,6 +29,19 @@ pub enum FfmpegError {
     /// An error that occurs when the arguments are invalid.
     #[error("invalid arguments: {0}")]
     Arguments(&'static str),
+    /// An error that occurs when the codec id is not known to ffmpeg.
+    #[error("unknown codec id: {}", .0.0)]
+    UnknownCodec(crate::AVCodecID),
+    /// An error that occurs when the codec is known to ffmpeg but this build
+    /// has no decoder for it, e.g. because the library providing it was not
+    /// enabled at configure time.
+    #[error("no decoder for {0} is compiled into this ffmpeg build")]
+    DecoderNotCompiled(&'static str),
+    /// An error that occurs when the codec is known to ffmpeg but this build
+    /// has no encoder for it, e.g. because the library providing it was not
+    /// enabled at configure time.
+    #[error("no encoder for {0} is compiled into this ffmpeg build")]
+    EncoderNotCompiled(&'static str),
 }
 
 nutype_enum! {
@@ -241,6 +254,18 @@ mod tests {
                 FfmpegError::Arguments("invalid argument example"),
                 "invalid arguments: invalid argument example",
             ),
+            (
+                FfmpegError::UnknownCodec(crate::AVCodecID(999_999)),
+                "unknown codec id: 999999",
+            ),
+            (
+                FfmpegError::DecoderNotCompiled("a64_multi"),
+                "no decoder for a64_multi is compiled into this ffmpeg build",
+            ),
+            (
+                FfmpegError::EncoderNotCompiled("vp6"),
+                "no encoder for vp6 is compiled into this ffmpeg build",
+            ),
         ];
 
         for (error, expected) in cases {
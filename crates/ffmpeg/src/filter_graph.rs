@@ -138,7 +138,7 @@ unsafe impl Send for FilterGraphParser<'_> {}
 
 impl<'a> FilterGraphParser<'a> {
     /// Create a new `FilterGraphParser`.
-    fn new(graph: &'a mut FilterGraph) -> Self {
+    pub(crate) fn new(graph: &'a mut FilterGraph) -> Self {
         Self {
             graph,
             // Safety: 'avfilter_inout_free' is safe to call with a null pointer, and the pointer is valid
@@ -3,7 +3,7 @@ use crate::ffi::*;
 use crate::rational::Rational;
 use crate::smart_object::{SmartObject, SmartPtr};
 use crate::utils::{check_i64, or_nopts};
-use crate::{AVPictureType, AVPixelFormat};
+use crate::{AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic, AVPictureType, AVPixelFormat};
 
 /// A frame. Thin wrapper around [`AVFrame`].
 pub struct GenericFrame(SmartPtr<AVFrame>);
@@ -152,6 +152,21 @@ impl GenericFrame {
         self.0.as_deref_except().width != 0
     }
 
+    /// Returns the metadata of the frame, which is owned by the frame.
+    pub(crate) const fn metadata(&self) -> *mut AVDictionary {
+        self.0.as_deref_except().metadata
+    }
+
+    /// Returns the user data of the frame, which is carried along through filters.
+    pub(crate) const fn opaque(&self) -> *mut std::ffi::c_void {
+        self.0.as_deref_except().opaque
+    }
+
+    /// Sets the user data of the frame.
+    pub(crate) const fn set_opaque(&mut self, opaque: *mut std::ffi::c_void) {
+        self.0.as_deref_mut_except().opaque = opaque;
+    }
+
     /// Returns the linesize of the frame.
     pub(crate) const fn linesize(&self, index: usize) -> Option<i32> {
         if index >= self.0.as_deref_except().linesize.len() {
@@ -228,6 +243,46 @@ impl VideoFrame {
         self.0 .0.as_deref_mut_except().pict_type = pict_type.0 as u32;
     }
 
+    /// Returns the color space (matrix coefficients) of the frame.
+    pub const fn colorspace(&self) -> AVColorSpace {
+        AVColorSpace(self.0 .0.as_deref_except().colorspace as i32)
+    }
+
+    /// Sets the color space (matrix coefficients) of the frame.
+    pub const fn set_colorspace(&mut self, colorspace: AVColorSpace) {
+        self.0 .0.as_deref_mut_except().colorspace = colorspace.0 as u32;
+    }
+
+    /// Returns the color range of the frame.
+    pub const fn color_range(&self) -> AVColorRange {
+        AVColorRange(self.0 .0.as_deref_except().color_range as i32)
+    }
+
+    /// Sets the color range of the frame.
+    pub const fn set_color_range(&mut self, color_range: AVColorRange) {
+        self.0 .0.as_deref_mut_except().color_range = color_range.0 as u32;
+    }
+
+    /// Returns the color primaries of the frame.
+    pub const fn color_primaries(&self) -> AVColorPrimaries {
+        AVColorPrimaries(self.0 .0.as_deref_except().color_primaries as i32)
+    }
+
+    /// Sets the color primaries of the frame.
+    pub const fn set_color_primaries(&mut self, color_primaries: AVColorPrimaries) {
+        self.0 .0.as_deref_mut_except().color_primaries = color_primaries.0 as u32;
+    }
+
+    /// Returns the transfer characteristic of the frame.
+    pub const fn color_trc(&self) -> AVColorTransferCharacteristic {
+        AVColorTransferCharacteristic(self.0 .0.as_deref_except().color_trc as i32)
+    }
+
+    /// Sets the transfer characteristic of the frame.
+    pub const fn set_color_trc(&mut self, color_trc: AVColorTransferCharacteristic) {
+        self.0 .0.as_deref_mut_except().color_trc = color_trc.0 as u32;
+    }
+
     /// Returns the data of the frame. By specifying the index of the plane.
     pub fn data(&self, index: usize) -> Option<&[u8]> {
         let line = self.linesize(index)? as usize;
@@ -252,6 +307,102 @@ impl VideoFrame {
     pub const fn format(&self) -> AVPixelFormat {
         AVPixelFormat(self.0 .0.as_deref_except().format)
     }
+
+    /// Copies the pixel data of the frame into a tightly packed buffer.
+    ///
+    /// The planes are written one after another, without any padding at the
+    /// end of each line. The size of each plane takes the chroma subsampling
+    /// of the pixel format into account, e.g. for `yuv420p` the buffer
+    /// contains `width * height` luma bytes followed by two chroma planes
+    /// which are each a quarter of that size.
+    pub fn to_packed_bytes(&self) -> Result<Vec<u8>, FfmpegError> {
+        let frame = self.0 .0.as_deref_except();
+
+        // Safety: `av_image_get_buffer_size` is safe to call with any arguments.
+        let size = FfmpegErrorCode(unsafe { av_image_get_buffer_size(frame.format, frame.width, frame.height, 1) })
+            .result()? as usize;
+
+        let mut buffer = vec![0; size];
+
+        // Safety: The buffer is `size` bytes long, which is the size required for the
+        // image. The data pointers and line sizes are owned by the frame and describe
+        // an image with the given format and dimensions.
+        FfmpegErrorCode(unsafe {
+            av_image_copy_to_buffer(
+                buffer.as_mut_ptr(),
+                size as i32,
+                frame.data.as_ptr() as *const *const u8,
+                frame.linesize.as_ptr(),
+                frame.format,
+                frame.width,
+                frame.height,
+                1,
+            )
+        })
+        .result()?;
+
+        Ok(buffer)
+    }
+
+    /// Creates a frame from tightly packed pixel data, in the layout produced
+    /// by [`VideoFrame::to_packed_bytes`].
+    ///
+    /// Returns an error if the length of `data` does not match the size of an
+    /// image with the given format and dimensions.
+    pub fn from_packed_bytes(format: AVPixelFormat, width: usize, height: usize, data: &[u8]) -> Result<Self, FfmpegError> {
+        let mut frame = VideoFrame::new()?;
+        frame.set_format(format.into());
+        frame.set_width(width);
+        frame.set_height(height);
+
+        let (width, height) = (width as i32, height as i32);
+
+        // Safety: `av_image_get_buffer_size` is safe to call with any arguments.
+        let size = FfmpegErrorCode(unsafe { av_image_get_buffer_size(format.into(), width, height, 1) }).result()?;
+        if data.len() != size as usize {
+            return Err(FfmpegError::Arguments("data does not match the size of the image"));
+        }
+
+        // Safety: The format, width and height have been set, and the buffer is fully
+        // initialized by the copy below.
+        unsafe { frame.alloc_frame_buffer(None) }?;
+
+        let mut src_data = [std::ptr::null_mut(); 4];
+        let mut src_linesize = [0; 4];
+
+        // Safety: This only computes the plane pointers into `data`, which was checked
+        // to be large enough for the image above. The pointers are only ever read from.
+        FfmpegErrorCode(unsafe {
+            av_image_fill_arrays(
+                src_data.as_mut_ptr(),
+                src_linesize.as_mut_ptr(),
+                data.as_ptr(),
+                format.into(),
+                width,
+                height,
+                1,
+            )
+        })
+        .result()?;
+
+        let dst = frame.0 .0.as_deref_mut_except();
+
+        // Safety: The destination buffer was allocated for an image with the same format
+        // and dimensions as the source planes.
+        unsafe {
+            av_image_copy(
+                dst.data.as_mut_ptr() as *const *mut u8,
+                dst.linesize.as_ptr(),
+                src_data.as_ptr() as *const *const u8,
+                src_linesize.as_ptr(),
+                format.into(),
+                width,
+                height,
+            )
+        };
+
+        Ok(frame)
+    }
 }
 
 impl std::fmt::Debug for VideoFrame {
@@ -451,7 +602,7 @@ mod tests {
     use crate::ffi::av_frame_get_buffer;
     use crate::frame::{AudioChannelLayout, GenericFrame, VideoFrame};
     use crate::rational::Rational;
-    use crate::{AVChannelOrder, AVPictureType, AVPixelFormat, AVSampleFormat};
+    use crate::{AVChannelOrder, AVMediaType, AVPictureType, AVPixelFormat, AVSampleFormat};
 
     #[test]
     fn test_frame_clone() {
@@ -856,4 +1007,75 @@ mod tests {
         }
         ");
     }
+
+    #[test]
+    fn test_packed_bytes_round_trip() {
+        let mut input = crate::io::Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open file");
+        let streams = input.streams();
+        let stream = streams.best(AVMediaType::Video).expect("No video stream found");
+        let stream_index = stream.index();
+        let mut decoder = crate::decoder::Decoder::new(&stream)
+            .expect("Failed to create decoder")
+            .video()
+            .expect("Failed to get video decoder");
+
+        let frame = loop {
+            let packet = input
+                .receive_packet()
+                .expect("Failed to receive packet")
+                .expect("No frame decoded");
+            if packet.stream_index() != stream_index {
+                continue;
+            }
+
+            decoder.send_packet(&packet).expect("Failed to send packet");
+            if let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+                break frame;
+            }
+        };
+
+        assert_eq!(frame.format(), AVPixelFormat::Yuv420p);
+        let (width, height) = (frame.width(), frame.height());
+
+        let packed = frame.to_packed_bytes().expect("Failed to export frame");
+        // Both chroma planes are subsampled by 2 in each direction.
+        let chroma = width.div_ceil(2) * height.div_ceil(2);
+        assert_eq!(packed.len(), width * height + 2 * chroma);
+
+        let imported =
+            VideoFrame::from_packed_bytes(frame.format(), width, height, &packed).expect("Failed to import frame");
+        assert_eq!(imported.width(), width);
+        assert_eq!(imported.height(), height);
+        assert_eq!(imported.format(), frame.format());
+
+        // Compare every plane row by row, as the line sizes may differ.
+        for (plane, (plane_width, plane_height)) in [
+            (width, height),
+            (width.div_ceil(2), height.div_ceil(2)),
+            (width.div_ceil(2), height.div_ceil(2)),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let original = frame.data(plane).expect("Missing plane");
+            let original_stride = frame.linesize(plane).unwrap() as usize;
+            let copy = imported.data(plane).expect("Missing plane");
+            let copy_stride = imported.linesize(plane).unwrap() as usize;
+
+            for row in 0..plane_height {
+                assert_eq!(
+                    &original[row * original_stride..][..plane_width],
+                    &copy[row * copy_stride..][..plane_width],
+                    "plane {plane} row {row} does not match"
+                );
+            }
+        }
+
+        assert_eq!(imported.to_packed_bytes().expect("Failed to export frame"), packed);
+
+        assert_eq!(
+            VideoFrame::from_packed_bytes(AVPixelFormat::Yuv420p, width, height, &packed[1..]).unwrap_err(),
+            crate::error::FfmpegError::Arguments("data does not match the size of the image")
+        );
+    }
 }
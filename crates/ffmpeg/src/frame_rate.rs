@@ -0,0 +1,282 @@
+use crate::error::FfmpegError;
+use crate::filter_graph::{FilterGraph, FilterGraphParser};
+use crate::frame::{GenericFrame, VideoFrame};
+use crate::rational::Rational;
+
+const SOURCE: &str = "Parsed_buffer_0";
+const SINK: &str = "Parsed_buffersink_2";
+
+/// How many frames a [`FrameRateConverter`] has dropped and duplicated.
+///
+/// While frames are still buffered in the filter the counters lag behind the
+/// output. Once [`FrameRateConverter::send_eof`] has been called and all
+/// frames have been received, `frames_in + duplicated - dropped` is equal to
+/// `frames_out`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameRateStats {
+    /// The number of frames sent to the converter.
+    pub frames_in: u64,
+    /// The number of frames received from the converter.
+    pub frames_out: u64,
+    /// The number of input frames which were never output.
+    pub dropped: u64,
+    /// The number of output frames which repeat the previous frame.
+    pub duplicated: u64,
+}
+
+/// Converts video to a constant frame rate by running it through ffmpeg's
+/// `fps` filter, dropping and duplicating frames as needed.
+///
+/// The filter graph is created from the properties of the first frame, all
+/// frames must have the same size and pixel format. The frames must have a
+/// time base, as set by the decoder. The frames which are output have a time
+/// base of `1 / frame_rate`, each frame is one tick after the previous.
+///
+/// ```rust,no_run
+/// # use scuffle_ffmpeg::decoder::Decoder;
+/// # use scuffle_ffmpeg::frame_rate::FrameRateConverter;
+/// # use scuffle_ffmpeg::io::Input;
+/// # use scuffle_ffmpeg::AVMediaType;
+/// # fn main() -> Result<(), scuffle_ffmpeg::error::FfmpegError> {
+/// let mut input = Input::open("input.mp4")?;
+/// let stream = input.streams().best(AVMediaType::Video).expect("no video stream");
+/// let stream_index = stream.index();
+/// let mut decoder = Decoder::new(&stream)?.video().expect("not a video stream");
+///
+/// let mut converter = FrameRateConverter::new(30.into());
+/// while let Some(packet) = input.receive_packet()? {
+///     if packet.stream_index() == stream_index {
+///         decoder.send_packet(&packet)?;
+///         while let Some(frame) = decoder.receive_frame()? {
+///             converter.send_frame(&frame)?;
+///             while let Some(frame) = converter.receive_frame()? {
+///                 // Encode the frame
+///             }
+///         }
+///     }
+/// }
+///
+/// converter.send_eof()?;
+/// while let Some(frame) = converter.receive_frame()? {
+///     // Encode the frame
+/// }
+///
+/// let stats = converter.stats();
+/// println!("dropped {} and duplicated {} frames", stats.dropped, stats.duplicated);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameRateConverter {
+    frame_rate: Rational,
+    graph: Option<FilterGraph>,
+    stats: FrameRateStats,
+    /// The number of the last input frame which was output, starting at 1.
+    last_output: u64,
+    eof: bool,
+}
+
+impl std::fmt::Debug for FrameRateConverter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameRateConverter")
+            .field("frame_rate", &self.frame_rate)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FrameRateConverter {
+    /// Creates a new converter which outputs the given frame rate.
+    pub const fn new(frame_rate: Rational) -> Self {
+        Self {
+            frame_rate,
+            graph: None,
+            stats: FrameRateStats {
+                frames_in: 0,
+                frames_out: 0,
+                dropped: 0,
+                duplicated: 0,
+            },
+            last_output: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the frame rate the converter outputs.
+    pub const fn frame_rate(&self) -> Rational {
+        self.frame_rate
+    }
+
+    /// Returns the dropped and duplicated frames so far.
+    ///
+    /// Frames still buffered in the filter are not counted as dropped until a
+    /// later frame has been output, the stats are final once
+    /// [`FrameRateConverter::send_eof`] has been called and all frames have
+    /// been received.
+    pub const fn stats(&self) -> FrameRateStats {
+        self.stats
+    }
+
+    /// Sends a frame to be converted.
+    pub fn send_frame(&mut self, frame: &VideoFrame) -> Result<(), FfmpegError> {
+        let graph = match &mut self.graph {
+            Some(graph) => graph,
+            graph => graph.insert(Self::graph(frame, self.frame_rate)?),
+        };
+
+        // The input frames are numbered through the opaque pointer, which the
+        // filter copies into the frames it outputs.
+        let mut frame = frame.clone();
+        self.stats.frames_in += 1;
+        frame.set_opaque(std::ptr::without_provenance_mut(self.stats.frames_in as usize));
+
+        graph.get(SOURCE).ok_or(FfmpegError::NoFilter)?.source().send_frame(&frame)
+    }
+
+    /// Signals the end of the input, so the remaining frames can be received.
+    pub fn send_eof(&mut self) -> Result<(), FfmpegError> {
+        self.eof = true;
+        match &mut self.graph {
+            Some(graph) => graph.get(SOURCE).ok_or(FfmpegError::NoFilter)?.source().send_eof(None),
+            None => Ok(()),
+        }
+    }
+
+    /// Receives a converted frame, returns `None` if more input is needed or
+    /// all frames have been received.
+    pub fn receive_frame(&mut self) -> Result<Option<VideoFrame>, FfmpegError> {
+        let Some(graph) = &mut self.graph else {
+            return Ok(None);
+        };
+
+        let Some(mut frame) = graph.get(SINK).ok_or(FfmpegError::NoFilter)?.sink().receive_frame()? else {
+            if self.eof {
+                // The frames after the last one which was output are never output.
+                self.stats.dropped += self.stats.frames_in - self.last_output;
+                self.last_output = self.stats.frames_in;
+            }
+
+            return Ok(None);
+        };
+
+        let number = frame.opaque().addr() as u64;
+        if number == self.last_output {
+            self.stats.duplicated += 1;
+        } else {
+            self.stats.dropped += number.saturating_sub(self.last_output + 1);
+            self.last_output = number;
+        }
+
+        self.stats.frames_out += 1;
+
+        frame.set_opaque(std::ptr::null_mut());
+        frame.set_time_base(Rational::new(
+            self.frame_rate.denominator.get(),
+            std::num::NonZero::new(self.frame_rate.numerator).ok_or(FfmpegError::Arguments("frame rate is 0"))?,
+        ));
+
+        Ok(Some(frame.video()))
+    }
+
+    fn graph(frame: &VideoFrame, frame_rate: Rational) -> Result<FilterGraph, FfmpegError> {
+        if frame_rate.numerator <= 0 || frame_rate.denominator.get() <= 0 {
+            return Err(FfmpegError::Arguments("frame rate must be positive"));
+        }
+
+        let time_base = frame.time_base();
+        if time_base.numerator <= 0 {
+            return Err(FfmpegError::Arguments("frame has no time base"));
+        }
+
+        let sample_aspect_ratio = frame.sample_aspect_ratio();
+        let generic: &GenericFrame = frame;
+
+        let spec = format!(
+            "buffer=video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{},fps=fps={}/{},buffersink",
+            frame.width(),
+            frame.height(),
+            generic.format(),
+            time_base.numerator,
+            time_base.denominator,
+            sample_aspect_ratio.numerator,
+            sample_aspect_ratio.denominator,
+            frame_rate.numerator,
+            frame_rate.denominator,
+        );
+
+        let mut graph = FilterGraph::new()?;
+        FilterGraphParser::new(&mut graph).parse(&spec)?;
+        graph.validate()?;
+
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::decoder::Decoder;
+    use crate::frame_rate::{FrameRateConverter, FrameRateStats};
+    use crate::io::Input;
+    use crate::rational::Rational;
+    use crate::AVMediaType;
+
+    #[test]
+    fn test_frame_rate_converter() {
+        // The file is 60fps.
+        let mut input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open valid file");
+        let streams = input.streams();
+        let video_stream = streams.best(AVMediaType::Video).expect("No video stream found");
+        let video_stream_index = video_stream.index();
+        let mut decoder = Decoder::new(&video_stream)
+            .expect("Failed to create decoder")
+            .video()
+            .expect("Failed to get video decoder");
+
+        let mut converter = FrameRateConverter::new(30.into());
+        let mut frames_in = 0;
+        let mut frames_out = Vec::new();
+
+        while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() == video_stream_index {
+                decoder.send_packet(&packet).expect("Failed to send packet");
+                while let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+                    frames_in += 1;
+                    converter.send_frame(&frame).expect("Failed to convert frame");
+                    while let Some(frame) = converter.receive_frame().expect("Failed to receive frame") {
+                        frames_out.push(frame);
+                    }
+                }
+            }
+        }
+
+        decoder.send_eof().expect("Failed to send eof");
+        while let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+            frames_in += 1;
+            converter.send_frame(&frame).expect("Failed to convert frame");
+        }
+
+        converter.send_eof().expect("Failed to send eof");
+        while let Some(frame) = converter.receive_frame().expect("Failed to receive frame") {
+            frames_out.push(frame);
+        }
+
+        let stats = converter.stats();
+        assert_eq!(stats.frames_in, frames_in);
+        assert_eq!(stats.frames_out, frames_out.len() as u64);
+        assert!(stats.frames_out.abs_diff(frames_in / 2) <= 1, "{stats:?}");
+        assert!(stats.dropped > 0, "{stats:?}");
+        assert_eq!(stats.frames_in + stats.duplicated - stats.dropped, stats.frames_out);
+
+        for frame in &frames_out {
+            assert_eq!(frame.time_base(), Rational::static_new::<1, 30>());
+        }
+    }
+
+    #[test]
+    fn test_frame_rate_converter_no_frames() {
+        let mut converter = FrameRateConverter::new(30.into());
+        converter.send_eof().expect("Failed to send eof");
+        assert!(converter.receive_frame().expect("Failed to receive frame").is_none());
+        assert_eq!(converter.stats(), FrameRateStats::default());
+    }
+}
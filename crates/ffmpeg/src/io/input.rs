@@ -1,13 +1,19 @@
 use std::ffi::CStr;
 
+use bytes::Bytes;
+
 use super::internal::{read_packet, seek, Inner, InnerOptions};
 use crate::consts::{Const, DEFAULT_BUFFER_SIZE};
+use crate::decoder::Decoder;
 use crate::dict::Dictionary;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
+use crate::frame::VideoFrame;
 use crate::packet::{Packet, Packets};
+use crate::scaler::VideoScaler;
 use crate::smart_object::SmartObject;
 use crate::stream::Streams;
+use crate::{AVMediaType, AVPixelFormat, AVSeekFlag};
 
 /// Represents an input stream.
 pub struct Input<T: Send + Sync> {
@@ -127,6 +133,108 @@ impl<T: Send + Sync> Input<T> {
         self.packets().receive()
     }
 
+    /// Returns the cover art of the input.
+    ///
+    /// This is the data of the first attached picture stream (see
+    /// [`Stream::attached_pic`](crate::stream::Stream::attached_pic)), which
+    /// audio files commonly use to carry their cover art.
+    pub fn cover_art(&self) -> Option<Bytes> {
+        self.streams()
+            .iter()
+            .find_map(|stream| stream.attached_pic().map(Bytes::copy_from_slice))
+    }
+
+    /// Seeks to the given timestamp.
+    ///
+    /// The timestamp is in the time base of the stream with the given index,
+    /// or in [`AV_TIME_BASE`] units if `stream_index` is `-1`. By default this
+    /// seeks to the closest keyframe after the timestamp, pass
+    /// [`AVSeekFlag::Backward`] to seek to the one before it.
+    ///
+    /// Seeking requires a seekable input, see [`Input::seekable`].
+    pub fn seek(&mut self, stream_index: i32, timestamp: i64, flags: AVSeekFlag) -> Result<(), FfmpegError> {
+        // Safety: `av_seek_frame` is safe to call, the context is valid.
+        FfmpegErrorCode(unsafe { av_seek_frame(self.as_mut_ptr(), stream_index, timestamp, flags.0) }).result()?;
+        Ok(())
+    }
+
+    /// Extracts a thumbnail from the best video stream.
+    ///
+    /// This seeks to the keyframe before `timestamp`, decodes up to the first
+    /// frame at or after `timestamp` (or the last frame, if the stream ends
+    /// before it) and scales it to `width` x `height` in
+    /// [`AVPixelFormat::Rgb24`].
+    ///
+    /// The timestamp is relative to the start of the stream. Seeking requires
+    /// a seekable input, see [`Input::seekable`]. The read position of the
+    /// input is left wherever decoding stopped.
+    pub fn thumbnail(&mut self, timestamp: std::time::Duration, width: i32, height: i32) -> Result<VideoFrame, FfmpegError> {
+        let (stream_index, target, mut decoder) = {
+            let streams = self.streams();
+            let stream = streams.best(AVMediaType::Video).ok_or(FfmpegError::NoStream)?;
+
+            let time_base = stream.time_base();
+            if time_base.numerator <= 0 {
+                return Err(FfmpegError::Arguments("video stream has an invalid time base"));
+            }
+
+            let offset =
+                timestamp.as_nanos() * time_base.denominator.get() as u128 / (time_base.numerator as u128 * 1_000_000_000);
+            let target = stream.start_time().unwrap_or(0) + offset as i64;
+
+            let decoder = Decoder::new(&stream)?
+                .video()
+                .map_err(|_| FfmpegError::Arguments("best video stream is not a video stream"))?;
+
+            (stream.index(), target, decoder)
+        };
+
+        self.seek(stream_index, target, AVSeekFlag::Backward)?;
+
+        let mut frame = None;
+        let is_target = |frame: &VideoFrame| frame.best_effort_timestamp().is_some_and(|ts| ts >= target);
+
+        'decode: while let Some(packet) = self.receive_packet()? {
+            if packet.stream_index() != stream_index {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+            while let Some(decoded) = decoder.receive_frame()? {
+                let done = is_target(&decoded);
+                frame = Some(decoded);
+                if done {
+                    break 'decode;
+                }
+            }
+        }
+
+        if !frame.as_ref().is_some_and(is_target) {
+            decoder.send_eof()?;
+            for decoded in decoder.finish() {
+                let decoded = decoded?;
+                let done = is_target(&decoded);
+                frame = Some(decoded);
+                if done {
+                    break;
+                }
+            }
+        }
+
+        let frame = frame.ok_or(FfmpegError::NoFrame)?;
+
+        let mut scaler = VideoScaler::new(
+            decoder.width(),
+            decoder.height(),
+            decoder.pixel_format(),
+            width,
+            height,
+            AVPixelFormat::Rgb24,
+        )?;
+
+        Ok(scaler.process(&frame)?.clone())
+    }
+
     fn create_input(mut inner: Inner<T>, path: Option<&CStr>, dictionary: &mut Dictionary) -> Result<Self, FfmpegError> {
         // Safety: avformat_open_input is safe to call
         FfmpegErrorCode(unsafe {
@@ -177,6 +285,7 @@ mod tests {
     use insta::Settings;
 
     use super::{FfmpegError, Input, InputOptions, DEFAULT_BUFFER_SIZE};
+    use crate::{AVMediaType, AVPixelFormat};
 
     fn configure_insta_filters(settings: &mut Settings) {
         settings.add_filter(r"0x0000000000000000", "[NULL_POINTER]");
@@ -379,6 +488,33 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_thumbnail() {
+        let valid_media_data: Vec<u8> = include_bytes!("../../../../assets/avc_aac_large.mp4").to_vec();
+        let mut input = Input::seekable(Cursor::new(valid_media_data)).expect("Failed to create Input");
+
+        let start_time = input
+            .streams()
+            .best(AVMediaType::Video)
+            .expect("No video stream")
+            .start_time()
+            .unwrap_or(0);
+
+        let frame = input
+            .thumbnail(std::time::Duration::from_millis(500), 64, 36)
+            .expect("Failed to extract thumbnail");
+
+        assert_eq!(frame.width(), 64);
+        assert_eq!(frame.height(), 36);
+        assert_eq!(frame.format(), AVPixelFormat::Rgb24);
+
+        // The file is 60fps with a 1/15360 time base, so 0.5s is pts 7680 and
+        // each frame lasts 256.
+        let pts = frame.pts().expect("Frame has no pts") - start_time;
+        assert!((7680..7680 + 256).contains(&pts), "unexpected pts: {pts}");
+        assert_eq!(frame.to_packed_bytes().expect("Failed to export frame").len(), 64 * 36 * 3);
+    }
+
     #[test]
     fn test_receive_packet() {
         let valid_file_path = "../../assets/avc_aac_large.mp4";
@@ -397,4 +533,61 @@ mod tests {
 
         insta::assert_debug_snapshot!(packets);
     }
+
+    /// Builds an MP3 file with an ID3v2.3 tag carrying the given picture as
+    /// its front cover, followed by a few silent MPEG-1 Layer III frames.
+    fn mp3_with_cover_art(mime: &str, picture: &[u8]) -> Vec<u8> {
+        // Text encoding, mime type, picture type (front cover) and an empty description.
+        let mut apic = vec![0];
+        apic.extend_from_slice(mime.as_bytes());
+        apic.extend_from_slice(&[0, 3, 0]);
+        apic.extend_from_slice(picture);
+
+        let mut frame = b"APIC".to_vec();
+        frame.extend_from_slice(&(apic.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&apic);
+
+        // The tag size is a syncsafe integer, 7 bits per byte.
+        let size = frame.len() as u32;
+        let mut file = b"ID3\x03\x00\x00".to_vec();
+        file.extend_from_slice(&[
+            (size >> 21) as u8 & 0x7f,
+            (size >> 14) as u8 & 0x7f,
+            (size >> 7) as u8 & 0x7f,
+            size as u8 & 0x7f,
+        ]);
+        file.extend_from_slice(&frame);
+
+        // 128 kbit/s, 44.1 kHz, stereo frames are 417 bytes long.
+        for _ in 0..16 {
+            let mut frame = vec![0; 417];
+            frame[..4].copy_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+            file.extend_from_slice(&frame);
+        }
+
+        file
+    }
+
+    #[test]
+    fn test_cover_art() {
+        const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+        let mut picture = PNG_MAGIC.to_vec();
+        picture.extend_from_slice(b"\x00\x00\x00\x0dIHDR\x00\x00\x00\x01\x00\x00\x00\x01\x08\x02\x00\x00\x00");
+
+        let input = Input::new(Cursor::new(mp3_with_cover_art("image/png", &picture))).expect("Failed to open mp3");
+        assert!(input.streams().iter().any(|stream| stream.is_attached_pic()));
+
+        let cover_art = input.cover_art().expect("Expected cover art");
+        assert!(cover_art.starts_with(PNG_MAGIC), "Expected a PNG header: {cover_art:02x?}");
+        assert_eq!(cover_art, picture);
+    }
+
+    #[test]
+    fn test_no_cover_art() {
+        let input = Input::open("../../assets/avc_aac_large.mp4").expect("Failed to open file");
+        assert!(input.streams().iter().all(|stream| stream.attached_pic().is_none()));
+        assert!(input.cover_art().is_none());
+    }
 }
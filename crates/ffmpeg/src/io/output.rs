@@ -2,12 +2,13 @@ use std::ffi::CString;
 use std::ptr::NonNull;
 
 use super::internal::{seek, write_packet, Inner, InnerOptions};
-use crate::consts::DEFAULT_BUFFER_SIZE;
+use crate::consts::{Const, DEFAULT_BUFFER_SIZE};
 use crate::dict::Dictionary;
 use crate::error::{FfmpegError, FfmpegErrorCode};
 use crate::ffi::*;
+use crate::option::{class_options, OptionInfo};
 use crate::packet::Packet;
-use crate::stream::Stream;
+use crate::stream::{Stream, Streams};
 use crate::{AVFmtFlags, AVFormatFlags};
 
 /// A struct that represents the options for the output.
@@ -18,6 +19,27 @@ pub struct OutputOptions {
     buffer_size: usize,
     #[builder(setters(vis = "", name = format_ffi_internal))]
     format_ffi: *const AVOutputFormat,
+    /// Format specific options which are passed to the muxer when the header is
+    /// written, for example `movflags=frag_keyframe+empty_moov+default_base_moof`
+    /// to produce a fragmented MP4.
+    ///
+    /// These are ignored if the header is written with
+    /// [`Output::write_header_with_options`].
+    ///
+    /// The options a muxer accepts can be listed with
+    /// [`OutputOptions::muxer_options`].
+    format_options: Option<Dictionary>,
+}
+
+impl OutputOptions {
+    /// Returns the format specific options accepted by the muxer, which can be
+    /// set with the `format_options`.
+    pub fn muxer_options(&self) -> Vec<OptionInfo> {
+        // Safety: The format ffi is a valid non-null pointer, checked by the builder.
+        let priv_class = unsafe { (*self.format_ffi).priv_class };
+        // Safety: The class is null or a valid pointer to a class.
+        unsafe { class_options(priv_class, 0) }
+    }
 }
 
 impl<S: output_options_builder::State> OutputOptionsBuilder<S> {
@@ -91,6 +113,7 @@ impl<S: output_options_builder::State> OutputOptionsBuilder<S> {
 pub struct Output<T: Send + Sync> {
     inner: Inner<T>,
     state: OutputState,
+    format_options: Option<Dictionary>,
 }
 
 /// Safety: `T` must be `Send` and `Sync`.
@@ -124,6 +147,7 @@ impl<T: std::io::Write + Send + Sync> Output<T> {
                 },
             )?,
             state: OutputState::Uninitialized,
+            format_options: options.format_options,
         })
     }
 
@@ -144,6 +168,7 @@ impl<T: std::io::Write + Send + Sync> Output<T> {
                 },
             )?,
             state: OutputState::Uninitialized,
+            format_options: options.format_options,
         })
     }
 }
@@ -169,6 +194,21 @@ impl<T: Send + Sync> Output<T> {
         self.inner.context.as_mut_ptr()
     }
 
+    /// Returns the streams of the output.
+    pub const fn streams(&self) -> Const<'_, Streams<'_>> {
+        // Safety: See the documentation of `Streams::new`.
+        // We upcast the pointer to be mut because the function signature requires it.
+        // However we do not mutate the pointer as its returned as a `Const<Streams>` which
+        // restricts the mutability of the streams to be const.
+        unsafe { Const::new(Streams::new(self.inner.context.as_ptr() as *mut _)) }
+    }
+
+    /// Returns a mutable reference to the streams of the output.
+    pub const fn streams_mut(&mut self) -> Streams<'_> {
+        // Safety: See the documentation of `Streams::new`.
+        unsafe { Streams::new(self.inner.context.as_mut_ptr()) }
+    }
+
     /// Adds a new stream to the output.
     pub fn add_stream(&mut self, codec: Option<*const AVCodec>) -> Option<Stream<'_>> {
         let mut stream =
@@ -210,11 +250,17 @@ impl<T: Send + Sync> Output<T> {
     }
 
     /// Writes the header to the output.
+    ///
+    /// The format options from the [`OutputOptions`] are passed to the muxer.
     pub fn write_header(&mut self) -> Result<(), FfmpegError> {
         if self.state != OutputState::Uninitialized {
             return Err(FfmpegError::Arguments("header already written"));
         }
 
+        if let Some(mut options) = self.format_options.take() {
+            return self.write_header_with_options(&mut options);
+        }
+
         // Safety: `avformat_write_header` is safe to call, if the header has not been
         // written yet.
         FfmpegErrorCode(unsafe { avformat_write_header(self.as_mut_ptr(), std::ptr::null_mut()) }).result()?;
@@ -287,6 +333,19 @@ impl<T: Send + Sync> Output<T> {
         AVFmtFlags(self.inner.context.as_deref_except().flags)
     }
 
+    /// Returns the format specific options accepted by the muxer.
+    ///
+    /// See [`OutputOptions::muxer_options`].
+    pub fn muxer_options(&self) -> Vec<OptionInfo> {
+        // Safety: The format is valid.
+        let Some(oformat) = (unsafe { self.inner.context.as_deref_except().oformat.as_ref() }) else {
+            return Vec::new();
+        };
+
+        // Safety: The class is null or a valid pointer to a class.
+        unsafe { class_options(oformat.priv_class, 0) }
+    }
+
     /// Returns the flags for the output.
     pub const fn output_flags(&self) -> Option<AVFormatFlags> {
         // Safety: The format is valid.
@@ -304,6 +363,7 @@ impl Output<()> {
         Ok(Self {
             inner: Inner::open_output(path)?,
             state: OutputState::Uninitialized,
+            format_options: None,
         })
     }
 }
@@ -324,7 +384,7 @@ mod tests {
     use crate::error::FfmpegError;
     use crate::io::output::{AVCodec, AVRational, OutputState};
     use crate::io::{Input, Output, OutputOptions};
-    use crate::{AVFmtFlags, AVMediaType};
+    use crate::{AVFmtFlags, AVMediaType, AVOptionType};
 
     #[test]
     fn test_output_options_get_format_ffi_null() {
@@ -538,6 +598,112 @@ mod tests {
         insta::assert_debug_snapshot!("test_output_write_mp4_trailer", get_boxes!(output));
     }
 
+    #[test]
+    fn test_output_format_options_fragmented() {
+        let options = OutputOptions::builder()
+            .format_name("mp4")
+            .unwrap()
+            .format_options(
+                Dictionary::try_from_iter([("movflags", "frag_keyframe+empty_moov+default_base_moof")])
+                    .expect("Failed to create dictionary"),
+            )
+            .build();
+
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+
+        let mut input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+        let streams = input.streams();
+        let best_video_stream = streams.best(AVMediaType::Video).expect("no video stream found");
+        let best_video_stream_index = best_video_stream.index();
+
+        output.copy_stream(&best_video_stream).expect("Failed to copy stream");
+        output.write_header().expect("Failed to write header");
+
+        while let Some(mut packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() != best_video_stream_index {
+                continue;
+            }
+
+            packet.set_stream_index(0);
+            output.write_packet(&packet).expect("Failed to write packet");
+        }
+
+        output.write_trailer().expect("Failed to write trailer");
+
+        let boxes = get_boxes!(output);
+        assert!(matches!(boxes[0], scuffle_mp4::DynBox::Ftyp(_)), "{:?}", boxes[0]);
+        let scuffle_mp4::DynBox::Moov(moov) = &boxes[1] else {
+            panic!("expected moov, found {:?}", boxes[1]);
+        };
+
+        // With `empty_moov` the moov contains no samples, they are all in fragments.
+        assert!(moov.mvex.is_some(), "expected mvex in fragmented moov");
+        assert!(moov
+            .traks
+            .iter()
+            .all(|trak| trak.mdia.minf.stbl.stsz.as_ref().is_none_or(|stsz| stsz.samples.is_empty())));
+        assert!(boxes.iter().any(|b| matches!(b, scuffle_mp4::DynBox::Moof(_))));
+    }
+
+    #[test]
+    fn test_output_muxer_options() {
+        let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+
+        let muxer_options = options.muxer_options();
+        let movflags = muxer_options
+            .iter()
+            .find(|option| option.name == "movflags")
+            .expect("mp4 muxer has no movflags option");
+        assert_eq!(movflags.option_type, AVOptionType::Flags);
+        assert!(movflags.help.is_some());
+        assert!(movflags.constants.iter().any(|c| c == "frag_keyframe"), "{movflags:?}");
+        assert!(movflags.constants.iter().any(|c| c == "empty_moov"), "{movflags:?}");
+
+        assert!(muxer_options.iter().all(|option| option.option_type != AVOptionType::Const));
+
+        // The output exposes the same options.
+        let output = Output::new(Cursor::new(Vec::new()), options.clone()).expect("Failed to create Output");
+        assert_eq!(output.muxer_options(), muxer_options);
+
+        // A numeric option is applied when the header is written.
+        let frag_duration = muxer_options
+            .iter()
+            .find(|option| option.name == "frag_duration")
+            .expect("mp4 muxer has no frag_duration option");
+        assert_eq!(frag_duration.option_type, AVOptionType::Int);
+
+        let options = OutputOptions::builder()
+            .format_name("mp4")
+            .unwrap()
+            .format_options(
+                Dictionary::try_from_iter([("movflags", "frag_keyframe+empty_moov"), ("frag_duration", "1000000")])
+                    .expect("Failed to create dictionary"),
+            )
+            .build();
+
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let input = Input::seekable(std::fs::File::open(dir.join("avc_aac.mp4")).expect("Failed to open file"))
+            .expect("Failed to create Input");
+        let streams = input.streams();
+        let best_video_stream = streams.best(AVMediaType::Video).expect("no video stream found");
+        output.copy_stream(&best_video_stream).expect("Failed to copy stream");
+        output.write_header().expect("Failed to write header");
+        assert_eq!(output.state, OutputState::HeaderWritten);
+
+        // An invalid value for a known option is rejected.
+        let options = OutputOptions::builder()
+            .format_name("mp4")
+            .unwrap()
+            .format_options(Dictionary::try_from_iter([("frag_duration", "invalid")]).expect("Failed to create dictionary"))
+            .build();
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        output.copy_stream(&best_video_stream).expect("Failed to copy stream");
+        assert!(output.write_header().is_err());
+    }
+
     #[test]
     fn test_output_write_mp4_fragmented() {
         let data = Cursor::new(Vec::new());
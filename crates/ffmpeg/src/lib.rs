@@ -66,17 +66,13 @@
 //!     }
 //! }
 //!
-//! // 9. Send the EOF to the decoders.
-//! video_decoder.send_eof()?;
-//! audio_decoder.send_eof()?;
-//!
-//! // 10. Receive the remaining frames from the decoders.
-//! while let Some(frame) = video_decoder.receive_frame()? {
-//!     dbg!(&frame);
+//! // 9. Send the EOF to the decoders and receive the remaining frames.
+//! for frame in video_decoder.finish() {
+//!     dbg!(&frame?);
 //! }
 //!
-//! while let Some(frame) = audio_decoder.receive_frame()? {
-//!     dbg!(&frame);
+//! for frame in audio_decoder.finish() {
+//!     dbg!(&frame?);
 //! }
 //! # Ok(())
 //! # }
@@ -172,23 +168,19 @@
 //!     }
 //! }
 //!
-//! // 15. Send the EOF to the decoders.
-//! video_decoder.send_eof()?;
-//! audio_decoder.send_eof()?;
-//!
-//! // 16. Receive the remaining packets from the encoders.
-//! while let Some(packet) = video_encoder.receive_packet()? {
-//!     output.write_packet(&packet)?;
+//! // 15. Send the EOF to the encoders and write the remaining packets to the output.
+//! for packet in video_encoder.finish() {
+//!     output.write_packet(&packet?)?;
 //! }
 //!
-//! while let Some(packet) = audio_encoder.receive_packet()? {
-//!     output.write_packet(&packet)?;
+//! for packet in audio_encoder.finish() {
+//!     output.write_packet(&packet?)?;
 //! }
 //!
-//! // 17. Write the trailer to the output.
+//! // 16. Write the trailer to the output.
 //! output.write_trailer()?;
 //!
-//! // 18. Do something with the output data (write to disk, upload to s3, etc).
+//! // 17. Do something with the output data (write to disk, upload to s3, etc).
 //! let output_data = output.into_inner();
 //! # drop(output_data);
 //! # Ok(())
@@ -231,18 +223,30 @@ pub mod error;
 pub mod filter_graph;
 /// Frame specific functionality.
 pub mod frame;
+/// Frame rate conversion functionality.
+pub mod frame_rate;
 /// Input/Output specific functionality.
 pub mod io;
 /// Logging specific functionality.
 pub mod log;
+/// Loudness measurement functionality.
+pub mod loudness;
+/// Option introspection functionality.
+pub mod option;
 /// Packet specific functionality.
 pub mod packet;
 /// Rational number specific functionality.
 pub mod rational;
+/// Remuxing (stream copy) specific functionality.
+pub mod remux;
 /// Scalar specific functionality.
 pub mod scaler;
 /// Stream specific functionality.
 pub mod stream;
+/// Writing to multiple outputs specific functionality.
+pub mod tee;
+/// Frame accurate cutting functionality.
+pub mod trim;
 /// Utility functionality.
 pub mod utils;
 
@@ -252,4 +256,12 @@ mod smart_object;
 
 mod enums;
 
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests;
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod test_util;
+
 pub use enums::*;
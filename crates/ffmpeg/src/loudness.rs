@@ -0,0 +1,242 @@
+use std::ffi::CStr;
+
+use crate::dict::Dictionary;
+use crate::error::FfmpegError;
+use crate::ffi::*;
+use crate::filter_graph::{Filter, FilterGraph, FilterGraphParser};
+use crate::frame::{AudioFrame, GenericFrame};
+
+const SOURCE: &str = "Parsed_abuffer_0";
+const SINK: &str = "Parsed_abuffersink_2";
+
+/// Loudness statistics as measured by [`LoudnessAnalyzer`], following EBU
+/// R128 (ITU-R BS.1770).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// The integrated loudness of the whole input in LUFS.
+    pub integrated: f64,
+    /// The loudness range in LU.
+    pub range: f64,
+    /// The highest true peak of all channels in dBTP.
+    pub true_peak: f64,
+}
+
+/// Measures the loudness of decoded audio by running it through ffmpeg's
+/// `ebur128` filter.
+///
+/// The filter graph is created from the properties of the first frame, all
+/// frames must have the same sample format, sample rate and channel layout.
+///
+/// ```rust,no_run
+/// # use scuffle_ffmpeg::decoder::Decoder;
+/// # use scuffle_ffmpeg::io::Input;
+/// # use scuffle_ffmpeg::loudness::LoudnessAnalyzer;
+/// # use scuffle_ffmpeg::AVMediaType;
+/// # fn main() -> Result<(), scuffle_ffmpeg::error::FfmpegError> {
+/// let mut input = Input::open("input.mp4")?;
+/// let stream = input.streams().best(AVMediaType::Audio).expect("no audio stream");
+/// let stream_index = stream.index();
+/// let mut decoder = Decoder::new(&stream)?.audio().expect("not an audio stream");
+///
+/// let mut analyzer = LoudnessAnalyzer::new();
+/// while let Some(packet) = input.receive_packet()? {
+///     if packet.stream_index() == stream_index {
+///         decoder.send_packet(&packet)?;
+///         while let Some(frame) = decoder.receive_frame()? {
+///             analyzer.send_frame(&frame)?;
+///         }
+///     }
+/// }
+///
+/// for frame in decoder.finish() {
+///     analyzer.send_frame(&frame?)?;
+/// }
+///
+/// let loudness = analyzer.finish()?;
+/// println!("{} LUFS", loudness.integrated);
+/// # Ok(())
+/// # }
+/// ```
+pub struct LoudnessAnalyzer {
+    graph: Option<FilterGraph>,
+    loudness: Option<Loudness>,
+}
+
+impl std::fmt::Debug for LoudnessAnalyzer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoudnessAnalyzer")
+            .field("loudness", &self.loudness)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for LoudnessAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoudnessAnalyzer {
+    /// Creates a new analyzer.
+    pub const fn new() -> Self {
+        Self {
+            graph: None,
+            loudness: None,
+        }
+    }
+
+    /// Sends a frame to be analyzed.
+    pub fn send_frame(&mut self, frame: &AudioFrame) -> Result<(), FfmpegError> {
+        let graph = match &mut self.graph {
+            Some(graph) => graph,
+            graph => graph.insert(Self::graph(frame)?),
+        };
+
+        graph.get(SOURCE).ok_or(FfmpegError::NoFilter)?.source().send_frame(frame)?;
+
+        self.drain()
+    }
+
+    /// Flushes the filter and returns the loudness of all the frames sent.
+    ///
+    /// Returns [`FfmpegError::NoFrame`] if no frame was analyzed.
+    pub fn finish(mut self) -> Result<Loudness, FfmpegError> {
+        if let Some(graph) = &mut self.graph {
+            graph.get(SOURCE).ok_or(FfmpegError::NoFilter)?.source().send_eof(None)?;
+            self.drain()?;
+        }
+
+        self.loudness.ok_or(FfmpegError::NoFrame)
+    }
+
+    fn graph(frame: &AudioFrame) -> Result<FilterGraph, FfmpegError> {
+        if Filter::get("ebur128").is_none() {
+            return Err(FfmpegError::NoFilter);
+        }
+
+        let mut layout = [0; 128];
+        // Safety: `channel_layout` is a valid layout and `layout` is a buffer of the given size.
+        let ret = unsafe { av_channel_layout_describe(&frame.channel_layout(), layout.as_mut_ptr(), layout.len()) };
+        crate::error::FfmpegErrorCode(ret).result()?;
+        // Safety: `av_channel_layout_describe` wrote a nul terminated string into the buffer.
+        let layout = unsafe { CStr::from_ptr(layout.as_ptr()) };
+
+        let spec =
+            format!(
+            "abuffer=sample_rate={}:sample_fmt={}:channel_layout={}:time_base=1/{},ebur128=metadata=1:peak=true,abuffersink",
+            frame.sample_rate(),
+            frame.format(),
+            layout.to_str().map_err(|_| FfmpegError::Arguments("invalid channel layout"))?,
+            frame.sample_rate(),
+        );
+
+        let mut graph = FilterGraph::new()?;
+        FilterGraphParser::new(&mut graph).parse(&spec)?;
+        graph.validate()?;
+
+        Ok(graph)
+    }
+
+    /// Reads the latest measurements from the frames which went through the
+    /// filter.
+    fn drain(&mut self) -> Result<(), FfmpegError> {
+        let Some(graph) = &mut self.graph else {
+            return Ok(());
+        };
+
+        let mut sink = graph.get(SINK).ok_or(FfmpegError::NoFilter)?.sink();
+        while let Some(frame) = sink.receive_frame()? {
+            if let Some(loudness) = Loudness::from_metadata(&frame) {
+                self.loudness = Some(loudness);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Loudness {
+    /// Reads the `lavfi.r128.*` metadata the `ebur128` filter attaches to the
+    /// frames it outputs.
+    fn from_metadata(frame: &GenericFrame) -> Option<Self> {
+        // Safety: The metadata belongs to the frame and the dictionary is dropped
+        // before the frame.
+        let metadata = unsafe { Dictionary::from_ptr_ref(frame.metadata()) };
+
+        let mut integrated = None;
+        let mut range = None;
+        let mut true_peak = None::<f64>;
+
+        for (key, value) in metadata.iter() {
+            let (Ok(key), Some(value)) = (key.to_str(), value.to_str().ok().and_then(|v| v.parse::<f64>().ok())) else {
+                continue;
+            };
+
+            match key.strip_prefix("lavfi.r128.") {
+                Some("I") => integrated = Some(value),
+                Some("LRA") => range = Some(value),
+                Some(key) if key.starts_with("true_peaks_ch") => {
+                    true_peak = Some(true_peak.map_or(value, |peak| peak.max(value)));
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            integrated: integrated?,
+            range: range?,
+            true_peak: true_peak?,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use crate::decoder::Decoder;
+    use crate::error::FfmpegError;
+    use crate::io::Input;
+    use crate::loudness::LoudnessAnalyzer;
+    use crate::AVMediaType;
+
+    #[test]
+    fn test_loudness_analyzer() {
+        let mut input = Input::open("../../assets/avc_aac.mp4").expect("Failed to open valid file");
+        let streams = input.streams();
+        let audio_stream = streams.best(AVMediaType::Audio).expect("No audio stream found");
+        let audio_stream_index = audio_stream.index();
+        let mut decoder = Decoder::new(&audio_stream)
+            .expect("Failed to create decoder")
+            .audio()
+            .expect("Failed to get audio decoder");
+
+        let mut analyzer = LoudnessAnalyzer::new();
+        while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+            if packet.stream_index() == audio_stream_index {
+                decoder.send_packet(&packet).expect("Failed to send packet");
+                while let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+                    analyzer.send_frame(&frame).expect("Failed to analyze frame");
+                }
+            }
+        }
+
+        for frame in decoder.finish() {
+            analyzer
+                .send_frame(&frame.expect("Failed to receive frame"))
+                .expect("Failed to analyze frame");
+        }
+
+        let loudness = analyzer.finish().expect("Failed to measure loudness");
+
+        assert!(loudness.integrated.is_finite(), "{loudness:?}");
+        assert!((-70.0..=0.0).contains(&loudness.integrated), "{loudness:?}");
+        assert!(loudness.range.is_finite() && loudness.range >= 0.0, "{loudness:?}");
+        // Silence has a true peak of -inf dBTP.
+        assert!(!loudness.true_peak.is_nan(), "{loudness:?}");
+    }
+
+    #[test]
+    fn test_loudness_analyzer_no_frames() {
+        assert_eq!(LoudnessAnalyzer::new().finish(), Err(FfmpegError::NoFrame));
+    }
+}
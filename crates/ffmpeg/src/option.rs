@@ -0,0 +1,110 @@
+use std::ffi::{c_void, CStr};
+
+use crate::ffi::*;
+use crate::AVOptionType;
+
+/// An option accepted by an FFmpeg component, such as a muxer.
+///
+/// Options are set by name through a [`Dictionary`](crate::dict::Dictionary),
+/// this describes an option so that the accepted options can be discovered at
+/// runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionInfo {
+    /// The name of the option, used as the dictionary key.
+    pub name: String,
+    /// A short help text.
+    pub help: Option<String>,
+    /// The type of the value.
+    pub option_type: AVOptionType,
+    /// The minimum value, for numeric options.
+    pub min: f64,
+    /// The maximum value, for numeric options.
+    pub max: f64,
+    /// The names of the named constants which can be used as the value.
+    pub constants: Vec<String>,
+}
+
+/// Returns the options described by an `AVClass`.
+///
+/// If `flags` is not zero only options with one of the `AV_OPT_FLAG_*` flags
+/// are returned.
+///
+/// # Safety
+/// `class` must be null or a valid pointer to an `AVClass`.
+pub(crate) unsafe fn class_options(class: *const AVClass, flags: u32) -> Vec<OptionInfo> {
+    if class.is_null() {
+        return Vec::new();
+    }
+
+    let mut options: Vec<OptionInfo> = Vec::new();
+    let mut units = Vec::new();
+    let mut constants = Vec::new();
+
+    // `av_opt_next` accepts a double pointer to the class.
+    let obj = &class as *const *const AVClass as *const c_void;
+    let mut prev = std::ptr::null();
+    loop {
+        // Safety: `obj` points to a valid class pointer and `prev` is null or the previous option.
+        prev = unsafe { av_opt_next(obj, prev) };
+        // Safety: The option is either null or a valid pointer returned by `av_opt_next`.
+        let Some(option) = (unsafe { prev.as_ref() }) else {
+            break;
+        };
+
+        if flags != 0 && option.flags as u32 & flags == 0 {
+            continue;
+        }
+
+        // Safety: The option strings are null or valid static c strings.
+        let Some(name) = (unsafe { to_string(option.name) }) else {
+            continue;
+        };
+        // Safety: See above.
+        let help = unsafe { to_string(option.help) };
+        // Safety: See above.
+        let unit = unsafe { to_string(option.unit) };
+
+        if AVOptionType(option.type_) == AVOptionType::Const {
+            if let Some(unit) = unit {
+                constants.push((unit, name));
+            }
+            continue;
+        }
+
+        units.push(unit);
+        options.push(OptionInfo {
+            name,
+            help,
+            option_type: AVOptionType(option.type_),
+            min: option.min,
+            max: option.max,
+            constants: Vec::new(),
+        });
+    }
+
+    for (option, unit) in options.iter_mut().zip(units) {
+        let Some(unit) = unit else {
+            continue;
+        };
+
+        option.constants = constants
+            .iter()
+            .filter(|(u, _)| *u == unit)
+            .map(|(_, name)| name.clone())
+            .collect();
+    }
+
+    options
+}
+
+/// # Safety
+/// `ptr` must be null or a valid c string.
+unsafe fn to_string(ptr: *const std::ffi::c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    // Safety: The pointer is a valid c string.
+    let s = unsafe { CStr::from_ptr(ptr) };
+    Some(s.to_string_lossy().into_owned())
+}
@@ -0,0 +1,335 @@
+use std::ffi::CString;
+
+use crate::error::{FfmpegError, FfmpegErrorCode};
+use crate::ffi::*;
+use crate::io::{Input, Output};
+use crate::packet::Packet;
+use crate::rational::Rational;
+use crate::smart_object::SmartPtr;
+use crate::stream::Stream;
+use crate::AVMediaType;
+
+/// A bitstream filter is a wrapper around an [`AVBSFContext`]. Which is used to
+/// rewrite the packets of a stream without decoding them, for example
+/// `h264_mp4toannexb` or `aac_adtstoasc`.
+pub struct BitstreamFilter(SmartPtr<AVBSFContext>);
+
+/// Safety: `BitstreamFilter` is safe to send between threads.
+unsafe impl Send for BitstreamFilter {}
+
+impl std::fmt::Debug for BitstreamFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BitstreamFilter")
+            .field("time_base", &self.time_base())
+            .finish()
+    }
+}
+
+impl BitstreamFilter {
+    /// Creates a new bitstream filter with the given name for packets of the
+    /// given stream.
+    pub fn new(name: &str, stream: &Stream<'_>) -> Result<Self, FfmpegError> {
+        let codec_parameters = stream.codec_parameters().ok_or(FfmpegError::NoStream)?;
+        let name = CString::new(name).map_err(|_| FfmpegError::Arguments("bitstream filter name contains a nul byte"))?;
+
+        // Safety: `av_bsf_get_by_name` is safe to call with a valid c string.
+        let filter = unsafe { av_bsf_get_by_name(name.as_ptr()) };
+        if filter.is_null() {
+            return Err(FfmpegError::NoFilter);
+        }
+
+        let mut ptr = std::ptr::null_mut();
+        // Safety: `filter` is a valid pointer and `ptr` is a valid place to store the context.
+        FfmpegErrorCode(unsafe { av_bsf_alloc(filter, &mut ptr) }).result()?;
+
+        let destructor = |ptr: &mut *mut AVBSFContext| {
+            // Safety: `av_bsf_free` is safe to call with a pointer to the context.
+            unsafe { av_bsf_free(ptr) };
+        };
+
+        // Safety: `ptr` is a valid pointer & `destructor` has been setup to free the context.
+        let mut ptr = unsafe { SmartPtr::wrap_non_null(ptr, destructor) }.ok_or(FfmpegError::Alloc)?;
+
+        // Safety: `par_in` has been allocated by `av_bsf_alloc` and `codec_parameters` is valid.
+        FfmpegErrorCode(unsafe { avcodec_parameters_copy(ptr.as_deref_mut_except().par_in, codec_parameters) }).result()?;
+        ptr.as_deref_mut_except().time_base_in = stream.time_base().into();
+
+        // Safety: The context has been allocated and its input parameters have been set.
+        FfmpegErrorCode(unsafe { av_bsf_init(ptr.as_mut_ptr()) }).result()?;
+
+        Ok(Self(ptr))
+    }
+
+    /// Returns the codec parameters of the filtered packets.
+    pub const fn codec_parameters(&self) -> Option<&AVCodecParameters> {
+        // Safety: `par_out` is either null or valid for the lifetime of the context.
+        unsafe { self.0.as_deref_except().par_out.as_ref() }
+    }
+
+    /// Returns the time base of the filtered packets.
+    pub fn time_base(&self) -> Rational {
+        self.0.as_deref_except().time_base_out.into()
+    }
+
+    /// Sends a packet to the filter.
+    pub fn send_packet(&mut self, mut packet: Packet) -> Result<(), FfmpegError> {
+        // Safety: `av_bsf_send_packet` is safe to call, the context and the packet are valid.
+        FfmpegErrorCode(unsafe { av_bsf_send_packet(self.0.as_mut_ptr(), packet.as_mut_ptr()) }).result()?;
+        Ok(())
+    }
+
+    /// Sends an end-of-file to the filter, the remaining packets can then be
+    /// received with [`BitstreamFilter::receive_packet`].
+    pub fn send_eof(&mut self) -> Result<(), FfmpegError> {
+        // Safety: `av_bsf_send_packet` is safe to call with a null packet to signal eof.
+        FfmpegErrorCode(unsafe { av_bsf_send_packet(self.0.as_mut_ptr(), std::ptr::null_mut()) }).result()?;
+        Ok(())
+    }
+
+    /// Receives a filtered packet from the filter.
+    pub fn receive_packet(&mut self) -> Result<Option<Packet>, FfmpegError> {
+        let mut packet = Packet::new()?;
+
+        // Safety: `av_bsf_receive_packet` is safe to call, the context and the packet are valid.
+        match FfmpegErrorCode(unsafe { av_bsf_receive_packet(self.0.as_mut_ptr(), packet.as_mut_ptr()) }) {
+            FfmpegErrorCode::Eagain | FfmpegErrorCode::Eof => Ok(None),
+            code if code.is_success() => Ok(Some(packet)),
+            code => Err(FfmpegError::Code(code)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RemuxStream {
+    target: RemuxTarget,
+    filter: Option<BitstreamFilter>,
+}
+
+/// Where and in which time base the packets of a stream are written.
+#[derive(Debug, Clone, Copy)]
+struct RemuxTarget {
+    output_index: i32,
+    input_time_base: Rational,
+    output_time_base: Rational,
+}
+
+impl RemuxTarget {
+    fn write_packet<O: Send + Sync>(&self, output: &mut Output<O>, mut packet: Packet) -> Result<(), FfmpegError> {
+        packet.convert_timebase(self.input_time_base, self.output_time_base);
+        packet.set_stream_index(self.output_index);
+        packet.set_pos(None);
+        output.write_interleaved_packet(packet)
+    }
+}
+
+/// Copies the audio, video and subtitle streams of an [`Input`] into an
+/// [`Output`] without decoding or encoding them (stream copy).
+///
+/// Packet timestamps are rescaled from the input to the output time bases.
+/// Bitstream filters which a muxer requires (for example `aac_adtstoasc`) are
+/// inserted by ffmpeg automatically, additional ones can be added per stream
+/// with [`Remuxer::with_bitstream_filter`].
+pub struct Remuxer<I: Send + Sync, O: Send + Sync> {
+    input: Input<I>,
+    output: Output<O>,
+    streams: Vec<Option<RemuxStream>>,
+}
+
+impl<I: Send + Sync, O: Send + Sync> std::fmt::Debug for Remuxer<I, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Remuxer").field("streams", &self.streams).finish()
+    }
+}
+
+impl<I: Send + Sync, O: Send + Sync> Remuxer<I, O> {
+    /// Creates a new `Remuxer`, adding a copy of every audio, video and
+    /// subtitle stream of the input to the output.
+    pub fn new(input: Input<I>, mut output: Output<O>) -> Result<Self, FfmpegError> {
+        let mut streams = Vec::new();
+
+        for stream in input.streams().iter() {
+            let media_type = stream
+                .codec_parameters()
+                .map(|par| AVMediaType(par.codec_type))
+                .unwrap_or(AVMediaType::Unknown);
+
+            if !matches!(media_type, AVMediaType::Video | AVMediaType::Audio | AVMediaType::Subtitle) {
+                streams.push(None);
+                continue;
+            }
+
+            let mut out_stream = output.copy_stream(&stream)?.ok_or(FfmpegError::NoStream)?;
+
+            // The tag is specific to the input container, let the muxer pick its own.
+            if let Some(codec_parameters) = out_stream.codec_parameters_mut() {
+                codec_parameters.codec_tag = 0;
+            }
+
+            streams.push(Some(RemuxStream {
+                target: RemuxTarget {
+                    output_index: out_stream.index(),
+                    input_time_base: stream.time_base(),
+                    output_time_base: stream.time_base(),
+                },
+                filter: None,
+            }));
+        }
+
+        if streams.iter().all(Option::is_none) {
+            return Err(FfmpegError::NoStream);
+        }
+
+        Ok(Self { input, output, streams })
+    }
+
+    /// Passes the packets of the input stream with the given index through the
+    /// bitstream filter with the given name before they are written.
+    pub fn with_bitstream_filter(mut self, stream_index: usize, name: &str) -> Result<Self, FfmpegError> {
+        let Some(Some(remux_stream)) = self.streams.get_mut(stream_index) else {
+            return Err(FfmpegError::NoStream);
+        };
+
+        // Safety: The stream is not mutated while it is borrowed.
+        let stream = unsafe { self.input.streams().get_unchecked(stream_index) }.ok_or(FfmpegError::NoStream)?;
+        let filter = BitstreamFilter::new(name, &stream)?;
+
+        let mut out_streams = self.output.streams_mut();
+        let mut out_stream = out_streams
+            .get(remux_stream.target.output_index as usize)
+            .ok_or(FfmpegError::NoStream)?;
+
+        let codec_parameters = filter.codec_parameters().ok_or(FfmpegError::NoStream)?;
+        let out_codec_parameters = out_stream.codec_parameters_mut().ok_or(FfmpegError::NoStream)?;
+        // Safety: `avcodec_parameters_copy` is safe to call when all arguments are valid.
+        FfmpegErrorCode(unsafe { avcodec_parameters_copy(out_codec_parameters, codec_parameters) }).result()?;
+        out_codec_parameters.codec_tag = 0;
+        out_stream.set_time_base(filter.time_base());
+
+        remux_stream.target.input_time_base = filter.time_base();
+        remux_stream.filter = Some(filter);
+
+        Ok(self)
+    }
+
+    /// Copies all packets from the input to the output, writing the header
+    /// and trailer, and returns the finished output.
+    pub fn run(mut self) -> Result<Output<O>, FfmpegError> {
+        self.output.write_header()?;
+
+        // The muxer may have changed the time bases when writing the header.
+        let output_time_bases = self.output.streams().iter().map(|s| s.time_base()).collect::<Vec<_>>();
+        for stream in self.streams.iter_mut().flatten() {
+            stream.target.output_time_base = output_time_bases[stream.target.output_index as usize];
+        }
+
+        while let Some(packet) = self.input.receive_packet()? {
+            let Some(Some(stream)) = self.streams.get_mut(packet.stream_index() as usize) else {
+                continue;
+            };
+
+            match &mut stream.filter {
+                Some(filter) => {
+                    filter.send_packet(packet)?;
+                    while let Some(packet) = filter.receive_packet()? {
+                        stream.target.write_packet(&mut self.output, packet)?;
+                    }
+                }
+                None => stream.target.write_packet(&mut self.output, packet)?,
+            }
+        }
+
+        for stream in self.streams.iter_mut().flatten() {
+            let Some(filter) = &mut stream.filter else {
+                continue;
+            };
+
+            filter.send_eof()?;
+            while let Some(packet) = filter.receive_packet()? {
+                stream.target.write_packet(&mut self.output, packet)?;
+            }
+        }
+
+        self.output.write_trailer()?;
+
+        Ok(self.output)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{BitstreamFilter, Remuxer};
+    use crate::error::FfmpegError;
+    use crate::io::{Input, Output, OutputOptions};
+    use crate::test_util::{asset, output};
+    use crate::AVMediaType;
+
+    #[test]
+    fn test_remux_flv_to_mp4() {
+        let input = Input::seekable(asset("avc_aac.flv")).unwrap();
+        let input_streams = input
+            .streams()
+            .iter()
+            .map(|s| s.codec_parameters().unwrap().codec_id)
+            .collect::<Vec<_>>();
+
+        let output = Remuxer::new(input, output("mp4")).unwrap().run().unwrap();
+        let data = output.into_inner().into_inner();
+
+        let mut remuxed = Input::seekable(Cursor::new(data)).unwrap();
+        let remuxed_streams = remuxed
+            .streams()
+            .iter()
+            .map(|s| s.codec_parameters().unwrap().codec_id)
+            .collect::<Vec<_>>();
+        assert_eq!(remuxed_streams, input_streams);
+
+        let packets = remuxed.packets().collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(!packets.is_empty());
+        assert!(packets.iter().all(|p| p.dts().is_some()));
+    }
+
+    #[test]
+    fn test_remux_with_bitstream_filter() {
+        let input = Input::seekable(asset("avc_aac.mp4")).unwrap();
+        let video = input.streams().best_index(AVMediaType::Video).unwrap();
+
+        let output = Output::new(
+            Cursor::new(Vec::new()),
+            OutputOptions::builder().format_name("mpegts").unwrap().build(),
+        )
+        .unwrap();
+
+        let output = Remuxer::new(input, output)
+            .unwrap()
+            .with_bitstream_filter(video, "h264_mp4toannexb")
+            .unwrap()
+            .run()
+            .unwrap();
+
+        let mut remuxed = Input::new(Cursor::new(output.into_inner().into_inner())).unwrap();
+        assert_eq!(remuxed.streams().len(), 2);
+
+        let video = remuxed.streams().best_index(AVMediaType::Video).unwrap() as i32;
+        let packet = remuxed
+            .packets()
+            .map(Result::unwrap)
+            .find(|p| p.stream_index() == video)
+            .unwrap();
+        assert!(packet.data().starts_with(&[0, 0, 0, 1]), "expected annex-b start code");
+    }
+
+    #[test]
+    fn test_bitstream_filter_not_found() {
+        let input = Input::seekable(asset("avc_aac.mp4")).unwrap();
+        let streams = input.streams();
+        let stream = streams.best(AVMediaType::Video).unwrap();
+
+        assert_eq!(
+            BitstreamFilter::new("does_not_exist", &stream).unwrap_err(),
+            FfmpegError::NoFilter
+        );
+    }
+}
@@ -113,6 +113,10 @@ impl VideoScaler {
         self.frame.set_pts(frame.pts());
         self.frame.set_duration(frame.duration());
         self.frame.set_time_base(frame.time_base());
+        self.frame.set_colorspace(frame.colorspace());
+        self.frame.set_color_range(frame.color_range());
+        self.frame.set_color_primaries(frame.color_primaries());
+        self.frame.set_color_trc(frame.color_trc());
 
         Ok(&self.frame)
     }
@@ -198,6 +198,12 @@ impl<'a> Stream<'a> {
         unsafe { self.0.codecpar.as_ref() }
     }
 
+    /// Returns a mutable reference to the codec parameters of the stream.
+    pub const fn codec_parameters_mut(&mut self) -> Option<&mut AVCodecParameters> {
+        // Safety: the pointer is valid
+        unsafe { self.0.codecpar.as_mut() }
+    }
+
     /// Returns the time base of the stream.
     pub fn time_base(&self) -> Rational {
         self.0.time_base.into()
@@ -254,6 +260,30 @@ impl<'a> Stream<'a> {
         self.0.disposition = disposition;
     }
 
+    /// Returns true if the stream is an attached picture, such as the cover
+    /// art of an audio file.
+    pub const fn is_attached_pic(&self) -> bool {
+        self.0.disposition & AV_DISPOSITION_ATTACHED_PIC as i32 != 0
+    }
+
+    /// Returns the data of the attached picture, if the stream is one.
+    ///
+    /// The picture is stored as a single packet which is read when the input
+    /// is opened, it is usually an encoded image such as a JPEG or a PNG.
+    pub const fn attached_pic(&self) -> Option<&[u8]> {
+        if !self.is_attached_pic() {
+            return None;
+        }
+
+        let packet = &self.0.attached_pic;
+        if packet.data.is_null() || packet.size <= 0 {
+            return None;
+        }
+
+        // Safety: The packet is owned by the stream and its data is valid for `size` bytes.
+        Some(unsafe { std::slice::from_raw_parts(packet.data, packet.size as usize) })
+    }
+
     /// Returns the discard flag of the stream.
     pub const fn discard(&self) -> AVDiscard {
         AVDiscard(self.0.discard)
@@ -0,0 +1,239 @@
+use crate::error::FfmpegError;
+use crate::io::Output;
+use crate::packet::Packet;
+use crate::rational::Rational;
+use crate::stream::Stream;
+
+/// A stream of the tee and where its packets are written in every output.
+#[derive(Debug, Clone)]
+struct TeeStream {
+    /// The time base of the packets written to the tee.
+    time_base: Rational,
+    /// The stream index and time base in each output, in output order.
+    targets: Vec<(i32, Rational)>,
+}
+
+/// Writes the same packets to several [`Output`]s, for example to archive a
+/// stream while also sending it live, without decoding or encoding it twice.
+///
+/// Streams are added to every output with [`Tee::add_stream`], packets
+/// written to the tee use the index it returns as their stream index and are
+/// rescaled to the time base of each output stream, which can differ between
+/// muxers. Every output gets its own header and trailer.
+///
+/// ```rust,no_run
+/// # use scuffle_ffmpeg::io::{Input, Output};
+/// # use scuffle_ffmpeg::tee::Tee;
+/// # fn main() -> Result<(), scuffle_ffmpeg::error::FfmpegError> {
+/// let mut input = Input::open("input.mp4")?;
+/// let mut tee = Tee::new([Output::open("archive.mkv")?, Output::open("live.flv")?])?;
+///
+/// for stream in input.streams().iter() {
+///     tee.add_stream(&stream)?;
+/// }
+///
+/// tee.write_header()?;
+/// while let Some(packet) = input.receive_packet()? {
+///     tee.write_packet(&packet)?;
+/// }
+/// tee.write_trailer()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Tee<O: Send + Sync> {
+    outputs: Vec<Output<O>>,
+    streams: Vec<TeeStream>,
+}
+
+impl<O: Send + Sync> std::fmt::Debug for Tee<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tee")
+            .field("outputs", &self.outputs.len())
+            .field("streams", &self.streams)
+            .finish()
+    }
+}
+
+impl<O: Send + Sync> Tee<O> {
+    /// Creates a new `Tee` writing to the given outputs, which must not have
+    /// any streams yet.
+    pub fn new(outputs: impl IntoIterator<Item = Output<O>>) -> Result<Self, FfmpegError> {
+        let outputs = outputs.into_iter().collect::<Vec<_>>();
+        if outputs.is_empty() {
+            return Err(FfmpegError::Arguments("tee requires at least one output"));
+        }
+
+        if outputs.iter().any(|output| !output.streams().is_empty()) {
+            return Err(FfmpegError::Arguments("tee outputs must not have any streams"));
+        }
+
+        Ok(Self {
+            outputs,
+            streams: Vec::new(),
+        })
+    }
+
+    /// Adds a copy of the stream to every output, returning the stream index
+    /// to write its packets with.
+    ///
+    /// The stream can be the stream of an [`Input`](crate::io::Input) for a
+    /// stream copy, or the stream an [`Encoder`](crate::encoder::Encoder) has
+    /// added to another output, the packets are expected in its time base.
+    pub fn add_stream(&mut self, stream: &Stream<'_>) -> Result<i32, FfmpegError> {
+        let mut targets = Vec::with_capacity(self.outputs.len());
+
+        for output in &mut self.outputs {
+            let mut out_stream = output.copy_stream(stream)?.ok_or(FfmpegError::NoStream)?;
+
+            // The tag is specific to the source container, let the muxer pick its own.
+            if let Some(codec_parameters) = out_stream.codec_parameters_mut() {
+                codec_parameters.codec_tag = 0;
+            }
+
+            targets.push((out_stream.index(), out_stream.time_base()));
+        }
+
+        self.streams.push(TeeStream {
+            time_base: stream.time_base(),
+            targets,
+        });
+
+        Ok(self.streams.len() as i32 - 1)
+    }
+
+    /// Returns the outputs of the tee.
+    pub fn outputs(&self) -> &[Output<O>] {
+        &self.outputs
+    }
+
+    /// Returns the outputs of the tee mutably, e.g. to set their metadata
+    /// before the header is written.
+    pub fn outputs_mut(&mut self) -> &mut [Output<O>] {
+        &mut self.outputs
+    }
+
+    /// Writes the header of every output.
+    pub fn write_header(&mut self) -> Result<(), FfmpegError> {
+        for output in &mut self.outputs {
+            output.write_header()?;
+        }
+
+        // The muxers may have changed the time bases when writing the header.
+        for (output_index, output) in self.outputs.iter().enumerate() {
+            let streams = output.streams();
+            for stream in &mut self.streams {
+                let (index, time_base) = &mut stream.targets[output_index];
+                *time_base = streams.iter().nth(*index as usize).ok_or(FfmpegError::NoStream)?.time_base();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the packet to every output, interleaving it with the other
+    /// packets of each output.
+    ///
+    /// The stream index of the packet is the index returned by
+    /// [`Tee::add_stream`]. If writing to one output fails the error is
+    /// returned right away, and the packet may have been written to some of
+    /// the other outputs.
+    pub fn write_packet(&mut self, packet: &Packet) -> Result<(), FfmpegError> {
+        let stream = usize::try_from(packet.stream_index())
+            .ok()
+            .and_then(|index| self.streams.get(index))
+            .ok_or(FfmpegError::NoStream)?;
+
+        for (output, &(index, time_base)) in self.outputs.iter_mut().zip(&stream.targets) {
+            let mut packet = packet.clone();
+            packet.convert_timebase(stream.time_base, time_base);
+            packet.set_stream_index(index);
+            packet.set_pos(None);
+            output.write_interleaved_packet(packet)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the trailer of every output.
+    pub fn write_trailer(&mut self) -> Result<(), FfmpegError> {
+        for output in &mut self.outputs {
+            output.write_trailer()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the outputs, in the order they were given to [`Tee::new`].
+    pub fn into_outputs(self) -> Vec<Output<O>> {
+        self.outputs
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io::Cursor;
+
+    use super::Tee;
+    use crate::error::FfmpegError;
+    use crate::io::Input;
+    use crate::test_util::{asset, output};
+
+    #[test]
+    fn test_tee() {
+        let mut input = Input::seekable(asset("avc_aac.mp4")).unwrap();
+        let mut tee = Tee::new([output("mp4"), output("flv")]).unwrap();
+
+        for stream in input.streams().iter() {
+            assert_eq!(tee.add_stream(&stream).unwrap(), stream.index());
+        }
+
+        tee.write_header().unwrap();
+        let mut packets = Vec::new();
+        while let Some(packet) = input.receive_packet().unwrap() {
+            tee.write_packet(&packet).unwrap();
+            packets.push(packet);
+        }
+        tee.write_trailer().unwrap();
+
+        let input_streams = input
+            .streams()
+            .iter()
+            .map(|s| s.codec_parameters().unwrap().codec_id)
+            .collect::<Vec<_>>();
+
+        for output in tee.into_outputs() {
+            let mut remuxed = Input::seekable(Cursor::new(output.into_inner().into_inner())).unwrap();
+            let remuxed_streams = remuxed
+                .streams()
+                .iter()
+                .map(|s| s.codec_parameters().unwrap().codec_id)
+                .collect::<Vec<_>>();
+            assert_eq!(remuxed_streams, input_streams);
+
+            let mut remuxed_packets = remuxed.packets().collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(remuxed_packets.len(), packets.len());
+
+            // The muxers interleave the streams differently, compare each stream in order.
+            remuxed_packets.sort_by_key(|p| p.stream_index());
+            let mut expected = packets.iter().collect::<Vec<_>>();
+            expected.sort_by_key(|p| p.stream_index());
+            for (remuxed, expected) in remuxed_packets.iter().zip(expected) {
+                assert_eq!(remuxed.stream_index(), expected.stream_index());
+                assert_eq!(remuxed.data(), expected.data());
+            }
+        }
+    }
+
+    #[test]
+    fn test_tee_errors() {
+        assert_eq!(
+            Tee::<Cursor<Vec<u8>>>::new([]).unwrap_err(),
+            FfmpegError::Arguments("tee requires at least one output")
+        );
+
+        let mut tee = Tee::new([output("mp4")]).unwrap();
+        let packet = crate::packet::Packet::new().unwrap();
+        assert_eq!(tee.write_packet(&packet).unwrap_err(), FfmpegError::NoStream);
+    }
+}
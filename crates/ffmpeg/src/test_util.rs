@@ -0,0 +1,80 @@
+//! Fixtures shared between the unit tests of the different modules.
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use crate::codec::EncoderCodec;
+use crate::decoder::{Decoder, VideoDecoder};
+use crate::encoder::{Encoder, VideoEncoderSettings};
+use crate::frame::GenericFrame;
+use crate::io::{Input, Output, OutputOptions};
+use crate::packet::Packet;
+use crate::{AVCodecID, AVMediaType};
+
+/// Opens a file in the `assets` directory at the root of the repository.
+pub(crate) fn asset(name: &str) -> std::fs::File {
+    std::fs::File::open(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets").join(name)).unwrap()
+}
+
+/// Creates a seekable in-memory output with the given format.
+pub(crate) fn output(format: &str) -> Output<Cursor<Vec<u8>>> {
+    Output::seekable(
+        Cursor::new(Vec::new()),
+        OutputOptions::builder().format_name(format).unwrap().build(),
+    )
+    .unwrap()
+}
+
+/// Decodes the video of `avc_aac.mp4` and encodes it as MPEG-4 with the
+/// settings returned by `settings`, passing every packet the encoder outputs
+/// to `on_packet`.
+///
+/// The decoder is drained, the encoder is not. It is returned together with
+/// the number of frames which were sent to it.
+pub(crate) fn encode_video(
+    settings: impl FnOnce(&VideoDecoder) -> VideoEncoderSettings,
+    mut on_packet: impl FnMut(Packet),
+) -> (Encoder, usize) {
+    let mut input = Input::seekable(asset("avc_aac.mp4")).expect("Failed to open input file");
+    let streams = input.streams();
+    let video_stream = streams.best(AVMediaType::Video).expect("No video stream found");
+    let mut decoder = Decoder::new(&video_stream)
+        .expect("Failed to create decoder")
+        .video()
+        .expect("Failed to create video decoder");
+    let mut encoder = Encoder::new(
+        EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder"),
+        &mut output("mp4"),
+        video_stream.time_base(),
+        video_stream.time_base(),
+        settings(&decoder),
+    )
+    .expect("Failed to create encoder");
+
+    let input_stream_index = video_stream.index();
+    let mut frames = 0;
+
+    let mut encode = |encoder: &mut Encoder, frame: &GenericFrame| {
+        frames += 1;
+        encoder.send_frame(frame).expect("Failed to send frame");
+        while let Some(packet) = encoder.receive_packet().expect("Failed to receive packet") {
+            on_packet(packet);
+        }
+    };
+
+    while let Some(packet) = input.receive_packet().expect("Failed to receive packet") {
+        if packet.stream_index() == input_stream_index {
+            decoder.send_packet(&packet).expect("Failed to send packet");
+            while let Some(frame) = decoder.receive_frame().expect("Failed to receive frame") {
+                encode(&mut encoder, &frame);
+            }
+        }
+    }
+
+    for frame in decoder.finish() {
+        encode(&mut encoder, &frame.expect("Failed to receive frame"));
+    }
+    assert!(decoder.finish().next().is_none());
+
+    (encoder, frames)
+}
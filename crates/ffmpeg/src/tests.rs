@@ -0,0 +1,175 @@
+//! Construct and drop every RAII wrapper repeatedly.
+//!
+//! These tests do not assert much on their own, they exist so that `just grind`
+//! (valgrind with `--leak-check=full`) can catch leaks and double frees in the
+//! `Drop` / [`SmartPtr`](crate::smart_object::SmartPtr) implementations of the
+//! wrapper types.
+
+use std::io::Cursor;
+
+use rusty_ffmpeg::ffi::AVRational;
+
+use crate::codec::EncoderCodec;
+use crate::decoder::Decoder;
+use crate::encoder::{Encoder, VideoEncoderSettings};
+use crate::filter_graph::{Filter, FilterGraph};
+use crate::frame::{GenericFrame, VideoFrame};
+use crate::io::{Input, Output, OutputOptions};
+use crate::packet::Packet;
+use crate::scaler::VideoScaler;
+use crate::{AVCodecID, AVMediaType, AVPixelFormat, AVSampleFormat};
+
+#[cfg(not(valgrind))]
+const ITERATIONS: usize = 64;
+#[cfg(valgrind)] // valgrind is slow, a few iterations is enough to find a leak
+const ITERATIONS: usize = 4;
+
+const FILE_PATH: &str = "../../assets/avc_aac_large.mp4";
+
+#[test]
+fn test_raii_packet() {
+    for _ in 0..ITERATIONS {
+        let packet = Packet::new().expect("Failed to create Packet");
+        let clone = packet.clone();
+        drop(packet);
+        drop(clone);
+    }
+}
+
+#[test]
+fn test_raii_frame() {
+    for _ in 0..ITERATIONS {
+        let frame = GenericFrame::new().expect("Failed to create GenericFrame");
+        drop(frame.clone());
+        drop(frame);
+
+        let mut frame = VideoFrame::new().expect("Failed to create VideoFrame");
+        frame.set_width(64);
+        frame.set_height(64);
+        frame.set_format(AVPixelFormat::Yuv420p.into());
+        // Safety: The width, height and format have been set.
+        unsafe { frame.alloc_frame_buffer(None) }.expect("Failed to allocate frame buffer");
+        let clone = frame.clone();
+        drop(frame);
+        drop(clone);
+
+        let mut frame = GenericFrame::new().expect("Failed to create GenericFrame");
+        frame.set_format(AVSampleFormat::S16.into());
+        let mut frame = frame.audio();
+        frame.set_nb_samples(1024);
+        frame.set_sample_rate(44100);
+        frame.set_channel_layout_default(2).expect("Failed to set channel layout");
+        // Safety: The sample format, sample count and channel layout have been set.
+        unsafe { frame.alloc_frame_buffer(None) }.expect("Failed to allocate frame buffer");
+        drop(frame.clone());
+        drop(frame);
+    }
+}
+
+#[test]
+fn test_raii_scaler() {
+    for _ in 0..ITERATIONS {
+        let scaler = VideoScaler::new(64, 64, AVPixelFormat::Yuv420p, 32, 32, AVPixelFormat::Rgb24)
+            .expect("Failed to create VideoScaler");
+        drop(scaler);
+    }
+}
+
+#[test]
+fn test_raii_filter_graph() {
+    for _ in 0..ITERATIONS {
+        let mut graph = FilterGraph::new().expect("Failed to create FilterGraph");
+        graph
+            .add(
+                Filter::get("buffer").expect("Failed to get buffer filter"),
+                "in",
+                "width=64:height=64:pix_fmt=0:time_base=1/30",
+            )
+            .expect("Failed to add buffer filter");
+        graph
+            .add(Filter::get("buffersink").expect("Failed to get buffersink filter"), "out", "")
+            .expect("Failed to add buffersink filter");
+        drop(graph);
+    }
+}
+
+#[test]
+fn test_raii_input() {
+    let data = std::fs::read(FILE_PATH).expect("Failed to read file");
+
+    for _ in 0..ITERATIONS {
+        let input = Input::open(FILE_PATH).expect("Failed to open file");
+        drop(input);
+
+        let input = Input::new(Cursor::new(data.as_slice())).expect("Failed to create Input");
+        drop(input);
+
+        let mut input = Input::seekable(Cursor::new(data.as_slice())).expect("Failed to create seekable Input");
+        let packet = input.receive_packet().expect("Failed to receive packet");
+        drop(input);
+        // The packet must outlive the input it was read from.
+        drop(packet);
+    }
+}
+
+#[test]
+fn test_raii_input_error() {
+    for _ in 0..ITERATIONS {
+        assert!(Input::new(Cursor::new(b"not a media file".as_slice())).is_err());
+    }
+}
+
+#[test]
+fn test_raii_output() {
+    for _ in 0..ITERATIONS {
+        let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+        let output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        drop(output);
+
+        let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+        let output = Output::seekable(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        drop(output.into_inner());
+    }
+}
+
+#[test]
+fn test_raii_decoder() {
+    for _ in 0..ITERATIONS {
+        let input = Input::open(FILE_PATH).expect("Failed to open file");
+
+        for media_type in [AVMediaType::Video, AVMediaType::Audio] {
+            let streams = input.streams();
+            let stream = streams.best(media_type).expect("Failed to find stream");
+            let decoder = Decoder::new(&stream).expect("Failed to create Decoder");
+            drop(decoder);
+        }
+
+        drop(input);
+    }
+}
+
+#[test]
+fn test_raii_encoder() {
+    for _ in 0..ITERATIONS {
+        let options = OutputOptions::builder().format_name("mp4").unwrap().build();
+        let mut output = Output::new(Cursor::new(Vec::new()), options).expect("Failed to create Output");
+        let settings = VideoEncoderSettings::builder()
+            .width(64)
+            .height(64)
+            .frame_rate(30.into())
+            .pixel_format(AVPixelFormat::Yuv420p)
+            .build();
+
+        let encoder = Encoder::new(
+            EncoderCodec::new(AVCodecID::Mpeg4).expect("Failed to find MPEG-4 encoder"),
+            &mut output,
+            AVRational { num: 1, den: 30 },
+            AVRational { num: 1, den: 30 },
+            settings,
+        )
+        .expect("Failed to create Encoder");
+
+        drop(encoder);
+        drop(output);
+    }
+}
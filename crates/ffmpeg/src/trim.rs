@@ -0,0 +1,311 @@
+use std::time::Duration;
+
+use crate::codec::EncoderCodec;
+use crate::decoder::Decoder;
+use crate::encoder::{Encoder, VideoEncoderSettings};
+use crate::error::FfmpegError;
+use crate::frame::VideoFrame;
+use crate::io::{Input, Output};
+use crate::rational::Rational;
+use crate::{AVMediaType, AVPictureType, AVSeekFlag};
+
+/// Cuts the best video stream of an [`Input`] to the frames between two
+/// timestamps and re-encodes them into an [`Output`].
+///
+/// The input is seeked to the keyframe before `start`, the frames before
+/// `start` are decoded (they are needed to decode the frames after them) and
+/// discarded, so the cut is frame accurate rather than keyframe accurate.
+/// Frames are kept if their presentation time is in `start..end`, the
+/// timestamps of the output are rebased so that the first frame starts at
+/// zero.
+///
+/// The timestamps are relative to the start of the stream. Seeking requires a
+/// seekable input, see [`Input::seekable`]. Only the video stream is written,
+/// the encoder uses the size, pixel format and frame rate of the input.
+///
+/// ```rust,no_run
+/// # use std::time::Duration;
+/// # use scuffle_ffmpeg::codec::EncoderCodec;
+/// # use scuffle_ffmpeg::io::{Input, Output};
+/// # use scuffle_ffmpeg::trim::Trimmer;
+/// # use scuffle_ffmpeg::AVCodecID;
+/// # fn main() -> Result<(), scuffle_ffmpeg::error::FfmpegError> {
+/// let input = Input::open("input.mp4")?;
+/// let output = Output::open("clip.mp4")?;
+/// let codec = EncoderCodec::new(AVCodecID::H264).expect("no h264 encoder");
+///
+/// Trimmer::new(input, output, codec, Duration::from_secs(10), Duration::from_secs(15))?.run()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Trimmer<I: Send + Sync, O: Send + Sync> {
+    input: Input<I>,
+    output: Output<O>,
+    codec: EncoderCodec,
+    start: Duration,
+    end: Duration,
+}
+
+impl<I: Send + Sync, O: Send + Sync> std::fmt::Debug for Trimmer<I, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trimmer")
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .finish()
+    }
+}
+
+/// The frames of the trimmed range, in the time base of the input stream.
+#[derive(Debug)]
+struct TrimRange {
+    start: i64,
+    end: i64,
+    /// The timestamp of the first frame which was kept.
+    first: Option<i64>,
+}
+
+impl TrimRange {
+    /// Rebases the timestamp of the frame if it is in the range, returns
+    /// `None` if the frame is before the range and `Some(false)` once the
+    /// frames are past the end.
+    fn rebase(&mut self, frame: &mut VideoFrame) -> Option<bool> {
+        let timestamp = frame.best_effort_timestamp()?;
+        if timestamp < self.start {
+            return None;
+        }
+
+        if timestamp >= self.end {
+            return Some(false);
+        }
+
+        let first = *self.first.get_or_insert(timestamp);
+        frame.set_pts(Some(timestamp - first));
+        // Let the encoder pick the keyframes, the first frame of the cut is rarely one in the input.
+        frame.set_pict_type(AVPictureType::None);
+
+        Some(true)
+    }
+}
+
+impl<I: Send + Sync, O: Send + Sync> Trimmer<I, O> {
+    /// Creates a new `Trimmer` cutting the input to the frames between
+    /// `start` and `end`, encoding them with the given codec.
+    pub fn new(
+        input: Input<I>,
+        output: Output<O>,
+        codec: EncoderCodec,
+        start: Duration,
+        end: Duration,
+    ) -> Result<Self, FfmpegError> {
+        if start >= end {
+            return Err(FfmpegError::Arguments("trim start must be before its end"));
+        }
+
+        Ok(Self {
+            input,
+            output,
+            codec,
+            start,
+            end,
+        })
+    }
+
+    /// Decodes, cuts and encodes the frames, writing the header and trailer,
+    /// and returns the finished output.
+    ///
+    /// Fails with [`FfmpegError::NoFrame`] if the input has no frames in the
+    /// range.
+    pub fn run(mut self) -> Result<Output<O>, FfmpegError> {
+        let (stream_index, time_base, mut range, mut decoder) = {
+            let streams = self.input.streams();
+            let stream = streams.best(AVMediaType::Video).ok_or(FfmpegError::NoStream)?;
+
+            let time_base = stream.time_base();
+            let start_time = stream.start_time().unwrap_or(0);
+            let range = TrimRange {
+                start: start_time + to_timestamp(self.start, time_base)?,
+                end: start_time + to_timestamp(self.end, time_base)?,
+                first: None,
+            };
+
+            let decoder = Decoder::new(&stream)?
+                .video()
+                .map_err(|_| FfmpegError::Arguments("best video stream is not a video stream"))?;
+
+            (stream.index(), time_base, range, decoder)
+        };
+
+        let frame_rate = if decoder.frame_rate().numerator > 0 {
+            decoder.frame_rate()
+        } else {
+            self.input
+                .streams()
+                .best(AVMediaType::Video)
+                .ok_or(FfmpegError::NoStream)?
+                .avg_frame_rate()
+        };
+
+        let mut encoder = Encoder::new(
+            self.codec,
+            &mut self.output,
+            time_base,
+            time_base,
+            VideoEncoderSettings::builder()
+                .width(decoder.width())
+                .height(decoder.height())
+                .frame_rate(frame_rate)
+                .pixel_format(decoder.pixel_format())
+                .sample_aspect_ratio(decoder.sample_aspect_ratio())
+                .build(),
+        )?;
+
+        self.output.write_header()?;
+        self.input.seek(stream_index, range.start, AVSeekFlag::Backward)?;
+
+        let mut encode = |mut frame: VideoFrame, output: &mut Output<O>| -> Result<bool, FfmpegError> {
+            match range.rebase(&mut frame) {
+                Some(true) => {
+                    encoder.send_frame(&frame)?;
+                    while let Some(packet) = encoder.receive_packet()? {
+                        output.write_interleaved_packet(packet)?;
+                    }
+                    Ok(true)
+                }
+                Some(false) => Ok(false),
+                None => Ok(true),
+            }
+        };
+
+        let mut done = false;
+        'decode: while let Some(packet) = self.input.receive_packet()? {
+            if packet.stream_index() != stream_index {
+                continue;
+            }
+
+            decoder.send_packet(&packet)?;
+            while let Some(frame) = decoder.receive_frame()? {
+                if !encode(frame, &mut self.output)? {
+                    done = true;
+                    break 'decode;
+                }
+            }
+        }
+
+        if !done {
+            for frame in decoder.finish() {
+                if !encode(frame?, &mut self.output)? {
+                    break;
+                }
+            }
+        }
+
+        if range.first.is_none() {
+            return Err(FfmpegError::NoFrame);
+        }
+
+        for packet in encoder.finish() {
+            self.output.write_interleaved_packet(packet?)?;
+        }
+
+        self.output.write_trailer()?;
+
+        Ok(self.output)
+    }
+}
+
+/// Converts a duration to a timestamp in the given time base.
+fn to_timestamp(duration: Duration, time_base: Rational) -> Result<i64, FfmpegError> {
+    if time_base.numerator <= 0 {
+        return Err(FfmpegError::Arguments("video stream has an invalid time base"));
+    }
+
+    let timestamp =
+        duration.as_nanos() * time_base.denominator.get() as u128 / (time_base.numerator as u128 * 1_000_000_000);
+    i64::try_from(timestamp).map_err(|_| FfmpegError::Arguments("trim timestamp is out of range"))
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    use super::Trimmer;
+    use crate::codec::EncoderCodec;
+    use crate::error::FfmpegError;
+    use crate::io::Input;
+    use crate::test_util::{asset, output};
+    use crate::{AVCodecID, AVMediaType};
+
+    #[test]
+    fn test_trim() {
+        let input = Input::seekable(asset("avc_aac.mp4")).unwrap();
+        let codec = EncoderCodec::new(AVCodecID::Mpeg4).unwrap();
+
+        let output = Trimmer::new(
+            input,
+            output("mp4"),
+            codec,
+            Duration::from_millis(200),
+            Duration::from_millis(800),
+        )
+        .unwrap()
+        .run()
+        .unwrap();
+
+        let mut trimmed = Input::seekable(Cursor::new(output.into_inner().into_inner())).unwrap();
+        let (video, time_base) = {
+            let streams = trimmed.streams();
+            let stream = streams.best(AVMediaType::Video).unwrap();
+            (stream.index(), stream.time_base())
+        };
+
+        let packets = trimmed
+            .packets()
+            .map(Result::unwrap)
+            .filter(|p| p.stream_index() == video)
+            .collect::<Vec<_>>();
+        assert!(!packets.is_empty());
+
+        let start = packets.iter().filter_map(|p| p.pts()).min().unwrap();
+        assert_eq!(start, 0);
+
+        let end = packets
+            .iter()
+            .filter_map(|p| Some(p.pts()? + p.duration().unwrap_or(0)))
+            .max()
+            .unwrap();
+        let duration = end as f64 * time_base.as_f64();
+        assert!((0.55..=0.65).contains(&duration), "unexpected duration: {duration}");
+    }
+
+    #[test]
+    fn test_trim_errors() {
+        let input = Input::seekable(asset("avc_aac.mp4")).unwrap();
+        let codec = EncoderCodec::new(AVCodecID::Mpeg4).unwrap();
+        assert_eq!(
+            Trimmer::new(
+                input,
+                output("mp4"),
+                codec,
+                Duration::from_millis(800),
+                Duration::from_millis(200)
+            )
+            .unwrap_err(),
+            FfmpegError::Arguments("trim start must be before its end")
+        );
+
+        // The range is after the end of the asset.
+        let input = Input::seekable(asset("avc_aac.mp4")).unwrap();
+        let codec = EncoderCodec::new(AVCodecID::Mpeg4).unwrap();
+        let trimmer = Trimmer::new(
+            input,
+            output("mp4"),
+            codec,
+            Duration::from_secs(3600),
+            Duration::from_secs(3601),
+        )
+        .unwrap();
+        assert_eq!(trimmer.run().err(), Some(FfmpegError::NoFrame));
+    }
+}
@@ -38,6 +38,22 @@ impl AacPacket {
             _ => AacPacket::Unknown { aac_packet_type, data },
         }
     }
+
+    /// Returns the packet type this packet is written with.
+    pub fn packet_type(&self) -> AacPacketType {
+        match self {
+            AacPacket::SequenceHeader(_) => AacPacketType::SequenceHeader,
+            AacPacket::Raw(_) => AacPacketType::Raw,
+            AacPacket::Unknown { aac_packet_type, .. } => *aac_packet_type,
+        }
+    }
+
+    /// Returns the data of the packet, without the packet type.
+    pub fn data(&self) -> &Bytes {
+        match self {
+            AacPacket::SequenceHeader(data) | AacPacket::Raw(data) | AacPacket::Unknown { data, .. } => data,
+        }
+    }
 }
 
 #[cfg(test)]
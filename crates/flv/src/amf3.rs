@@ -0,0 +1,377 @@
+//! A minimal AMF3 decoder used for script data which switched to AMF3 with
+//! the AMF0 `avmplus-object` marker.
+//!
+//! Values are converted into [`Amf0Value`]s so they can be used in the same
+//! way as values decoded from AMF0.
+//!
+//! Defined by:
+//! - amf3-file-format-spec.pdf (Section 3 - AMF 3 Data Types)
+
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+use byteorder::{BigEndian, ReadBytesExt};
+use scuffle_amf0::Amf0Value;
+
+const UNDEFINED: u8 = 0x00;
+const NULL: u8 = 0x01;
+const FALSE: u8 = 0x02;
+const TRUE: u8 = 0x03;
+const INTEGER: u8 = 0x04;
+const DOUBLE: u8 = 0x05;
+const STRING: u8 = 0x06;
+const XML_DOC: u8 = 0x07;
+const DATE: u8 = 0x08;
+const ARRAY: u8 = 0x09;
+const OBJECT: u8 = 0x0a;
+const XML: u8 = 0x0b;
+
+/// How deep arrays and objects can be nested before decoding fails, so that
+/// crafted input cannot overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+/// The traits of an AMF3 object, see section 3.12.
+#[derive(Clone)]
+struct Traits {
+    dynamic: bool,
+    sealed: Vec<String>,
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Decodes AMF3 values, keeping the reference tables between values.
+pub(crate) struct Amf3Decoder<'a> {
+    cursor: io::Cursor<&'a [u8]>,
+    strings: Vec<String>,
+    objects: Vec<Amf0Value<'static>>,
+    traits: Vec<Traits>,
+    depth: usize,
+}
+
+impl<'a> Amf3Decoder<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self {
+            cursor: io::Cursor::new(buf),
+            strings: Vec::new(),
+            objects: Vec::new(),
+            traits: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    /// Returns the number of bytes consumed so far.
+    pub(crate) fn position(&self) -> usize {
+        self.cursor.position() as usize
+    }
+
+    /// Decodes the next value.
+    pub(crate) fn decode(&mut self) -> io::Result<Amf0Value<'static>> {
+        match self.cursor.read_u8()? {
+            UNDEFINED | NULL => Ok(Amf0Value::Null),
+            FALSE => Ok(Amf0Value::Boolean(false)),
+            TRUE => Ok(Amf0Value::Boolean(true)),
+            INTEGER => {
+                // Sign extend the 29 bit integer.
+                let value = ((self.read_u29()? << 3) as i32) >> 3;
+                Ok(Amf0Value::Number(value as f64))
+            }
+            DOUBLE => Ok(Amf0Value::Number(self.cursor.read_f64::<BigEndian>()?)),
+            STRING => Ok(Amf0Value::String(Cow::Owned(self.read_string()?))),
+            XML_DOC | XML => self.read_reference_or(|this, len| {
+                let xml = String::from_utf8(this.read_bytes(len)?).map_err(|_| invalid("invalid utf-8 in xml"))?;
+                Ok(Amf0Value::String(Cow::Owned(xml)))
+            }),
+            DATE => self.read_reference_or(|this, _| Ok(Amf0Value::Number(this.cursor.read_f64::<BigEndian>()?))),
+            marker @ (ARRAY | OBJECT) => {
+                if self.depth >= MAX_DEPTH {
+                    return Err(invalid("amf3 values are nested too deeply"));
+                }
+
+                self.depth += 1;
+                let value = if marker == ARRAY {
+                    self.read_array()
+                } else {
+                    self.read_object()
+                };
+                self.depth -= 1;
+
+                value
+            }
+            _ => Err(invalid("unsupported amf3 type")),
+        }
+    }
+
+    /// Reads `len` bytes, the length is checked against the remaining input
+    /// before allocating as it comes straight from the input.
+    fn read_bytes(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let remaining = self.cursor.get_ref().len() - self.position();
+        if len > remaining {
+            return Err(invalid("amf3 length exceeds the remaining data"));
+        }
+
+        let mut buf = vec![0; len];
+        self.cursor.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads a variable length unsigned 29 bit integer, see section 1.3.1.
+    fn read_u29(&mut self) -> io::Result<u32> {
+        let mut value = 0;
+        for _ in 0..3 {
+            let byte = self.cursor.read_u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Ok((value << 8) | self.cursor.read_u8()? as u32)
+    }
+
+    fn read_string(&mut self) -> io::Result<String> {
+        let header = self.read_u29()?;
+        if header & 1 == 0 {
+            return self
+                .strings
+                .get((header >> 1) as usize)
+                .cloned()
+                .ok_or_else(|| invalid("invalid amf3 string reference"));
+        }
+
+        let buf = self.read_bytes((header >> 1) as usize)?;
+        let string = String::from_utf8(buf).map_err(|_| invalid("invalid utf-8 in string"))?;
+
+        // The empty string is never sent by reference.
+        if !string.is_empty() {
+            self.strings.push(string.clone());
+        }
+
+        Ok(string)
+    }
+
+    /// Reads the `U29O-ref` header shared by complex types, either returning the
+    /// referenced object or decoding a new one with `decode`, which is given the
+    /// remaining bits of the header.
+    fn read_reference_or(
+        &mut self,
+        decode: impl FnOnce(&mut Self, usize) -> io::Result<Amf0Value<'static>>,
+    ) -> io::Result<Amf0Value<'static>> {
+        let header = self.read_u29()?;
+        if header & 1 == 0 {
+            return self.reference(header);
+        }
+
+        // Reserve the slot before decoding so that nested values get the correct
+        // reference indices.
+        let index = self.objects.len();
+        self.objects.push(Amf0Value::Null);
+        let value = decode(self, (header >> 1) as usize)?;
+        self.objects[index] = value.clone();
+
+        Ok(value)
+    }
+
+    fn reference(&self, header: u32) -> io::Result<Amf0Value<'static>> {
+        self.objects
+            .get((header >> 1) as usize)
+            .cloned()
+            .ok_or_else(|| invalid("invalid amf3 object reference"))
+    }
+
+    /// Arrays are converted into objects, the dense part is keyed by the index.
+    fn read_array(&mut self) -> io::Result<Amf0Value<'static>> {
+        self.read_reference_or(|this, dense_len| {
+            let mut properties = Vec::new();
+
+            loop {
+                let key = this.read_string()?;
+                if key.is_empty() {
+                    break;
+                }
+
+                properties.push((Cow::Owned(key), this.decode()?));
+            }
+
+            for index in 0..dense_len {
+                properties.push((Cow::Owned(index.to_string()), this.decode()?));
+            }
+
+            Ok(Amf0Value::Object(Cow::Owned(properties)))
+        })
+    }
+
+    fn read_object(&mut self) -> io::Result<Amf0Value<'static>> {
+        let header = self.read_u29()?;
+        if header & 1 == 0 {
+            return self.reference(header);
+        }
+
+        let traits = if header & 0b10 == 0 {
+            self.traits
+                .get((header >> 2) as usize)
+                .cloned()
+                .ok_or_else(|| invalid("invalid amf3 traits reference"))?
+        } else {
+            if header & 0b100 != 0 {
+                return Err(invalid("externalizable amf3 objects are not supported"));
+            }
+
+            // The class name is not needed.
+            self.read_string()?;
+
+            let traits = Traits {
+                dynamic: header & 0b1000 != 0,
+                sealed: (0..header >> 4).map(|_| self.read_string()).collect::<io::Result<_>>()?,
+            };
+            self.traits.push(traits.clone());
+            traits
+        };
+
+        let index = self.objects.len();
+        self.objects.push(Amf0Value::Null);
+
+        let mut properties = Vec::with_capacity(traits.sealed.len());
+        for key in traits.sealed {
+            properties.push((Cow::Owned(key), self.decode()?));
+        }
+
+        if traits.dynamic {
+            loop {
+                let key = self.read_string()?;
+                if key.is_empty() {
+                    break;
+                }
+
+                properties.push((Cow::Owned(key), self.decode()?));
+            }
+        }
+
+        let value = Amf0Value::Object(Cow::Owned(properties));
+        self.objects[index] = value.clone();
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::*;
+
+    fn decode_all(data: &[u8]) -> Vec<Amf0Value<'static>> {
+        let mut decoder = Amf3Decoder::new(data);
+        let mut values = Vec::new();
+        while decoder.position() < data.len() {
+            values.push(decoder.decode().unwrap());
+        }
+        values
+    }
+
+    #[test]
+    fn test_scalars() {
+        let mut data = vec![
+            UNDEFINED, NULL, FALSE, TRUE, //
+            INTEGER, 0x7f, // 127
+            INTEGER, 0x81, 0x00, // 128
+            INTEGER, 0xff, 0xff, 0xff, 0xff, // -1
+            STRING, 0x07, b'a', b'b', b'c', // "abc"
+            STRING, 0x00, // reference to "abc"
+            DOUBLE,
+        ];
+        data.extend_from_slice(&1.5f64.to_be_bytes());
+
+        assert_eq!(
+            decode_all(&data),
+            [
+                Amf0Value::Null,
+                Amf0Value::Null,
+                Amf0Value::Boolean(false),
+                Amf0Value::Boolean(true),
+                Amf0Value::Number(127.0),
+                Amf0Value::Number(128.0),
+                Amf0Value::Number(-1.0),
+                Amf0Value::String("abc".into()),
+                Amf0Value::String("abc".into()),
+                Amf0Value::Number(1.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_array_and_references() {
+        let data = [
+            ARRAY, 0x05, // 2 dense values
+            0x03, b'k', // key "k"
+            TRUE, 0x01, // end of associative part
+            INTEGER, 0x01, INTEGER, 0x02, // dense values
+            ARRAY, 0x00, // reference to the first array
+            OBJECT, 0x1b, // inline dynamic object with one sealed member
+            0x01, // anonymous class
+            0x03, b'a',  // sealed member "a"
+            FALSE, // a = false
+            0x00, NULL, // dynamic member "k" (string reference) = null
+            0x01, // end of dynamic members
+            OBJECT, 0x01, // traits reference 0
+            TRUE, 0x01, // a = true, no dynamic members
+        ];
+
+        let array = Amf0Value::Object(Cow::Owned(vec![
+            ("k".into(), Amf0Value::Boolean(true)),
+            ("0".into(), Amf0Value::Number(1.0)),
+            ("1".into(), Amf0Value::Number(2.0)),
+        ]));
+
+        assert_eq!(
+            decode_all(&data),
+            [
+                array.clone(),
+                array,
+                Amf0Value::Object(Cow::Owned(vec![
+                    ("a".into(), Amf0Value::Boolean(false)),
+                    ("k".into(), Amf0Value::Null),
+                ])),
+                Amf0Value::Object(Cow::Owned(vec![("a".into(), Amf0Value::Boolean(true))])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            Amf3Decoder::new(&[0x0c, 0x01]).decode().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            Amf3Decoder::new(&[STRING, 0x02]).decode().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_length_exceeds_data() {
+        // A string and an xml document claiming to be about 256 MiB long.
+        for marker in [STRING, XML] {
+            let err = Amf3Decoder::new(&[marker, 0xff, 0xff, 0xff, 0xff, b'a'])
+                .decode()
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_nested_too_deeply() {
+        // Arrays with a single dense value, each containing the next one.
+        let mut data = [ARRAY, 0x03, 0x01].repeat(MAX_DEPTH);
+        data.push(NULL);
+        assert!(Amf3Decoder::new(&data).decode().is_ok());
+
+        let mut data = [ARRAY, 0x03, 0x01].repeat(100_000);
+        data.push(NULL);
+        assert_eq!(
+            Amf3Decoder::new(&data).decode().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
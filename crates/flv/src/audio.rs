@@ -1,6 +1,6 @@
 use std::io;
 
-use byteorder::ReadBytesExt;
+use byteorder::{ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
 use nutype_enum::nutype_enum;
 use scuffle_bytes_util::BytesCursorExt;
@@ -48,6 +48,23 @@ impl AudioData {
             body,
         })
     }
+
+    /// Mux the audio data into the given writer, including the header byte
+    /// with the sound format, rate, size and type.
+    ///
+    /// This is the inverse of [`AudioData::demux`].
+    pub fn mux<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let sound_format = self.body.sound_format();
+        if sound_format.0 > 0b1111 || self.sound_rate.0 > 0b11 || self.sound_size.0 > 0b1 || self.sound_type.0 > 0b1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "audio header field does not fit in its bits",
+            ));
+        }
+
+        writer.write_u8((sound_format.0 << 4) | (self.sound_rate.0 << 2) | (self.sound_size.0 << 1) | self.sound_type.0)?;
+        self.body.mux(writer)
+    }
 }
 
 nutype_enum! {
@@ -121,6 +138,28 @@ impl AudioDataBody {
             }),
         }
     }
+
+    /// Returns the sound format this body is written with.
+    pub fn sound_format(&self) -> SoundFormat {
+        match self {
+            Self::Aac(_) => SoundFormat::Aac,
+            Self::Unknown { sound_format, .. } => *sound_format,
+        }
+    }
+
+    /// Mux the audio data body into the given writer.
+    ///
+    /// This is the inverse of [`AudioDataBody::demux`], the sound format is
+    /// written as part of the [`AudioData`] header.
+    pub fn mux<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::Aac(packet) => {
+                writer.write_u8(packet.packet_type().0)?;
+                writer.write_all(packet.data())
+            }
+            Self::Unknown { data, .. } => writer.write_all(data),
+        }
+    }
 }
 
 nutype_enum! {
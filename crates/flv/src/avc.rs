@@ -1,6 +1,6 @@
 use std::io;
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
 use nutype_enum::nutype_enum;
 use scuffle_bytes_util::BytesCursorExt;
@@ -42,6 +42,39 @@ impl AvcPacket {
             }),
         }
     }
+
+    /// Mux the AVC packet into the given writer, including the packet type
+    /// and the composition time.
+    ///
+    /// This is the inverse of [`AvcPacket::demux`].
+    pub fn mux<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (avc_packet_type, composition_time) = match self {
+            Self::SequenceHeader(_) => (AvcPacketType::SeqHdr, 0),
+            Self::Nalu { composition_time, .. } => (AvcPacketType::Nalu, *composition_time),
+            Self::EndOfSequence => (AvcPacketType::EndOfSequence, 0),
+            Self::Unknown {
+                avc_packet_type,
+                composition_time,
+                ..
+            } => (*avc_packet_type, *composition_time),
+        };
+
+        if composition_time > 0xFF_FFFF {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "composition time does not fit in 24 bits",
+            ));
+        }
+
+        writer.write_u8(avc_packet_type.0)?;
+        writer.write_u24::<BigEndian>(composition_time)?;
+
+        match self {
+            Self::SequenceHeader(config) => config.mux(writer),
+            Self::Nalu { data, .. } | Self::Unknown { data, .. } => writer.write_all(data),
+            Self::EndOfSequence => Ok(()),
+        }
+    }
 }
 
 nutype_enum! {
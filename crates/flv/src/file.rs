@@ -1,8 +1,133 @@
-use byteorder::{BigEndian, ReadBytesExt};
+use std::ops::Range;
+
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use bytes::{Buf, Bytes};
 
+use super::aac::AacPacket;
+use super::audio::AudioDataBody;
+use super::av1::Av1Packet;
+use super::avc::AvcPacket;
 use super::header::FlvHeader;
-use super::tag::FlvTag;
+use super::hevc::HevcPacket;
+use super::script::ScriptData;
+use super::tag::{FlvTag, FlvTagData, FlvTagType, TrackFilter};
+use super::video::{EnhancedPacket, VideoTagBody};
+use super::writer::FlvWriter;
+
+/// The size of the tag header which precedes the tag data.
+const TAG_HEADER_SIZE: u32 = 11;
+
+/// Options for [`FlvFile::demux_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DemuxOptions {
+    /// Which tags should be demuxed.
+    pub filter: TrackFilter,
+    /// Recover from corrupt tags instead of failing.
+    ///
+    /// When a tag fails to parse, or is not followed by a matching
+    /// `PreviousTagSize`, the demuxer scans forward for the next plausible tag
+    /// boundary and resumes from there. The skipped byte ranges are returned
+    /// alongside the file.
+    pub recover: bool,
+}
+
+/// The initialization data of a stream, the sequence header tags a decoder
+/// needs before it can decode any media.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InitSegment {
+    /// The video sequence header, an AVC `SequenceHeader` or an HEVC/AV1
+    /// `SequenceStart`.
+    pub video: Option<FlvTag>,
+    /// The audio sequence header, an AAC `SequenceHeader`.
+    pub audio: Option<FlvTag>,
+}
+
+/// A run of media tags sharing the same [`InitSegment`], see
+/// [`FlvFile::into_segments`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlvSegment {
+    /// The sequence headers the tags are decoded with.
+    pub init: InitSegment,
+    /// Every other tag, in file order.
+    pub tags: Vec<FlvTag>,
+}
+
+/// A sequence header which replaced a different one of the same track,
+/// reported by [`SequenceHeaderTracker`].
+///
+/// This usually means the codec or its parameters changed mid-stream and the
+/// decoder of the track has to be reinitialized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// The index of the tag with the new sequence header, counting every tag
+    /// passed to the tracker.
+    pub tag_index: usize,
+    /// The track which changed, [`FlvTagType::Audio`] or [`FlvTagType::Video`].
+    pub track: FlvTagType,
+    /// The sequence header which was replaced.
+    pub old: FlvTag,
+    /// The new sequence header.
+    pub new: FlvTag,
+}
+
+/// Keeps track of the sequence headers of a stream as its tags are demuxed
+/// one by one, e.g. from a live stream.
+///
+/// ```rust
+/// # use scuffle_flv::file::SequenceHeaderTracker;
+/// # use scuffle_flv::tag::FlvTag;
+/// # fn demux(tags: Vec<FlvTag>) {
+/// let mut tracker = SequenceHeaderTracker::new();
+/// for tag in &tags {
+///     if let Some(change) = tracker.push(tag) {
+///         println!("{:?} changed at tag {}", change.track, change.tag_index);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SequenceHeaderTracker {
+    init: InitSegment,
+    tags: usize,
+}
+
+impl SequenceHeaderTracker {
+    /// Create a tracker which has not seen any tags.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track the next tag of the stream.
+    ///
+    /// Returns a [`ConfigChange`] if the tag is a sequence header which
+    /// differs from the previous one of its track. The first sequence header
+    /// of a track and repeated identical ones are not changes.
+    pub fn push(&mut self, tag: &FlvTag) -> Option<ConfigChange> {
+        let tag_index = self.tags;
+        self.tags += 1;
+
+        let track = sequence_header_type(tag)?;
+        let header = match track {
+            FlvTagType::Video => &mut self.init.video,
+            _ => &mut self.init.audio,
+        };
+
+        match header.replace(tag.clone()) {
+            Some(old) if old.data != tag.data => Some(ConfigChange {
+                tag_index,
+                track,
+                old,
+                new: tag.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The latest sequence headers of the stream.
+    pub fn init(&self) -> &InitSegment {
+        &self.init
+    }
+}
 
 /// An FLV file is a combination of a [`FlvHeader`] followed by the
 /// `FLVFileBody` (which is just a series of [`FlvTag`]s)
@@ -21,6 +146,14 @@ impl FlvFile {
     /// The reader needs to be a [`std::io::Cursor`] with a [`Bytes`] buffer because we
     /// take advantage of zero-copy reading.
     pub fn demux(reader: &mut std::io::Cursor<Bytes>) -> std::io::Result<Self> {
+        Self::demux_with_filter(reader, TrackFilter::All)
+    }
+
+    /// Demux an FLV file from a reader, only keeping the tags included by the
+    /// filter.
+    ///
+    /// Tags which are not included are skipped without parsing their data.
+    pub fn demux_with_filter(reader: &mut std::io::Cursor<Bytes>, filter: TrackFilter) -> std::io::Result<Self> {
         let header = FlvHeader::demux(reader)?;
 
         let mut tags = Vec::new();
@@ -35,10 +168,294 @@ impl FlvFile {
             }
 
             // Demux the tag from the reader.
-            let tag = FlvTag::demux(reader)?;
-            tags.push(tag);
+            if let Some(tag) = FlvTag::demux_with_filter(reader, filter)? {
+                tags.push(tag);
+            }
         }
 
         Ok(FlvFile { header, tags })
     }
+
+    /// Mux the FLV file into the given writer, see [`FlvWriter`].
+    pub fn mux<W: std::io::Write>(&self, writer: W) -> std::io::Result<W> {
+        let mut writer = FlvWriter::new(writer, &self.header)?;
+        for tag in &self.tags {
+            writer.write_tag(tag)?;
+        }
+
+        Ok(writer.into_inner())
+    }
+
+    /// Demux an FLV file from a reader with the given options.
+    ///
+    /// Returns the file and the byte ranges of the reader which were skipped
+    /// while recovering from corrupt tags, which is always empty unless
+    /// [`DemuxOptions::recover`] is set. The header is never recovered, if it
+    /// is corrupt an error is returned.
+    pub fn demux_with_options(
+        reader: &mut std::io::Cursor<Bytes>,
+        options: DemuxOptions,
+    ) -> std::io::Result<(Self, Vec<Range<u64>>)> {
+        if !options.recover {
+            return Ok((Self::demux_with_filter(reader, options.filter)?, Vec::new()));
+        }
+
+        let header = FlvHeader::demux(reader)?;
+        let len = reader.get_ref().len() as u64;
+
+        // PreviousTagSize0, which is always 0.
+        if reader.remaining() < 4 {
+            return Ok((
+                FlvFile {
+                    header,
+                    tags: Vec::new(),
+                },
+                Vec::new(),
+            ));
+        }
+        reader.read_u32::<BigEndian>()?;
+
+        let mut tags = Vec::new();
+        let mut skipped: Vec<Range<u64>> = Vec::new();
+        while reader.has_remaining() {
+            let start = reader.position();
+
+            match Self::demux_checked_tag(reader, options.filter) {
+                Ok(tag) => tags.extend(tag),
+                Err(_) => {
+                    let end = resync(reader.get_ref(), start as usize + 1).map_or(len, |pos| pos as u64);
+                    reader.set_position(end);
+
+                    // A false boundary found while resyncing fails to parse as well, so the
+                    // skipped region continues.
+                    match skipped.last_mut() {
+                        Some(last) if last.end == start => last.end = end,
+                        _ => skipped.push(start..end),
+                    }
+                }
+            }
+        }
+
+        Ok((FlvFile { header, tags }, skipped))
+    }
+
+    /// Demux a tag and the `PreviousTagSize` after it, failing if the size
+    /// does not match the tag.
+    fn demux_checked_tag(reader: &mut std::io::Cursor<Bytes>, filter: TrackFilter) -> std::io::Result<Option<FlvTag>> {
+        let start = reader.position();
+        let tag = FlvTag::demux_with_filter(reader, filter)?;
+
+        // The last tag is not always followed by its size.
+        if reader.has_remaining() {
+            let size = reader.read_u32::<BigEndian>()?;
+            if size as u64 != reader.position() - 4 - start {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "previous tag size does not match the tag",
+                ));
+            }
+        }
+
+        Ok(tag)
+    }
+
+    /// Normalize the timestamps of all tags in the file.
+    ///
+    /// The timestamp of the first tag is treated as the start of the file and
+    /// subtracted from every tag, so that the output starts at zero. Tags
+    /// whose timestamps go backwards (such as the `0` timestamps some live
+    /// encoders write mid-stream) are clamped to the previous timestamp,
+    /// so the output is always monotonically increasing.
+    ///
+    /// If `correct_rollover` is set, a large backwards jump is treated as the
+    /// 24-bit timestamp field wrapping around (some muxers do not write the
+    /// extended timestamp byte) and is corrected for.
+    pub fn normalize_timestamps(&mut self, correct_rollover: bool) {
+        /// A backwards jump larger than half the 24-bit range is treated as a
+        /// rollover.
+        const ROLLOVER_THRESHOLD: u32 = 1 << 23;
+        const ROLLOVER: u64 = 1 << 24;
+
+        let mut offset = None;
+        let mut epoch = 0u64;
+        let mut last_raw = None;
+        let mut last_ms = 0u32;
+
+        for tag in &mut self.tags {
+            let raw = tag.timestamp_ms;
+
+            // A zero timestamp after the start of the file is a missing timestamp,
+            // not a rollover.
+            if offset.is_some() && raw == 0 {
+                tag.timestamp_ms = last_ms;
+                continue;
+            }
+
+            if let Some(last_raw) = last_raw {
+                if correct_rollover && raw < last_raw && last_raw - raw > ROLLOVER_THRESHOLD {
+                    epoch += ROLLOVER;
+                }
+            }
+            last_raw = Some(raw);
+
+            let timestamp = epoch + raw as u64;
+            let offset = *offset.get_or_insert(timestamp);
+
+            last_ms = last_ms.max(timestamp.saturating_sub(offset).min(u32::MAX as u64) as u32);
+            tag.timestamp_ms = last_ms;
+        }
+    }
+
+    /// Returns the `onMetaData` script data of the file, if there is one.
+    pub fn metadata(&self) -> Option<&ScriptData> {
+        self.tags.iter().find_map(|tag| match &tag.data {
+            FlvTagData::ScriptData(script) if script.name == ScriptData::ON_METADATA => Some(script),
+            _ => None,
+        })
+    }
+
+    /// Replace the `onMetaData` script data of the file.
+    ///
+    /// All existing `onMetaData` tags are removed and the given script data is
+    /// inserted as the first tag, with a timestamp of `0`. This is the usual
+    /// step after processing a file to fix metadata which no longer matches
+    /// the tags, such as the duration.
+    ///
+    /// ```rust
+    /// # use scuffle_flv::file::FlvFile;
+    /// # use scuffle_flv::script::ScriptData;
+    /// # use scuffle_amf0::Amf0Value;
+    /// # fn fix(mut file: FlvFile) {
+    /// let mut metadata = file.metadata().cloned().unwrap_or_else(ScriptData::on_metadata);
+    /// metadata.set_property("duration", Amf0Value::Number(file.duration_ms() as f64 / 1000.0));
+    /// file.set_metadata(metadata);
+    /// # }
+    /// ```
+    pub fn set_metadata(&mut self, metadata: ScriptData) {
+        self.tags
+            .retain(|tag| !matches!(&tag.data, FlvTagData::ScriptData(script) if script.name == ScriptData::ON_METADATA));
+        self.tags.insert(
+            0,
+            FlvTag {
+                timestamp_ms: 0,
+                stream_id: 0,
+                data: FlvTagData::ScriptData(metadata),
+            },
+        );
+    }
+
+    /// Returns the duration of the file in milliseconds, the time between the
+    /// first and the last audio or video tag.
+    pub fn duration_ms(&self) -> u32 {
+        let mut timestamps = self
+            .tags
+            .iter()
+            .filter(|tag| matches!(tag.data, FlvTagData::Audio(_) | FlvTagData::Video(_)))
+            .map(|tag| tag.timestamp_ms);
+
+        let Some(first) = timestamps.next() else {
+            return 0;
+        };
+
+        let (min, max) = timestamps.fold((first, first), |(min, max), ts| (min.min(ts), max.max(ts)));
+        max - min
+    }
+
+    /// Returns every sequence header which replaced a different one of the
+    /// same track, see [`SequenceHeaderTracker`].
+    pub fn config_changes(&self) -> Vec<ConfigChange> {
+        let mut tracker = SequenceHeaderTracker::new();
+        self.tags.iter().filter_map(|tag| tracker.push(tag)).collect()
+    }
+
+    /// Split the tags into the sequence headers and the media tags which
+    /// use them, e.g. to build the init segment of a fragmented MP4.
+    ///
+    /// Usually this returns a single segment. A sequence header which replaces
+    /// a different one after media tags have been seen is a codec change, and
+    /// starts a new segment whose init carries over the header of the other
+    /// track. Repeated identical sequence headers are dropped.
+    pub fn into_segments(self) -> Vec<FlvSegment> {
+        let mut segments = Vec::new();
+        let mut current = FlvSegment {
+            init: InitSegment::default(),
+            tags: Vec::new(),
+        };
+
+        for tag in self.tags {
+            let Some(tag_type) = sequence_header_type(&tag) else {
+                current.tags.push(tag);
+                continue;
+            };
+
+            let header = match tag_type {
+                FlvTagType::Video => &current.init.video,
+                _ => &current.init.audio,
+            };
+            match header {
+                Some(header) if header.data == tag.data => continue,
+                // The first header of a track, or one before any media, belongs to the
+                // current segment.
+                Some(_) if !current.tags.is_empty() => {
+                    let init = current.init.clone();
+                    segments.push(std::mem::replace(&mut current, FlvSegment { init, tags: Vec::new() }));
+                }
+                _ => {}
+            }
+
+            match tag_type {
+                FlvTagType::Video => current.init.video = Some(tag),
+                _ => current.init.audio = Some(tag),
+            }
+        }
+
+        segments.push(current);
+        segments
+    }
+}
+
+/// Returns the track of the tag if it is a sequence header.
+fn sequence_header_type(tag: &FlvTag) -> Option<FlvTagType> {
+    match &tag.data {
+        FlvTagData::Video(video) => match &video.body {
+            VideoTagBody::Avc(AvcPacket::SequenceHeader(_))
+            | VideoTagBody::Enhanced(EnhancedPacket::Hevc(HevcPacket::SequenceStart(_)))
+            | VideoTagBody::Enhanced(EnhancedPacket::Av1(Av1Packet::SequenceStart(_))) => Some(FlvTagType::Video),
+            _ => None,
+        },
+        FlvTagData::Audio(audio) => match &audio.body {
+            AudioDataBody::Aac(AacPacket::SequenceHeader(_)) => Some(FlvTagType::Audio),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Finds the next plausible tag boundary at or after `from`.
+///
+/// A boundary is plausible if it starts with a known tag type, has a zero
+/// stream id, and the tag is followed by a `PreviousTagSize` matching its size
+/// (or the end of the data).
+fn resync(buf: &[u8], from: usize) -> Option<usize> {
+    let header_size = TAG_HEADER_SIZE as usize;
+
+    (from..buf.len().saturating_sub(header_size - 1)).find(|&pos| {
+        let header = &buf[pos..pos + header_size];
+
+        let tag_type = FlvTagType::from(header[0]);
+        if !matches!(tag_type, FlvTagType::Audio | FlvTagType::Video | FlvTagType::ScriptData) {
+            return false;
+        }
+
+        if BigEndian::read_u24(&header[8..]) != 0 {
+            return false;
+        }
+
+        let tag_size = TAG_HEADER_SIZE + BigEndian::read_u24(&header[1..]);
+        let end = pos + tag_size as usize;
+        match buf.get(end..end + 4) {
+            Some(size) => BigEndian::read_u32(size) == tag_size,
+            None => end == buf.len(),
+        }
+    })
 }
@@ -1,6 +1,6 @@
 use std::io;
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
 use scuffle_bytes_util::BytesCursorExt;
 
@@ -60,4 +60,19 @@ impl FlvHeader {
             extra,
         })
     }
+
+    /// Mux the FLV header into the given writer.
+    ///
+    /// The data offset is computed from the size of the extra data. This is
+    /// the inverse of [`FlvHeader::demux`].
+    pub fn mux<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let offset = u32::try_from(9 + self.extra.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "header extra data is too large"))?;
+
+        writer.write_all(b"FLV")?;
+        writer.write_u8(self.version)?;
+        writer.write_u8(((self.has_audio as u8) << 2) | self.has_video as u8)?;
+        writer.write_u32::<BigEndian>(offset)?;
+        writer.write_all(&self.extra)
+    }
 }
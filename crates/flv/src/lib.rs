@@ -1,5 +1,5 @@
-//! A pure Rust implementation of the FLV format, allowing for demuxing of FLV
-//! files or streams.
+//! A pure Rust implementation of the FLV format, allowing for demuxing and
+//! muxing of FLV files or streams.
 //!
 //! This does not support all FLV features (mainly those from FLV 10.1), however
 //! it does support some newer features, from the enhanced FLV specification.
@@ -21,6 +21,7 @@
 #![cfg_attr(all(coverage_nightly, test), feature(coverage_attribute))]
 
 pub mod aac;
+mod amf3;
 pub mod audio;
 pub mod av1;
 pub mod avc;
@@ -29,11 +30,14 @@ pub mod header;
 pub mod hevc;
 pub mod script;
 pub mod tag;
+pub mod validate;
 pub mod video;
+pub mod writer;
 
-pub use crate::file::FlvFile;
+pub use crate::file::{ConfigChange, DemuxOptions, FlvFile, FlvSegment, InitSegment, SequenceHeaderTracker};
 pub use crate::header::FlvHeader;
-pub use crate::tag::{FlvTag, FlvTagData, FlvTagType};
+pub use crate::tag::{FlvTag, FlvTagData, FlvTagType, TrackFilter};
+pub use crate::writer::FlvWriter;
 
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
@@ -53,11 +57,13 @@ mod tests {
     use crate::audio::{AudioData, AudioDataBody, SoundRate, SoundSize, SoundType};
     use crate::av1::Av1Packet;
     use crate::avc::AvcPacket;
-    use crate::file::FlvFile;
+    use crate::file::{ConfigChange, DemuxOptions, FlvFile, SequenceHeaderTracker};
+    use crate::header::FlvHeader;
     use crate::hevc::HevcPacket;
     use crate::script::ScriptData;
-    use crate::tag::FlvTagData;
-    use crate::video::{EnhancedPacket, FrameType, VideoFourCC, VideoTagBody, VideoTagHeader};
+    use crate::tag::{FlvTag, FlvTagData, FlvTagType, TrackFilter};
+    use crate::video::{CommandPacket, EnhancedPacket, FrameType, VideoFourCC, VideoTagBody, VideoTagHeader};
+    use crate::writer::FlvWriter;
 
     #[test]
     fn test_demux_flv_avc_aac() {
@@ -868,4 +874,472 @@ mod tests {
 
         assert!(read_seq_end);
     }
+
+    #[test]
+    fn test_normalize_timestamps_av1_aac() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+
+        let data = Bytes::from(std::fs::read(dir.join("av1_aac.flv")).expect("failed to read file"));
+        let mut reader = io::Cursor::new(data);
+
+        let mut flv = FlvFile::demux(&mut reader).expect("failed to demux flv");
+        let raw_max = flv.tags.iter().map(|tag| tag.timestamp_ms).max().expect("expected tags");
+
+        flv.normalize_timestamps(true);
+
+        assert_eq!(flv.tags[0].timestamp_ms, 0);
+
+        let mut last_timestamp = 0;
+        for tag in &flv.tags {
+            assert!(tag.timestamp_ms >= last_timestamp);
+            last_timestamp = tag.timestamp_ms;
+        }
+
+        assert!(last_timestamp > 0);
+        assert!(last_timestamp <= raw_max);
+    }
+
+    #[test]
+    fn test_normalize_timestamps_offset_rollover() {
+        let tag = |timestamp_ms| FlvTag {
+            timestamp_ms,
+            stream_id: 0,
+            data: FlvTagData::Unknown {
+                tag_type: FlvTagType(0),
+                data: Bytes::new(),
+            },
+        };
+
+        let header = FlvHeader {
+            version: 1,
+            has_audio: false,
+            has_video: false,
+            extra: Bytes::new(),
+        };
+
+        let timestamps = [0xFF_FF00, 0xFF_FF10, 0, 0x00_0010, 0x00_0005, 0x00_0020];
+
+        let mut flv = FlvFile {
+            header: header.clone(),
+            tags: timestamps.into_iter().map(tag).collect(),
+        };
+        flv.normalize_timestamps(true);
+        assert_eq!(
+            flv.tags.iter().map(|tag| tag.timestamp_ms).collect::<Vec<_>>(),
+            vec![0x00, 0x10, 0x10, 0x110, 0x110, 0x120]
+        );
+
+        let mut flv = FlvFile {
+            header,
+            tags: timestamps.into_iter().map(tag).collect(),
+        };
+        flv.normalize_timestamps(false);
+        assert_eq!(
+            flv.tags.iter().map(|tag| tag.timestamp_ms).collect::<Vec<_>>(),
+            vec![0x00, 0x10, 0x10, 0x10, 0x10, 0x10]
+        );
+    }
+
+    #[test]
+    fn test_demux_flv_track_filter() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let data = Bytes::from(std::fs::read(dir.join("avc_aac.flv")).expect("failed to read file"));
+
+        let full = FlvFile::demux(&mut io::Cursor::new(data.clone())).expect("failed to demux flv");
+
+        for (filter, is_included) in [
+            (
+                TrackFilter::VideoOnly,
+                (|data| matches!(data, FlvTagData::Video(_))) as fn(&FlvTagData) -> bool,
+            ),
+            (TrackFilter::AudioOnly, |data| matches!(data, FlvTagData::Audio(_))),
+        ] {
+            let filtered =
+                FlvFile::demux_with_filter(&mut io::Cursor::new(data.clone()), filter).expect("failed to demux flv");
+
+            assert_eq!(filtered.header, full.header);
+            assert!(!filtered.tags.is_empty());
+
+            let expected = full.tags.iter().filter(|tag| is_included(&tag.data)).collect::<Vec<_>>();
+            assert_eq!(filtered.tags.iter().collect::<Vec<_>>(), expected);
+        }
+
+        let all = FlvFile::demux_with_filter(&mut io::Cursor::new(data), TrackFilter::All).expect("failed to demux flv");
+        assert_eq!(all, full);
+    }
+
+    #[test]
+    fn test_demux_flv_recover() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let data = std::fs::read(dir.join("avc_aac.flv")).expect("failed to read file");
+
+        let full = FlvFile::demux(&mut io::Cursor::new(Bytes::from(data.clone()))).expect("failed to demux flv");
+
+        // Find the offsets of the tags, after the header and PreviousTagSize0.
+        let mut offsets = Vec::new();
+        let mut pos = 9 + 4;
+        while pos < data.len() {
+            offsets.push(pos);
+            let size = u32::from_be_bytes([0, data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+            pos += 11 + size + 4;
+        }
+        assert_eq!(offsets.len(), full.tags.len());
+
+        // Corrupt the data size of a tag in the middle of the file.
+        let corrupt = offsets.len() / 2;
+        let mut corrupted = data.clone();
+        corrupted[offsets[corrupt] + 1..offsets[corrupt] + 4].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+        let corrupted = Bytes::from(corrupted);
+
+        assert!(FlvFile::demux(&mut io::Cursor::new(corrupted.clone())).is_err());
+
+        let (flv, skipped) = FlvFile::demux_with_options(
+            &mut io::Cursor::new(corrupted),
+            DemuxOptions {
+                recover: true,
+                ..Default::default()
+            },
+        )
+        .expect("failed to demux flv");
+
+        assert_eq!(skipped, vec![offsets[corrupt] as u64..offsets[corrupt + 1] as u64]);
+
+        let mut expected = full.tags.clone();
+        expected.remove(corrupt);
+        assert_eq!(flv.header, full.header);
+        assert_eq!(flv.tags, expected);
+
+        // Nothing is skipped in an intact file.
+        let (flv, skipped) = FlvFile::demux_with_options(
+            &mut io::Cursor::new(Bytes::from(data)),
+            DemuxOptions {
+                recover: true,
+                filter: TrackFilter::All,
+            },
+        )
+        .expect("failed to demux flv");
+        assert!(skipped.is_empty());
+        assert_eq!(flv, full);
+    }
+
+    #[test]
+    fn test_flv_into_segments() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+
+        for (file, is_video_header) in [
+            (
+                "avc_aac.flv",
+                (|body| matches!(body, VideoTagBody::Avc(AvcPacket::SequenceHeader(_)))) as fn(&VideoTagBody) -> bool,
+            ),
+            ("hevc_aac.flv", |body| {
+                matches!(
+                    body,
+                    VideoTagBody::Enhanced(EnhancedPacket::Hevc(HevcPacket::SequenceStart(_)))
+                )
+            }),
+            ("av1_aac.flv", |body| {
+                matches!(body, VideoTagBody::Enhanced(EnhancedPacket::Av1(Av1Packet::SequenceStart(_))))
+            }),
+        ] {
+            let data = Bytes::from(std::fs::read(dir.join(file)).expect("failed to read file"));
+            let flv = FlvFile::demux(&mut io::Cursor::new(data)).expect("failed to demux flv");
+            let tags = flv.tags.len();
+
+            let mut segments = flv.into_segments();
+            assert_eq!(segments.len(), 1, "{file}");
+            let segment = segments.pop().unwrap();
+
+            match segment.init.video.map(|tag| tag.data) {
+                Some(FlvTagData::Video(VideoTagHeader { body, .. })) => assert!(is_video_header(&body), "{file}: {body:?}"),
+                data => panic!("{file}: expected a video sequence header, got {data:?}"),
+            }
+            match segment.init.audio.map(|tag| tag.data) {
+                Some(FlvTagData::Audio(AudioData {
+                    body: AudioDataBody::Aac(AacPacket::SequenceHeader(_)),
+                    ..
+                })) => {}
+                data => panic!("{file}: expected an audio sequence header, got {data:?}"),
+            }
+
+            assert_eq!(segment.tags.len(), tags - 2, "{file}");
+        }
+    }
+
+    #[test]
+    fn test_flv_into_segments_codec_change() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let avc = FlvFile::demux(&mut io::Cursor::new(Bytes::from(
+            std::fs::read(dir.join("avc_aac.flv")).expect("failed to read file"),
+        )))
+        .expect("failed to demux flv");
+        let hevc = FlvFile::demux(&mut io::Cursor::new(Bytes::from(
+            std::fs::read(dir.join("hevc_aac.flv")).expect("failed to read file"),
+        )))
+        .expect("failed to demux flv");
+
+        let avc_segment = avc.clone().into_segments().remove(0);
+        let hevc_segment = hevc.clone().into_segments().remove(0);
+
+        // The video codec changes mid-stream, the audio header and metadata are only
+        // sent once.
+        let is_media = |tag: &FlvTag| !matches!(tag.data, FlvTagData::ScriptData(_));
+        let mut tags = avc.tags;
+        tags.extend(
+            hevc.tags
+                .into_iter()
+                .filter(|tag| is_media(tag) && Some(tag) != hevc_segment.init.audio.as_ref()),
+        );
+        // A repeated identical header does not start a new segment.
+        tags.push(hevc_segment.init.video.clone().unwrap());
+        let spliced = FlvFile {
+            header: avc.header,
+            tags,
+        };
+
+        let segments = spliced.into_segments();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0], avc_segment);
+        assert_eq!(segments[1].init.video, hevc_segment.init.video);
+        assert_eq!(segments[1].init.audio, avc_segment.init.audio);
+        assert_eq!(
+            segments[1].tags,
+            hevc_segment.tags.into_iter().filter(is_media).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_flv_config_changes() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let avc = FlvFile::demux(&mut io::Cursor::new(Bytes::from(
+            std::fs::read(dir.join("avc_aac.flv")).expect("failed to read file"),
+        )))
+        .expect("failed to demux flv");
+        let hevc = FlvFile::demux(&mut io::Cursor::new(Bytes::from(
+            std::fs::read(dir.join("hevc_aac.flv")).expect("failed to read file"),
+        )))
+        .expect("failed to demux flv");
+
+        assert!(avc.config_changes().is_empty());
+
+        let avc_init = avc.clone().into_segments().remove(0).init;
+        let hevc_init = hevc.clone().into_segments().remove(0).init;
+        let avc_header = avc_init.video.clone().unwrap();
+        let hevc_header = hevc_init.video.clone().unwrap();
+
+        // The video sequence header changes mid-stream and is repeated afterwards.
+        let mut tags = avc.tags;
+        let change_index = tags.len();
+        tags.push(hevc_header.clone());
+        tags.push(hevc_header.clone());
+        tags.extend(
+            hevc.tags
+                .into_iter()
+                .filter(|tag| matches!(tag.data, FlvTagData::Video(_)) && *tag != hevc_header),
+        );
+        let spliced = FlvFile {
+            header: avc.header,
+            tags,
+        };
+
+        let changes = spliced.config_changes();
+        assert_eq!(
+            changes,
+            vec![ConfigChange {
+                tag_index: change_index,
+                track: FlvTagType::Video,
+                old: avc_header,
+                new: hevc_header.clone(),
+            }]
+        );
+
+        let mut tracker = SequenceHeaderTracker::new();
+        for tag in &spliced.tags {
+            tracker.push(tag);
+        }
+        assert_eq!(tracker.init().video, Some(hevc_header));
+        assert_eq!(tracker.init().audio, avc_init.audio);
+    }
+
+    #[test]
+    fn test_flv_set_metadata() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let data = Bytes::from(std::fs::read(dir.join("avc_aac.flv")).expect("failed to read file"));
+
+        let mut flv = FlvFile::demux(&mut io::Cursor::new(data)).expect("failed to demux flv");
+        let tags = flv.tags.len();
+        let duration_ms = flv.duration_ms();
+        assert!(duration_ms > 0);
+
+        let mut metadata = flv.metadata().cloned().expect("expected metadata");
+        let width = metadata.property("width").cloned().expect("expected a width");
+        metadata.set_property("duration", Amf0Value::Number(duration_ms as f64 / 1000.0));
+        metadata.set_property("fixedBy", Amf0Value::String("scuffle".into()));
+        flv.set_metadata(metadata);
+
+        // The existing metadata is replaced, not added to.
+        assert_eq!(flv.tags.len(), tags);
+        assert_eq!(flv.tags[0].timestamp_ms, 0);
+        let FlvTagData::ScriptData(metadata) = &flv.tags[0].data else {
+            panic!("expected the metadata first, got {:?}", flv.tags[0].data);
+        };
+
+        let mut buf = Vec::new();
+        metadata.mux(&mut buf).expect("failed to mux metadata");
+        let metadata = ScriptData::demux(&mut io::Cursor::new(Bytes::from(buf))).expect("failed to demux metadata");
+
+        assert_eq!(metadata.name, "onMetaData");
+        assert_eq!(
+            metadata.property("duration"),
+            Some(&Amf0Value::Number(duration_ms as f64 / 1000.0))
+        );
+        assert_eq!(metadata.property("fixedBy"), Some(&Amf0Value::String("scuffle".into())));
+        assert_eq!(metadata.property("width"), Some(&width));
+
+        // A file without metadata gets a new one.
+        let mut metadata = ScriptData::on_metadata();
+        metadata.set_property("duration", Amf0Value::Number(1.5));
+        let mut flv = FlvFile {
+            header: flv.header,
+            tags: flv.tags.split_off(1),
+        };
+        assert!(flv.metadata().is_none());
+        flv.set_metadata(metadata.clone());
+        assert_eq!(flv.metadata(), Some(&metadata));
+    }
+
+    #[test]
+    fn test_mux_enhanced_video_hevc() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        let data = Bytes::from(std::fs::read(dir.join("hevc_aac.flv")).expect("failed to read file"));
+
+        let flv = FlvFile::demux(&mut io::Cursor::new(data)).expect("failed to demux flv");
+
+        let mut count = 0;
+        for tag in flv.tags {
+            let FlvTagData::Video(VideoTagHeader {
+                frame_type,
+                body: VideoTagBody::Enhanced(packet),
+            }) = tag.data
+            else {
+                continue;
+            };
+
+            let mut buf = Vec::new();
+            packet.mux(frame_type, &mut buf).expect("failed to mux packet");
+
+            let header = VideoTagHeader::demux(&mut io::Cursor::new(Bytes::from(buf))).expect("failed to demux packet");
+            assert_eq!(header.frame_type, frame_type);
+            assert_eq!(header.body, VideoTagBody::Enhanced(packet));
+            count += 1;
+        }
+
+        assert!(count > 0, "expected enhanced video tags");
+    }
+
+    #[test]
+    fn test_mux_flv_roundtrip() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+
+        for name in ["avc_aac.flv", "hevc_aac.flv", "av1_aac.flv"] {
+            let data = Bytes::from(std::fs::read(dir.join(name)).expect("failed to read file"));
+            let flv = FlvFile::demux(&mut io::Cursor::new(data.clone())).expect("failed to demux flv");
+
+            let mut writer = FlvWriter::new(Vec::new(), &flv.header).expect("failed to write header");
+            for tag in &flv.tags {
+                writer.write_tag(tag).expect("failed to mux tag");
+            }
+            let buf = Bytes::from(writer.into_inner());
+
+            // Every tag is followed by a matching previous tag size.
+            let (remuxed, skipped) = FlvFile::demux_with_options(
+                &mut io::Cursor::new(buf.clone()),
+                DemuxOptions {
+                    recover: true,
+                    ..Default::default()
+                },
+            )
+            .expect("failed to demux muxed flv");
+            assert!(skipped.is_empty(), "{name}: skipped {skipped:?}");
+            assert_eq!(remuxed, flv, "{name}");
+
+            assert_eq!(flv.mux(Vec::new()).expect("failed to mux flv"), buf, "{name}");
+        }
+
+        // The media tags are written exactly as they were read. The metadata is not, since
+        // its ECMA array is demuxed as an AMF0 object.
+        let data = std::fs::read(dir.join("avc_aac.flv")).expect("failed to read file");
+        let flv = FlvFile::demux(&mut io::Cursor::new(Bytes::from(data.clone()))).expect("failed to demux flv");
+        assert!(matches!(flv.tags[0].data, FlvTagData::ScriptData(_)));
+        let muxed = flv.mux(Vec::new()).expect("failed to mux flv");
+
+        let media = |buf: &[u8]| {
+            // The header, the first previous tag size and the metadata tag with its size.
+            let metadata_end = 13 + 11 + u32::from_be_bytes([0, buf[14], buf[15], buf[16]]) as usize + 4;
+            buf[metadata_end..].to_vec()
+        };
+        assert_eq!(muxed[..13], data[..13]);
+        assert_eq!(media(&muxed), media(&data));
+    }
+
+    #[test]
+    fn test_mux_flv_tag() {
+        let tag = FlvTag {
+            timestamp_ms: 0x1234_5678,
+            stream_id: 0,
+            data: FlvTagData::Video(VideoTagHeader {
+                frame_type: FrameType::Keyframe,
+                body: VideoTagBody::Avc(AvcPacket::Nalu {
+                    composition_time: 40,
+                    data: Bytes::from_static(&[1, 2, 3]),
+                }),
+            }),
+        };
+
+        let mut buf = Vec::new();
+        tag.mux(&mut buf).expect("failed to mux tag");
+        assert_eq!(
+            buf,
+            [
+                9, // video
+                0, 0, 8, // data size
+                0x34, 0x56, 0x78, 0x12, // timestamp, extended
+                0, 0, 0,    // stream id
+                0x17, // keyframe + avc
+                1, 0, 0, 40, // nalu + composition time
+                1, 2, 3,
+            ]
+        );
+        assert_eq!(FlvTag::demux(&mut io::Cursor::new(Bytes::from(buf))).unwrap(), tag);
+
+        let tag = FlvTag {
+            timestamp_ms: 0,
+            stream_id: 1 << 24,
+            data: FlvTagData::Unknown {
+                tag_type: FlvTagType(20),
+                data: Bytes::new(),
+            },
+        };
+        assert_eq!(tag.mux(&mut Vec::new()).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let tag = FlvTag {
+            timestamp_ms: 0,
+            stream_id: 0,
+            data: FlvTagData::Video(VideoTagHeader {
+                frame_type: FrameType::Keyframe,
+                body: VideoTagBody::Command(CommandPacket::StartOfClientSeeking),
+            }),
+        };
+        assert_eq!(tag.mux(&mut Vec::new()).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let header = FlvHeader {
+            version: 1,
+            has_audio: true,
+            has_video: false,
+            extra: Bytes::from_static(&[0xaa]),
+        };
+        let mut buf = Vec::new();
+        header.mux(&mut buf).expect("failed to mux header");
+        assert_eq!(buf, [b'F', b'L', b'V', 1, 0b100, 0, 0, 0, 10, 0xaa]);
+        assert_eq!(FlvHeader::demux(&mut io::Cursor::new(Bytes::from(buf))).unwrap(), header);
+    }
 }
@@ -1,9 +1,16 @@
+use std::borrow::Cow;
 use std::io;
 
 use bytes::Bytes;
-use scuffle_amf0::{Amf0Decoder, Amf0Marker, Amf0Value};
+use scuffle_amf0::{Amf0Decoder, Amf0Encoder, Amf0Marker, Amf0Value};
 use scuffle_bytes_util::BytesCursorExt;
 
+use crate::amf3::Amf3Decoder;
+
+/// FLV Script Data
+///
+/// Values are decoded with AMF0, a top level value which switches to AMF3 (with
+/// the avmplus-object marker) is converted into the equivalent AMF0 value.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ScriptData {
     /// The name of the script data
@@ -13,6 +20,62 @@ pub struct ScriptData {
 }
 
 impl ScriptData {
+    /// The name of the script data which carries the metadata of the stream.
+    pub const ON_METADATA: &'static str = "onMetaData";
+
+    /// Create an `onMetaData` script data with an empty metadata object.
+    pub fn on_metadata() -> Self {
+        Self {
+            name: Self::ON_METADATA.to_owned(),
+            data: vec![Amf0Value::Object(Cow::Owned(Vec::new()))],
+        }
+    }
+
+    /// Returns a property of the metadata object, which is the first value.
+    pub fn property(&self, key: &str) -> Option<&Amf0Value<'static>> {
+        match self.data.first() {
+            Some(Amf0Value::Object(properties)) => properties.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Set a property of the metadata object, which is the first value,
+    /// replacing the property with the same key.
+    ///
+    /// If the first value is not an object, an empty object is inserted
+    /// before the other values.
+    pub fn set_property(&mut self, key: impl Into<Cow<'static, str>>, value: Amf0Value<'static>) {
+        if !matches!(self.data.first(), Some(Amf0Value::Object(_))) {
+            self.data.insert(0, Amf0Value::Object(Cow::Owned(Vec::new())));
+        }
+
+        let Some(Amf0Value::Object(properties)) = self.data.first_mut() else {
+            unreachable!("the first value is an object");
+        };
+
+        let key = key.into();
+        let properties = properties.to_mut();
+        match properties.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => properties.push((key, value)),
+        }
+    }
+
+    /// Mux the script data into the given writer, encoding all values with
+    /// AMF0.
+    ///
+    /// This is the inverse of [`ScriptData::demux`].
+    pub fn mux<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let to_io = |err| io::Error::new(io::ErrorKind::InvalidInput, err);
+
+        Amf0Encoder::encode_string(writer, &self.name).map_err(to_io)?;
+        for value in &self.data {
+            Amf0Encoder::encode(writer, value).map_err(to_io)?;
+        }
+
+        Ok(())
+    }
+
     pub fn demux(reader: &mut io::Cursor<Bytes>) -> io::Result<Self> {
         let buf = reader.extract_remaining();
         let mut amf0_reader = Amf0Decoder::new(&buf);
@@ -22,13 +85,29 @@ impl ScriptData {
             _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid script data name")),
         };
 
-        let data = amf0_reader
-            .decode_all()
-            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid script data"))?;
+        let mut rest = &buf[amf0_reader.position()..];
+        let mut data = Vec::new();
+
+        // Values prefixed with the avmplus-object marker are encoded with AMF3,
+        // they are converted into AMF0 values.
+        while let Some((&marker, remaining)) = rest.split_first() {
+            if marker == Amf0Marker::AVMPlusObject as u8 {
+                let mut amf3_reader = Amf3Decoder::new(remaining);
+                data.push(amf3_reader.decode()?);
+                rest = &remaining[amf3_reader.position()..];
+            } else {
+                let mut amf0_reader = Amf0Decoder::new(rest);
+                let value = amf0_reader
+                    .decode()
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid script data"))?;
+                data.push(value.to_owned());
+                rest = &rest[amf0_reader.position()..];
+            }
+        }
 
         Ok(Self {
             name: name.into_owned(),
-            data: data.into_iter().map(|v| v.to_owned()).collect(),
+            data,
         })
     }
 }
@@ -53,4 +132,39 @@ mod tests {
         assert_eq!(script_data.data[0], Amf0Value::Null);
         assert_eq!(script_data.data[1], Amf0Value::Null);
     }
+
+    #[test]
+    fn test_script_data_amf3() {
+        let mut data = vec![
+            0x02, // String marker
+            0x00, 0x0A, // Length (10 bytes)
+            b'o', b'n', b'M', b'e', b't', b'a', b'D', b'a', b't', b'a', // "onMetaData"
+            0x11, // avmplus-object marker
+            0x0A, 0x0B, 0x01, // AMF3 anonymous dynamic object
+            0x0B, b'w', b'i', b'd', b't', b'h', // "width"
+            0x04, 0x8A, 0x00, // integer 1280
+            0x13, b'f', b'r', b'a', b'm', b'e', b'r', b'a', b't', b'e', // "framerate"
+            0x05, // double
+        ];
+        data.extend_from_slice(&29.97f64.to_be_bytes());
+        data.extend_from_slice(&[
+            0x0F, b'e', b'n', b'c', b'o', b'd', b'e', b'r', // "encoder"
+            0x06, 0x0B, b'o', b'b', b's', b'-', b'1', // string "obs-1"
+            0x01, // end of dynamic members
+            0x05, // AMF0 null marker
+        ]);
+
+        let script_data = ScriptData::demux(&mut io::Cursor::new(Bytes::from(data))).unwrap();
+        assert_eq!(script_data.name, "onMetaData");
+        assert_eq!(script_data.data.len(), 2);
+        assert_eq!(script_data.data[1], Amf0Value::Null);
+
+        let Amf0Value::Object(metadata) = &script_data.data[0] else {
+            panic!("expected object, got {:?}", script_data.data[0]);
+        };
+        let get = |key: &str| metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+        assert_eq!(get("width"), Some(Amf0Value::Number(1280.0)));
+        assert_eq!(get("framerate"), Some(Amf0Value::Number(29.97)));
+        assert_eq!(get("encoder"), Some(Amf0Value::String("obs-1".into())));
+    }
 }
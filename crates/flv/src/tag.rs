@@ -1,4 +1,4 @@
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
 use nutype_enum::nutype_enum;
 use scuffle_bytes_util::BytesCursorExt;
@@ -41,15 +41,7 @@ impl FlvTag {
     /// The reader needs to be a [`std::io::Cursor`] with a [`Bytes`] buffer because we
     /// take advantage of zero-copy reading.
     pub fn demux(reader: &mut std::io::Cursor<Bytes>) -> std::io::Result<Self> {
-        let tag_type = FlvTagType::from(reader.read_u8()?);
-
-        let data_size = reader.read_u24::<BigEndian>()?;
-        // The timestamp bit is weird. Its 24bits but then there is an extended 8 bit
-        // number to create a 32bit number.
-        let timestamp_ms = reader.read_u24::<BigEndian>()? | ((reader.read_u8()? as u32) << 24);
-
-        // The stream id according to the spec is ALWAYS 0. (likely not true)
-        let stream_id = reader.read_u24::<BigEndian>()?;
+        let (tag_type, data_size, timestamp_ms, stream_id) = Self::demux_header(reader)?;
 
         // We then extract the data from the reader. (advancing the cursor to the end of
         // the tag)
@@ -64,6 +56,105 @@ impl FlvTag {
             data,
         })
     }
+
+    /// Demux a FLV tag from the given reader, if the tag is included by the
+    /// filter.
+    ///
+    /// The reader will be advanced to the end of the tag. Tags which are not
+    /// included by the filter are skipped without parsing their data and
+    /// `None` is returned.
+    pub fn demux_with_filter(reader: &mut std::io::Cursor<Bytes>, filter: TrackFilter) -> std::io::Result<Option<Self>> {
+        let (tag_type, data_size, timestamp_ms, stream_id) = Self::demux_header(reader)?;
+
+        // Skip over the data, this is O(1) since we only slice the buffer.
+        let data = reader.extract_bytes(data_size as usize)?;
+
+        if !filter.includes(tag_type) {
+            return Ok(None);
+        }
+
+        let data = FlvTagData::demux(tag_type, &mut std::io::Cursor::new(data))?;
+
+        Ok(Some(FlvTag {
+            timestamp_ms,
+            stream_id,
+            data,
+        }))
+    }
+
+    /// Mux the FLV tag into the given writer, including the tag header.
+    ///
+    /// The `PreviousTagSize` which follows every tag in a file is not written,
+    /// see [`FlvWriter`](crate::writer::FlvWriter). This is the inverse of
+    /// [`FlvTag::demux`].
+    pub fn mux<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        // The header starts with the size of the data, so the data is muxed first.
+        let mut data = Vec::new();
+        self.data.mux(&mut data)?;
+
+        let data_size = u32::try_from(data.len())
+            .ok()
+            .filter(|size| *size <= 0xFF_FFFF)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "tag data does not fit in 24 bits"))?;
+
+        if self.stream_id > 0xFF_FFFF {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "stream id does not fit in 24 bits",
+            ));
+        }
+
+        writer.write_u8(self.data.tag_type().0)?;
+        writer.write_u24::<BigEndian>(data_size)?;
+        // The lower 24 bits of the timestamp followed by the upper 8 bits.
+        writer.write_u24::<BigEndian>(self.timestamp_ms & 0xFF_FFFF)?;
+        writer.write_u8((self.timestamp_ms >> 24) as u8)?;
+        writer.write_u24::<BigEndian>(self.stream_id)?;
+        writer.write_all(&data)
+    }
+
+    /// Demux the tag header, returning the tag type, data size, timestamp and
+    /// stream id.
+    fn demux_header(reader: &mut std::io::Cursor<Bytes>) -> std::io::Result<(FlvTagType, u32, u32, u32)> {
+        let tag_type = FlvTagType::from(reader.read_u8()?);
+
+        let data_size = reader.read_u24::<BigEndian>()?;
+        // The timestamp bit is weird. Its 24bits but then there is an extended 8 bit
+        // number to create a 32bit number.
+        let timestamp_ms = reader.read_u24::<BigEndian>()? | ((reader.read_u8()? as u32) << 24);
+
+        // The stream id according to the spec is ALWAYS 0. (likely not true)
+        let stream_id = reader.read_u24::<BigEndian>()?;
+
+        Ok((tag_type, data_size, timestamp_ms, stream_id))
+    }
+}
+
+/// A filter for which tags should be demuxed.
+///
+/// Tags which are not included are skipped over without parsing their data,
+/// which is useful when only one track is needed (e.g. building a seek index
+/// from the video tags).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrackFilter {
+    /// Demux all tags.
+    #[default]
+    All,
+    /// Only demux video tags.
+    VideoOnly,
+    /// Only demux audio tags.
+    AudioOnly,
+}
+
+impl TrackFilter {
+    /// Returns true if tags of the given type are included by this filter.
+    pub fn includes(&self, tag_type: FlvTagType) -> bool {
+        match self {
+            TrackFilter::All => true,
+            TrackFilter::VideoOnly => tag_type == FlvTagType::Video,
+            TrackFilter::AudioOnly => tag_type == FlvTagType::Audio,
+        }
+    }
 }
 
 nutype_enum! {
@@ -135,4 +226,26 @@ impl FlvTagData {
             }),
         }
     }
+
+    /// Returns the tag type this data is written with.
+    pub fn tag_type(&self) -> FlvTagType {
+        match self {
+            FlvTagData::Audio(_) => FlvTagType::Audio,
+            FlvTagData::Video(_) => FlvTagType::Video,
+            FlvTagData::ScriptData(_) => FlvTagType::ScriptData,
+            FlvTagData::Unknown { tag_type, .. } => *tag_type,
+        }
+    }
+
+    /// Mux the FLV tag data into the given writer.
+    ///
+    /// This is the inverse of [`FlvTagData::demux`].
+    pub fn mux<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            FlvTagData::Audio(audio) => audio.mux(writer),
+            FlvTagData::Video(video) => video.mux(writer),
+            FlvTagData::ScriptData(script) => script.mux(writer),
+            FlvTagData::Unknown { data, .. } => writer.write_all(data),
+        }
+    }
 }
@@ -0,0 +1,400 @@
+use std::io;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use bytes::{Buf, Bytes};
+
+use super::tag::{FlvTagData, FlvTagType};
+
+/// How severe a [`ConformanceIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file violates the spec, but can still be demuxed.
+    Warning,
+    /// The file cannot be (fully) demuxed.
+    Error,
+}
+
+/// The kind of a [`ConformanceIssue`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IssueKind {
+    /// The file does not start with the `FLV` signature.
+    #[error("invalid signature {0:?}, expected \"FLV\"")]
+    InvalidSignature([u8; 3]),
+    /// The header version is not 1.
+    #[error("unsupported version {0}")]
+    UnsupportedVersion(u8),
+    /// Reserved bits of the header flags are set.
+    #[error("reserved header flags set: {0:#010b}")]
+    ReservedHeaderFlags(u8),
+    /// The header data offset is smaller than the header itself.
+    #[error("invalid data offset {0}")]
+    InvalidDataOffset(u32),
+    /// The `PreviousTagSize` field does not match the size of the previous
+    /// tag.
+    #[error("previous tag size is {actual}, expected {expected}")]
+    PreviousTagSizeMismatch {
+        /// The size of the previous tag.
+        expected: u32,
+        /// The size written in the file.
+        actual: u32,
+    },
+    /// Reserved bits of the tag type byte are set.
+    #[error("reserved tag bits set: {0:#010b}")]
+    ReservedTagBits(u8),
+    /// The tag type is not audio, video or script data.
+    #[error("invalid tag type {0}")]
+    InvalidTagType(u8),
+    /// The stream id is not 0.
+    #[error("stream id is {0}, expected 0")]
+    NonZeroStreamId(u32),
+    /// The tag data size is larger than the remaining data.
+    #[error("tag data size {data_size} exceeds the {remaining} remaining bytes")]
+    DataSizeExceedsStream {
+        /// The data size written in the tag header.
+        data_size: u32,
+        /// The number of bytes left in the stream.
+        remaining: u64,
+    },
+    /// The stream ends in the middle of a header.
+    #[error("unexpected end of stream")]
+    UnexpectedEof,
+    /// The tag data could not be demuxed.
+    #[error("invalid tag data: {0}")]
+    InvalidTagData(String),
+    /// The header says the file has no audio (or video) but it contains such
+    /// tags.
+    #[error("header does not announce {0:?} tags")]
+    UnannouncedTrack(FlvTagType),
+}
+
+/// A single spec violation found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceIssue {
+    /// How severe the issue is.
+    pub severity: Severity,
+    /// The byte offset into the file where the issue was found.
+    pub offset: u64,
+    /// The index of the tag the issue belongs to, `None` for issues in the
+    /// file header.
+    ///
+    /// A `PreviousTagSize` field belongs to the tag before it.
+    pub tag_index: Option<usize>,
+    /// What is wrong.
+    pub kind: IssueKind,
+}
+
+impl std::fmt::Display for ConformanceIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} at byte {}", self.severity, self.offset)?;
+        if let Some(tag_index) = self.tag_index {
+            write!(f, " (tag {tag_index})")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// The size of the FLV header without any extra data.
+const HEADER_SIZE: u32 = 9;
+/// The size of a tag header.
+const TAG_HEADER_SIZE: u32 = 11;
+
+/// Validate an FLV file against the spec.
+///
+/// Unlike [`FlvFile::demux`](crate::FlvFile::demux), which stops at the first
+/// error and discards fields such as the signature and reserved bits, this
+/// walks the raw bytes of the file and reports every issue it finds.
+/// An empty result means the file is conformant.
+///
+/// Validation continues past errors where possible, so a single broken file
+/// can produce multiple issues.
+pub fn validate(data: Bytes) -> Vec<ConformanceIssue> {
+    let mut issues = Vec::new();
+    let mut reader = io::Cursor::new(data);
+
+    let mut report = |severity, offset, tag_index, kind| {
+        issues.push(ConformanceIssue {
+            severity,
+            offset,
+            tag_index,
+            kind,
+        });
+    };
+
+    let Some((has_audio, has_video)) = validate_header(&mut reader, &mut report) else {
+        return issues;
+    };
+
+    let mut previous_tag_size = 0;
+    let mut tag_index = 0usize;
+
+    while reader.has_remaining() {
+        let offset = reader.position();
+        let Ok(actual) = reader.read_u32::<BigEndian>() else {
+            report(Severity::Error, offset, tag_index.checked_sub(1), IssueKind::UnexpectedEof);
+            break;
+        };
+
+        if actual != previous_tag_size {
+            report(
+                Severity::Warning,
+                offset,
+                tag_index.checked_sub(1),
+                IssueKind::PreviousTagSizeMismatch {
+                    expected: previous_tag_size,
+                    actual,
+                },
+            );
+        }
+
+        if !reader.has_remaining() {
+            break;
+        }
+
+        let offset = reader.position();
+        let Some(data_size) = validate_tag(&mut reader, tag_index, has_audio, has_video, &mut report) else {
+            break;
+        };
+
+        previous_tag_size = TAG_HEADER_SIZE + data_size;
+        tag_index += 1;
+        debug_assert_eq!(reader.position(), offset + previous_tag_size as u64);
+    }
+
+    issues
+}
+
+type Report<'a> = dyn FnMut(Severity, u64, Option<usize>, IssueKind) + 'a;
+
+/// Validates the file header, returning the audio and video flags or `None`
+/// if the header is truncated.
+fn validate_header(reader: &mut io::Cursor<Bytes>, report: &mut Report<'_>) -> Option<(bool, bool)> {
+    if reader.remaining() < HEADER_SIZE as usize {
+        report(Severity::Error, 0, None, IssueKind::UnexpectedEof);
+        return None;
+    }
+
+    let mut signature = [0; 3];
+    reader.copy_to_slice(&mut signature);
+    if &signature != b"FLV" {
+        report(Severity::Error, 0, None, IssueKind::InvalidSignature(signature));
+    }
+
+    let version = reader.get_u8();
+    if version != 1 {
+        report(Severity::Warning, 3, None, IssueKind::UnsupportedVersion(version));
+    }
+
+    let flags = reader.get_u8();
+    if flags & 0b1111_1010 != 0 {
+        report(Severity::Warning, 4, None, IssueKind::ReservedHeaderFlags(flags));
+    }
+
+    let data_offset = reader.get_u32();
+    if data_offset < HEADER_SIZE {
+        report(Severity::Error, 5, None, IssueKind::InvalidDataOffset(data_offset));
+    } else if reader.remaining() < (data_offset - HEADER_SIZE) as usize {
+        report(Severity::Error, 5, None, IssueKind::InvalidDataOffset(data_offset));
+        return None;
+    } else {
+        reader.advance((data_offset - HEADER_SIZE) as usize);
+    }
+
+    Some((flags & 0b0000_0100 != 0, flags & 0b0000_0001 != 0))
+}
+
+/// Validates a single tag, returning its data size or `None` if the stream
+/// cannot be read any further.
+fn validate_tag(
+    reader: &mut io::Cursor<Bytes>,
+    tag_index: usize,
+    has_audio: bool,
+    has_video: bool,
+    report: &mut Report<'_>,
+) -> Option<u32> {
+    let offset = reader.position();
+    let tag_index = Some(tag_index);
+
+    if reader.remaining() < TAG_HEADER_SIZE as usize {
+        report(Severity::Error, offset, tag_index, IssueKind::UnexpectedEof);
+        return None;
+    }
+
+    let type_byte = reader.get_u8();
+    // The upper 2 bits are reserved and the 3rd is the encryption filter flag.
+    if type_byte & 0b1100_0000 != 0 {
+        report(Severity::Warning, offset, tag_index, IssueKind::ReservedTagBits(type_byte));
+    }
+
+    let tag_type = FlvTagType::from(type_byte & 0b0001_1111);
+    match tag_type {
+        FlvTagType::Audio if !has_audio => {
+            report(Severity::Warning, offset, tag_index, IssueKind::UnannouncedTrack(tag_type))
+        }
+        FlvTagType::Video if !has_video => {
+            report(Severity::Warning, offset, tag_index, IssueKind::UnannouncedTrack(tag_type))
+        }
+        FlvTagType::Audio | FlvTagType::Video | FlvTagType::ScriptData => {}
+        _ => report(Severity::Error, offset, tag_index, IssueKind::InvalidTagType(type_byte)),
+    }
+
+    let data_size = reader.read_u24::<BigEndian>().ok()?;
+    // Timestamp and extended timestamp.
+    reader.advance(4);
+
+    let stream_id = reader.read_u24::<BigEndian>().ok()?;
+    if stream_id != 0 {
+        report(
+            Severity::Warning,
+            offset + 8,
+            tag_index,
+            IssueKind::NonZeroStreamId(stream_id),
+        );
+    }
+
+    let remaining = reader.remaining() as u64;
+    if data_size as u64 > remaining {
+        report(
+            Severity::Error,
+            offset + 1,
+            tag_index,
+            IssueKind::DataSizeExceedsStream { data_size, remaining },
+        );
+        return None;
+    }
+
+    let data = reader.copy_to_bytes(data_size as usize);
+
+    // Encrypted tags (filter flag set) cannot be demuxed, so we only check the
+    // data of plain tags with a known type.
+    if type_byte & 0b0010_0000 == 0 && matches!(tag_type, FlvTagType::Audio | FlvTagType::Video | FlvTagType::ScriptData) {
+        if let Err(err) = FlvTagData::demux(tag_type, &mut io::Cursor::new(data)) {
+            report(
+                Severity::Error,
+                offset + TAG_HEADER_SIZE as u64,
+                tag_index,
+                IssueKind::InvalidTagData(err.to_string()),
+            );
+        }
+    }
+
+    Some(data_size)
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::path::PathBuf;
+
+    use bytes::{BufMut, Bytes, BytesMut};
+
+    use super::{validate, IssueKind, Severity};
+
+    fn assets(name: &str) -> Bytes {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../assets");
+        Bytes::from(std::fs::read(dir.join(name)).expect("failed to read file"))
+    }
+
+    /// A header announcing audio and video followed by the first
+    /// `PreviousTagSize`.
+    fn header(signature: &[u8; 3]) -> BytesMut {
+        let mut buf = BytesMut::new();
+        buf.put_slice(signature);
+        buf.put_u8(1);
+        buf.put_u8(0b0000_0101);
+        buf.put_u32(9);
+        buf.put_u32(0);
+        buf
+    }
+
+    fn put_tag(buf: &mut BytesMut, type_byte: u8, data: &[u8]) {
+        buf.put_u8(type_byte);
+        buf.put_uint(data.len() as u64, 3);
+        buf.put_u32(0);
+        buf.put_uint(0, 3);
+        buf.put_slice(data);
+        buf.put_u32(11 + data.len() as u32);
+    }
+
+    #[test]
+    fn conformant_file() {
+        assert_eq!(validate(assets("avc_aac.flv")), vec![]);
+    }
+
+    #[test]
+    fn bad_signature() {
+        let mut buf = header(b"FLX");
+        // A plain AAC raw frame.
+        put_tag(&mut buf, 8, &[0xaf, 0x01, 0x00]);
+
+        let issues = validate(buf.freeze());
+
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(issues[0].offset, 0);
+        assert_eq!(issues[0].tag_index, None);
+        assert_eq!(issues[0].kind, IssueKind::InvalidSignature(*b"FLX"));
+        assert_eq!(
+            issues[0].to_string(),
+            "Error at byte 0: invalid signature [70, 76, 88], expected \"FLV\""
+        );
+    }
+
+    #[test]
+    fn invalid_tag_type() {
+        let mut buf = header(b"FLV");
+        put_tag(&mut buf, 8, &[0xaf, 0x01, 0x00]);
+        put_tag(&mut buf, 7, &[1, 2, 3]);
+
+        let issues = validate(buf.freeze());
+
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert_eq!(issues[0].severity, Severity::Error);
+        assert_eq!(issues[0].offset, 13 + 14 + 4);
+        assert_eq!(issues[0].tag_index, Some(1));
+        assert_eq!(issues[0].kind, IssueKind::InvalidTagType(7));
+    }
+
+    #[test]
+    fn data_size_exceeds_stream() {
+        let mut buf = header(b"FLV");
+        put_tag(&mut buf, 8, &[0xaf, 0x01, 0x00]);
+        // Truncate the tag data and the trailing previous tag size.
+        buf.truncate(buf.len() - 6);
+
+        let issues = validate(buf.freeze());
+
+        assert_eq!(issues.len(), 1, "{issues:?}");
+        assert_eq!(
+            issues[0].kind,
+            IssueKind::DataSizeExceedsStream {
+                data_size: 3,
+                remaining: 1
+            }
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let mut buf = header(b"FLV");
+        // Reserved header flag bit.
+        buf[4] |= 0b1000_0000;
+        // Reserved tag bit and wrong previous tag size.
+        put_tag(&mut buf, 8 | 0b0100_0000, &[0xaf, 0x01, 0x00]);
+        let len = buf.len();
+        buf[len - 4..].copy_from_slice(&1u32.to_be_bytes());
+        put_tag(&mut buf, 8, &[0xaf, 0x01, 0x00]);
+
+        let issues = validate(buf.freeze());
+        let kinds = issues.iter().map(|issue| issue.kind.clone()).collect::<Vec<_>>();
+
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Warning), "{issues:?}");
+        assert_eq!(
+            kinds,
+            vec![
+                IssueKind::ReservedHeaderFlags(0b1000_0101),
+                IssueKind::ReservedTagBits(0b0100_1000),
+                IssueKind::PreviousTagSizeMismatch { expected: 14, actual: 1 },
+            ]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::io::{self, Read};
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
 use nutype_enum::nutype_enum;
 use scuffle_av1::{AV1CodecConfigurationRecord, AV1VideoDescriptor};
@@ -63,6 +63,52 @@ impl VideoTagHeader {
 
         Ok(VideoTagHeader { frame_type, body })
     }
+
+    /// Mux the video data into the given writer, including the header byte
+    /// with the frame type and the codec id or enhanced packet type.
+    ///
+    /// This is the inverse of [`VideoTagHeader::demux`].
+    pub fn mux<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match &self.body {
+            VideoTagBody::Enhanced(packet) => packet.mux(self.frame_type, writer),
+            VideoTagBody::Avc(packet) => {
+                self.mux_legacy_header(VideoCodecId::Avc, writer)?;
+                packet.mux(writer)
+            }
+            VideoTagBody::Command(command) => {
+                // Command frames do not have a codec.
+                self.mux_legacy_header(VideoCodecId(0), writer)?;
+                writer.write_u8(command.0)
+            }
+            VideoTagBody::Unknown { codec_id, data } => {
+                self.mux_legacy_header(*codec_id, writer)?;
+                writer.write_all(data)
+            }
+        }
+    }
+
+    fn mux_legacy_header<W: io::Write>(&self, codec_id: VideoCodecId, writer: &mut W) -> io::Result<()> {
+        if self.frame_type.0 > 0b0111 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame type does not fit in 3 bits",
+            ));
+        }
+
+        if codec_id.0 > 0b1111 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "codec id does not fit in 4 bits"));
+        }
+
+        // The demuxer reads a command packet for every command frame, and only for those.
+        if (self.frame_type == FrameType::VideoInfoOrCommandFrame) != matches!(self.body, VideoTagBody::Command(_)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only command frames can have a command packet body",
+            ));
+        }
+
+        writer.write_u8((self.frame_type.0 << 4) | codec_id.0)
+    }
 }
 
 nutype_enum! {
@@ -248,6 +294,84 @@ pub enum EnhancedPacket {
     },
 }
 
+impl EnhancedPacket {
+    /// Returns the FourCC of the codec this packet belongs to.
+    pub fn video_codec(&self) -> VideoFourCC {
+        match self {
+            Self::Metadata { video_codec, .. } | Self::SequenceEnd { video_codec } | Self::Unknown { video_codec, .. } => {
+                *video_codec
+            }
+            Self::Av1(_) => VideoFourCC::Av1,
+            Self::Hevc(_) => VideoFourCC::Hevc,
+        }
+    }
+
+    /// Returns the packet type this packet is written with.
+    ///
+    /// HEVC NALUs without a composition time are written as
+    /// [`EnhancedPacketType::CodedFramesX`].
+    pub fn packet_type(&self) -> EnhancedPacketType {
+        match self {
+            Self::Metadata { .. } => EnhancedPacketType::Metadata,
+            Self::SequenceEnd { .. } => EnhancedPacketType::SequenceEnd,
+            Self::Av1(Av1Packet::SequenceStart(_)) | Self::Hevc(HevcPacket::SequenceStart(_)) => {
+                EnhancedPacketType::SequenceStart
+            }
+            Self::Av1(Av1Packet::Raw(_)) => EnhancedPacketType::CodedFrames,
+            Self::Hevc(HevcPacket::Nalu { composition_time, .. }) => {
+                if composition_time.is_some() {
+                    EnhancedPacketType::CodedFrames
+                } else {
+                    EnhancedPacketType::CodedFramesX
+                }
+            }
+            Self::Unknown { packet_type, .. } => *packet_type,
+        }
+    }
+
+    /// Mux the packet into the given writer, including the enhanced video tag
+    /// header byte (the `IsExHeader` bit, the frame type and the packet
+    /// type) and the FourCC.
+    ///
+    /// This is the inverse of [`VideoTagHeader::demux`] for enhanced packets.
+    pub fn mux<W: io::Write>(&self, frame_type: FrameType, writer: &mut W) -> io::Result<()> {
+        if frame_type.0 > 0b0111 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame type does not fit in 3 bits",
+            ));
+        }
+
+        let packet_type = self.packet_type();
+        if packet_type.0 > 0b1111 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "packet type does not fit in 4 bits",
+            ));
+        }
+
+        writer.write_u8(0b1000_0000 | (frame_type.0 << 4) | packet_type.0)?;
+        writer.write_all(&self.video_codec().0)?;
+
+        match self {
+            Self::Metadata { data, .. } | Self::Unknown { data, .. } | Self::Av1(Av1Packet::Raw(data)) => {
+                writer.write_all(data)?;
+            }
+            Self::SequenceEnd { .. } => {}
+            Self::Av1(Av1Packet::SequenceStart(config)) => config.mux(writer)?,
+            Self::Hevc(HevcPacket::SequenceStart(config)) => config.mux(writer)?,
+            Self::Hevc(HevcPacket::Nalu { composition_time, data }) => {
+                if let Some(composition_time) = composition_time {
+                    writer.write_i24::<BigEndian>(*composition_time)?;
+                }
+                writer.write_all(data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 nutype_enum! {
     /// FLV Video FourCC
     ///
@@ -549,6 +673,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_enhanced_packet_mux() {
+        let cases: [(EnhancedPacket, &[u8]); 4] = [
+            (
+                EnhancedPacket::SequenceEnd {
+                    video_codec: VideoFourCC::Hevc,
+                },
+                &[0b1001_0010, b'h', b'v', b'c', b'1'],
+            ),
+            (
+                EnhancedPacket::Hevc(HevcPacket::Nalu {
+                    composition_time: Some(-2),
+                    data: Bytes::from_static(&[1, 2]),
+                }),
+                &[0b1001_0001, b'h', b'v', b'c', b'1', 0xff, 0xff, 0xfe, 1, 2],
+            ),
+            (
+                EnhancedPacket::Hevc(HevcPacket::Nalu {
+                    composition_time: None,
+                    data: Bytes::from_static(&[1, 2]),
+                }),
+                &[0b1001_0011, b'h', b'v', b'c', b'1', 1, 2],
+            ),
+            (
+                EnhancedPacket::Av1(Av1Packet::Raw(Bytes::from_static(&[1, 2]))),
+                &[0b1001_0001, b'a', b'v', b'0', b'1', 1, 2],
+            ),
+        ];
+
+        for (packet, expected) in cases {
+            let mut buf = Vec::new();
+            packet.mux(FrameType::Keyframe, &mut buf).unwrap();
+            assert_eq!(buf, expected);
+
+            let header = VideoTagHeader::demux(&mut io::Cursor::new(Bytes::from(buf))).unwrap();
+            assert_eq!(header.frame_type, FrameType::Keyframe);
+            assert_eq!(header.body, VideoTagBody::Enhanced(packet));
+        }
+
+        let packet = EnhancedPacket::SequenceEnd {
+            video_codec: VideoFourCC::Hevc,
+        };
+        assert_eq!(
+            packet.mux(FrameType(8), &mut Vec::new()).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
     #[test]
     fn test_av1_mpeg2_sequence_start() {
         let mut reader = io::Cursor::new(Bytes::from_static(&[
@@ -0,0 +1,81 @@
+use std::io;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use super::header::FlvHeader;
+use super::tag::FlvTag;
+
+/// Writes an FLV file or stream, the inverse of [`FlvFile::demux`](crate::file::FlvFile::demux).
+///
+/// The [`FlvHeader`] is written when the writer is created, after that each
+/// [`FlvTag`] is written followed by its `PreviousTagSize`.
+///
+/// ```rust
+/// # use bytes::Bytes;
+/// # use scuffle_flv::{FlvFile, FlvHeader, FlvTag, FlvWriter};
+/// # fn mux(tags: Vec<FlvTag>) -> std::io::Result<()> {
+/// let header = FlvHeader {
+///     version: 1,
+///     has_audio: true,
+///     has_video: true,
+///     extra: Bytes::new(),
+/// };
+///
+/// let mut writer = FlvWriter::new(Vec::new(), &header)?;
+/// for tag in &tags {
+///     writer.write_tag(tag)?;
+/// }
+///
+/// let file = FlvFile::demux(&mut std::io::Cursor::new(Bytes::from(writer.into_inner())))?;
+/// assert_eq!(file.tags, tags);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FlvWriter<W: io::Write> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> FlvWriter<W> {
+    /// Create a new writer, writing the header and the first
+    /// `PreviousTagSize` (which is always 0) to the given writer.
+    pub fn new(mut writer: W, header: &FlvHeader) -> io::Result<Self> {
+        header.mux(&mut writer)?;
+        writer.write_u32::<BigEndian>(0)?;
+
+        Ok(Self { writer, buf: Vec::new() })
+    }
+
+    /// Write a tag followed by its `PreviousTagSize`.
+    pub fn write_tag(&mut self, tag: &FlvTag) -> io::Result<()> {
+        self.buf.clear();
+        tag.mux(&mut self.buf)?;
+
+        let size =
+            u32::try_from(self.buf.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "tag is too large"))?;
+
+        self.writer.write_all(&self.buf)?;
+        self.writer.write_u32::<BigEndian>(size)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -2,7 +2,7 @@ use std::io;
 
 use byteorder::{BigEndian, ReadBytesExt};
 use bytes::Bytes;
-use scuffle_bytes_util::BitReader;
+use scuffle_bytes_util::{strip_emulation_prevention, BitReader};
 use scuffle_expgolomb::BitReaderExpGolombExt;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -29,22 +29,10 @@ pub struct ColorConfig {
 
 impl Sps {
     pub fn parse(data: Bytes) -> io::Result<Self> {
-        let mut vec = Vec::with_capacity(data.len());
-
         // We need to remove the emulation prevention byte
         // This is BARELY documented in the spec, but it's there.
         // ISO/IEC-14496-10-2022 - 3.1.48
-        let mut i = 0;
-        while i < data.len() - 3 {
-            if data[i] == 0x00 && data[i + 1] == 0x00 && data[i + 2] == 0x03 {
-                vec.push(0x00);
-                vec.push(0x00);
-                i += 3;
-            } else {
-                vec.push(data[i]);
-                i += 1;
-            }
-        }
+        let vec = strip_emulation_prevention(&data);
 
         let mut bit_reader = BitReader::new_from_slice(vec);
 
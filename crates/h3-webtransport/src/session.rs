@@ -162,6 +162,13 @@ where
         self.inner.lock().unwrap().datagram_request_rx.poll_recv(cx)
     }
 
+    /// Returns a stream of the datagrams received on this session.
+    ///
+    /// The stream ends once the session is closed.
+    pub fn datagrams(&self) -> impl futures_util::Stream<Item = B> + '_ {
+        futures_util::stream::poll_fn(|cx| self.poll_accept_datagram(cx))
+    }
+
     /// Sends a datagram
     pub async fn send_datagram(&self, datagram: B) -> Result<(), h3::Error> {
         let (tx, rx) = oneshot::channel();
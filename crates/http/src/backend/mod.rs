@@ -42,6 +42,39 @@ pub trait HttpServer {
 
     /// Returns the address the server is bound to.
     fn local_addr(&self) -> Result<std::net::SocketAddr, Self::Error>;
+
+    /// Starts the server and serves until one of the given signals is
+    /// received, then gracefully shuts the server down.
+    ///
+    /// This returns once all connections have been closed, or earlier if the
+    /// server stops on its own before a signal is received.
+    ///
+    /// The signal handler is borrowed so it can be used to wait for another
+    /// signal afterwards, for example to force an exit when a second signal
+    /// arrives during the shutdown.
+    #[cfg(feature = "signal")]
+    fn serve_until_signal<S: ConnectionAcceptor + Clone>(
+        &self,
+        service: S,
+        workers: usize,
+        signals: &mut scuffle_signal::SignalHandler,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> {
+        async move {
+            self.start(service, workers).await?;
+
+            {
+                // The wait future must be dropped before shutting down, since
+                // shutting down waits for the same threads.
+                let wait = std::pin::pin!(self.wait());
+                let signal = std::pin::pin!(signals.recv());
+                if let futures::future::Either::Left((result, _)) = futures::future::select(wait, signal).await {
+                    return result;
+                }
+            }
+
+            self.shutdown().await
+        }
+    }
 }
 
 impl HttpServer for Server {
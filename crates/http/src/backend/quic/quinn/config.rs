@@ -267,6 +267,20 @@ impl<C, L> QuinnServerConfigBuilder<C, L> {
         self
     }
 
+    /// Enables webtransport on the http/3 connection, including extended
+    /// CONNECT and datagrams, allowing up to `max_sessions` concurrent
+    /// sessions per connection.
+    #[cfg(feature = "http3-webtransport")]
+    pub fn with_webtransport(self, max_sessions: u64) -> Self {
+        self.modify_http_builder(|builder| {
+            builder
+                .enable_webtransport(true)
+                .enable_connect(true)
+                .enable_datagram(true)
+                .max_webtransport_sessions(max_sessions);
+        })
+    }
+
     pub fn with_idle_timeout(mut self, idle_timeout: impl Into<Option<std::time::Duration>>) -> Self {
         self.idle_timeout = idle_timeout.into();
         self
@@ -39,8 +39,10 @@ impl StartGroup {
         let mut threads = self.threads.lock().await;
 
         while !threads.is_empty() {
-            let (result, _, remaining) = futures::future::select_all(threads.drain(..).map(|thread| thread.disarm())).await;
-            *threads = remaining.into_iter().map(AbortOnDrop::new).collect();
+            // Poll the threads in place so that dropping this future does not lose track
+            // of them.
+            let (result, index, _) = futures::future::select_all(threads.iter_mut()).await;
+            threads.remove(index);
             result??;
         }
 
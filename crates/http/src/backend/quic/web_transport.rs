@@ -1,14 +1,170 @@
 use std::future::Future;
 
 use bytes::Bytes;
-use scuffle_h3_webtransport::session::WebTransportSession;
+
+/// A webtransport session running on top of a quinn connection.
+///
+/// Datagrams can be sent with
+/// [`send_datagram`](scuffle_h3_webtransport::session::WebTransportSession::send_datagram)
+/// and received with
+/// [`datagrams`](scuffle_h3_webtransport::session::WebTransportSession::datagrams).
+/// The server must be configured with
+/// [`with_webtransport`](super::quinn::config::QuinnServerConfigBuilder::with_webtransport)
+/// for datagrams to be negotiated.
+pub type WebTransportSession = scuffle_h3_webtransport::session::WebTransportSession<h3_quinn::Connection, Bytes>;
 
 // TODO: make this generic over any quic connection
 /// Upgrades a request to a webtransport session
 pub async fn upgrade_webtransport<B, F, Fut>(request: &mut http::Request<B>, on_upgrade: F) -> Option<http::Response<()>>
 where
-    F: FnOnce(WebTransportSession<h3_quinn::Connection, Bytes>) -> Fut + Send + Sync + 'static,
+    F: FnOnce(WebTransportSession) -> Fut + Send + Sync + 'static,
     Fut: Future<Output = ()> + Send + Sync + 'static,
 {
     WebTransportSession::begin(request, on_upgrade)
 }
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(all(test, feature = "tls-rustls-pem"))]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::Arc;
+
+    use bytes::{BufMut, Bytes, BytesMut};
+    use futures::StreamExt;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{DigitallySignedStruct, SignatureScheme};
+
+    use super::upgrade_webtransport;
+    use crate::backend::quic::quinn::config::QuinnServerConfig;
+    use crate::backend::HttpServer;
+    use crate::svc::function_service;
+
+    const CERT: &[u8] = include_bytes!("../../../../../assets/tls/one.test.crt");
+    const KEY: &[u8] = include_bytes!("../../../../../assets/tls/one.test.key");
+
+    /// Accepts any certificate, the test only cares about the datagrams.
+    #[derive(Debug)]
+    struct NoVerifier(Arc<rustls::crypto::CryptoProvider>);
+
+    impl ServerCertVerifier for NoVerifier {
+        fn verify_server_cert(
+            &self,
+            _: &CertificateDer<'_>,
+            _: &[CertificateDer<'_>],
+            _: &ServerName<'_>,
+            _: &[u8],
+            _: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+
+    fn client_endpoint() -> quinn::Endpoint {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let mut tls = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+            .with_no_client_auth();
+        tls.alpn_protocols = vec![b"h3".to_vec()];
+
+        let mut endpoint = quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(
+            quinn::crypto::rustls::QuicClientConfig::try_from(tls).unwrap(),
+        )));
+        endpoint
+    }
+
+    #[tokio::test]
+    async fn datagram_echo() {
+        let server = QuinnServerConfig::builder()
+            .with_tls_from_pem(CERT, KEY)
+            .unwrap()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_webtransport(1)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|mut req| async move {
+                    let res = upgrade_webtransport(&mut req, |session| async move {
+                        let mut datagrams = std::pin::pin!(session.datagrams());
+                        while let Some(datagram) = datagrams.next().await {
+                            session.send_datagram(datagram).await.unwrap();
+                        }
+                    })
+                    .await
+                    .unwrap_or_else(|| {
+                        http::Response::builder()
+                            .status(http::StatusCode::BAD_REQUEST)
+                            .body(())
+                            .unwrap()
+                    });
+
+                    Ok::<_, Infallible>(res.map(|()| http_body_util::Empty::<Bytes>::new()))
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let addr = server.local_addr().unwrap();
+
+        let conn = client_endpoint().connect(addr, "one.test").unwrap().await.unwrap();
+        let (mut driver, mut send_request) = h3::client::new(h3_quinn::Connection::new(conn.clone())).await.unwrap();
+        let driver = tokio::spawn(async move { std::future::poll_fn(|cx| driver.poll_close(cx)).await });
+
+        let mut req = http::Request::builder()
+            .method(http::Method::CONNECT)
+            .uri(format!("https://one.test:{}/", addr.port()))
+            .body(())
+            .unwrap();
+        req.extensions_mut().insert(h3::ext::Protocol::WEB_TRANSPORT);
+
+        let mut stream = send_request.send_request(req).await.unwrap();
+        let res = stream.recv_response().await.unwrap();
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        // The CONNECT request is the first client initiated bidi stream (id 0),
+        // so the quarter stream id prefixing every datagram is 0.
+        let mut datagram = BytesMut::new();
+        datagram.put_u8(0);
+        datagram.put_slice(b"hello datagram");
+        conn.send_datagram(datagram.freeze()).unwrap();
+
+        let echoed = tokio::time::timeout(std::time::Duration::from_secs(5), conn.read_datagram())
+            .await
+            .expect("timed out waiting for the echo")
+            .unwrap();
+        assert_eq!(echoed, Bytes::from_static(b"\0hello datagram"));
+
+        conn.close(0u32.into(), b"");
+        driver.abort();
+        server.shutdown().await.unwrap();
+    }
+}
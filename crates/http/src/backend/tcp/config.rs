@@ -1,6 +1,9 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use super::conn_limit::ConnectionLimiter;
+pub use super::rate_limit::RateLimit;
+use super::rate_limit::RateLimiter;
 use super::TcpServer;
 use crate::builder::MakeListener;
 
@@ -75,6 +78,100 @@ impl<A: RustlsLazyAcceptor + 'static> From<A> for TlsAcceptor {
     }
 }
 
+/// A certificate resolver which selects a certificate based on the SNI
+/// hostname sent by the client, falling back to a default certificate when
+/// the hostname is unknown or no SNI was sent.
+#[cfg(feature = "tls-rustls")]
+#[derive(Debug, Default, Clone)]
+pub struct SniCertResolver {
+    certs: std::collections::HashMap<String, Arc<rustls::sign::CertifiedKey>>,
+    default: Option<Arc<rustls::sign::CertifiedKey>>,
+}
+
+#[cfg(feature = "tls-rustls")]
+impl SniCertResolver {
+    /// Creates a resolver without any certificates, which does not present a
+    /// certificate to any client until one is added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a certificate which is presented to clients requesting the given
+    /// hostname.
+    pub fn with_cert(mut self, hostname: impl AsRef<str>, cert: impl Into<Arc<rustls::sign::CertifiedKey>>) -> Self {
+        self.certs.insert(hostname.as_ref().to_ascii_lowercase(), cert.into());
+        self
+    }
+
+    /// Sets the certificate which is presented when no other certificate
+    /// matches.
+    pub fn with_default(mut self, cert: impl Into<Arc<rustls::sign::CertifiedKey>>) -> Self {
+        self.default = Some(cert.into());
+        self
+    }
+
+    /// Adds a certificate for the given hostname from a PEM encoded
+    /// certificate chain and private key, see
+    /// [`with_cert`](Self::with_cert).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HyperBackendBuilderError::Io`] if either PEM cannot be
+    /// parsed or contains no certificate or no private key, and
+    /// [`HyperBackendBuilderError::Rustls`] if the key type is not supported.
+    #[cfg(feature = "tls-rustls-pem")]
+    pub fn with_cert_from_pem(
+        self,
+        hostname: impl AsRef<str>,
+        cert: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Self, HyperBackendBuilderError> {
+        let cert = certified_key_from_pem(cert.as_ref(), key.as_ref())?;
+        Ok(self.with_cert(hostname, cert))
+    }
+
+    /// Sets the default certificate from a PEM encoded certificate chain and
+    /// private key, see [`with_default`](Self::with_default).
+    ///
+    /// # Errors
+    ///
+    /// The same as [`with_cert_from_pem`](Self::with_cert_from_pem).
+    #[cfg(feature = "tls-rustls-pem")]
+    pub fn with_default_from_pem(
+        self,
+        cert: impl AsRef<[u8]>,
+        key: impl AsRef<[u8]>,
+    ) -> Result<Self, HyperBackendBuilderError> {
+        let cert = certified_key_from_pem(cert.as_ref(), key.as_ref())?;
+        Ok(self.with_default(cert))
+    }
+}
+
+#[cfg(feature = "tls-rustls")]
+impl rustls::server::ResolvesServerCert for SniCertResolver {
+    fn resolve(&self, client_hello: rustls::server::ClientHello<'_>) -> Option<Arc<rustls::sign::CertifiedKey>> {
+        client_hello
+            .server_name()
+            .and_then(|name| self.certs.get(name))
+            .or(self.default.as_ref())
+            .cloned()
+    }
+}
+
+#[cfg(feature = "tls-rustls-pem")]
+fn certified_key_from_pem(cert: &[u8], key: &[u8]) -> Result<rustls::sign::CertifiedKey, HyperBackendBuilderError> {
+    let cert_chain = rustls_pemfile::certs(&mut std::io::Cursor::new(cert)).collect::<Result<Vec<_>, _>>()?;
+    if cert_chain.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "no certificate found").into());
+    }
+
+    let key = rustls_pemfile::private_key(&mut std::io::Cursor::new(key))?
+        .ok_or(std::io::Error::new(std::io::ErrorKind::InvalidInput, "no key found"))?;
+    let key = rustls::crypto::aws_lc_rs::sign::any_supported_type(&key)?;
+
+    Ok(rustls::sign::CertifiedKey::new(cert_chain, key))
+}
+
 #[derive(Debug)]
 #[must_use = "TcpServerConfig must be used to create a TcpServer"]
 pub struct TcpServerConfig {
@@ -85,6 +182,20 @@ pub struct TcpServerConfig {
     pub idle_timeout: Option<std::time::Duration>,
     /// The maximum time a TLS handshake can take. (default: 5 seconds)
     pub handshake_timeout: Option<std::time::Duration>,
+    /// The maximum time a connection can stay open before it is gracefully
+    /// shut down. (default: unlimited)
+    pub max_connection_lifetime: Option<std::time::Duration>,
+    /// The per-IP rate limit applied to requests. (default: unlimited)
+    pub rate_limit: Option<RateLimit>,
+    /// The maximum number of connections a single IP can have open at once.
+    /// (default: unlimited)
+    pub max_connections_per_ip: Option<usize>,
+    /// The maximum number of pipelined HTTP/1.1 requests which are read ahead
+    /// while a request is being answered. (default: unlimited)
+    pub http1_max_pipelined: Option<usize>,
+    /// Whether handlers can send `103 Early Hints` responses over HTTP/1.1,
+    /// see [`EarlyHints`](super::early_hints::EarlyHints). (default: false)
+    pub http1_early_hints: bool,
     pub server_name: Option<Arc<str>>,
     pub allow_upgrades: bool,
     pub only_http: Option<HttpVersion>,
@@ -92,10 +203,19 @@ pub struct TcpServerConfig {
 }
 
 impl TcpServerConfig {
-    pub(crate) fn inner(&self) -> TcpServerConfigInner {
+    pub(crate) fn inner(
+        &self,
+        rate_limiter: Option<Arc<RateLimiter>>,
+        connection_limiter: Option<Arc<ConnectionLimiter>>,
+    ) -> TcpServerConfigInner {
         TcpServerConfigInner {
             idle_timeout: self.idle_timeout,
             handshake_timeout: self.handshake_timeout,
+            max_connection_lifetime: self.max_connection_lifetime,
+            rate_limiter,
+            connection_limiter,
+            http1_max_pipelined: self.http1_max_pipelined,
+            http1_early_hints: self.http1_early_hints,
             server_name: self.server_name.clone(),
             allow_upgrades: self.allow_upgrades,
             http_builder: self.http_builder.clone(),
@@ -108,6 +228,13 @@ pub(crate) struct TcpServerConfigInner {
     pub idle_timeout: Option<std::time::Duration>,
     #[cfg_attr(not(feature = "tls-rustls"), allow(unused))]
     pub handshake_timeout: Option<std::time::Duration>,
+    pub max_connection_lifetime: Option<std::time::Duration>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    pub connection_limiter: Option<Arc<ConnectionLimiter>>,
+    #[cfg_attr(not(feature = "http1"), allow(unused))]
+    pub http1_max_pipelined: Option<usize>,
+    #[cfg_attr(not(feature = "http1"), allow(unused))]
+    pub http1_early_hints: bool,
     pub server_name: Option<Arc<str>>,
     pub allow_upgrades: bool,
     pub http_builder: hyper_util::server::conn::auto::Builder<hyper_util::rt::TokioExecutor>,
@@ -135,6 +262,22 @@ pub enum HttpVersion {
     Http1,
 }
 
+/// The default HTTP/2 `SETTINGS_MAX_CONCURRENT_STREAMS`.
+#[cfg(feature = "http2")]
+pub const DEFAULT_HTTP2_MAX_CONCURRENT_STREAMS: u32 = 200;
+/// The default HTTP/2 initial stream-level flow control window (2 MiB).
+#[cfg(feature = "http2")]
+pub const DEFAULT_HTTP2_INITIAL_STREAM_WINDOW_SIZE: u32 = 2 * 1024 * 1024;
+/// The default HTTP/2 initial connection-level flow control window (8 MiB).
+///
+/// This is larger than the stream window so that a few concurrent large
+/// responses do not starve each other.
+#[cfg(feature = "http2")]
+pub const DEFAULT_HTTP2_INITIAL_CONNECTION_WINDOW_SIZE: u32 = 8 * 1024 * 1024;
+/// The default HTTP/2 maximum frame size (16 KiB, the protocol minimum).
+#[cfg(feature = "http2")]
+pub const DEFAULT_HTTP2_MAX_FRAME_SIZE: u32 = 16 * 1024;
+
 #[must_use = "TcpServerConfigBuilder must be built to create a TcpServerConfig"]
 pub struct TcpServerConfigBuilder<A = (), L = ()> {
     http_builder: hyper_util::server::conn::auto::Builder<hyper_util::rt::TokioExecutor>,
@@ -143,9 +286,16 @@ pub struct TcpServerConfigBuilder<A = (), L = ()> {
     connection_limit: Option<usize>,
     idle_timeout: Option<std::time::Duration>,
     handshake_timeout: Option<std::time::Duration>,
+    max_connection_lifetime: Option<std::time::Duration>,
+    rate_limit: Option<RateLimit>,
+    max_connections_per_ip: Option<usize>,
+    http1_max_pipelined: Option<usize>,
+    http1_early_hints: bool,
     server_name: Option<Arc<str>>,
     allow_upgrades: bool,
     only_http: Option<HttpVersion>,
+    #[cfg(feature = "tls-rustls")]
+    client_cert_verifier: Option<Arc<dyn rustls::server::danger::ClientCertVerifier>>,
 }
 
 impl Default for TcpServerConfigBuilder {
@@ -156,16 +306,34 @@ impl Default for TcpServerConfigBuilder {
 
 impl TcpServerConfigBuilder {
     pub fn new() -> Self {
+        #[allow(unused_mut)]
+        let mut http_builder = hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new());
+
+        #[cfg(feature = "http2")]
+        http_builder
+            .http2()
+            .max_concurrent_streams(DEFAULT_HTTP2_MAX_CONCURRENT_STREAMS)
+            .initial_stream_window_size(DEFAULT_HTTP2_INITIAL_STREAM_WINDOW_SIZE)
+            .initial_connection_window_size(DEFAULT_HTTP2_INITIAL_CONNECTION_WINDOW_SIZE)
+            .max_frame_size(DEFAULT_HTTP2_MAX_FRAME_SIZE);
+
         Self {
-            http_builder: hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new()),
+            http_builder,
             listener: (),
             acceptor: (),
             connection_limit: None,
             idle_timeout: Some(std::time::Duration::from_secs(30)),
             handshake_timeout: Some(std::time::Duration::from_secs(5)),
+            max_connection_lifetime: None,
+            rate_limit: None,
+            max_connections_per_ip: None,
+            http1_max_pipelined: None,
+            http1_early_hints: false,
             server_name: None,
             allow_upgrades: true,
             only_http: None,
+            #[cfg(feature = "tls-rustls")]
+            client_cert_verifier: None,
         }
     }
 }
@@ -179,9 +347,16 @@ impl<A> TcpServerConfigBuilder<A, ()> {
             connection_limit: self.connection_limit,
             idle_timeout: self.idle_timeout,
             handshake_timeout: self.handshake_timeout,
+            max_connection_lifetime: self.max_connection_lifetime,
+            rate_limit: self.rate_limit,
+            max_connections_per_ip: self.max_connections_per_ip,
+            http1_max_pipelined: self.http1_max_pipelined,
+            http1_early_hints: self.http1_early_hints,
             server_name: self.server_name,
             allow_upgrades: self.allow_upgrades,
             only_http: self.only_http,
+            #[cfg(feature = "tls-rustls")]
+            client_cert_verifier: self.client_cert_verifier,
         }
     }
 
@@ -196,12 +371,30 @@ impl<A> TcpServerConfigBuilder<A, ()> {
             connection_limit: self.connection_limit,
             idle_timeout: self.idle_timeout,
             handshake_timeout: self.handshake_timeout,
+            max_connection_lifetime: self.max_connection_lifetime,
+            rate_limit: self.rate_limit,
+            max_connections_per_ip: self.max_connections_per_ip,
+            http1_max_pipelined: self.http1_max_pipelined,
+            http1_early_hints: self.http1_early_hints,
             server_name: self.server_name,
             allow_upgrades: self.allow_upgrades,
             only_http: self.only_http,
+            #[cfg(feature = "tls-rustls")]
+            client_cert_verifier: self.client_cert_verifier,
         }
     }
 
+    /// Use an already bound listener socket, for example one inherited from a
+    /// parent process (zero-downtime restarts) or passed in by systemd socket
+    /// activation.
+    #[cfg(unix)]
+    pub fn with_listener_fd(
+        self,
+        fd: impl Into<std::os::fd::OwnedFd>,
+    ) -> TcpServerConfigBuilder<A, MakeListener<std::net::TcpListener>> {
+        self.with_listener(std::net::TcpListener::from(fd.into()))
+    }
+
     pub fn with_make_listener(
         self,
         make_listener: impl Fn() -> std::io::Result<std::net::TcpListener> + 'static + Send,
@@ -213,9 +406,16 @@ impl<A> TcpServerConfigBuilder<A, ()> {
             connection_limit: self.connection_limit,
             idle_timeout: self.idle_timeout,
             handshake_timeout: self.handshake_timeout,
+            max_connection_lifetime: self.max_connection_lifetime,
+            rate_limit: self.rate_limit,
+            max_connections_per_ip: self.max_connections_per_ip,
+            http1_max_pipelined: self.http1_max_pipelined,
+            http1_early_hints: self.http1_early_hints,
             server_name: self.server_name,
             allow_upgrades: self.allow_upgrades,
             only_http: self.only_http,
+            #[cfg(feature = "tls-rustls")]
+            client_cert_verifier: self.client_cert_verifier,
         }
     }
 }
@@ -228,6 +428,8 @@ pub enum HyperBackendBuilderError {
     Io(#[from] std::io::Error),
     #[cfg(feature = "tls-rustls-pem")]
     Rustls(#[from] rustls::Error),
+    #[cfg(feature = "tls-rustls-pem")]
+    ClientCertVerifier(#[from] rustls::server::VerifierBuilderError),
 }
 
 impl<L> TcpServerConfigBuilder<(), L> {
@@ -240,9 +442,16 @@ impl<L> TcpServerConfigBuilder<(), L> {
             connection_limit: self.connection_limit,
             idle_timeout: self.idle_timeout,
             handshake_timeout: self.handshake_timeout,
+            max_connection_lifetime: self.max_connection_lifetime,
+            rate_limit: self.rate_limit,
+            max_connections_per_ip: self.max_connections_per_ip,
+            http1_max_pipelined: self.http1_max_pipelined,
+            http1_early_hints: self.http1_early_hints,
             server_name: self.server_name,
             allow_upgrades: self.allow_upgrades,
             only_http: self.only_http,
+            #[cfg(feature = "tls-rustls")]
+            client_cert_verifier: self.client_cert_verifier,
         }
     }
 
@@ -256,13 +465,71 @@ impl<L> TcpServerConfigBuilder<(), L> {
         let key = rustls_pemfile::private_key(&mut std::io::Cursor::new(key.as_ref()))?
             .ok_or(std::io::Error::new(std::io::ErrorKind::InvalidInput, "no key found"))?;
 
-        let config = rustls::ServerConfig::builder_with_provider(rustls::crypto::aws_lc_rs::default_provider().into())
-            .with_safe_default_protocol_versions()?
-            .with_no_client_auth()
-            .with_single_cert(cert_chain, key)?;
+        let config = self.rustls_config_builder()?.with_single_cert(cert_chain, key)?;
+
+        Ok(self.with_tls_acceptor(config))
+    }
+
+    /// Uses a certificate resolver to select the certificate for each
+    /// connection, for example a [`SniCertResolver`] to serve multiple
+    /// hostnames from a single listener.
+    #[cfg(feature = "tls-rustls")]
+    pub fn with_tls_cert_resolver(
+        self,
+        resolver: impl rustls::server::ResolvesServerCert + 'static,
+    ) -> Result<TcpServerConfigBuilder<TlsAcceptor, L>, rustls::Error> {
+        let config = self.rustls_config_builder()?.with_cert_resolver(Arc::new(resolver));
 
         Ok(self.with_tls_acceptor(config))
     }
+
+    /// Requires clients to present a certificate which is accepted by the
+    /// given verifier (mutual TLS), connections without one are rejected
+    /// during the handshake. The verified certificate is available to
+    /// handlers as a [`ClientCertificate`](super::tls::ClientCertificate)
+    /// request extension.
+    ///
+    /// This must be called before [`with_tls_from_pem`](Self::with_tls_from_pem)
+    /// or [`with_tls_cert_resolver`](Self::with_tls_cert_resolver). Configs
+    /// passed to [`with_tls_acceptor`](Self::with_tls_acceptor) are used
+    /// as-is and must set up client authentication themselves.
+    #[cfg(feature = "tls-rustls")]
+    pub fn with_client_cert_verifier(mut self, verifier: Arc<dyn rustls::server::danger::ClientCertVerifier>) -> Self {
+        self.client_cert_verifier = Some(verifier);
+        self
+    }
+
+    /// Requires clients to present a certificate signed by one of the CA
+    /// certificates in `ca`, see
+    /// [`with_client_cert_verifier`](Self::with_client_cert_verifier).
+    #[cfg(feature = "tls-rustls-pem")]
+    pub fn with_client_ca_from_pem(self, ca: impl AsRef<[u8]>) -> Result<Self, HyperBackendBuilderError> {
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut std::io::Cursor::new(ca.as_ref())) {
+            roots.add(cert?)?;
+        }
+
+        let verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+            Arc::new(roots),
+            rustls::crypto::aws_lc_rs::default_provider().into(),
+        )
+        .build()?;
+
+        Ok(self.with_client_cert_verifier(verifier))
+    }
+
+    #[cfg(feature = "tls-rustls")]
+    fn rustls_config_builder(
+        &self,
+    ) -> Result<rustls::ConfigBuilder<rustls::ServerConfig, rustls::server::WantsServerCert>, rustls::Error> {
+        let builder = rustls::ServerConfig::builder_with_provider(rustls::crypto::aws_lc_rs::default_provider().into())
+            .with_safe_default_protocol_versions()?;
+
+        Ok(match self.client_cert_verifier.clone() {
+            Some(verifier) => builder.with_client_cert_verifier(verifier),
+            None => builder.with_no_client_auth(),
+        })
+    }
 }
 
 impl<C, L> TcpServerConfigBuilder<C, L> {
@@ -304,6 +571,79 @@ impl<C, L> TcpServerConfigBuilder<C, L> {
         self
     }
 
+    /// Limits how many pipelined HTTP/1.1 requests are read ahead while a
+    /// request is being answered.
+    ///
+    /// Pipelined requests are always answered one at a time and in the order
+    /// they were sent. Once the limit is reached nothing more is read from the
+    /// connection until the current request has been answered, so a client
+    /// cannot make the server buffer an unbounded number of requests.
+    ///
+    /// Without a limit the requests are only bounded by hyper's read buffer,
+    /// and the connection is not scanned for request boundaries.
+    /// (default: unlimited)
+    #[cfg(feature = "http1")]
+    pub fn with_http1_max_pipelined(mut self, max: impl Into<Option<usize>>) -> Self {
+        self.http1_max_pipelined = max.into();
+        self
+    }
+
+    /// Allows handlers to send `103 Early Hints` responses over HTTP/1.1.
+    ///
+    /// When enabled an [`EarlyHints`](super::early_hints::EarlyHints)
+    /// extension is inserted into every HTTP/1.1 request. The IO of every
+    /// HTTP/1 connection is then wrapped to write the hints, which is why this
+    /// is opt-in. (default: false)
+    #[cfg(feature = "http1")]
+    pub fn with_http1_early_hints(mut self, enabled: bool) -> Self {
+        self.http1_early_hints = enabled;
+        self
+    }
+
+    /// Sets the HTTP/2 `SETTINGS_MAX_CONCURRENT_STREAMS`, the number of
+    /// requests a client may have in flight on a single connection.
+    ///
+    /// Passing `None` removes the limit. (default:
+    /// [`DEFAULT_HTTP2_MAX_CONCURRENT_STREAMS`])
+    #[cfg(feature = "http2")]
+    pub fn with_http2_max_concurrent_streams(mut self, max: impl Into<Option<u32>>) -> Self {
+        self.http_builder.http2().max_concurrent_streams(max);
+        self
+    }
+
+    /// Sets the HTTP/2 initial stream-level flow control window. (default:
+    /// [`DEFAULT_HTTP2_INITIAL_STREAM_WINDOW_SIZE`])
+    #[cfg(feature = "http2")]
+    pub fn with_http2_initial_stream_window_size(mut self, size: u32) -> Self {
+        self.http_builder.http2().initial_stream_window_size(size);
+        self
+    }
+
+    /// Sets the HTTP/2 initial connection-level flow control window.
+    /// (default: [`DEFAULT_HTTP2_INITIAL_CONNECTION_WINDOW_SIZE`])
+    #[cfg(feature = "http2")]
+    pub fn with_http2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.http_builder.http2().initial_connection_window_size(size);
+        self
+    }
+
+    /// Sets the HTTP/2 maximum frame size, must be between 16 KiB and 16 MiB.
+    /// (default: [`DEFAULT_HTTP2_MAX_FRAME_SIZE`])
+    #[cfg(feature = "http2")]
+    pub fn with_http2_max_frame_size(mut self, size: u32) -> Self {
+        self.http_builder.http2().max_frame_size(size);
+        self
+    }
+
+    /// Enables HTTP/2 adaptive flow control, which sizes the windows based on
+    /// the measured bandwidth-delay product and overrides the initial window
+    /// sizes. (default: disabled)
+    #[cfg(feature = "http2")]
+    pub fn with_http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http_builder.http2().adaptive_window(enabled);
+        self
+    }
+
     pub fn with_connection_limit(mut self, limit: usize) -> Self {
         self.connection_limit = Some(limit);
         self
@@ -319,6 +659,42 @@ impl<C, L> TcpServerConfigBuilder<C, L> {
         self
     }
 
+    /// Gracefully shuts down connections once they have been open for the
+    /// given duration.
+    ///
+    /// HTTP/2 connections are sent a GOAWAY so that no new streams are opened
+    /// while in-flight streams are allowed to finish, HTTP/1 connections are
+    /// closed after the current request. This lets clients behind a load
+    /// balancer reconnect and be redistributed.
+    pub fn with_max_connection_lifetime(mut self, lifetime: std::time::Duration) -> Self {
+        self.max_connection_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Limits the rate of requests each client IP can make.
+    ///
+    /// Requests over the limit are answered with `429 Too Many Requests` and a
+    /// `Retry-After` header without being passed to the service. The limit is
+    /// shared between all the workers of the server.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the limit is invalid, see [`RateLimit::new`].
+    pub fn with_rate_limit(mut self, requests_per_sec: f64, burst: u32) -> Self {
+        self.rate_limit = Some(RateLimit::new(requests_per_sec, burst));
+        self
+    }
+
+    /// Limits the number of connections each client IP can have open at once.
+    ///
+    /// Connections over the limit are closed as soon as they are accepted,
+    /// without being passed to the service. The limit is shared between all
+    /// the workers of the server.
+    pub fn with_max_connections_per_ip(mut self, max_connections_per_ip: usize) -> Self {
+        self.max_connections_per_ip = Some(max_connections_per_ip);
+        self
+    }
+
     pub fn with_server_name(mut self, server_name: impl Into<Arc<str>>) -> Self {
         self.server_name = Some(server_name.into());
         self
@@ -353,6 +729,11 @@ impl<A: MaybeTlsAcceptor> TcpServerConfigBuilder<A, MakeListener<std::net::TcpLi
             make_listener: self.listener,
             idle_timeout: self.idle_timeout,
             handshake_timeout: self.handshake_timeout,
+            max_connection_lifetime: self.max_connection_lifetime,
+            rate_limit: self.rate_limit,
+            max_connections_per_ip: self.max_connections_per_ip,
+            http1_max_pipelined: self.http1_max_pipelined,
+            http1_early_hints: self.http1_early_hints,
             server_name: self.server_name,
             acceptor: self.acceptor.into_tls_acceptor(),
             allow_upgrades: self.allow_upgrades,
@@ -360,3 +741,254 @@ impl<A: MaybeTlsAcceptor> TcpServerConfigBuilder<A, MakeListener<std::net::TcpLi
         }
     }
 }
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(all(test, feature = "tls-rustls-pem"))]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::Arc;
+
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{DigitallySignedStruct, SignatureScheme};
+
+    use super::{SniCertResolver, TcpServerConfig};
+    use crate::backend::tcp::tls::{ClientCertificate, SubjectAltName};
+    use crate::backend::HttpServer;
+    use crate::svc::function_service;
+
+    const ONE_CERT: &[u8] = include_bytes!("../../../../../assets/tls/one.test.crt");
+    const ONE_KEY: &[u8] = include_bytes!("../../../../../assets/tls/one.test.key");
+    const TWO_CERT: &[u8] = include_bytes!("../../../../../assets/tls/two.test.crt");
+    const TWO_KEY: &[u8] = include_bytes!("../../../../../assets/tls/two.test.key");
+    const CA_CERT: &[u8] = include_bytes!("../../../../../assets/tls/ca.test.crt");
+    const CLIENT_CERT: &[u8] = include_bytes!("../../../../../assets/tls/client.test.crt");
+    const CLIENT_KEY: &[u8] = include_bytes!("../../../../../assets/tls/client.test.key");
+
+    /// Accepts any certificate, the tests only care about which certificate is
+    /// presented.
+    #[derive(Debug)]
+    struct NoVerifier(Arc<rustls::crypto::CryptoProvider>);
+
+    impl ServerCertVerifier for NoVerifier {
+        fn verify_server_cert(
+            &self,
+            _: &CertificateDer<'_>,
+            _: &[CertificateDer<'_>],
+            _: &ServerName<'_>,
+            _: &[u8],
+            _: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            rustls::crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.0.signature_verification_algorithms.supported_schemes()
+        }
+    }
+
+    fn cert_der(pem: &[u8]) -> CertificateDer<'static> {
+        rustls_pemfile::certs(&mut std::io::Cursor::new(pem)).next().unwrap().unwrap()
+    }
+
+    async fn presented_cert(addr: std::net::SocketAddr, server_name: &'static str) -> CertificateDer<'static> {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+            .with_no_client_auth();
+
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let stream = tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from(server_name).unwrap(), stream)
+            .await
+            .unwrap();
+
+        stream.get_ref().1.peer_certificates().unwrap()[0].clone()
+    }
+
+    /// Sends a request presenting the given client certificate, returning the
+    /// response or `None` if the server rejected the connection.
+    async fn request_with_client_cert(addr: std::net::SocketAddr, client_cert: Option<(&[u8], &[u8])>) -> Option<String> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)));
+
+        let config = match client_cert {
+            Some((cert, key)) => config
+                .with_client_auth_cert(
+                    rustls_pemfile::certs(&mut std::io::Cursor::new(cert))
+                        .collect::<Result<_, _>>()
+                        .unwrap(),
+                    rustls_pemfile::private_key(&mut std::io::Cursor::new(key)).unwrap().unwrap(),
+                )
+                .unwrap(),
+            None => config.with_no_client_auth(),
+        };
+
+        let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        // With TLS 1.3 the client finishes the handshake before the server has
+        // verified its certificate, so a rejection only shows up when reading.
+        let mut stream = tokio_rustls::TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("one.test").unwrap(), stream)
+            .await
+            .ok()?;
+
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nhost: one.test\r\nconnection: close\r\n\r\n")
+            .await
+            .ok()?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.ok()?;
+        Some(response).filter(|response| !response.is_empty())
+    }
+
+    #[tokio::test]
+    async fn client_cert_verification() {
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_client_ca_from_pem(CA_CERT)
+            .unwrap()
+            .with_tls_from_pem(ONE_CERT, ONE_KEY)
+            .unwrap()
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|req| async move {
+                    let cert = req.extensions().get::<ClientCertificate>().unwrap();
+                    assert_eq!(cert.certificate(), &cert_der(CLIENT_CERT));
+                    assert_eq!(cert.subject_alt_names(), &[SubjectAltName::Dns("client.test".into())]);
+                    Ok::<_, Infallible>(http::Response::new(cert.subject().to_owned()))
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let addr = server.local_addr().unwrap();
+
+        let response = request_with_client_cert(addr, Some((CLIENT_CERT, CLIENT_KEY)))
+            .await
+            .expect("trusted client certificate was rejected");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("CN=client.test"), "{response}");
+
+        // Self-signed, so not signed by the trusted CA.
+        assert_eq!(request_with_client_cert(addr, Some((TWO_CERT, TWO_KEY))).await, None);
+        assert_eq!(request_with_client_cert(addr, None).await, None);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn sni_cert_selection() {
+        let resolver = SniCertResolver::new()
+            .with_cert_from_pem("one.test", ONE_CERT, ONE_KEY)
+            .unwrap()
+            .with_cert_from_pem("two.test", TWO_CERT, TWO_KEY)
+            .unwrap()
+            .with_default_from_pem(ONE_CERT, ONE_KEY)
+            .unwrap();
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_tls_cert_resolver(resolver)
+            .unwrap()
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|_| async { Ok::<_, Infallible>(http::Response::new(String::new())) }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let addr = server.local_addr().unwrap();
+
+        assert_eq!(presented_cert(addr, "one.test").await, cert_der(ONE_CERT));
+        assert_eq!(presented_cert(addr, "two.test").await, cert_der(TWO_CERT));
+        assert_eq!(presented_cert(addr, "unknown.test").await, cert_der(ONE_CERT));
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn handshake_timeout() {
+        use tokio::io::AsyncReadExt;
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_tls_from_pem(ONE_CERT, ONE_KEY)
+            .unwrap()
+            .with_handshake_timeout(std::time::Duration::from_millis(100))
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|_| async { Ok::<_, Infallible>(http::Response::new(String::new())) }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        // Open a connection but never start the handshake.
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+
+        let mut buf = [0; 1];
+        let read = tokio::time::timeout(std::time::Duration::from_secs(2), stream.read(&mut buf))
+            .await
+            .expect("server did not close the connection");
+        assert_eq!(read.unwrap(), 0);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn sni_pem_errors() {
+        use super::HyperBackendBuilderError;
+
+        let err = SniCertResolver::new()
+            .with_cert_from_pem("one.test", b"", ONE_KEY)
+            .unwrap_err();
+        assert!(matches!(err, HyperBackendBuilderError::Io(_)), "{err:?}");
+
+        let err = SniCertResolver::new().with_default_from_pem(ONE_CERT, b"").unwrap_err();
+        assert!(matches!(err, HyperBackendBuilderError::Io(_)), "{err:?}");
+
+        assert!(SniCertResolver::new()
+            .with_cert_from_pem("one.test", ONE_CERT, ONE_KEY)
+            .and_then(|resolver| resolver.with_default_from_pem(TWO_CERT, TWO_KEY))
+            .is_ok());
+    }
+}
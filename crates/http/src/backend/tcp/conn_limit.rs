@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Counts the open connections of each client IP, shared between all the
+/// workers of a server.
+#[derive(Debug)]
+pub(crate) struct ConnectionLimiter {
+    max_per_ip: usize,
+    connections: std::sync::Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl ConnectionLimiter {
+    pub(crate) fn new(max_per_ip: usize) -> Self {
+        Self {
+            max_per_ip,
+            connections: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<IpAddr, usize>> {
+        self.connections.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Counts a new connection from the given IP, returns `None` if the IP
+    /// already has the maximum number of connections open.
+    ///
+    /// The connection is counted until the returned permit is dropped.
+    pub(crate) fn acquire(self: &Arc<Self>, ip: IpAddr) -> Option<ConnectionPermit> {
+        let mut connections = self.lock();
+        let count = connections.entry(ip).or_insert(0);
+        if *count >= self.max_per_ip {
+            return None;
+        }
+
+        *count += 1;
+
+        Some(ConnectionPermit {
+            limiter: self.clone(),
+            ip,
+        })
+    }
+}
+
+/// A connection counted by a [`ConnectionLimiter`].
+#[derive(Debug)]
+pub(crate) struct ConnectionPermit {
+    limiter: Arc<ConnectionLimiter>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut connections = self.limiter.lock();
+        if let Some(count) = connections.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                connections.remove(&self.ip);
+            }
+        }
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::ConnectionLimiter;
+
+    #[test]
+    fn per_ip() {
+        let limiter = Arc::new(ConnectionLimiter::new(2));
+        let ip = "127.0.0.1".parse().unwrap();
+
+        let first = limiter.acquire(ip).unwrap();
+        let _second = limiter.acquire(ip).unwrap();
+        assert!(limiter.acquire(ip).is_none());
+
+        // Other IPs are counted separately.
+        assert!(limiter.acquire("127.0.0.2".parse().unwrap()).is_some());
+
+        drop(first);
+        let _third = limiter.acquire(ip).unwrap();
+        assert!(limiter.acquire(ip).is_none());
+    }
+
+    #[test]
+    fn forgets_closed() {
+        let limiter = Arc::new(ConnectionLimiter::new(1));
+        let permit = limiter.acquire("127.0.0.1".parse().unwrap()).unwrap();
+        assert_eq!(limiter.lock().len(), 1);
+
+        drop(permit);
+        assert!(limiter.lock().is_empty());
+    }
+}
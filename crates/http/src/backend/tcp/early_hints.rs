@@ -0,0 +1,385 @@
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Sends `103 Early Hints` responses, so the client can start preloading
+/// resources (with `Link: <...>; rel=preload` headers) while the handler is
+/// still preparing the final response.
+///
+/// When enabled with
+/// [`TcpServerConfigBuilder::with_http1_early_hints`](super::config::TcpServerConfigBuilder::with_http1_early_hints),
+/// the TCP backend inserts this as an extension into every HTTP/1.1 request.
+/// It is not inserted for HTTP/1.0 requests, which must not receive
+/// informational responses, nor for HTTP/2 requests, because hyper cannot send
+/// informational responses over HTTP/2.
+///
+/// # Example
+///
+/// ```rust
+/// use scuffle_http::backend::tcp::early_hints::EarlyHints;
+/// use scuffle_http::body::IncomingBody;
+///
+/// async fn handle(req: http::Request<IncomingBody>) -> http::Response<String> {
+///     if let Some(hints) = req.extensions().get::<EarlyHints>() {
+///         let mut headers = http::HeaderMap::new();
+///         headers.insert(
+///             http::header::LINK,
+///             http::HeaderValue::from_static("</style.css>; rel=preload; as=style"),
+///         );
+///
+///         // The final response can still be sent if the hints could not be.
+///         hints.send(&headers).await.ok();
+///     }
+///
+///     http::Response::new("<link rel=\"stylesheet\" href=\"/style.css\">".to_owned())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EarlyHints {
+    state: Arc<Mutex<State>>,
+    /// The request this handle belongs to.
+    request: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Hints waiting to be written, in the order they were sent.
+    queue: VecDeque<QueuedHints>,
+    /// The id of the next request.
+    next_request: u64,
+    /// The requests whose final response has not been returned yet.
+    open_requests: HashSet<u64>,
+    /// The id of the next hints to be sent.
+    next_id: u64,
+    /// All hints with a lower id have been written.
+    written: u64,
+    /// Set once the connection is closed or failed to write.
+    closed: bool,
+    /// The connection, woken when there are hints to write.
+    conn: Option<Waker>,
+}
+
+#[derive(Debug)]
+struct QueuedHints {
+    id: u64,
+    request: u64,
+    /// The part of the encoded response which has not been written yet.
+    remaining: Bytes,
+    len: usize,
+    /// The sender, woken once the hints are written.
+    waker: Option<Waker>,
+}
+
+impl QueuedHints {
+    fn started(&self) -> bool {
+        self.remaining.len() != self.len
+    }
+}
+
+impl State {
+    fn close(&mut self) {
+        self.closed = true;
+        for hints in self.queue.drain(..) {
+            if let Some(waker) = hints.waker {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Removes the hints of the request which have not started to be written,
+    /// waking their senders.
+    fn retract(&mut self, request: u64) {
+        self.queue.retain_mut(|hints| {
+            if hints.request != request || hints.started() {
+                return true;
+            }
+
+            if let Some(waker) = hints.waker.take() {
+                waker.wake();
+            }
+
+            false
+        });
+    }
+}
+
+impl EarlyHints {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Sends a `103 Early Hints` response with the given headers.
+    ///
+    /// Resolves once the response has been written to the connection. Hints
+    /// can be sent several times, each call sends a separate response, but
+    /// only until the handler has returned the final response.
+    ///
+    /// Returns an error if the connection has been closed or the final
+    /// response has already been returned. Hints which have not started to be
+    /// written when the final response is returned are not sent either. If
+    /// the future is dropped before the hints started to be written, they are
+    /// not sent.
+    pub async fn send(&self, headers: &http::HeaderMap) -> io::Result<()> {
+        let id = {
+            let mut state = self.lock();
+            if state.closed {
+                return Err(closed());
+            }
+
+            if !state.open_requests.contains(&self.request) {
+                return Err(final_response_sent());
+            }
+
+            let remaining = encode(headers);
+            let id = state.next_id;
+            state.next_id += 1;
+            state.queue.push_back(QueuedHints {
+                id,
+                request: self.request,
+                len: remaining.len(),
+                remaining,
+                waker: None,
+            });
+
+            if let Some(conn) = state.conn.as_ref() {
+                conn.wake_by_ref();
+            }
+
+            id
+        };
+
+        let _guard = RetractGuard { hints: self, id };
+
+        std::future::poll_fn(|cx| {
+            let mut state = self.lock();
+            if id < state.written {
+                return Poll::Ready(Ok(()));
+            }
+
+            let is_closed = state.closed;
+            match state.queue.iter_mut().find(|hints| hints.id == id) {
+                Some(hints) => {
+                    hints.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+                None if is_closed => Poll::Ready(Err(closed())),
+                None => Poll::Ready(Err(final_response_sent())),
+            }
+        })
+        .await
+    }
+}
+
+/// Removes the hints from the queue if the send is dropped before they started
+/// to be written, so they cannot end up after the final response.
+struct RetractGuard<'a> {
+    hints: &'a EarlyHints,
+    id: u64,
+}
+
+impl Drop for RetractGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.hints.lock();
+        if let Some(idx) = state.queue.iter().position(|hints| hints.id == self.id) {
+            if !state.queue[idx].started() {
+                state.queue.remove(idx);
+            }
+        }
+    }
+}
+
+/// Retires the [`EarlyHints`] of a request when dropped, which happens as
+/// soon as the service has returned the final response.
+///
+/// Hints cannot be sent after that, since they could end up in the middle of
+/// the response body or in front of the next response.
+pub(crate) struct RequestHints(EarlyHints);
+
+impl RequestHints {
+    /// The handle inserted into the request.
+    pub(crate) fn hints(&self) -> &EarlyHints {
+        &self.0
+    }
+}
+
+impl Drop for RequestHints {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.open_requests.remove(&self.0.request);
+        state.retract(self.0.request);
+    }
+}
+
+fn closed() -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, "connection closed")
+}
+
+fn final_response_sent() -> io::Error {
+    io::Error::other("the final response has already been returned")
+}
+
+fn encode(headers: &http::HeaderMap) -> Bytes {
+    let mut buf = BytesMut::new();
+    buf.put_slice(b"HTTP/1.1 103 Early Hints\r\n");
+    for (name, value) in headers {
+        buf.put_slice(name.as_str().as_bytes());
+        buf.put_slice(b": ");
+        buf.put_slice(value.as_bytes());
+        buf.put_slice(b"\r\n");
+    }
+    buf.put_slice(b"\r\n");
+    buf.freeze()
+}
+
+/// Wraps the IO of a connection to write the hints sent with [`EarlyHints`].
+///
+/// Hyper only flushes the IO once it has written everything it buffered, so
+/// queued hints are written on flush, which keeps them after the previous
+/// response and before the final response of the request they belong to.
+/// Hints which were partially written are finished before any other write.
+///
+/// Without a state the IO is passed through untouched, so connections which
+/// do not use early hints do not pay for them.
+pub(crate) struct EarlyHintsIo<S> {
+    inner: S,
+    state: Option<Arc<Mutex<State>>>,
+}
+
+/// The early hints state of a connection, shared with its [`EarlyHintsIo`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionHints(Arc<Mutex<State>>);
+
+impl ConnectionHints {
+    /// Creates the [`EarlyHints`] of a new request.
+    pub(crate) fn request(&self) -> RequestHints {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let request = state.next_request;
+        state.next_request += 1;
+        state.open_requests.insert(request);
+
+        RequestHints(EarlyHints {
+            state: self.0.clone(),
+            request,
+        })
+    }
+}
+
+impl<S> EarlyHintsIo<S> {
+    pub(crate) fn new(inner: S, hints: Option<&ConnectionHints>) -> Self {
+        Self {
+            inner,
+            state: hints.map(|hints| hints.0.clone()),
+        }
+    }
+
+    fn lock(&self) -> Option<MutexGuard<'_, State>> {
+        self.state
+            .as_ref()
+            .map(|state| state.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Registers the connection waker, which is woken when hints are queued.
+fn register(state: &mut State, cx: &Context<'_>) {
+    if !state.conn.as_ref().is_some_and(|conn| conn.will_wake(cx.waker())) {
+        state.conn = Some(cx.waker().clone());
+    }
+}
+
+impl<S: AsyncWrite + Unpin> EarlyHintsIo<S> {
+    /// Writes the queued hints, or only a partially written one if `all` is
+    /// false.
+    fn poll_write_hints(&mut self, cx: &mut Context<'_>, all: bool) -> Poll<io::Result<()>> {
+        let Some(state) = self.state.as_ref() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        register(&mut state, cx);
+
+        while let Some(hints) = state.queue.front_mut() {
+            if !all && !hints.started() {
+                break;
+            }
+
+            match Pin::new(&mut self.inner).poll_write(cx, &hints.remaining) {
+                Poll::Ready(Ok(0)) => {
+                    state.close();
+                    return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+                }
+                Poll::Ready(Ok(n)) => {
+                    hints.remaining.advance(n);
+                    if hints.remaining.is_empty() {
+                        let hints = state.queue.pop_front().expect("front exists");
+                        state.written = hints.id + 1;
+                        if let Some(waker) = hints.waker {
+                            waker.wake();
+                        }
+                    }
+                }
+                Poll::Ready(Err(err)) => {
+                    state.close();
+                    return Poll::Ready(Err(err));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> Drop for EarlyHintsIo<S> {
+    fn drop(&mut self) {
+        if let Some(mut state) = self.lock() {
+            state.close();
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for EarlyHintsIo<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let Some(mut state) = this.lock() {
+            register(&mut state, cx);
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for EarlyHintsIo<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        std::task::ready!(this.poll_write_hints(cx, false))?;
+        Pin::new(&mut this.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        std::task::ready!(this.poll_write_hints(cx, false))?;
+        Pin::new(&mut this.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        std::task::ready!(this.poll_write_hints(cx, true))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if let Some(mut state) = this.lock() {
+            state.close();
+        }
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
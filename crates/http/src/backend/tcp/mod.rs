@@ -1,10 +1,20 @@
 mod util;
 
 pub mod config;
+mod conn_limit;
+#[cfg(feature = "http1")]
+pub mod early_hints;
+#[cfg(feature = "http1")]
+mod pipeline;
+mod rate_limit;
+#[cfg(feature = "tls-rustls")]
+pub mod tls;
 
 use std::sync::Arc;
 
 pub use config::TcpServerConfig;
+use conn_limit::ConnectionLimiter;
+use rate_limit::RateLimiter;
 use serve::serve_tcp;
 use tokio::sync::Mutex;
 
@@ -37,8 +47,10 @@ impl StartGroup {
         let mut threads = self.threads.lock().await;
 
         while !threads.is_empty() {
-            let (result, _, remaining) = futures::future::select_all(threads.drain(..).map(|thread| thread.disarm())).await;
-            *threads = remaining.into_iter().map(AbortOnDrop::new).collect();
+            // Poll the threads in place so that dropping this future does not lose track
+            // of them.
+            let (result, index, _) = futures::future::select_all(threads.iter_mut()).await;
+            threads.remove(index);
             result??;
         }
 
@@ -60,6 +72,21 @@ impl TcpServer {
     }
 }
 
+impl TcpServer {
+    /// Returns a clone of the listener socket, binding it if the server has not
+    /// been started yet.
+    ///
+    /// The listener's file descriptor can be handed to a replacement process
+    /// (which then uses
+    /// [`with_listener_fd`](config::TcpServerConfigBuilder::with_listener_fd)) to
+    /// take over accepting connections without closing the port. Note that the
+    /// returned descriptor has `FD_CLOEXEC` set, so it must be cleared before
+    /// it can be inherited across an `exec`.
+    pub async fn try_clone_listener(&self) -> Result<std::net::TcpListener, TcpServerError> {
+        Ok(self.config.lock().await.make_listener.make()?)
+    }
+}
+
 impl HttpServer for TcpServer {
     type Error = TcpServerError;
 
@@ -99,6 +126,11 @@ impl HttpServer for TcpServer {
             acceptor.set_alpn(alpn);
         }
 
+        // A single limiter is shared between the workers so that the limit applies to the
+        // whole server.
+        let rate_limiter = config.rate_limit.map(RateLimiter::new).map(Arc::new);
+        let connection_limiter = config.max_connections_per_ip.map(ConnectionLimiter::new).map(Arc::new);
+
         let threads = listeners
             .into_iter()
             .map(|listener| {
@@ -106,7 +138,7 @@ impl HttpServer for TcpServer {
                     listener,
                     service.clone(),
                     config.acceptor.clone(),
-                    config.inner(),
+                    config.inner(rate_limiter.clone(), connection_limiter.clone()),
                     handler.context(),
                 )))
             })
@@ -138,3 +170,705 @@ impl HttpServer for TcpServer {
         Ok(self.start_group.lock().as_ref().ok_or(TcpServerError::NotStarted)?.address)
     }
 }
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(all(test, unix, feature = "http1"))]
+mod tests {
+    use std::convert::Infallible;
+    use std::os::fd::OwnedFd;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::TcpServerConfig;
+    use crate::backend::HttpServer;
+    use crate::svc::function_service;
+
+    #[tokio::test]
+    async fn listener_fd() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = TcpServerConfig::builder()
+            .with_listener_fd(OwnedFd::from(listener))
+            .build()
+            .into_server();
+
+        assert_eq!(server.try_clone_listener().await.unwrap().local_addr().unwrap(), addr);
+
+        server
+            .start(
+                function_service(|_| async { Ok::<_, Infallible>(http::Response::new("hello".to_owned())) }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(server.local_addr().unwrap(), addr);
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("hello"), "{response}");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[cfg(feature = "http2")]
+    #[tokio::test]
+    async fn max_connection_lifetime() {
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_max_connection_lifetime(std::time::Duration::from_millis(100))
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|_| async {
+                    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+                    Ok::<_, Infallible>(http::Response::new("hello".to_owned()))
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        let (client, connection) = h2::client::handshake(stream).await.unwrap();
+        let connection = tokio::spawn(connection);
+
+        let mut client = client.ready().await.unwrap();
+        let (response, _) = client
+            .send_request(http::Request::get("http://localhost/").body(()).unwrap(), true)
+            .unwrap();
+
+        // The lifetime expires while the request is in flight, it must still complete.
+        assert_eq!(response.await.unwrap().status(), http::StatusCode::OK);
+
+        // The server sent a GOAWAY, so the connection closes once the stream is done.
+        tokio::time::timeout(std::time::Duration::from_secs(2), connection)
+            .await
+            .expect("connection was not closed")
+            .unwrap()
+            .unwrap();
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[cfg(feature = "signal")]
+    #[tokio::test]
+    async fn serve_until_signal() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        use scuffle_signal::SignalHandler;
+        use tokio::signal::unix::SignalKind;
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .build()
+            .into_server();
+
+        let mut signals = SignalHandler::new().with_signal(SignalKind::user_defined1());
+        let started = Arc::new(tokio::sync::Notify::new());
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let service = function_service({
+            let started = started.clone();
+            let finished = finished.clone();
+            move |_| {
+                let started = started.clone();
+                let finished = finished.clone();
+                async move {
+                    started.notify_one();
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                    finished.store(true, Ordering::SeqCst);
+                    Ok::<_, Infallible>(http::Response::new("hello".to_owned()))
+                }
+            }
+        });
+
+        let client = async {
+            let addr = loop {
+                match server.local_addr() {
+                    Ok(addr) => break addr,
+                    Err(_) => tokio::task::yield_now().await,
+                }
+            };
+
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+
+            started.notified().await;
+
+            // Safety: This is a test, and we control the process.
+            unsafe {
+                libc::raise(SignalKind::user_defined1().as_raw_value());
+            }
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let (result, response) = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            futures::future::join(server.serve_until_signal(service, 1, &mut signals), client),
+        )
+        .await
+        .expect("server did not shut down");
+
+        result.unwrap();
+        assert!(finished.load(Ordering::SeqCst), "returned before the connection drained");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("hello"), "{response}");
+    }
+
+    // Other loopback addresses are only routable by default on linux.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn rate_limit() {
+        async fn get(from: std::net::IpAddr, addr: std::net::SocketAddr) -> String {
+            let socket = tokio::net::TcpSocket::new_v4().unwrap();
+            socket.bind(std::net::SocketAddr::new(from, 0)).unwrap();
+            let mut stream = socket.connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        }
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_rate_limit(0.1, 3)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|_| async { Ok::<_, Infallible>(http::Response::new("hello".to_owned())) }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let addr = server.local_addr().unwrap();
+        let first = "127.0.0.1".parse().unwrap();
+
+        for _ in 0..3 {
+            let response = get(first, addr).await;
+            assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        }
+
+        for _ in 0..5 {
+            let response = get(first, addr).await;
+            assert!(response.starts_with("HTTP/1.1 429 Too Many Requests"), "{response}");
+            assert!(response.to_lowercase().contains("retry-after: 10\r\n"), "{response}");
+        }
+
+        // A different client is not affected.
+        let response = get("127.0.0.2".parse().unwrap(), addr).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+        server.shutdown().await.unwrap();
+    }
+
+    // Other loopback addresses are only routable by default on linux.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn max_connections_per_ip() {
+        async fn connect(from: std::net::IpAddr, addr: std::net::SocketAddr) -> tokio::net::TcpStream {
+            let socket = tokio::net::TcpSocket::new_v4().unwrap();
+            socket.bind(std::net::SocketAddr::new(from, 0)).unwrap();
+            socket.connect(addr).await.unwrap()
+        }
+
+        /// Makes a keep-alive request, returns `None` if the server closed the
+        /// connection instead.
+        async fn get(stream: &mut tokio::net::TcpStream) -> Option<String> {
+            stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await.ok()?;
+
+            let mut response = Vec::new();
+            let mut buf = [0; 1024];
+            while !response.ends_with(b"hello") {
+                match stream.read(&mut buf).await {
+                    Ok(0) | Err(_) => return None,
+                    Ok(n) => response.extend_from_slice(&buf[..n]),
+                }
+            }
+
+            Some(String::from_utf8(response).unwrap())
+        }
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_max_connections_per_ip(2)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|_| async { Ok::<_, Infallible>(http::Response::new("hello".to_owned())) }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let addr = server.local_addr().unwrap();
+        let first = "127.0.0.1".parse().unwrap();
+
+        let mut open = Vec::new();
+        for _ in 0..2 {
+            let mut stream = connect(first, addr).await;
+            let response = get(&mut stream).await.expect("connection was rejected");
+            assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+            open.push(stream);
+        }
+
+        for _ in 0..3 {
+            let mut stream = connect(first, addr).await;
+            assert_eq!(get(&mut stream).await, None);
+        }
+
+        // A different client is not affected.
+        let mut stream = connect("127.0.0.2".parse().unwrap(), addr).await;
+        let response = get(&mut stream).await.expect("connection was rejected");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+        // Once a connection is closed the client can connect again.
+        drop(open.pop());
+        let response = tokio::time::timeout(std::time::Duration::from_secs(2), async {
+            loop {
+                let mut stream = connect(first, addr).await;
+                if let Some(response) = get(&mut stream).await {
+                    break response;
+                }
+
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("connection was not released");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn early_hints() {
+        use std::sync::Arc;
+
+        use super::early_hints::EarlyHints;
+
+        let release = Arc::new(tokio::sync::Notify::new());
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_http1_early_hints(true)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service({
+                    let release = release.clone();
+                    move |req| {
+                        let release = release.clone();
+                        async move {
+                            let Some(hints) = req.extensions().get::<EarlyHints>().cloned() else {
+                                return Ok::<_, Infallible>(http::Response::new("no hints".to_owned()));
+                            };
+
+                            let mut headers = http::HeaderMap::new();
+                            headers.insert(
+                                http::header::LINK,
+                                http::HeaderValue::from_static("</style.css>; rel=preload; as=style"),
+                            );
+                            hints.send(&headers).await.unwrap();
+
+                            // The final response is only ready once the client has seen the hints.
+                            release.notified().await;
+                            Ok(http::Response::new("hello".to_owned()))
+                        }
+                    }
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let addr = server.local_addr().unwrap();
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut hints = Vec::new();
+        let mut buf = [0; 1024];
+        while !hints.ends_with(b"\r\n\r\n") {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert_ne!(n, 0, "connection closed");
+            hints.extend_from_slice(&buf[..n]);
+        }
+
+        assert_eq!(
+            String::from_utf8(hints).unwrap(),
+            "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style\r\n\r\n"
+        );
+
+        release.notify_one();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("hello"), "{response}");
+
+        // HTTP/1.0 clients do not support informational responses.
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.0\r\nHost: localhost\r\n\r\n").await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.0 200 OK"), "{response}");
+        assert!(response.ends_with("no hints"), "{response}");
+
+        server.shutdown().await.unwrap();
+
+        // Early hints are opt-in.
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|req: http::Request<crate::body::IncomingBody>| async move {
+                    let hints = req.extensions().get::<EarlyHints>().is_some();
+                    Ok::<_, Infallible>(http::Response::new(format!("hints: {hints}")))
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.ends_with("hints: false"), "{response}");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn early_hints_after_response() {
+        use std::sync::Arc;
+
+        use bytes::Bytes;
+        use http_body::Frame;
+
+        use super::early_hints::EarlyHints;
+
+        let late = Arc::new(tokio::sync::Notify::new());
+        let (result_tx, mut result_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_http1_early_hints(true)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service({
+                    let late = late.clone();
+                    move |req: http::Request<crate::body::IncomingBody>| {
+                        let late = late.clone();
+                        let result_tx = result_tx.clone();
+                        async move {
+                            let (tx, rx) = futures::channel::mpsc::unbounded();
+                            let body = http_body_util::StreamBody::new(rx);
+                            if req.uri().path() != "/late" {
+                                tx.unbounded_send(Ok::<_, Infallible>(Frame::data(Bytes::from_static(b"done"))))
+                                    .unwrap();
+                                return Ok::<_, Infallible>(http::Response::new(body));
+                            }
+
+                            let hints = req.extensions().get::<EarlyHints>().cloned().unwrap();
+                            let mut headers = http::HeaderMap::new();
+                            headers.insert(
+                                http::header::LINK,
+                                http::HeaderValue::from_static("</style.css>; rel=preload; as=style"),
+                            );
+                            hints.send(&headers).await.unwrap();
+
+                            // The handle is kept after the response is returned, while its body is
+                            // still being written.
+                            tokio::spawn(async move {
+                                late.notified().await;
+                                result_tx.send(hints.send(&headers).await).unwrap();
+                                tx.unbounded_send(Ok(Frame::data(Bytes::from_static(b"world")))).unwrap();
+                            });
+
+                            Ok(http::Response::new(body))
+                        }
+                    }
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        stream
+            .write_all(b"GET /late HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        // Wait until the response head has been written.
+        let mut response = Vec::new();
+        let mut buf = [0; 1024];
+        while response.windows(9).filter(|w| *w == b"HTTP/1.1 ").count() < 2 {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert_ne!(n, 0, "connection closed");
+            response.extend_from_slice(&buf[..n]);
+        }
+
+        late.notify_one();
+        let err = result_rx
+            .recv()
+            .await
+            .unwrap()
+            .expect_err("hints were sent after the response");
+        assert_eq!(err.kind(), std::io::ErrorKind::Other);
+
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        stream.read_to_end(&mut response).await.unwrap();
+        let response = String::from_utf8(response).unwrap();
+
+        // The stream only contains the early hints and the two complete responses.
+        let (hints, rest) = response.split_once("\r\n\r\n").unwrap();
+        assert_eq!(hints, "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style");
+        assert_eq!(response.matches("103 Early Hints").count(), 1, "{response}");
+
+        let (first, second) = rest.split_once("0\r\n\r\n").unwrap();
+        assert!(first.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(first.ends_with("\r\n\r\n5\r\nworld\r\n"), "{response}");
+        assert!(second.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(second.ends_with("\r\n\r\n4\r\ndone\r\n0\r\n\r\n"), "{response}");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn http1_pipelining() {
+        use std::sync::Arc;
+
+        let release = Arc::new(tokio::sync::Notify::new());
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_http1_max_pipelined(1)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service({
+                    let release = release.clone();
+                    move |req| {
+                        let release = release.clone();
+                        async move {
+                            let path = req.uri().path().to_owned();
+                            // The first response is held back, the others must still come after it.
+                            if path == "/0" {
+                                release.notified().await;
+                            }
+
+                            Ok::<_, Infallible>(http::Response::new(format!("response {path}\n")))
+                        }
+                    }
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        stream
+            .write_all(
+                b"GET /0 HTTP/1.1\r\nHost: localhost\r\n\r\n\
+                POST /1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbody\
+                GET /2 HTTP/1.1\r\nHost: localhost\r\n\r\n\
+                GET /3 HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        release.notify_one();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let bodies: Vec<_> = response.lines().filter(|line| line.starts_with("response")).collect();
+        assert_eq!(
+            bodies,
+            ["response /0", "response /1", "response /2", "response /3"],
+            "{response}"
+        );
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[cfg(feature = "http2")]
+    #[tokio::test]
+    async fn http2_trailers() {
+        use bytes::Bytes;
+        use http_body::Frame;
+
+        use crate::body::TrailersBody;
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service(|_| async {
+                    let (tx, rx) = futures::channel::mpsc::unbounded();
+                    let (body, trailers) = TrailersBody::channel(http_body_util::StreamBody::new(rx));
+
+                    tokio::spawn(async move {
+                        for chunk in ["hello", " ", "world"] {
+                            tx.unbounded_send(Ok::<_, Infallible>(Frame::data(Bytes::from_static(chunk.as_bytes()))))
+                                .unwrap();
+                            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                        }
+                        drop(tx);
+
+                        let mut map = http::HeaderMap::new();
+                        map.insert("grpc-status", http::HeaderValue::from_static("0"));
+                        trailers.send(map).unwrap();
+                    });
+
+                    Ok::<_, Infallible>(http::Response::new(body))
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        let (client, connection) = h2::client::handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+
+        let mut client = client.ready().await.unwrap();
+        let (response, _) = client
+            .send_request(http::Request::get("http://localhost/").body(()).unwrap(), true)
+            .unwrap();
+        let response = response.await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        let mut body = response.into_body();
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(data, b"hello world");
+
+        let trailers = body.trailers().await.unwrap().expect("no trailers received");
+        assert_eq!(trailers["grpc-status"], "0");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[cfg(feature = "http2")]
+    #[tokio::test]
+    async fn http2_max_concurrent_streams() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .with_http2_max_concurrent_streams(1)
+            .build()
+            .into_server();
+
+        server
+            .start(
+                function_service({
+                    let in_flight = in_flight.clone();
+                    let max_in_flight = max_in_flight.clone();
+                    move |_| {
+                        let in_flight = in_flight.clone();
+                        let max_in_flight = max_in_flight.clone();
+                        async move {
+                            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            max_in_flight.fetch_max(current, Ordering::SeqCst);
+                            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                            Ok::<_, Infallible>(http::Response::new("hello".to_owned()))
+                        }
+                    }
+                }),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        let (client, connection) = h2::client::handshake(stream).await.unwrap();
+        tokio::spawn(connection);
+
+        // The first response guarantees that the server settings have been received.
+        let mut client = client.ready().await.unwrap();
+        let (response, _) = client
+            .send_request(http::Request::get("http://localhost/").body(()).unwrap(), true)
+            .unwrap();
+        assert_eq!(response.await.unwrap().status(), http::StatusCode::OK);
+        assert_eq!(client.current_max_send_streams(), 1);
+
+        let mut responses = Vec::new();
+        for _ in 0..3 {
+            client = client.ready().await.unwrap();
+            let (response, _) = client
+                .send_request(http::Request::get("http://localhost/").body(()).unwrap(), true)
+                .unwrap();
+            responses.push(response);
+        }
+
+        for response in responses {
+            assert_eq!(response.await.unwrap().status(), http::StatusCode::OK);
+        }
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+
+        server.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,545 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// How much is read from the connection at once.
+const READ_SIZE: usize = 8 * 1024;
+/// Heads and chunk lines larger than this are not scanned, hyper rejects
+/// them anyway.
+const MAX_LINE_SIZE: usize = 64 * 1024;
+/// The same as hyper's default limit.
+const MAX_HEADERS: usize = 100;
+
+/// Tracks the HTTP/1.1 requests of a connection, shared between the
+/// [`PipelineIo`] which reads them and the service which answers them.
+#[derive(Debug, Clone)]
+pub(crate) struct Pipeline(Arc<Mutex<State>>);
+
+#[derive(Debug, Default)]
+struct State {
+    /// The number of requests which have started to be read.
+    received: u64,
+    /// The number of requests which have been answered.
+    completed: u64,
+    /// The reader, woken when a request is answered.
+    reader: Option<Waker>,
+}
+
+impl Pipeline {
+    pub(crate) fn new() -> Self {
+        Self(Arc::default())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns a guard which marks a request as answered when it is dropped.
+    ///
+    /// Hyper answers HTTP/1.1 requests one at a time and in order, so the
+    /// guard should live until the response body has been written.
+    pub(crate) fn guard(&self) -> PipelineGuard {
+        PipelineGuard(self.clone())
+    }
+
+    /// Starts reading another request if less than `max_pipelined` requests
+    /// are queued behind the one which is being answered, so at most
+    /// `max_pipelined + 1` requests are in flight.
+    ///
+    /// Otherwise the reader is woken once a request has been answered.
+    fn try_start(&self, max_pipelined: usize, cx: &mut Context<'_>) -> bool {
+        let mut state = self.lock();
+
+        // The oldest request in flight is the one being answered, the others are
+        // queued behind it.
+        let in_flight = state.received.saturating_sub(state.completed);
+        let queued = in_flight.saturating_sub(1);
+        if in_flight > 0 && queued >= max_pipelined as u64 {
+            state.reader = Some(cx.waker().clone());
+            return false;
+        }
+
+        state.received += 1;
+        true
+    }
+}
+
+pub(crate) struct PipelineGuard(Pipeline);
+
+impl Drop for PipelineGuard {
+    fn drop(&mut self) {
+        let mut state = self.0.lock();
+        state.completed += 1;
+        if let Some(reader) = state.reader.take() {
+            reader.wake();
+        }
+    }
+}
+
+/// Where the scanner is in the HTTP/1.1 byte stream.
+#[derive(Debug)]
+enum Scan {
+    /// Between requests, skipping the empty lines allowed before a request.
+    Idle,
+    /// Parsing the head of a request to find out how long its body is.
+    Head,
+    /// The number of bytes left in a body with a content length.
+    Body(u64),
+    /// Buffering the size line of a chunk.
+    ChunkSize,
+    /// The number of bytes left in a chunk.
+    ChunkData(u64),
+    /// Skipping the line ending after the data of a chunk.
+    ChunkEnd,
+    /// Buffering the trailers after the last chunk.
+    Trailers,
+    /// The connection is no longer HTTP/1.1, because of an upgrade or HTTP/2,
+    /// or could not be parsed. Everything is passed through.
+    Passthrough,
+}
+
+/// Finds the boundaries between the requests of an HTTP/1.1 connection.
+///
+/// Only as much is parsed as is needed to skip over the bodies, hyper still
+/// parses and validates the requests. Anything the scanner does not
+/// understand makes it pass the rest of the connection through.
+#[derive(Debug)]
+struct Scanner {
+    state: Scan,
+    /// The part of a head or line which was split across reads.
+    line: Vec<u8>,
+}
+
+impl Scanner {
+    const fn new() -> Self {
+        Self {
+            state: Scan::Idle,
+            line: Vec::new(),
+        }
+    }
+
+    /// Returns true if `next` is the first byte of a new request.
+    fn at_request(&self, next: u8) -> bool {
+        matches!(self.state, Scan::Idle) && next != b'\r' && next != b'\n'
+    }
+
+    /// Starts a new request, must only be called if [`Scanner::at_request`]
+    /// returned true.
+    fn start_request(&mut self) {
+        self.state = Scan::Head;
+    }
+
+    /// Scans the data until the end of the current request, returns how many
+    /// bytes were scanned.
+    ///
+    /// Always scans at least one byte unless the data starts a new request.
+    fn scan(&mut self, data: &[u8]) -> usize {
+        let mut n = 0;
+
+        while n < data.len() {
+            match &mut self.state {
+                Scan::Idle => {
+                    if self.at_request(data[n]) {
+                        break;
+                    }
+
+                    n += 1;
+                }
+                Scan::Body(remaining) | Scan::ChunkData(remaining) => {
+                    let len = (*remaining).min((data.len() - n) as u64);
+                    *remaining -= len;
+                    n += len as usize;
+
+                    if *remaining == 0 {
+                        self.state = match self.state {
+                            Scan::Body(_) => Scan::Idle,
+                            _ => Scan::ChunkEnd,
+                        };
+
+                        if matches!(self.state, Scan::Idle) {
+                            break;
+                        }
+                    }
+                }
+                Scan::ChunkEnd => {
+                    if data[n] == b'\n' {
+                        self.state = Scan::ChunkSize;
+                    }
+
+                    n += 1;
+                }
+                Scan::Head => {
+                    let rest = &data[n..];
+                    let buffered = self.line.len();
+
+                    // Heads are usually read at once and parsed in place, only a head which is
+                    // split across reads is buffered.
+                    let parsed = if buffered == 0 {
+                        parse_head(rest)
+                    } else {
+                        self.line.extend_from_slice(rest);
+                        parse_head(&self.line)
+                    };
+
+                    match parsed {
+                        Some((len, state)) => {
+                            n += len - buffered;
+                            self.line.clear();
+                            self.state = state;
+                            if matches!(self.state, Scan::Idle) {
+                                break;
+                            }
+                        }
+                        None => {
+                            if buffered == 0 {
+                                self.line.extend_from_slice(rest);
+                            }
+
+                            n = data.len();
+                            if self.line.len() > MAX_LINE_SIZE {
+                                self.state = Scan::Passthrough;
+                            }
+                        }
+                    }
+                }
+                Scan::ChunkSize | Scan::Trailers => {
+                    let rest = &data[n..];
+                    let end = rest.iter().position(|&b| b == b'\n').map(|i| i + 1);
+                    let len = end.unwrap_or(rest.len());
+                    self.line.extend_from_slice(&rest[..len]);
+                    n += len;
+
+                    if self.line.len() > MAX_LINE_SIZE {
+                        self.state = Scan::Passthrough;
+                    } else if end.is_some() {
+                        self.on_line();
+                        if matches!(self.state, Scan::Idle) {
+                            break;
+                        }
+                    }
+                }
+                Scan::Passthrough => n = data.len(),
+            }
+        }
+
+        n
+    }
+
+    /// Handles the end of a line while buffering a chunk size or the
+    /// trailers.
+    fn on_line(&mut self) {
+        match self.state {
+            Scan::ChunkSize => {
+                let size = std::str::from_utf8(&self.line)
+                    .ok()
+                    .and_then(|line| line.split(';').next())
+                    .and_then(|size| u64::from_str_radix(size.trim(), 16).ok());
+
+                self.state = match size {
+                    Some(0) => Scan::Trailers,
+                    Some(size) => Scan::ChunkData(size),
+                    None => Scan::Passthrough,
+                };
+                self.line.clear();
+            }
+            Scan::Trailers => {
+                if self.line == b"\r\n" || self.line == b"\n" {
+                    self.state = Scan::Idle;
+                }
+                self.line.clear();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses the head at the start of `buf`, returning its length and how the
+/// body is framed, or `None` if the head is incomplete.
+///
+/// A head which cannot be parsed is passed through, its length is then the
+/// whole buffer.
+fn parse_head(buf: &[u8]) -> Option<(usize, Scan)> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut req = httparse::Request::new(&mut headers);
+    match req.parse(buf) {
+        Ok(httparse::Status::Complete(len)) => Some((len, body_framing(&req))),
+        Ok(httparse::Status::Partial) => None,
+        Err(_) => Some((buf.len(), Scan::Passthrough)),
+    }
+}
+
+/// Returns how the body of a request is framed.
+fn body_framing(req: &httparse::Request<'_, '_>) -> Scan {
+    if req.method == Some("CONNECT") {
+        return Scan::Passthrough;
+    }
+
+    let mut state = Scan::Idle;
+    for header in req.headers.iter() {
+        let value = String::from_utf8_lossy(header.value);
+        if header.name.eq_ignore_ascii_case("transfer-encoding") {
+            if value
+                .rsplit(',')
+                .next()
+                .is_some_and(|v| v.trim().eq_ignore_ascii_case("chunked"))
+            {
+                return Scan::ChunkSize;
+            }
+
+            return Scan::Passthrough;
+        } else if header.name.eq_ignore_ascii_case("content-length") {
+            state = match value.trim().parse() {
+                Ok(0) => Scan::Idle,
+                Ok(len) => Scan::Body(len),
+                Err(_) => return Scan::Passthrough,
+            };
+        } else if header.name.eq_ignore_ascii_case("upgrade") {
+            // The connection might be switched to another protocol after
+            // the request.
+            return Scan::Passthrough;
+        }
+    }
+
+    state
+}
+
+/// Wraps the IO of a connection to limit how many pipelined HTTP/1.1
+/// requests are read ahead, everything is passed through if there is no
+/// limit or the connection is not HTTP/1.1.
+///
+/// Hyper answers pipelined requests one at a time and in request order, the
+/// requests behind the one being answered stay in its read buffer. Once
+/// `max_pipelined` requests are waiting, nothing more is read from the
+/// connection until a request has been answered, which leaves the rest in the
+/// socket buffers and eventually stops the client from sending.
+pub(crate) struct PipelineIo<S> {
+    inner: S,
+    pipeline: Pipeline,
+    max_pipelined: usize,
+    scanner: Scanner,
+    /// Data read from the connection which has not been passed on yet.
+    buf: BytesMut,
+}
+
+impl<S> PipelineIo<S> {
+    pub(crate) fn new(inner: S, pipeline: Pipeline, max_pipelined: Option<usize>) -> Self {
+        let mut scanner = Scanner::new();
+        if max_pipelined.is_none() {
+            scanner.state = Scan::Passthrough;
+        }
+
+        Self {
+            inner,
+            pipeline,
+            max_pipelined: max_pipelined.unwrap_or(usize::MAX),
+            scanner,
+            buf: BytesMut::new(),
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for PipelineIo<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        // Nothing could be passed on, and a request must not be started without
+        // reading any of it.
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        if this.buf.is_empty() {
+            if matches!(this.scanner.state, Scan::Passthrough) {
+                return Pin::new(&mut this.inner).poll_read(cx, buf);
+            }
+
+            this.buf.resize(READ_SIZE, 0);
+            let mut read = ReadBuf::new(&mut this.buf);
+            let result = Pin::new(&mut this.inner).poll_read(cx, &mut read);
+            let filled = read.filled().len();
+            this.buf.truncate(filled);
+
+            std::task::ready!(result)?;
+            if filled == 0 {
+                return Poll::Ready(Ok(()));
+            }
+        }
+
+        let available = this.buf.len().min(buf.remaining());
+        let mut n = 0;
+        while n < available {
+            if this.scanner.at_request(this.buf[n]) {
+                if !this.pipeline.try_start(this.max_pipelined, cx) {
+                    if n == 0 {
+                        // The waker was stored by `try_start`.
+                        return Poll::Pending;
+                    }
+
+                    break;
+                }
+
+                this.scanner.start_request();
+            }
+
+            n += this.scanner.scan(&this.buf[n..available]);
+        }
+
+        buf.put_slice(&this.buf[..n]);
+        this.buf.advance(n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for PipelineIo<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::{Pipeline, PipelineIo, Scanner};
+
+    /// Splits the data into the requests the scanner finds, feeding it at most
+    /// `read` bytes at a time.
+    fn requests(data: &[u8], read: usize) -> Vec<&[u8]> {
+        let mut scanner = Scanner::new();
+        let mut requests = Vec::new();
+        let mut start = None;
+        let mut n = 0;
+
+        while n < data.len() {
+            if scanner.at_request(data[n]) {
+                scanner.start_request();
+                start = Some(n);
+            }
+
+            n += scanner.scan(&data[n..data.len().min(n + read)]);
+            if matches!(scanner.state, super::Scan::Idle) {
+                if let Some(start) = start.take() {
+                    requests.push(&data[start..n]);
+                }
+            }
+        }
+
+        requests
+    }
+
+    #[test]
+    fn scanner() {
+        let data = b"GET / HTTP/1.1\r\nHost: a\r\n\r\n\
+            POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello\
+            POST /b HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext\r\nhello\r\n0\r\nx: y\r\n\r\n\
+            \r\nGET /c HTTP/1.1\r\n\r\n";
+
+        let expected = [
+            &b"GET / HTTP/1.1\r\nHost: a\r\n\r\n"[..],
+            b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello",
+            b"POST /b HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5;ext\r\nhello\r\n0\r\nx: y\r\n\r\n",
+            b"GET /c HTTP/1.1\r\n\r\n",
+        ];
+
+        assert_eq!(requests(data, data.len()), expected);
+
+        // Heads and lines split across reads.
+        for read in [1, 3, 7, 20] {
+            assert_eq!(requests(data, read), expected, "read size {read}");
+        }
+
+        // Upgraded connections are passed through.
+        let data = b"GET / HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: websocket\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        assert_eq!(requests(data, data.len()), Vec::<&[u8]>::new());
+    }
+
+    #[tokio::test]
+    async fn backpressure() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let pipeline = Pipeline::new();
+        let mut io = PipelineIo::new(server, pipeline.clone(), Some(1));
+
+        client
+            .write_all(b"GET /0 HTTP/1.1\r\n\r\nGET /1 HTTP/1.1\r\n\r\nGET /2 HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+
+        // The request being answered and one pipelined request are read.
+        let mut buf = [0; 1024];
+        let n = io.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"GET /0 HTTP/1.1\r\n\r\nGET /1 HTTP/1.1\r\n\r\n");
+
+        let read = tokio::time::timeout(std::time::Duration::from_millis(50), io.read(&mut buf)).await;
+        assert!(read.is_err(), "read past the limit");
+
+        // Once the first request is answered the next one can be read.
+        drop(pipeline.guard());
+        let n = io.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"GET /2 HTTP/1.1\r\n\r\n");
+    }
+
+    #[tokio::test]
+    async fn max_pipelined() {
+        const REQUESTS: &[u8] =
+            b"GET /0 HTTP/1.1\r\n\r\nGET /1 HTTP/1.1\r\n\r\nGET /2 HTTP/1.1\r\n\r\nGET /3 HTTP/1.1\r\n\r\n";
+
+        // The request being answered and up to `max_pipelined` requests behind it are
+        // read.
+        for (max_pipelined, expected) in [(0, 19), (1, 38), (2, 57)] {
+            let (mut client, server) = tokio::io::duplex(1024);
+            let pipeline = Pipeline::new();
+            let mut io = PipelineIo::new(server, pipeline.clone(), Some(max_pipelined));
+            client.write_all(REQUESTS).await.unwrap();
+
+            let mut buf = [0; 1024];
+            let n = io.read(&mut buf).await.unwrap();
+            assert_eq!(&buf[..n], &REQUESTS[..expected], "max_pipelined {max_pipelined}");
+
+            let read = tokio::time::timeout(std::time::Duration::from_millis(20), io.read(&mut buf)).await;
+            assert!(read.is_err(), "read past the limit with max_pipelined {max_pipelined}");
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_read_buf() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let pipeline = Pipeline::new();
+        let mut io = PipelineIo::new(server, pipeline.clone(), Some(0));
+        client
+            .write_all(b"GET /0 HTTP/1.1\r\n\r\nGET /1 HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+
+        // A read without any space left neither blocks nor starts a request.
+        assert_eq!(io.read(&mut []).await.unwrap(), 0);
+
+        let mut buf = [0; 1024];
+        let n = io.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"GET /0 HTTP/1.1\r\n\r\n");
+
+        // At the limit a read without any space is still ready.
+        assert_eq!(io.read(&mut []).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// How often idle buckets are evicted from the limiter.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The number of shards the buckets are split into, so that workers checking
+/// different IPs rarely contend on the same lock.
+const SHARDS: usize = 16;
+
+/// A token-bucket rate limit applied to each client IP.
+///
+/// Every IP may make up to `burst` requests at once, after which tokens are
+/// refilled at `requests_per_sec`. Requests made without a token are rejected
+/// with `429 Too Many Requests` and a `Retry-After` header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// The number of requests per second a single IP is allowed to make.
+    pub requests_per_sec: f64,
+    /// The number of requests a single IP can make in a burst.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Creates a rate limit which refills `requests_per_sec` tokens per second
+    /// up to a maximum of `burst`.
+    ///
+    /// A `requests_per_sec` of `0.0` never refills, so each IP can only ever
+    /// make `burst` requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests_per_sec` is negative, NaN or infinite, or if
+    /// `burst` is zero, since no request would ever be allowed.
+    pub fn new(requests_per_sec: f64, burst: u32) -> Self {
+        assert!(
+            requests_per_sec.is_finite() && requests_per_sec >= 0.0,
+            "requests_per_sec must be finite and not negative, got {requests_per_sec}"
+        );
+        assert!(burst > 0, "burst must be at least 1");
+
+        Self { requests_per_sec, burst }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug)]
+struct Shard {
+    buckets: HashMap<IpAddr, Bucket>,
+    last_eviction: Instant,
+}
+
+/// The limiter shared between all the workers of a server.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    limit: RateLimit,
+    hasher: RandomState,
+    shards: Box<[std::sync::Mutex<Shard>]>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        let now = Instant::now();
+
+        Self {
+            limit,
+            hasher: RandomState::new(),
+            shards: (0..SHARDS)
+                .map(|_| {
+                    std::sync::Mutex::new(Shard {
+                        buckets: HashMap::new(),
+                        last_eviction: now,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Takes a token for the given IP, if there are none left the time until
+    /// the next token is available is returned.
+    pub(crate) fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn shard(&self, ip: IpAddr) -> &std::sync::Mutex<Shard> {
+        &self.shards[self.hasher.hash_one(ip) as usize % self.shards.len()]
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let burst = self.limit.burst as f64;
+        let rate = self.limit.requests_per_sec;
+
+        let mut shard = self.shard(ip).lock().unwrap_or_else(|e| e.into_inner());
+
+        if now.saturating_duration_since(shard.last_eviction) >= EVICTION_INTERVAL {
+            // A bucket which would have refilled completely is indistinguishable from a
+            // new one, so it can be dropped.
+            shard.buckets.retain(|_, bucket| {
+                bucket.tokens + now.saturating_duration_since(bucket.updated).as_secs_f64() * rate < burst
+            });
+            shard.last_eviction = now;
+        }
+
+        let bucket = shard.buckets.entry(ip).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            // A tiny rate can take longer than a `Duration` can hold, and a rate of zero
+            // never refills.
+            Err(Duration::try_from_secs_f64((1.0 - bucket.tokens) / rate).unwrap_or(Duration::MAX))
+        }
+    }
+
+    /// Builds the response returned to a client which has been rate limited.
+    pub(crate) fn response<B>(retry_after: Duration, body: B) -> http::Response<B> {
+        // Retry-After only has a resolution of seconds, round up so that clients which
+        // respect it are not rejected again.
+        let secs = retry_after
+            .as_secs()
+            .saturating_add(u64::from(retry_after.subsec_nanos() > 0));
+
+        let mut res = http::Response::new(body);
+        *res.status_mut() = http::StatusCode::TOO_MANY_REQUESTS;
+        res.headers_mut()
+            .insert(http::header::RETRY_AFTER, http::HeaderValue::from(secs.max(1)));
+        res
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{RateLimit, RateLimiter, EVICTION_INTERVAL};
+
+    fn buckets(limiter: &RateLimiter) -> usize {
+        limiter.shards.iter().map(|shard| shard.lock().unwrap().buckets.len()).sum()
+    }
+
+    #[test]
+    fn token_bucket() {
+        let limiter = RateLimiter::new(RateLimit::new(2.0, 3));
+        let ip = "127.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(ip, now).is_ok());
+        }
+        assert_eq!(limiter.check_at(ip, now), Err(Duration::from_millis(500)));
+
+        // Half a second refills one token.
+        let now = now + Duration::from_millis(500);
+        assert!(limiter.check_at(ip, now).is_ok());
+        assert!(limiter.check_at(ip, now).is_err());
+
+        // Other IPs have their own bucket.
+        assert!(limiter.check_at("127.0.0.2".parse().unwrap(), now).is_ok());
+    }
+
+    #[test]
+    fn eviction() {
+        let limiter = RateLimiter::new(RateLimit::new(1.0, 1));
+        let now = Instant::now();
+
+        let first = "127.0.0.1".parse().unwrap();
+        assert!(limiter.check_at(first, now).is_ok());
+        assert!(limiter.check_at("127.0.0.2".parse().unwrap(), now).is_ok());
+        assert_eq!(buckets(&limiter), 2);
+
+        // Buckets are evicted from a shard the next time it is used.
+        let other = (3..=255)
+            .map(|i| std::net::IpAddr::from([127, 0, 0, i]))
+            .find(|&ip| std::ptr::eq(limiter.shard(ip), limiter.shard(first)))
+            .unwrap();
+        assert!(limiter.check_at(other, now + EVICTION_INTERVAL).is_ok());
+
+        let shard = limiter.shard(first).lock().unwrap();
+        assert_eq!(shard.buckets.len(), 1);
+        assert!(shard.buckets.contains_key(&other));
+    }
+
+    #[test]
+    fn retry_after_saturates() {
+        let ip = "127.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        let limiter = RateLimiter::new(RateLimit::new(f64::MIN_POSITIVE, 1));
+        assert!(limiter.check_at(ip, now).is_ok());
+        assert_eq!(limiter.check_at(ip, now), Err(Duration::MAX));
+
+        let limiter = RateLimiter::new(RateLimit::new(0.0, 1));
+        assert!(limiter.check_at(ip, now).is_ok());
+        assert_eq!(limiter.check_at(ip, now), Err(Duration::MAX));
+    }
+
+    #[test]
+    #[should_panic = "requests_per_sec must be finite and not negative"]
+    fn invalid_rate() {
+        RateLimit::new(f64::NAN, 1);
+    }
+
+    #[test]
+    #[should_panic = "burst must be at least 1"]
+    fn invalid_burst() {
+        RateLimit::new(1.0, 0);
+    }
+
+    #[test]
+    fn retry_after() {
+        let res = RateLimiter::response(Duration::from_millis(1500), ());
+        assert_eq!(res.status(), http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[http::header::RETRY_AFTER], "2");
+
+        let res = RateLimiter::response(Duration::from_millis(10), ());
+        assert_eq!(res.headers()[http::header::RETRY_AFTER], "1");
+    }
+}
@@ -5,8 +5,10 @@ use http::HeaderValue;
 use scuffle_context::ContextFutExt;
 
 use super::config::{TcpServerConfigInner, TlsAcceptor};
+use super::conn_limit::ConnectionPermit;
+use super::rate_limit::RateLimiter;
 use super::{util, TcpServerError};
-use crate::body::{has_body, Tracker};
+use crate::body::{has_body, OptionalBody, Tracker};
 use crate::svc::{ConnectionAcceptor, ConnectionHandle, IncomingConnection};
 use crate::util::{TimeoutTracker, TimeoutTrackerDropGuard};
 
@@ -45,6 +47,19 @@ async fn serve_tcp_inner(
             None => break,
         };
 
+        // Dropping the stream closes the connection.
+        let permit = match &config.connection_limiter {
+            Some(limiter) => match limiter.acquire(addr.ip()) {
+                Some(permit) => Some(permit),
+                None => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("too many connections from {}, closing: {:?}", addr.ip(), addr);
+                    continue;
+                }
+            },
+            None => None,
+        };
+
         let Some(handle) = service.accept(IncomingConnection { addr }) else {
             continue;
         };
@@ -56,6 +71,7 @@ async fn serve_tcp_inner(
             tls_acceptor.clone(),
             config.clone(),
             ctx.clone(),
+            permit,
         ));
     }
 
@@ -69,6 +85,8 @@ async fn serve_stream(
     tls_acceptor: Option<TlsAcceptor>,
     config: TcpServerConfigInner,
     ctx: scuffle_context::Context,
+    // Counts the connection against its IP until it is closed.
+    _permit: Option<ConnectionPermit>,
 ) {
     let (ctx, ctx_handler) = ctx.new_child();
 
@@ -182,16 +200,28 @@ async fn serve_stream_inner(
                 return Ok(());
             };
 
-            serve_handle(stream, addr, handle, config, &ctx).await
+            let mut extensions = http::Extensions::new();
+            if let Some(cert) = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(super::tls::ClientCertificate::new)
+            {
+                extensions.insert(cert);
+            }
+
+            serve_handle(stream, addr, extensions, handle, config, &ctx).await
         }
         #[cfg(not(feature = "tls-rustls"))]
         Some(_) => unreachable!(),
-        None => serve_handle(stream, addr, handle, config, &ctx).await,
+        None => serve_handle(stream, addr, http::Extensions::new(), handle, config, &ctx).await,
     }
 }
 
 struct DropTracker {
     _guard: Option<TimeoutTrackerDropGuard>,
+    #[cfg(feature = "http1")]
+    _pipelined: super::pipeline::PipelineGuard,
 }
 
 impl Tracker for DropTracker {
@@ -201,6 +231,7 @@ impl Tracker for DropTracker {
 async fn serve_handle(
     stream: impl tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Sync + Unpin + 'static,
     addr: std::net::SocketAddr,
+    extensions: http::Extensions,
     handle: &Arc<impl ConnectionHandle>,
     config: TcpServerConfigInner,
     ctx: &scuffle_context::Context,
@@ -208,12 +239,23 @@ async fn serve_handle(
     #[cfg(feature = "tracing")]
     tracing::debug!("serving connection: {:?}", addr);
 
+    #[cfg(feature = "http1")]
+    let early_hints = config.http1_early_hints.then(super::early_hints::ConnectionHints::default);
+    #[cfg(feature = "http1")]
+    let stream = super::early_hints::EarlyHintsIo::new(stream, early_hints.as_ref());
+    #[cfg(feature = "http1")]
+    let pipeline = super::pipeline::Pipeline::new();
+    #[cfg(feature = "http1")]
+    let stream = super::pipeline::PipelineIo::new(stream, pipeline.clone(), config.http1_max_pipelined);
+
     let io = hyper_util::rt::TokioIo::new(stream);
 
     let timeout_tracker = config.idle_timeout.map(TimeoutTracker::new).map(Arc::new);
 
     let service = hyper::service::service_fn(|req: hyper::Request<hyper::body::Incoming>| {
         let guard = timeout_tracker.as_ref().map(|t| t.new_guard());
+        #[cfg(feature = "http1")]
+        let pipelined = pipeline.guard();
         let handle = handle.clone();
         let has_body = has_body(req.method());
         let mut req = req.map(|body| {
@@ -225,13 +267,44 @@ async fn serve_handle(
         });
         let ctx = ctx.clone();
 
+        req.extensions_mut().extend(extensions.clone());
         req.extensions_mut().insert(addr.ip());
+        // Retired once the final response is returned, when the async block below completes.
+        #[cfg(feature = "http1")]
+        let request_hints = early_hints
+            .as_ref()
+            .filter(|_| req.version() == http::Version::HTTP_11)
+            .map(|hints| hints.request());
+        #[cfg(feature = "http1")]
+        if let Some(hints) = request_hints.as_ref() {
+            req.extensions_mut().insert(hints.hints().clone());
+        }
         let server_name = config.server_name.clone();
+        let limited = config
+            .rate_limiter
+            .as_ref()
+            .and_then(|limiter| limiter.check(addr.ip()).err());
         async move {
             let _ctx = ctx.clone();
-            match handle.on_request(req).await {
+            #[cfg(feature = "http1")]
+            let _request_hints = request_hints;
+            let res = match limited {
+                Some(retry_after) => Ok(RateLimiter::response(retry_after, OptionalBody::empty())),
+                None => handle.on_request(req).await.map(|res| res.map(OptionalBody::new)),
+            };
+
+            match res {
                 Ok(res) => {
-                    let mut res = res.map(|body| crate::body::TrackedBody::new(body, DropTracker { _guard: guard }));
+                    let mut res = res.map(|body| {
+                        crate::body::TrackedBody::new(
+                            body,
+                            DropTracker {
+                                _guard: guard,
+                                #[cfg(feature = "http1")]
+                                _pipelined: pipelined,
+                            },
+                        )
+                    });
                     if let Some(server_name) = server_name.as_ref() {
                         res.headers_mut()
                             .insert(hyper::header::SERVER, HeaderValue::from_str(server_name).unwrap());
@@ -244,20 +317,41 @@ async fn serve_handle(
         }
     });
 
+    // Resolves once the connection should be gracefully shut down, either
+    // because the server is shutting down or the connection has reached its
+    // maximum lifetime.
+    let shutdown = async {
+        let lifetime = async {
+            if let Some(lifetime) = config.max_connection_lifetime {
+                tokio::time::sleep(lifetime).await
+            } else {
+                std::future::pending().await
+            }
+        };
+
+        futures::future::select(std::pin::pin!(ctx.done()), std::pin::pin!(lifetime)).await;
+    };
+
     let conn = async {
         if config.allow_upgrades {
             let conn = config.http_builder.serve_connection_with_upgrades(io, service);
             let mut pinned = std::pin::pin!(conn);
-            if pinned.as_mut().with_context(ctx).await.transpose()?.is_none() {
-                pinned.as_mut().graceful_shutdown();
-                pinned.await?;
+            match futures::future::select(pinned.as_mut(), std::pin::pin!(shutdown)).await {
+                Either::Left((result, _)) => result?,
+                Either::Right(_) => {
+                    pinned.as_mut().graceful_shutdown();
+                    pinned.await?;
+                }
             }
         } else {
             let conn = config.http_builder.serve_connection(io, service);
             let mut pinned = std::pin::pin!(conn);
-            if pinned.as_mut().with_context(ctx).await.transpose()?.is_none() {
-                pinned.as_mut().graceful_shutdown();
-                pinned.await?;
+            match futures::future::select(pinned.as_mut(), std::pin::pin!(shutdown)).await {
+                Either::Left((result, _)) => result?,
+                Either::Right(_) => {
+                    pinned.as_mut().graceful_shutdown();
+                    pinned.await?;
+                }
             }
         }
 
@@ -0,0 +1,92 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use rustls::pki_types::CertificateDer;
+
+/// The certificate a client authenticated with.
+///
+/// When client authentication is enabled (see
+/// [`with_client_cert_verifier`](super::config::TcpServerConfigBuilder::with_client_cert_verifier))
+/// this is inserted as an extension into every request made on the
+/// connection. The certificate has already been verified by the configured
+/// verifier by the time a handler sees it.
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    chain: Arc<[CertificateDer<'static>]>,
+    subject: Arc<str>,
+    subject_alt_names: Arc<[SubjectAltName]>,
+}
+
+/// A subject alternative name of a [`ClientCertificate`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SubjectAltName {
+    /// A `dNSName` entry.
+    Dns(String),
+    /// An `rfc822Name` (email address) entry.
+    Email(String),
+    /// A `uniformResourceIdentifier` entry, for example a SPIFFE id.
+    Uri(String),
+    /// An `iPAddress` entry.
+    Ip(IpAddr),
+}
+
+impl ClientCertificate {
+    /// Parses the certificate chain presented by the client, returns `None`
+    /// if the chain is empty or the end-entity certificate cannot be parsed.
+    pub(crate) fn new(chain: &[CertificateDer<'_>]) -> Option<Self> {
+        use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+        let (_, cert) = X509Certificate::from_der(chain.first()?).ok()?;
+
+        let subject_alt_names = cert
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(name) => Some(SubjectAltName::Dns(name.to_string())),
+                        GeneralName::RFC822Name(name) => Some(SubjectAltName::Email(name.to_string())),
+                        GeneralName::URI(name) => Some(SubjectAltName::Uri(name.to_string())),
+                        GeneralName::IPAddress(ip) => match ip.len() {
+                            4 => Some(SubjectAltName::Ip(IpAddr::from(<[u8; 4]>::try_from(*ip).ok()?))),
+                            16 => Some(SubjectAltName::Ip(IpAddr::from(<[u8; 16]>::try_from(*ip).ok()?))),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            subject: cert.subject().to_string().into(),
+            subject_alt_names,
+            chain: chain.iter().map(|cert| cert.clone().into_owned()).collect(),
+        })
+    }
+
+    /// The end-entity certificate, in DER.
+    pub fn certificate(&self) -> &CertificateDer<'static> {
+        &self.chain[0]
+    }
+
+    /// The full chain presented by the client, starting with the end-entity
+    /// certificate.
+    pub fn chain(&self) -> &[CertificateDer<'static>] {
+        &self.chain
+    }
+
+    /// The subject distinguished name of the end-entity certificate, for
+    /// example `CN=client.test`.
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// The subject alternative names of the end-entity certificate.
+    pub fn subject_alt_names(&self) -> &[SubjectAltName] {
+        &self.subject_alt_names
+    }
+}
@@ -1,7 +1,8 @@
+use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, Bytes, BytesMut};
 use http_body::Frame;
 
 #[cfg(feature = "h3")]
@@ -37,6 +38,8 @@ pub(crate) enum IncomingBodyInner {
     Tcp(#[from] hyper::body::Incoming),
     #[cfg(feature = "h3")]
     Quic(#[from] QuicIncomingBody),
+    #[cfg(feature = "decompression")]
+    Decompressed(Box<crate::svc::DecompressedBody>),
     #[cfg_attr(not(any(feature = "http3", feature = "http1", feature = "http2")), allow(dead_code))]
     Empty,
 }
@@ -54,6 +57,8 @@ impl http_body::Body for IncomingBody {
             IncomingBodyInner::Tcp(body) => Pin::new(body).poll_frame(cx).map_err(Into::into),
             #[cfg(feature = "h3")]
             IncomingBodyInner::Quic(body) => Pin::new(body).poll_frame(cx).map_err(Into::into),
+            #[cfg(feature = "decompression")]
+            IncomingBodyInner::Decompressed(body) => body.poll_frame(cx),
             IncomingBodyInner::Empty => Poll::Ready(None),
         }
     }
@@ -64,6 +69,8 @@ impl http_body::Body for IncomingBody {
             IncomingBodyInner::Tcp(body) => body.is_end_stream(),
             #[cfg(feature = "h3")]
             IncomingBodyInner::Quic(body) => body.is_end_stream(),
+            #[cfg(feature = "decompression")]
+            IncomingBodyInner::Decompressed(body) => body.is_end_stream(),
             IncomingBodyInner::Empty => true,
         }
     }
@@ -74,6 +81,8 @@ impl http_body::Body for IncomingBody {
             IncomingBodyInner::Tcp(body) => body.size_hint(),
             #[cfg(feature = "h3")]
             IncomingBodyInner::Quic(body) => body.size_hint(),
+            #[cfg(feature = "decompression")]
+            IncomingBodyInner::Decompressed(_) => http_body::SizeHint::default(),
             IncomingBodyInner::Empty => http_body::SizeHint::with_exact(0),
         }
     }
@@ -140,6 +149,167 @@ where
     }
 }
 
+pin_project_lite::pin_project! {
+    /// A body which is either the service's body or empty, used for responses
+    /// generated by the server itself.
+    pub(crate) struct OptionalBody<B> {
+        #[pin]
+        body: Option<B>,
+    }
+}
+
+#[cfg_attr(not(any(feature = "http1", feature = "http2")), allow(dead_code))]
+impl<B> OptionalBody<B> {
+    pub(crate) fn new(body: B) -> Self {
+        Self { body: Some(body) }
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self { body: None }
+    }
+}
+
+impl<B: http_body::Body> http_body::Body for OptionalBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match self.project().body.as_pin_mut() {
+            Some(body) => body.poll_frame(cx),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.as_ref().is_none_or(|body| body.is_end_stream())
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        match &self.body {
+            Some(body) => body.size_hint(),
+            None => http_body::SizeHint::with_exact(0),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// A body which sends trailers (trailing headers) after the data of the
+    /// inner body.
+    ///
+    /// Trailers are used, for example by gRPC, to report the final status of a
+    /// response which has already been streamed. They are sent as a `HEADERS`
+    /// frame with HTTP/2 and HTTP/3. With HTTP/1.1 they are only sent for
+    /// chunked responses to clients that sent `TE: trailers`.
+    ///
+    /// If the inner body sends trailers itself, they are merged, with the
+    /// values given here replacing those of the inner body.
+    pub struct TrailersBody<B> {
+        #[pin]
+        body: B,
+        trailers: Trailers,
+        inner_trailers: Option<http::HeaderMap>,
+        body_done: bool,
+    }
+}
+
+enum Trailers {
+    Ready(http::HeaderMap),
+    Pending(tokio::sync::oneshot::Receiver<http::HeaderMap>),
+    Done,
+}
+
+impl<B> TrailersBody<B> {
+    /// Create a body which sends `trailers` once `body` is done.
+    pub fn new(body: B, trailers: http::HeaderMap) -> Self {
+        Self::with_trailers(body, Trailers::Ready(trailers))
+    }
+
+    /// Create a body whose trailers are sent through the returned
+    /// [`TrailersSender`], for trailers which are only known once the body has
+    /// been produced.
+    ///
+    /// Once `body` is done the response waits for the trailers, if the sender
+    /// is dropped without sending any the response ends without trailers.
+    pub fn channel(body: B) -> (Self, TrailersSender) {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        (Self::with_trailers(body, Trailers::Pending(rx)), TrailersSender(tx))
+    }
+
+    fn with_trailers(body: B, trailers: Trailers) -> Self {
+        Self {
+            body,
+            trailers,
+            inner_trailers: None,
+            body_done: false,
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for TrailersBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrailersBody").finish()
+    }
+}
+
+/// The sending half of [`TrailersBody::channel`].
+#[derive(Debug)]
+pub struct TrailersSender(tokio::sync::oneshot::Sender<http::HeaderMap>);
+
+impl TrailersSender {
+    /// Send the trailers, they are written once the body is done.
+    ///
+    /// Returns the trailers back if the body has already been dropped.
+    pub fn send(self, trailers: http::HeaderMap) -> Result<(), http::HeaderMap> {
+        self.0.send(trailers)
+    }
+}
+
+impl<B: http_body::Body> http_body::Body for TrailersBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        while !*this.body_done {
+            match std::task::ready!(this.body.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_trailers() {
+                    Ok(trailers) => this.inner_trailers.get_or_insert_with(Default::default).extend(trailers),
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => *this.body_done = true,
+            }
+        }
+
+        let trailers = match this.trailers {
+            Trailers::Ready(trailers) => Some(std::mem::take(trailers)),
+            Trailers::Pending(rx) => std::task::ready!(Pin::new(rx).poll(cx)).ok(),
+            Trailers::Done => None,
+        };
+        *this.trailers = Trailers::Done;
+
+        let trailers = match (this.inner_trailers.take(), trailers) {
+            (Some(mut inner), Some(trailers)) => {
+                inner.extend(trailers);
+                inner
+            }
+            (Some(trailers), None) | (None, Some(trailers)) => trailers,
+            (None, None) => return Poll::Ready(None),
+        };
+
+        Poll::Ready(Some(Ok(Frame::trailers(trailers))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        matches!(self.trailers, Trailers::Done) && self.inner_trailers.is_none() && self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.body.size_hint()
+    }
+}
+
 #[cfg_attr(not(any(feature = "http3", feature = "http1", feature = "http2")), allow(dead_code))]
 pub(crate) fn has_body(method: &http::Method) -> bool {
     !matches!(
@@ -147,3 +317,156 @@ pub(crate) fn has_body(method: &http::Method) -> bool {
         &http::Method::GET | &http::Method::HEAD | &http::Method::OPTIONS | &http::Method::CONNECT | &http::Method::TRACE
     )
 }
+
+/// An error returned by [`collect_with_limit`].
+#[derive(Debug, thiserror::Error)]
+pub enum BodyError {
+    /// The body was larger than the allowed limit.
+    #[error("body exceeded the limit of {0} bytes")]
+    TooLarge(usize),
+    /// Reading the body failed.
+    #[error(transparent)]
+    Body(crate::Error),
+}
+
+impl BodyError {
+    /// The status code which should be returned to the client for this error.
+    ///
+    /// [`BodyError::TooLarge`] maps to `413 Payload Too Large` and
+    /// everything else to `400 Bad Request`, except for decompression errors
+    /// which use [`DecompressionError::status_code`](crate::svc::DecompressionError::status_code).
+    pub fn status_code(&self) -> http::StatusCode {
+        match self {
+            Self::TooLarge(_) => http::StatusCode::PAYLOAD_TOO_LARGE,
+            #[cfg(feature = "decompression")]
+            Self::Body(err) => match err.kind() {
+                Some(crate::error::ErrorKind::Decompression(err)) => err.status_code(),
+                _ => http::StatusCode::BAD_REQUEST,
+            },
+            #[cfg(not(feature = "decompression"))]
+            Self::Body(_) => http::StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Collects a body into a single [`Bytes`] buffer, failing with
+/// [`BodyError::TooLarge`] as soon as more than `max` bytes have been received.
+///
+/// Trailers are discarded.
+pub async fn collect_with_limit<B>(body: B, max: usize) -> Result<Bytes, BodyError>
+where
+    B: http_body::Body,
+    B::Error: Into<crate::Error>,
+{
+    if body.size_hint().lower() > max as u64 {
+        return Err(BodyError::TooLarge(max));
+    }
+
+    let mut body = std::pin::pin!(body);
+    let mut buf = BytesMut::new();
+
+    while let Some(frame) = std::future::poll_fn(|cx| body.as_mut().poll_frame(cx)).await {
+        let frame = frame.map_err(|err| BodyError::Body(err.into()))?;
+        let Ok(mut data) = frame.into_data() else {
+            continue;
+        };
+
+        if buf.len() + data.remaining() > max {
+            return Err(BodyError::TooLarge(max));
+        }
+
+        while data.has_remaining() {
+            let chunk = data.chunk();
+            let len = chunk.len();
+            buf.extend_from_slice(chunk);
+            data.advance(len);
+        }
+    }
+
+    Ok(buf.freeze())
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use http_body::Frame;
+
+    use super::{collect_with_limit, BodyError, IncomingBody, TrailersBody};
+
+    fn chunked(chunks: &'static [&'static [u8]]) -> impl http_body::Body<Data = Bytes, Error = Infallible> {
+        http_body_util::StreamBody::new(futures::stream::iter(
+            chunks.iter().map(|chunk| Ok(Frame::data(Bytes::from_static(chunk)))),
+        ))
+    }
+
+    #[tokio::test]
+    async fn collect_small_body() {
+        let body = collect_with_limit(chunked(&[b"hello", b" ", b"world"]), 11).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"hello world"));
+
+        let body = collect_with_limit(IncomingBody::empty(), 0).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn collect_oversized_body() {
+        // The size is only known once the chunks are read.
+        let err = collect_with_limit(chunked(&[b"hello", b" ", b"world"]), 10)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BodyError::TooLarge(10)), "{err:?}");
+        assert_eq!(err.status_code(), http::StatusCode::PAYLOAD_TOO_LARGE);
+
+        // The size hint is enough to reject the body without reading it.
+        let full = http_body_util::Full::new(Bytes::from_static(b"hello world"));
+        let err = collect_with_limit(full, 5).await.unwrap_err();
+        assert!(matches!(err, BodyError::TooLarge(5)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn trailers_body() {
+        use http_body_util::BodyExt;
+
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+
+        let collected = TrailersBody::new(chunked(&[b"hello", b" world"]), trailers.clone())
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(collected.trailers(), Some(&trailers));
+        assert_eq!(collected.to_bytes(), Bytes::from_static(b"hello world"));
+
+        // Trailers of the inner body are merged.
+        let mut inner = http::HeaderMap::new();
+        inner.insert("grpc-status", http::HeaderValue::from_static("2"));
+        inner.insert("x-inner", http::HeaderValue::from_static("1"));
+        let body = http_body_util::StreamBody::new(futures::stream::iter([
+            Ok::<_, Infallible>(Frame::data(Bytes::from_static(b"hello"))),
+            Ok(Frame::trailers(inner)),
+        ]));
+        let collected = TrailersBody::new(body, trailers).collect().await.unwrap();
+        let trailers = collected.trailers().unwrap();
+        assert_eq!(trailers["grpc-status"], "0");
+        assert_eq!(trailers["x-inner"], "1");
+
+        // Trailers sent after the body has been streamed.
+        let (body, sender) = TrailersBody::channel(chunked(&[b"hello"]));
+        let task = tokio::spawn(body.collect());
+        tokio::task::yield_now().await;
+        let mut trailers = http::HeaderMap::new();
+        trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+        sender.send(trailers.clone()).unwrap();
+        let collected = task.await.unwrap().unwrap();
+        assert_eq!(collected.trailers(), Some(&trailers));
+
+        // No trailers if the sender is dropped.
+        let (body, sender) = TrailersBody::channel(chunked(&[b"hello"]));
+        drop(sender);
+        let collected = body.collect().await.unwrap();
+        assert!(collected.trailers().is_none());
+    }
+}
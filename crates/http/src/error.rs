@@ -324,6 +324,9 @@ pub enum ErrorKind {
     Configuration,
     #[error("bad request")]
     BadRequest,
+    #[cfg(feature = "decompression")]
+    #[error("decompression: {0}")]
+    Decompression(#[from] crate::svc::DecompressionError),
 }
 
 trait ErrorKindExt {
@@ -437,6 +440,8 @@ impl ErrorKind {
             Self::Axum(err) => err.severity(),
             #[cfg(feature = "quinn")]
             Self::QuinnConnection(err) => err.severity(),
+            #[cfg(feature = "decompression")]
+            Self::Decompression(_) => ErrorSeverity::Debug,
             Self::Io(io) => io.severity(),
             Self::Http(err) => err.severity(),
         }
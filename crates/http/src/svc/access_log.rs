@@ -0,0 +1,287 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::{ConnectionHandle, IncomingConnection};
+use crate::body::{IncomingBody, TrackedBody, Tracker};
+
+/// The fields of a single request/response pair which are made available to
+/// an access log formatter.
+#[derive(Debug, Clone)]
+pub struct AccessLogEntry {
+    /// The ip address of the peer that made the request.
+    pub client_ip: Option<IpAddr>,
+    /// The request method.
+    pub method: http::Method,
+    /// The request path including the query string.
+    pub path: String,
+    /// The http version of the request.
+    pub version: http::Version,
+    /// The response status code.
+    pub status: http::StatusCode,
+    /// The number of response body bytes sent to the client.
+    pub bytes: u64,
+    /// The time from receiving the request until the response body was
+    /// finished.
+    pub duration: std::time::Duration,
+    /// The `Referer` header of the request, if any.
+    pub referer: Option<String>,
+    /// The `User-Agent` header of the request, if any.
+    pub user_agent: Option<String>,
+    /// The time at which the request was received.
+    pub time: std::time::SystemTime,
+    /// The id of the request, if the service is wrapped in a
+    /// [`RequestIdService`](super::RequestIdService).
+    pub request_id: Option<super::RequestId>,
+}
+
+/// A function which turns an [`AccessLogEntry`] into a log line.
+pub type AccessLogFormatter = dyn Fn(&AccessLogEntry) -> String + Send + Sync;
+
+/// The default access log formatter, producing a line similar to the
+/// combined log format used by NGINX and Apache.
+///
+/// `127.0.0.1 - - [Sun, 06 Nov 1994 08:49:37 GMT] "GET /index.html HTTP/1.1"
+/// 200 1234 "-" "curl/8.0" 12ms`
+pub fn default_access_log_formatter(entry: &AccessLogEntry) -> String {
+    format!(
+        "{} - - [{}] \"{} {} {:?}\" {} {} \"{}\" \"{}\" {}ms",
+        entry.client_ip.map(|ip| ip.to_string()).as_deref().unwrap_or("-"),
+        httpdate::fmt_http_date(entry.time),
+        entry.method,
+        entry.path,
+        entry.version,
+        entry.status.as_u16(),
+        entry.bytes,
+        entry.referer.as_deref().unwrap_or("-"),
+        entry.user_agent.as_deref().unwrap_or("-"),
+        entry.duration.as_millis(),
+    )
+}
+
+/// A wrapper around a [`ConnectionHandle`] which emits an access log line
+/// for every request once the response body has been fully sent (or
+/// dropped).
+///
+/// Log lines are emitted at the `INFO` level with the target
+/// `scuffle_http::access_log`.
+#[derive(derive_more::Debug)]
+pub struct AccessLogService<H> {
+    inner: H,
+    #[debug(skip)]
+    formatter: Arc<AccessLogFormatter>,
+}
+
+impl<H: Clone> Clone for AccessLogService<H> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            formatter: self.formatter.clone(),
+        }
+    }
+}
+
+impl<H> AccessLogService<H> {
+    /// Replaces the formatter used to produce log lines.
+    pub fn with_formatter(mut self, formatter: impl Fn(&AccessLogEntry) -> String + Send + Sync + 'static) -> Self {
+        self.formatter = Arc::new(formatter);
+        self
+    }
+}
+
+/// The [`Tracker`] of a response body passed through an [`AccessLogService`].
+///
+/// It counts the bytes of the body and logs the [`AccessLogEntry`] once the
+/// body is dropped, after it has been sent or the connection was closed. It
+/// is only public because it is part of [`AccessLogService`]'s body type and
+/// cannot be created outside of this crate.
+pub struct AccessLogTracker {
+    entry: AccessLogEntry,
+    start: std::time::Instant,
+    bytes: AtomicU64,
+    formatter: Arc<AccessLogFormatter>,
+}
+
+impl Tracker for AccessLogTracker {
+    type Error = crate::Error;
+
+    fn on_data(&self, size: usize) -> Result<(), Self::Error> {
+        self.bytes.fetch_add(size as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Drop for AccessLogTracker {
+    fn drop(&mut self) {
+        self.entry.bytes = *self.bytes.get_mut();
+        self.entry.duration = self.start.elapsed();
+
+        tracing::info!(target: "scuffle_http::access_log", "{}", (self.formatter)(&self.entry));
+    }
+}
+
+#[async_trait::async_trait]
+impl<H> ConnectionHandle for AccessLogService<H>
+where
+    H: ConnectionHandle,
+    H::BodyError: 'static,
+{
+    type Body = TrackedBody<H::Body, AccessLogTracker>;
+    type BodyData = H::BodyData;
+    type BodyError = crate::Error;
+    type Error = H::Error;
+
+    async fn accept(&self, conn: IncomingConnection) -> Result<(), Self::Error> {
+        self.inner.accept(conn).await
+    }
+
+    async fn on_request(&self, req: http::Request<IncomingBody>) -> Result<http::Response<Self::Body>, Self::Error> {
+        let start = std::time::Instant::now();
+        let time = std::time::SystemTime::now();
+
+        let header = |name: http::HeaderName| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        let entry = AccessLogEntry {
+            client_ip: req.extensions().get::<IpAddr>().copied(),
+            method: req.method().clone(),
+            path: req
+                .uri()
+                .path_and_query()
+                .map(|p| p.as_str())
+                .unwrap_or_else(|| req.uri().path())
+                .to_owned(),
+            version: req.version(),
+            status: http::StatusCode::OK,
+            bytes: 0,
+            duration: std::time::Duration::ZERO,
+            referer: header(http::header::REFERER),
+            user_agent: header(http::header::USER_AGENT),
+            time,
+            request_id: req.extensions().get::<super::RequestId>().cloned(),
+        };
+
+        let res = self.inner.on_request(req).await?;
+
+        let mut entry = entry;
+        entry.status = res.status();
+
+        let tracker = AccessLogTracker {
+            entry,
+            start,
+            bytes: AtomicU64::new(0),
+            formatter: self.formatter.clone(),
+        };
+
+        Ok(res.map(|body| TrackedBody::new(body, tracker)))
+    }
+
+    fn on_ready(&self) {
+        self.inner.on_ready();
+    }
+
+    fn on_close(&self) {
+        self.inner.on_close();
+    }
+
+    fn on_error(&self, err: crate::Error) {
+        self.inner.on_error(err);
+    }
+}
+
+/// Wraps a [`ConnectionHandle`] so that every request is written to the
+/// access log using the [`default_access_log_formatter`].
+pub fn access_log_service<H>(handle: H) -> AccessLogService<H> {
+    AccessLogService {
+        inner: handle,
+        formatter: Arc::new(default_access_log_formatter),
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use http_body_util::BodyExt;
+
+    use super::access_log_service;
+    use crate::body::IncomingBody;
+    use crate::svc::{function_service, ConnectionHandle};
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn access_log_line() {
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let service = access_log_service(function_service(|_| async {
+            Ok::<_, Infallible>(
+                http::Response::builder()
+                    .status(http::StatusCode::CREATED)
+                    .body(http_body_util::Full::new(Bytes::from_static(b"hello")))
+                    .unwrap(),
+            )
+        }));
+
+        let mut req = http::Request::builder()
+            .method(http::Method::POST)
+            .uri("/some/path?query=1")
+            .body(IncomingBody::empty())
+            .unwrap();
+        req.extensions_mut().insert(std::net::IpAddr::from([127, 0, 0, 1]));
+
+        let res = service.on_request(req).await.unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, Bytes::from_static(b"hello"));
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("127.0.0.1"), "{output}");
+        assert!(output.contains("\"POST /some/path?query=1 HTTP/1.1\""), "{output}");
+        assert!(output.contains(" 201 5 "), "{output}");
+    }
+
+    #[tokio::test]
+    async fn custom_formatter() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let captured = lines.clone();
+
+        let service = access_log_service(function_service(|_| async {
+            Ok::<_, Infallible>(http::Response::new(http_body_util::Empty::<Bytes>::new()))
+        }))
+        .with_formatter(move |entry| {
+            let line = format!("{} {} {}", entry.method, entry.path, entry.status.as_u16());
+            captured.lock().unwrap().push(line.clone());
+            line
+        });
+
+        let req = http::Request::builder().uri("/").body(IncomingBody::empty()).unwrap();
+        drop(service.on_request(req).await.unwrap());
+
+        assert_eq!(*lines.lock().unwrap(), vec!["GET / 200".to_owned()]);
+    }
+}
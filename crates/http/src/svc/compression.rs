@@ -0,0 +1,688 @@
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes};
+use http_body::{Body, Frame};
+
+use super::{ConnectionHandle, IncomingConnection};
+use crate::body::IncomingBody;
+
+/// The default minimum size of a response body to be compressed, 1 KiB.
+pub const DEFAULT_MIN_COMPRESSED_SIZE: u64 = 1024;
+
+/// A wrapper around a [`ConnectionHandle`] which transparently compresses
+/// response bodies based on the request's `Accept-Encoding` header.
+///
+/// `br` (with the `compression-br` feature) is preferred over `gzip`, and
+/// `gzip` over `deflate`, when the client accepts them with the same quality.
+/// When a body is compressed the `Content-Encoding` header is set, the
+/// `Content-Length` header is removed and a strong `ETag` is made weak.
+///
+/// Responses are left untouched if they
+/// - are smaller than the configured minimum (see
+///   [`CompressionService::with_min_size`]), bodies without a known size are
+///   always compressed,
+/// - already have a `Content-Encoding`,
+/// - have a content type which is usually compressed already, like images,
+///   audio, video and archives,
+/// - have a `Cache-Control: no-transform` header,
+/// - are partial content (`206 Partial Content` or a `Content-Range` header),
+///   whose ranges refer to the uncompressed representation,
+/// - are answers to `HEAD` requests or have a status without a body.
+///
+/// All other responses get a `Vary: Accept-Encoding` header, even if the
+/// client did not accept any encoding, so caches keep the variants apart.
+///
+/// Every chunk of the body is flushed through the encoder as it is produced,
+/// so streamed responses (like server-sent events) are not held back.
+#[derive(Debug, Clone)]
+pub struct CompressionService<H> {
+    inner: H,
+    min_size: u64,
+}
+
+impl<H> CompressionService<H> {
+    /// Sets the minimum size of a response body to be compressed. Defaults to
+    /// [`DEFAULT_MIN_COMPRESSED_SIZE`].
+    pub fn with_min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<H> ConnectionHandle for CompressionService<H>
+where
+    H: ConnectionHandle,
+{
+    type Body = CompressionBody<H::Body>;
+    type BodyData = Bytes;
+    type BodyError = crate::Error;
+    type Error = H::Error;
+
+    async fn accept(&self, conn: IncomingConnection) -> Result<(), Self::Error> {
+        self.inner.accept(conn).await
+    }
+
+    async fn on_request(&self, req: http::Request<IncomingBody>) -> Result<http::Response<Self::Body>, Self::Error> {
+        let encoding = Encoding::negotiate(req.headers());
+        let is_head = req.method() == http::Method::HEAD;
+
+        let res = self.inner.on_request(req).await?;
+        if is_head || !self.should_compress(&res) {
+            return Ok(res.map(CompressionBody::identity));
+        }
+
+        let (mut parts, body) = res.into_parts();
+        vary_accept_encoding(&mut parts.headers);
+
+        let Some(encoding) = encoding else {
+            return Ok(http::Response::from_parts(parts, CompressionBody::identity(body)));
+        };
+
+        parts.headers.insert(
+            http::header::CONTENT_ENCODING,
+            http::HeaderValue::from_static(encoding.as_str()),
+        );
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+
+        // The compressed body is no longer byte for byte the same.
+        if let Some(etag) = parts.headers.get(http::header::ETAG) {
+            if !etag.as_bytes().starts_with(b"W/") {
+                let mut weak = b"W/".to_vec();
+                weak.extend_from_slice(etag.as_bytes());
+                if let Ok(weak) = http::HeaderValue::from_bytes(&weak) {
+                    parts.headers.insert(http::header::ETAG, weak);
+                }
+            }
+        }
+
+        Ok(http::Response::from_parts(parts, CompressionBody::new(body, encoding)))
+    }
+
+    fn on_ready(&self) {
+        self.inner.on_ready();
+    }
+
+    fn on_close(&self) {
+        self.inner.on_close();
+    }
+
+    fn on_error(&self, err: crate::Error) {
+        self.inner.on_error(err);
+    }
+}
+
+impl<H> CompressionService<H> {
+    fn should_compress<B: Body>(&self, res: &http::Response<B>) -> bool {
+        let status = res.status();
+        if status.is_informational() || status == http::StatusCode::NO_CONTENT || status == http::StatusCode::NOT_MODIFIED {
+            return false;
+        }
+
+        if status == http::StatusCode::PARTIAL_CONTENT {
+            return false;
+        }
+
+        let headers = res.headers();
+        if headers.contains_key(http::header::CONTENT_ENCODING) || headers.contains_key(http::header::CONTENT_RANGE) {
+            return false;
+        }
+
+        let no_transform = headers
+            .get_all(http::header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"));
+        if no_transform {
+            return false;
+        }
+
+        if let Some(content_type) = headers.get(http::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+            if is_compressed_content_type(content_type) {
+                return false;
+            }
+        }
+
+        res.body().size_hint().exact().is_none_or(|size| size >= self.min_size)
+    }
+}
+
+/// Wraps a [`ConnectionHandle`] so that response bodies of at least
+/// [`DEFAULT_MIN_COMPRESSED_SIZE`] bytes are compressed for clients which
+/// accept it.
+pub fn compression_service<H>(handle: H) -> CompressionService<H> {
+    CompressionService {
+        inner: handle,
+        min_size: DEFAULT_MIN_COMPRESSED_SIZE,
+    }
+}
+
+/// Returns true for content types which are usually compressed already, so
+/// compressing them again only costs time.
+fn is_compressed_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+
+    match mime.split_once('/') {
+        Some(("image", subtype)) => subtype != "svg+xml" && subtype != "bmp",
+        Some(("audio" | "video", _)) => true,
+        Some(("font", subtype)) => subtype == "woff" || subtype == "woff2",
+        Some(("application", subtype)) => matches!(
+            subtype,
+            "zip" | "gzip" | "x-gzip" | "zstd" | "x-bzip2" | "x-xz" | "x-7z-compressed" | "x-rar-compressed" | "pdf"
+        ),
+        _ => false,
+    }
+}
+
+/// Adds `Accept-Encoding` to the `Vary` header, unless it is already listed.
+fn vary_accept_encoding(headers: &mut http::HeaderMap) {
+    let listed = headers
+        .get_all(http::header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|name| {
+            let name = name.trim();
+            name == "*" || name.eq_ignore_ascii_case("accept-encoding")
+        });
+
+    if !listed {
+        headers.append(http::header::VARY, http::HeaderValue::from_static("accept-encoding"));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    #[cfg(feature = "compression-br")]
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    /// All supported encodings, in order of preference.
+    const ALL: &[Self] = &[
+        #[cfg(feature = "compression-br")]
+        Self::Brotli,
+        Self::Gzip,
+        Self::Deflate,
+    ];
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            #[cfg(feature = "compression-br")]
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+
+    fn matches(self, coding: &str) -> bool {
+        coding.eq_ignore_ascii_case(self.as_str()) || (self == Self::Gzip && coding.eq_ignore_ascii_case("x-gzip"))
+    }
+
+    /// Picks the encoding with the highest quality in the `Accept-Encoding`
+    /// headers, `None` if the client accepts none of them.
+    fn negotiate(headers: &http::HeaderMap) -> Option<Self> {
+        let entries = headers
+            .get_all(http::header::ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|entry| {
+                let mut params = entry.split(';');
+                let coding = params.next().unwrap_or_default().trim();
+                let quality = params
+                    .filter_map(|param| param.trim().strip_prefix("q=").or_else(|| param.trim().strip_prefix("Q=")))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+
+                (coding, quality)
+            })
+            .collect::<Vec<_>>();
+
+        // Later entries win over earlier ones for the same coding.
+        let quality = |matches: &dyn Fn(&str) -> bool| {
+            entries
+                .iter()
+                .rev()
+                .find(|(coding, _)| matches(coding))
+                .map(|(_, quality)| *quality)
+        };
+        let any = quality(&|coding| coding == "*");
+
+        let mut best: Option<(Self, f32)> = None;
+        for &encoding in Self::ALL {
+            let quality = quality(&|coding| encoding.matches(coding)).or(any).unwrap_or(0.0);
+            if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+                best = Some((encoding, quality));
+            }
+        }
+
+        best.map(|(encoding, _)| encoding)
+    }
+}
+
+/// The brotli quality used, the default of 11 is meant for compressing ahead
+/// of time and far too slow for responses.
+#[cfg(feature = "compression-br")]
+const BROTLI_QUALITY: u32 = 4;
+
+/// The brotli window size (as a power of two), the same as the reference
+/// encoder uses.
+#[cfg(feature = "compression-br")]
+const BROTLI_WINDOW: u32 = 22;
+
+enum Encoder {
+    #[cfg(feature = "compression-br")]
+    Brotli(Box<brotli::CompressorWriter<Vec<u8>>>),
+    Gzip(flate2::write::GzEncoder<Vec<u8>>),
+    Deflate(flate2::write::ZlibEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(encoding: Encoding) -> Self {
+        match encoding {
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                Vec::new(),
+                4096,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            ))),
+            Encoding::Gzip => Self::Gzip(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default())),
+            Encoding::Deflate => Self::Deflate(flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default())),
+        }
+    }
+
+    /// Compresses the data, returning the output which is ready so far. The
+    /// encoder may hold on to some of the data until it is flushed.
+    fn write(&mut self, data: &[u8]) -> std::io::Result<Bytes> {
+        match self {
+            #[cfg(feature = "compression-br")]
+            Self::Brotli(encoder) => encoder.write_all(data)?,
+            Self::Gzip(encoder) => encoder.write_all(data)?,
+            Self::Deflate(encoder) => encoder.write_all(data)?,
+        }
+
+        Ok(self.take())
+    }
+
+    /// Flushes the data written so far, returning the compressed output.
+    fn flush(&mut self) -> std::io::Result<Bytes> {
+        match self {
+            #[cfg(feature = "compression-br")]
+            Self::Brotli(encoder) => encoder.flush()?,
+            Self::Gzip(encoder) => encoder.flush()?,
+            Self::Deflate(encoder) => encoder.flush()?,
+        }
+
+        Ok(self.take())
+    }
+
+    /// Finishes the stream, returning the remaining output.
+    fn finish(self) -> std::io::Result<Bytes> {
+        let buf = match self {
+            #[cfg(feature = "compression-br")]
+            Self::Brotli(encoder) => encoder.into_inner(),
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Deflate(encoder) => encoder.finish()?,
+        };
+
+        Ok(Bytes::from(buf))
+    }
+
+    fn take(&mut self) -> Bytes {
+        let buf = match self {
+            #[cfg(feature = "compression-br")]
+            Self::Brotli(encoder) => encoder.get_mut(),
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Deflate(encoder) => encoder.get_mut(),
+        };
+
+        Bytes::from(std::mem::take(buf))
+    }
+}
+
+enum State {
+    /// The body is passed through as is.
+    Identity,
+    Compressing(Box<Encoder>),
+    /// The compressed stream has been finished, only trailers may be left.
+    Done,
+}
+
+impl State {
+    /// Finishes the compressed stream, returning the remaining output.
+    fn finish(&mut self) -> std::io::Result<Bytes> {
+        match std::mem::replace(self, Self::Done) {
+            Self::Compressing(encoder) => encoder.finish(),
+            _ => Ok(Bytes::new()),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The body of a response passed through a [`CompressionService`], which
+    /// is either compressed or passed through as is.
+    pub struct CompressionBody<B> {
+        #[pin]
+        body: B,
+        state: State,
+        // Whether data was written to the encoder since it was last flushed.
+        unflushed: bool,
+        trailers: Option<http::HeaderMap>,
+    }
+}
+
+impl<B> CompressionBody<B> {
+    fn identity(body: B) -> Self {
+        Self {
+            body,
+            state: State::Identity,
+            unflushed: false,
+            trailers: None,
+        }
+    }
+
+    fn new(body: B, encoding: Encoding) -> Self {
+        Self {
+            body,
+            state: State::Compressing(Box::new(Encoder::new(encoding))),
+            unflushed: false,
+            trailers: None,
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for CompressionBody<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompressionBody").finish_non_exhaustive()
+    }
+}
+
+impl<B> Body for CompressionBody<B>
+where
+    B: Body,
+    B::Error: Into<crate::Error>,
+{
+    type Data = Bytes;
+    type Error = crate::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            let encoder = match this.state {
+                State::Identity => {
+                    return this.body.poll_frame(cx).map(|frame| {
+                        frame.map(|frame| {
+                            frame
+                                .map(|frame| frame.map_data(|mut data| data.copy_to_bytes(data.remaining())))
+                                .map_err(Into::into)
+                        })
+                    });
+                }
+                State::Compressing(encoder) => encoder,
+                State::Done => return Poll::Ready(this.trailers.take().map(|trailers| Ok(Frame::trailers(trailers)))),
+            };
+
+            let frame = match this.body.as_mut().poll_frame(cx) {
+                Poll::Ready(Some(Ok(frame))) => frame,
+                Poll::Ready(Some(Err(err))) => {
+                    *this.state = State::Done;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                // Compressed data is only flushed when the body stalls, so the client
+                // is not kept waiting for data which was already produced.
+                Poll::Pending if *this.unflushed => {
+                    *this.unflushed = false;
+                    let data = encoder.flush()?;
+                    return Poll::Ready(Some(Ok(Frame::data(data))));
+                }
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => {
+                    let data = this.state.finish();
+                    return Poll::Ready(Some(data.map(Frame::data).map_err(Into::into)));
+                }
+            };
+
+            match frame.into_data() {
+                Ok(mut data) => {
+                    let data = encoder.write(&data.copy_to_bytes(data.remaining()))?;
+                    *this.unflushed = true;
+                    if !data.is_empty() {
+                        return Poll::Ready(Some(Ok(Frame::data(data))));
+                    }
+                }
+                // Trailers end the body, so finish the stream before passing them on.
+                Err(frame) => {
+                    *this.trailers = frame.into_trailers().ok();
+                    let data = this.state.finish();
+                    return Poll::Ready(Some(data.map(Frame::data).map_err(Into::into)));
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        match self.state {
+            State::Identity => self.body.is_end_stream(),
+            State::Compressing(_) => false,
+            State::Done => self.trailers.is_none(),
+        }
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        match self.state {
+            State::Identity => self.body.size_hint(),
+            _ => http_body::SizeHint::default(),
+        }
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::io::{Read, Write};
+
+    use bytes::Bytes;
+    use http_body::Frame;
+    use http_body_util::{BodyExt, Full, StreamBody};
+
+    use super::{compression_service, CompressionBody, CompressionService, Encoding};
+    use crate::body::IncomingBody;
+    use crate::svc::{function_service, ConnectionHandle, FunctionService};
+
+    type Handler = FunctionService<
+        fn(http::Request<IncomingBody>) -> std::future::Ready<Result<http::Response<Full<Bytes>>, Infallible>>,
+    >;
+
+    fn service() -> CompressionService<Handler> {
+        compression_service(function_service(|req: http::Request<IncomingBody>| {
+            let (content_type, body) = match req.uri().path() {
+                "/large" | "/partial" | "/range" => ("text/plain", "hello world ".repeat(1000)),
+                "/image" => ("image/png", "hello world ".repeat(1000)),
+                _ => ("text/plain", "hello world".to_owned()),
+            };
+
+            let mut res = http::Response::builder()
+                .header(http::header::CONTENT_TYPE, content_type)
+                .header(http::header::ETAG, "\"abc\"");
+            match req.uri().path() {
+                "/partial" => {
+                    res = res
+                        .status(http::StatusCode::PARTIAL_CONTENT)
+                        .header(http::header::CONTENT_RANGE, "bytes 0-11999/24000");
+                }
+                "/range" => res = res.header(http::header::CONTENT_RANGE, "bytes 0-11999/12000"),
+                _ => {}
+            }
+
+            std::future::ready(Ok(res.body(Full::new(Bytes::from(body))).unwrap()))
+        }))
+    }
+
+    async fn get(path: &str, accept_encoding: &str) -> (http::response::Parts, Bytes) {
+        let req = http::Request::builder()
+            .uri(path)
+            .header(http::header::ACCEPT_ENCODING, accept_encoding)
+            .body(IncomingBody::empty())
+            .unwrap();
+        let (parts, body) = service().on_request(req).await.unwrap().into_parts();
+        (parts, body.collect().await.unwrap().to_bytes())
+    }
+
+    #[tokio::test]
+    async fn compress_large() {
+        let (parts, body) = get("/large", "zstd, gzip, deflate;q=0.8").await;
+
+        assert_eq!(parts.headers[http::header::CONTENT_ENCODING], "gzip");
+        assert_eq!(parts.headers[http::header::VARY], "accept-encoding");
+        assert_eq!(parts.headers[http::header::ETAG], "W/\"abc\"");
+        assert!(body.len() < 1000, "body was not compressed: {} bytes", body.len());
+
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&body[..]).read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, "hello world ".repeat(1000));
+    }
+
+    #[tokio::test]
+    async fn skip_small_and_compressed() {
+        let (parts, body) = get("/small", "gzip").await;
+        assert!(parts.headers.get(http::header::CONTENT_ENCODING).is_none());
+        assert_eq!(body, "hello world");
+
+        let (parts, body) = get("/image", "gzip").await;
+        assert!(parts.headers.get(http::header::CONTENT_ENCODING).is_none());
+        assert!(parts.headers.get(http::header::VARY).is_none());
+        assert_eq!(body.len(), 12000);
+
+        // The client does not accept any supported encoding.
+        let (parts, body) = get("/large", "zstd, gzip;q=0").await;
+        assert!(parts.headers.get(http::header::CONTENT_ENCODING).is_none());
+        assert_eq!(parts.headers[http::header::VARY], "accept-encoding");
+        assert_eq!(parts.headers[http::header::ETAG], "\"abc\"");
+        assert_eq!(body.len(), 12000);
+    }
+
+    #[tokio::test]
+    async fn skip_partial_content() {
+        for path in ["/partial", "/range"] {
+            let (parts, body) = get(path, "gzip").await;
+            assert!(parts.headers.get(http::header::CONTENT_ENCODING).is_none(), "{path}");
+            assert!(parts.headers.get(http::header::VARY).is_none(), "{path}");
+            assert_eq!(parts.headers[http::header::ETAG], "\"abc\"", "{path}");
+            assert_eq!(body.len(), 12000, "{path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn flush_on_stall() {
+        // A body whose chunks are all ready is only flushed at the end, instead of
+        // once per chunk.
+        let chunks = (0..100).map(|_| Ok::<_, Infallible>(Frame::data(Bytes::from_static(b"hello world "))));
+        let mut body = CompressionBody::new(StreamBody::new(futures::stream::iter(chunks)), Encoding::Gzip);
+        let mut frames = Vec::new();
+        while let Some(frame) = body.frame().await {
+            frames.push(frame.unwrap().into_data().unwrap());
+        }
+
+        assert!(frames.len() <= 2, "flushed {} times", frames.len());
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&frames.concat()[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "hello world ".repeat(100));
+
+        // When the body stalls what was written so far is flushed.
+        let (tx, rx) = futures::channel::mpsc::unbounded::<Result<Frame<Bytes>, Infallible>>();
+        let mut body = CompressionBody::new(StreamBody::new(rx), Encoding::Gzip);
+        tx.unbounded_send(Ok(Frame::data(Bytes::from_static(b"hello ")))).unwrap();
+        tx.unbounded_send(Ok(Frame::data(Bytes::from_static(b"world")))).unwrap();
+
+        let mut decoder = flate2::write::GzDecoder::new(Vec::new());
+        while decoder.get_ref().len() < 11 {
+            let data = body.frame().await.unwrap().unwrap().into_data().unwrap();
+            decoder.write_all(&data).unwrap();
+            decoder.flush().unwrap();
+        }
+        assert_eq!(decoder.get_ref(), b"hello world");
+
+        drop(tx);
+        let data = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        decoder.write_all(&data).unwrap();
+        assert_eq!(decoder.finish().unwrap(), b"hello world");
+        assert!(body.frame().await.is_none());
+    }
+
+    #[test]
+    fn negotiate() {
+        fn negotiate(value: &str) -> Option<Encoding> {
+            let mut headers = http::HeaderMap::new();
+            headers.insert(http::header::ACCEPT_ENCODING, value.parse().unwrap());
+            Encoding::negotiate(&headers)
+        }
+
+        assert_eq!(negotiate("gzip, deflate"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("gzip;q=0.5, deflate"), Some(Encoding::Deflate));
+        assert_eq!(negotiate("x-gzip;q=0.5, deflate;q=0.2"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*;q=0, deflate"), Some(Encoding::Deflate));
+        assert_eq!(negotiate("identity"), None);
+        assert_eq!(Encoding::negotiate(&http::HeaderMap::new()), None);
+
+        #[cfg(not(feature = "compression-br"))]
+        {
+            assert_eq!(negotiate("*"), Some(Encoding::Gzip));
+            assert_eq!(negotiate("br"), None);
+        }
+
+        #[cfg(feature = "compression-br")]
+        {
+            assert_eq!(negotiate("*"), Some(Encoding::Brotli));
+            assert_eq!(negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+            assert_eq!(negotiate("br;q=0.5, gzip"), Some(Encoding::Gzip));
+            assert_eq!(negotiate("br;q=0, *"), Some(Encoding::Gzip));
+        }
+    }
+
+    #[cfg(feature = "compression-br")]
+    #[tokio::test]
+    async fn compress_brotli() {
+        let (parts, body) = get("/large", "gzip, deflate, br").await;
+
+        assert_eq!(parts.headers[http::header::CONTENT_ENCODING], "br");
+        assert_eq!(parts.headers[http::header::VARY], "accept-encoding");
+        assert!(body.len() < 1000, "body was not compressed: {} bytes", body.len());
+
+        let mut decoded = String::new();
+        brotli::Decompressor::new(&body[..], 4096)
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "hello world ".repeat(1000));
+
+        // Flushed output can be decoded before the stream is finished.
+        let (tx, rx) = futures::channel::mpsc::unbounded::<Result<Frame<Bytes>, Infallible>>();
+        let mut body = CompressionBody::new(StreamBody::new(rx), Encoding::Brotli);
+        tx.unbounded_send(Ok(Frame::data(Bytes::from_static(b"hello world"))))
+            .unwrap();
+
+        let mut decoder = brotli::DecompressorWriter::new(Vec::new(), 4096);
+        while decoder.get_ref().len() < 11 {
+            let data = body.frame().await.unwrap().unwrap().into_data().unwrap();
+            decoder.write_all(&data).unwrap();
+            decoder.flush().unwrap();
+        }
+        assert_eq!(decoder.get_ref(), b"hello world");
+
+        drop(tx);
+        let data = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        decoder.write_all(&data).unwrap();
+        assert_eq!(decoder.into_inner().unwrap(), b"hello world");
+        assert!(body.frame().await.is_none());
+    }
+}
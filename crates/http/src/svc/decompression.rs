@@ -0,0 +1,402 @@
+use std::io::Write;
+use std::task::{ready, Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use http_body::{Body, Frame};
+
+use super::{ConnectionHandle, IncomingConnection};
+use crate::body::{IncomingBody, IncomingBodyInner};
+use crate::error::ErrorKind;
+
+/// The default limit on the decompressed size of a request body, 16 MiB.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// An error produced while decompressing a request body.
+#[derive(Debug, thiserror::Error)]
+pub enum DecompressionError {
+    /// The decompressed body was larger than the allowed limit.
+    #[error("decompressed body exceeded the limit of {0} bytes")]
+    TooLarge(usize),
+    /// The body was not validly encoded.
+    #[error("invalid compressed body: {0}")]
+    Invalid(std::io::Error),
+}
+
+impl DecompressionError {
+    /// The status code which should be returned to the client for this error.
+    ///
+    /// [`DecompressionError::TooLarge`] maps to `413 Payload Too Large` and
+    /// [`DecompressionError::Invalid`] to `400 Bad Request`.
+    pub fn status_code(&self) -> http::StatusCode {
+        match self {
+            Self::TooLarge(_) => http::StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Invalid(_) => http::StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A wrapper around a [`ConnectionHandle`] which transparently decompresses
+/// request bodies based on their `Content-Encoding` header.
+///
+/// `gzip` (and `x-gzip`) and `deflate` are supported, `br` is supported when
+/// the `decompression-br` feature is enabled. When a body is decompressed the
+/// `Content-Encoding` and `Content-Length` headers are removed before the
+/// request is passed on. Requests with any other encoding, or with more than
+/// one encoding applied, are passed on untouched.
+///
+/// Reading a decompressed body fails with
+/// [`DecompressionError::TooLarge`] as soon as it produces more than the
+/// configured limit (see [`DecompressionService::with_max_size`]), which
+/// protects handlers from decompression bombs.
+#[derive(Debug, Clone)]
+pub struct DecompressionService<H> {
+    inner: H,
+    max_size: usize,
+}
+
+impl<H> DecompressionService<H> {
+    /// Sets the maximum number of bytes a single request body may decompress
+    /// to. Defaults to [`DEFAULT_MAX_DECOMPRESSED_SIZE`].
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<H> ConnectionHandle for DecompressionService<H>
+where
+    H: ConnectionHandle,
+{
+    type Body = H::Body;
+    type BodyData = H::BodyData;
+    type BodyError = H::BodyError;
+    type Error = H::Error;
+
+    async fn accept(&self, conn: IncomingConnection) -> Result<(), Self::Error> {
+        self.inner.accept(conn).await
+    }
+
+    async fn on_request(&self, req: http::Request<IncomingBody>) -> Result<http::Response<Self::Body>, Self::Error> {
+        let Some(encoding) = Encoding::from_headers(req.headers()) else {
+            return self.inner.on_request(req).await;
+        };
+
+        let (mut parts, body) = req.into_parts();
+        parts.headers.remove(http::header::CONTENT_ENCODING);
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+
+        let body = IncomingBody::new(IncomingBodyInner::Decompressed(Box::new(DecompressedBody::new(
+            body,
+            encoding,
+            self.max_size,
+        ))));
+
+        self.inner.on_request(http::Request::from_parts(parts, body)).await
+    }
+
+    fn on_ready(&self) {
+        self.inner.on_ready();
+    }
+
+    fn on_close(&self) {
+        self.inner.on_close();
+    }
+
+    fn on_error(&self, err: crate::Error) {
+        self.inner.on_error(err);
+    }
+}
+
+/// Wraps a [`ConnectionHandle`] so that compressed request bodies are
+/// decompressed before they reach it, limited to
+/// [`DEFAULT_MAX_DECOMPRESSED_SIZE`] bytes.
+pub fn decompression_service<H>(handle: H) -> DecompressionService<H> {
+    DecompressionService {
+        inner: handle,
+        max_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+    Deflate,
+    #[cfg(feature = "decompression-br")]
+    Brotli,
+}
+
+impl Encoding {
+    fn from_headers(headers: &http::HeaderMap) -> Option<Self> {
+        let mut values = headers.get_all(http::header::CONTENT_ENCODING).iter();
+        let value = values.next()?;
+        if values.next().is_some() {
+            return None;
+        }
+
+        let value = value.to_str().ok()?.trim();
+        if value.eq_ignore_ascii_case("gzip") || value.eq_ignore_ascii_case("x-gzip") {
+            Some(Self::Gzip)
+        } else if value.eq_ignore_ascii_case("deflate") {
+            Some(Self::Deflate)
+        } else {
+            #[cfg(feature = "decompression-br")]
+            if value.eq_ignore_ascii_case("br") {
+                return Some(Self::Brotli);
+            }
+
+            None
+        }
+    }
+}
+
+/// Collects the decompressed output and refuses to grow past the limit.
+struct Sink {
+    buf: BytesMut,
+    written: usize,
+    max_size: usize,
+    exceeded: bool,
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written + buf.len() > self.max_size {
+            self.exceeded = true;
+            return Err(std::io::Error::other("decompressed body too large"));
+        }
+
+        self.written += buf.len();
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Decoder {
+    Gzip(flate2::write::GzDecoder<Sink>),
+    Deflate(flate2::write::ZlibDecoder<Sink>),
+    #[cfg(feature = "decompression-br")]
+    Brotli(Box<brotli_decompressor::DecompressorWriter<Sink>>),
+}
+
+impl Decoder {
+    fn sink(&mut self) -> &mut Sink {
+        match self {
+            Self::Gzip(decoder) => decoder.get_mut(),
+            Self::Deflate(decoder) => decoder.get_mut(),
+            #[cfg(feature = "decompression-br")]
+            Self::Brotli(decoder) => decoder.get_mut(),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Gzip(decoder) => decoder.write_all(data).and_then(|_| decoder.flush()),
+            Self::Deflate(decoder) => decoder.write_all(data).and_then(|_| decoder.flush()),
+            #[cfg(feature = "decompression-br")]
+            Self::Brotli(decoder) => decoder.write_all(data).and_then(|_| decoder.flush()),
+        }
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Gzip(decoder) => decoder.try_finish(),
+            Self::Deflate(decoder) => decoder.try_finish(),
+            #[cfg(feature = "decompression-br")]
+            Self::Brotli(decoder) => decoder.close(),
+        }
+    }
+}
+
+pub(crate) struct DecompressedBody {
+    body: IncomingBody,
+    decoder: Option<Decoder>,
+    max_size: usize,
+    pending: Option<Frame<Bytes>>,
+}
+
+impl DecompressedBody {
+    fn new(body: IncomingBody, encoding: Encoding, max_size: usize) -> Self {
+        let sink = Sink {
+            buf: BytesMut::new(),
+            written: 0,
+            max_size,
+            exceeded: false,
+        };
+
+        let decoder = match encoding {
+            Encoding::Gzip => Decoder::Gzip(flate2::write::GzDecoder::new(sink)),
+            Encoding::Deflate => Decoder::Deflate(flate2::write::ZlibDecoder::new(sink)),
+            #[cfg(feature = "decompression-br")]
+            Encoding::Brotli => Decoder::Brotli(Box::new(brotli_decompressor::DecompressorWriter::new(sink, 4096))),
+        };
+
+        Self {
+            body,
+            decoder: Some(decoder),
+            max_size,
+            pending: None,
+        }
+    }
+
+    fn error(&self, exceeded: bool, err: std::io::Error) -> crate::Error {
+        let err = if exceeded {
+            DecompressionError::TooLarge(self.max_size)
+        } else {
+            DecompressionError::Invalid(err)
+        };
+
+        crate::Error::with_kind(ErrorKind::Decompression(err))
+    }
+
+    /// Finishes the decoder, returning any output which was still buffered.
+    fn finish(&mut self) -> Result<Option<Bytes>, crate::Error> {
+        let Some(mut decoder) = self.decoder.take() else {
+            return Ok(None);
+        };
+
+        if let Err(err) = decoder.finish() {
+            return Err(self.error(decoder.sink().exceeded, err));
+        }
+
+        let data = decoder.sink().buf.split().freeze();
+        Ok(Some(data).filter(|data| !data.is_empty()))
+    }
+
+    pub(crate) fn poll_frame(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, crate::Error>>> {
+        if let Some(frame) = self.pending.take() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        loop {
+            let Some(decoder) = self.decoder.as_mut() else {
+                return std::pin::Pin::new(&mut self.body).poll_frame(cx);
+            };
+
+            match ready!(std::pin::Pin::new(&mut self.body).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => {
+                        if let Err(err) = decoder.write(&data) {
+                            let exceeded = decoder.sink().exceeded;
+                            self.decoder = None;
+                            return Poll::Ready(Some(Err(self.error(exceeded, err))));
+                        }
+
+                        let data = decoder.sink().buf.split().freeze();
+                        if !data.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(data))));
+                        }
+                    }
+                    // Trailers end the body, so flush the decoder before
+                    // passing them on.
+                    Err(frame) => {
+                        return Poll::Ready(Some(match self.finish() {
+                            Ok(Some(data)) => {
+                                self.pending = Some(frame);
+                                Ok(Frame::data(data))
+                            }
+                            Ok(None) => Ok(frame),
+                            Err(err) => Err(err),
+                        }));
+                    }
+                },
+                Some(Err(err)) => {
+                    self.decoder = None;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                None => return Poll::Ready(self.finish().transpose().map(|data| data.map(Frame::data))),
+            }
+        }
+    }
+
+    pub(crate) fn is_end_stream(&self) -> bool {
+        self.decoder.is_none() && self.pending.is_none() && self.body.is_end_stream()
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(all(test, feature = "http1"))]
+mod tests {
+    use std::io::Write;
+
+    use bytes::Bytes;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::decompression_service;
+    use crate::backend::tcp::config::TcpServerConfig;
+    use crate::backend::HttpServer;
+    use crate::body::collect_with_limit;
+    use crate::svc::function_service;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    async fn post(max_size: usize, body: &[u8]) -> String {
+        let server = TcpServerConfig::builder()
+            .with_bind("127.0.0.1:0".parse().unwrap())
+            .build()
+            .into_server();
+
+        server
+            .start(
+                decompression_service(function_service(|req: http::Request<_>| async move {
+                    assert!(req.headers().get(http::header::CONTENT_ENCODING).is_none());
+                    let response = match collect_with_limit(req.into_body(), usize::MAX).await {
+                        Ok(body) => http::Response::new(body),
+                        Err(err) => http::Response::builder()
+                            .status(err.status_code())
+                            .body(Bytes::new())
+                            .unwrap(),
+                    };
+                    Ok::<_, std::convert::Infallible>(response.map(http_body_util::Full::new))
+                }))
+                .with_max_size(max_size),
+                1,
+            )
+            .await
+            .unwrap();
+
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr().unwrap()).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "POST / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        stream.write_all(body).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        server.shutdown().await.unwrap();
+
+        response
+    }
+
+    #[tokio::test]
+    async fn gzip_body() {
+        let response = post(1024, &gzip(b"hello compressed world")).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.ends_with("hello compressed world"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn decompression_bomb() {
+        // 64 MiB of zeros compresses to roughly 64 KiB.
+        let bomb = gzip(&vec![0; 64 * 1024 * 1024]);
+        assert!(bomb.len() < 128 * 1024);
+
+        let response = post(1024 * 1024, &bomb).await;
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large"), "{response}");
+    }
+}
@@ -2,15 +2,39 @@ use std::net::SocketAddr;
 
 use crate::body::IncomingBody;
 
+#[cfg(feature = "tracing")]
+mod access_log;
 #[cfg(feature = "axum")]
 mod axum;
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "decompression")]
+mod decompression;
 mod function;
+mod request_id;
+mod response_hook;
+mod router;
+mod server_timing;
 #[cfg(feature = "tower")]
 mod tower;
 
+#[cfg(feature = "tracing")]
+pub use access_log::{
+    access_log_service, default_access_log_formatter, AccessLogEntry, AccessLogFormatter, AccessLogService, AccessLogTracker,
+};
 #[cfg(feature = "axum")]
 pub use axum::{axum_service, AxumService};
+#[cfg(feature = "compression")]
+pub use compression::{compression_service, CompressionBody, CompressionService, DEFAULT_MIN_COMPRESSED_SIZE};
+#[cfg(feature = "decompression")]
+pub(crate) use decompression::DecompressedBody;
+#[cfg(feature = "decompression")]
+pub use decompression::{decompression_service, DecompressionError, DecompressionService, DEFAULT_MAX_DECOMPRESSED_SIZE};
 pub use function::{function_service, FunctionService};
+pub use request_id::{request_id_service, RequestId, RequestIdService, REQUEST_ID_HEADER};
+pub use response_hook::{response_hook_service, ResponseHookService};
+pub use router::RouterService;
+pub use server_timing::{server_timing_service, ServerTimingService};
 #[cfg(feature = "tower")]
 pub use tower::{tower_service, TowerService};
 
@@ -0,0 +1,239 @@
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use super::{ConnectionHandle, IncomingConnection};
+use crate::body::IncomingBody;
+
+/// The header used to carry the request id, `X-Request-Id`.
+pub const REQUEST_ID_HEADER: http::HeaderName = http::HeaderName::from_static("x-request-id");
+
+/// Incoming request ids longer than this are replaced with a generated one.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The id of a request, added as a request extension by the
+/// [`RequestIdService`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(http::HeaderValue);
+
+impl RequestId {
+    /// Generates a new id which is unique within this process and very
+    /// likely unique across processes.
+    ///
+    /// The id is made of a random per process prefix and a counter, e.g.
+    /// `5f0c2a7d9e1b4c38-0000000000000001`.
+    pub fn generate() -> Self {
+        static PREFIX: OnceLock<u64> = OnceLock::new();
+        static COUNTER: AtomicU64 = AtomicU64::new(1);
+
+        let prefix = *PREFIX.get_or_init(|| {
+            let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+            hasher.write_u32(std::process::id());
+            if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+                hasher.write_u128(now.as_nanos());
+            }
+            hasher.finish()
+        });
+        let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        let value =
+            http::HeaderValue::try_from(format!("{prefix:016x}-{counter:016x}")).expect("hex is a valid header value");
+        Self(value)
+    }
+
+    /// Returns the id as a string.
+    pub fn as_str(&self) -> &str {
+        // Only ids which are valid strings are accepted or generated.
+        self.0.to_str().unwrap_or_default()
+    }
+
+    /// Returns the id as a header value.
+    pub fn header_value(&self) -> &http::HeaderValue {
+        &self.0
+    }
+
+    fn from_header(value: &http::HeaderValue) -> Option<Self> {
+        let valid = !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN && value.to_str().is_ok();
+        valid.then(|| Self(value.clone()))
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A wrapper around a [`ConnectionHandle`] which makes sure every request
+/// has an id.
+///
+/// The id is taken from the `X-Request-Id` header of the request if present,
+/// so that ids set by a load balancer or a client are kept, otherwise a new
+/// one is generated with [`RequestId::generate`]. The id is set on the
+/// request header, added as a [`RequestId`] request extension and echoed in
+/// the `X-Request-Id` header of the response.
+///
+/// With the `tracing` feature the handler runs inside a `request` span with
+/// a `request_id` field, so that everything logged while handling the
+/// request carries the id.
+#[derive(Debug, Clone)]
+pub struct RequestIdService<H> {
+    inner: H,
+    header: http::HeaderName,
+}
+
+impl<H> RequestIdService<H> {
+    /// Set the name of the header carrying the request id.
+    ///
+    /// Defaults to [`REQUEST_ID_HEADER`].
+    pub fn with_header(mut self, header: http::HeaderName) -> Self {
+        self.header = header;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<H: ConnectionHandle> ConnectionHandle for RequestIdService<H> {
+    type Body = H::Body;
+    type BodyData = H::BodyData;
+    type BodyError = H::BodyError;
+    type Error = H::Error;
+
+    async fn accept(&self, conn: IncomingConnection) -> Result<(), Self::Error> {
+        self.inner.accept(conn).await
+    }
+
+    async fn on_request(&self, mut req: http::Request<IncomingBody>) -> Result<http::Response<Self::Body>, Self::Error> {
+        let id = req
+            .headers()
+            .get(&self.header)
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+
+        req.headers_mut().insert(self.header.clone(), id.header_value().clone());
+        req.extensions_mut().insert(id.clone());
+
+        #[cfg(feature = "tracing")]
+        let res = {
+            use tracing::Instrument;
+
+            let span = tracing::info_span!("request", request_id = %id);
+            self.inner.on_request(req).instrument(span).await
+        };
+        #[cfg(not(feature = "tracing"))]
+        let res = self.inner.on_request(req).await;
+
+        let mut res = res?;
+        res.headers_mut().insert(self.header.clone(), id.0);
+
+        Ok(res)
+    }
+
+    fn on_ready(&self) {
+        self.inner.on_ready();
+    }
+
+    fn on_close(&self) {
+        self.inner.on_close();
+    }
+
+    fn on_error(&self, err: crate::Error) {
+        self.inner.on_error(err);
+    }
+}
+
+/// Wraps a [`ConnectionHandle`] so that every request gets an id, which is
+/// propagated from or added to the `X-Request-Id` header.
+pub fn request_id_service<H: ConnectionHandle>(handle: H) -> RequestIdService<H> {
+    RequestIdService {
+        inner: handle,
+        header: REQUEST_ID_HEADER,
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+
+    use super::{request_id_service, RequestId};
+    use crate::body::IncomingBody;
+    use crate::svc::{function_service, ConnectionHandle};
+
+    async fn echo(req: http::Request<IncomingBody>) -> Result<http::Response<http_body_util::Full<Bytes>>, Infallible> {
+        let id = req.extensions().get::<RequestId>().expect("missing request id");
+        assert_eq!(req.headers()["x-request-id"], id.header_value());
+
+        Ok(http::Response::new(http_body_util::Full::new(Bytes::from(id.to_string()))))
+    }
+
+    async fn body(res: http::Response<http_body_util::Full<Bytes>>) -> String {
+        use http_body_util::BodyExt;
+
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn generated() {
+        let service = request_id_service(function_service(echo));
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let req = http::Request::builder().uri("/").body(IncomingBody::empty()).unwrap();
+            let res = service.on_request(req).await.unwrap();
+
+            let header = res.headers()["x-request-id"].to_str().unwrap().to_owned();
+            assert_eq!(body(res).await, header);
+            assert_eq!(header.len(), 33, "{header}");
+            ids.push(header);
+        }
+
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[tokio::test]
+    async fn propagated() {
+        let service = request_id_service(function_service(echo));
+
+        let req = http::Request::builder()
+            .uri("/")
+            .header("x-request-id", "my-request")
+            .body(IncomingBody::empty())
+            .unwrap();
+        let res = service.on_request(req).await.unwrap();
+
+        assert_eq!(res.headers()["x-request-id"], "my-request");
+        assert_eq!(body(res).await, "my-request");
+
+        // Ids which are too long are replaced.
+        let req = http::Request::builder()
+            .uri("/")
+            .header("x-request-id", "a".repeat(200))
+            .body(IncomingBody::empty())
+            .unwrap();
+        let res = service.on_request(req).await.unwrap();
+        assert_eq!(res.headers()["x-request-id"].len(), 33);
+    }
+
+    #[tokio::test]
+    async fn custom_header() {
+        let service = request_id_service(function_service(|req: http::Request<IncomingBody>| async move {
+            assert!(req.extensions().get::<RequestId>().is_some());
+            Ok::<_, Infallible>(http::Response::new(http_body_util::Empty::<Bytes>::new()))
+        }))
+        .with_header(http::HeaderName::from_static("x-correlation-id"));
+
+        let req = http::Request::builder()
+            .uri("/")
+            .header("x-correlation-id", "abc")
+            .body(IncomingBody::empty())
+            .unwrap();
+        let res = service.on_request(req).await.unwrap();
+
+        assert_eq!(res.headers()["x-correlation-id"], "abc");
+        assert!(res.headers().get("x-request-id").is_none());
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use super::{ConnectionHandle, IncomingConnection};
+use crate::body::IncomingBody;
+
+/// A wrapper around a [`ConnectionHandle`] which calls a hook with every
+/// response after the handler has returned it and before it is written.
+///
+/// This is the place for blanket changes to responses, such as adding
+/// security headers (`Strict-Transport-Security`, `X-Content-Type-Options`,
+/// ...) to every response without touching each handler.
+#[derive(derive_more::Debug)]
+pub struct ResponseHookService<H, F> {
+    inner: H,
+    #[debug(skip)]
+    hook: Arc<F>,
+}
+
+impl<H: Clone, F> Clone for ResponseHookService<H, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            hook: self.hook.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<H, F> ConnectionHandle for ResponseHookService<H, F>
+where
+    H: ConnectionHandle,
+    F: Fn(&mut http::Response<H::Body>) + Send + Sync + 'static,
+{
+    type Body = H::Body;
+    type BodyData = H::BodyData;
+    type BodyError = H::BodyError;
+    type Error = H::Error;
+
+    async fn accept(&self, conn: IncomingConnection) -> Result<(), Self::Error> {
+        self.inner.accept(conn).await
+    }
+
+    async fn on_request(&self, req: http::Request<IncomingBody>) -> Result<http::Response<Self::Body>, Self::Error> {
+        let mut res = self.inner.on_request(req).await?;
+        (self.hook)(&mut res);
+        Ok(res)
+    }
+
+    fn on_ready(&self) {
+        self.inner.on_ready();
+    }
+
+    fn on_close(&self) {
+        self.inner.on_close();
+    }
+
+    fn on_error(&self, err: crate::Error) {
+        self.inner.on_error(err);
+    }
+}
+
+/// Wraps a [`ConnectionHandle`] so that `hook` is called with every response
+/// it returns.
+pub fn response_hook_service<H, F>(handle: H, hook: F) -> ResponseHookService<H, F>
+where
+    H: ConnectionHandle,
+    F: Fn(&mut http::Response<H::Body>) + Send + Sync + 'static,
+{
+    ResponseHookService {
+        inner: handle,
+        hook: Arc::new(hook),
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use http_body_util::BodyExt;
+
+    use super::response_hook_service;
+    use crate::body::IncomingBody;
+    use crate::svc::{function_service, ConnectionHandle};
+
+    #[tokio::test]
+    async fn adds_headers() {
+        let service = response_hook_service(
+            function_service(|_| async {
+                Ok::<_, Infallible>(
+                    http::Response::builder()
+                        .header(http::header::CONTENT_TYPE, "text/plain")
+                        .body(http_body_util::Full::new(Bytes::from_static(b"hello")))
+                        .unwrap(),
+                )
+            }),
+            |res| {
+                res.headers_mut().insert(
+                    http::header::STRICT_TRANSPORT_SECURITY,
+                    http::HeaderValue::from_static("max-age=63072000"),
+                );
+                res.headers_mut().insert(
+                    http::header::X_CONTENT_TYPE_OPTIONS,
+                    http::HeaderValue::from_static("nosniff"),
+                );
+            },
+        );
+
+        for _ in 0..2 {
+            let req = http::Request::builder().uri("/").body(IncomingBody::empty()).unwrap();
+            let res = service.on_request(req).await.unwrap();
+
+            assert_eq!(res.headers()[http::header::STRICT_TRANSPORT_SECURITY], "max-age=63072000");
+            assert_eq!(res.headers()[http::header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+            assert_eq!(res.headers()[http::header::CONTENT_TYPE], "text/plain");
+
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, Bytes::from_static(b"hello"));
+        }
+    }
+}
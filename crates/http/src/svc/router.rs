@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http::{Method, Request, Response, StatusCode};
+
+use super::ConnectionHandle;
+use crate::body::IncomingBody;
+
+type Route<B, E> = Arc<dyn Fn(Request<IncomingBody>) -> BoxFuture<'static, Result<Response<B>, E>> + Send + Sync>;
+/// The routes of each path, in the order they were added.
+type Routes<B, E> = HashMap<String, Vec<(Method, Route<B, E>)>>;
+
+/// A [`ConnectionHandle`] which dispatches requests to handlers by their
+/// method and exact path.
+///
+/// Requests for a path without any route are answered with
+/// `404 Not Found`. Requests for a known path with a method which has no
+/// route are answered with `405 Method Not Allowed` and an `Allow` header
+/// listing the methods of the path. A `HEAD` request is handled by the `GET`
+/// route if the path has no `HEAD` route.
+///
+/// ```rust
+/// use std::convert::Infallible;
+///
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use scuffle_http::svc::RouterService;
+///
+/// let router = RouterService::<Full<Bytes>, Infallible>::new()
+///     .route(http::Method::GET, "/users", |_| async { Ok(http::Response::new(Full::from("users"))) })
+///     .route(http::Method::POST, "/users", |_| async {
+///         Ok(http::Response::builder()
+///             .status(http::StatusCode::CREATED)
+///             .body(Full::default())
+///             .unwrap())
+///     })
+///     .not_found_body("nothing here");
+/// ```
+#[derive(derive_more::Debug)]
+pub struct RouterService<B, E> {
+    #[debug(skip)]
+    routes: Arc<Routes<B, E>>,
+    not_found_body: Bytes,
+    method_not_allowed_body: Bytes,
+}
+
+impl<B, E> Clone for RouterService<B, E> {
+    fn clone(&self) -> Self {
+        Self {
+            routes: self.routes.clone(),
+            not_found_body: self.not_found_body.clone(),
+            method_not_allowed_body: self.method_not_allowed_body.clone(),
+        }
+    }
+}
+
+impl<B, E> Default for RouterService<B, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B, E> RouterService<B, E> {
+    /// Create a router without any routes.
+    pub fn new() -> Self {
+        Self {
+            routes: Arc::default(),
+            not_found_body: Bytes::from_static(b"Not Found"),
+            method_not_allowed_body: Bytes::from_static(b"Method Not Allowed"),
+        }
+    }
+
+    /// Add a route handling requests with the given method and path.
+    ///
+    /// A later route for the same method and path replaces the earlier one.
+    pub fn route<S, F>(mut self, method: Method, path: impl Into<String>, handler: S) -> Self
+    where
+        S: Fn(Request<IncomingBody>) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<Response<B>, E>> + Send + 'static,
+    {
+        let handler: Route<B, E> = Arc::new(move |req| Box::pin(handler(req)));
+        let routes = Arc::make_mut(&mut self.routes).entry(path.into()).or_default();
+
+        match routes.iter_mut().find(|(m, _)| *m == method) {
+            Some((_, route)) => *route = handler,
+            None => routes.push((method, handler)),
+        }
+
+        self
+    }
+
+    /// Set the body of `404 Not Found` responses.
+    ///
+    /// Defaults to `Not Found`.
+    pub fn not_found_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.not_found_body = body.into();
+        self
+    }
+
+    /// Set the body of `405 Method Not Allowed` responses.
+    ///
+    /// Defaults to `Method Not Allowed`.
+    pub fn method_not_allowed_body(mut self, body: impl Into<Bytes>) -> Self {
+        self.method_not_allowed_body = body.into();
+        self
+    }
+}
+
+/// The value of the `Allow` header for a path with the given routes.
+fn allow<R>(routes: &[(Method, R)]) -> String {
+    let mut methods: Vec<&str> = routes.iter().map(|(method, _)| method.as_str()).collect();
+    if methods.contains(&"GET") && !methods.contains(&"HEAD") {
+        methods.push("HEAD");
+    }
+
+    methods.join(", ")
+}
+
+fn default_response<B: From<Bytes>>(status: StatusCode, body: &Bytes) -> Response<B> {
+    let mut res = Response::new(B::from(body.clone()));
+    *res.status_mut() = status;
+    res.headers_mut().insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    res
+}
+
+#[async_trait::async_trait]
+impl<B, E> ConnectionHandle for RouterService<B, E>
+where
+    B: http_body::Body + From<Bytes> + Send + 'static,
+    <B as http_body::Body>::Error: Into<crate::Error> + Send + Sync + 'static,
+    <B as http_body::Body>::Data: Send,
+    E: Into<crate::Error> + Send + Sync + 'static,
+{
+    type Body = B;
+    type BodyData = <B as http_body::Body>::Data;
+    type BodyError = <B as http_body::Body>::Error;
+    type Error = E;
+
+    async fn on_request(&self, req: Request<IncomingBody>) -> Result<Response<Self::Body>, Self::Error> {
+        let Some(routes) = self.routes.get(req.uri().path()) else {
+            return Ok(default_response(StatusCode::NOT_FOUND, &self.not_found_body));
+        };
+
+        let route = routes.iter().find(|(method, _)| method == req.method()).or_else(|| {
+            (req.method() == Method::HEAD)
+                .then(|| routes.iter().find(|(method, _)| method == Method::GET))
+                .flatten()
+        });
+
+        match route {
+            Some((_, route)) => route(req).await,
+            None => {
+                let mut res = default_response(StatusCode::METHOD_NOT_ALLOWED, &self.method_not_allowed_body);
+                if let Ok(allow) = http::HeaderValue::try_from(allow(routes)) {
+                    res.headers_mut().insert(http::header::ALLOW, allow);
+                }
+                Ok(res)
+            }
+        }
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+
+    use super::RouterService;
+    use crate::body::IncomingBody;
+    use crate::svc::ConnectionHandle;
+
+    fn router() -> RouterService<Full<Bytes>, Infallible> {
+        RouterService::new()
+            .route(http::Method::GET, "/users", |_| async {
+                Ok(http::Response::new(Full::new(Bytes::from_static(b"users"))))
+            })
+            .route(http::Method::POST, "/users", |_| async {
+                Ok(http::Response::new(Full::new(Bytes::from_static(b"created"))))
+            })
+    }
+
+    async fn request(
+        router: &RouterService<Full<Bytes>, Infallible>,
+        method: http::Method,
+        path: &str,
+    ) -> http::Response<Full<Bytes>> {
+        let req = http::Request::builder()
+            .method(method)
+            .uri(path)
+            .body(IncomingBody::empty())
+            .unwrap();
+        router.on_request(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn routes() {
+        let router = router();
+
+        let res = request(&router, http::Method::GET, "/users").await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.into_body().collect().await.unwrap().to_bytes(), "users");
+
+        let res = request(&router, http::Method::POST, "/users?page=2").await;
+        assert_eq!(res.into_body().collect().await.unwrap().to_bytes(), "created");
+
+        let res = request(&router, http::Method::HEAD, "/users").await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn method_not_allowed() {
+        let res = request(&router(), http::Method::DELETE, "/users").await;
+
+        assert_eq!(res.status(), http::StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[http::header::ALLOW], "GET, POST, HEAD");
+        assert_eq!(res.into_body().collect().await.unwrap().to_bytes(), "Method Not Allowed");
+    }
+
+    #[tokio::test]
+    async fn not_found() {
+        let res = request(&router(), http::Method::GET, "/posts").await;
+        assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()[http::header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        assert_eq!(res.into_body().collect().await.unwrap().to_bytes(), "Not Found");
+
+        let res = request(&router().not_found_body("nothing here"), http::Method::GET, "/posts").await;
+        assert_eq!(res.into_body().collect().await.unwrap().to_bytes(), "nothing here");
+    }
+}
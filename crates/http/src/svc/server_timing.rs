@@ -0,0 +1,132 @@
+use std::time::Instant;
+
+use super::{ConnectionHandle, IncomingConnection};
+use crate::body::IncomingBody;
+
+/// A wrapper around a [`ConnectionHandle`] which measures how long the handler
+/// took to produce a response and reports it to the client in a
+/// `Server-Timing` header, e.g. `Server-Timing: app;dur=12.3`.
+///
+/// The duration is measured until the handler returns the response, time
+/// spent streaming the body is not included. Existing `Server-Timing` headers
+/// set by the handler are kept.
+#[derive(Debug, Clone)]
+pub struct ServerTimingService<H> {
+    inner: H,
+    metric: &'static str,
+}
+
+impl<H> ServerTimingService<H> {
+    /// Set the name of the reported metric.
+    ///
+    /// Defaults to `app`.
+    pub fn with_metric(mut self, metric: &'static str) -> Self {
+        self.metric = metric;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<H: ConnectionHandle> ConnectionHandle for ServerTimingService<H> {
+    type Body = H::Body;
+    type BodyData = H::BodyData;
+    type BodyError = H::BodyError;
+    type Error = H::Error;
+
+    async fn accept(&self, conn: IncomingConnection) -> Result<(), Self::Error> {
+        self.inner.accept(conn).await
+    }
+
+    async fn on_request(&self, req: http::Request<IncomingBody>) -> Result<http::Response<Self::Body>, Self::Error> {
+        let start = Instant::now();
+        let mut res = self.inner.on_request(req).await?;
+        let dur = start.elapsed().as_secs_f64() * 1000.0;
+
+        if let Ok(value) = http::HeaderValue::try_from(format!("{};dur={dur:.1}", self.metric)) {
+            res.headers_mut()
+                .append(http::header::HeaderName::from_static("server-timing"), value);
+        }
+
+        Ok(res)
+    }
+
+    fn on_ready(&self) {
+        self.inner.on_ready();
+    }
+
+    fn on_close(&self) {
+        self.inner.on_close();
+    }
+
+    fn on_error(&self, err: crate::Error) {
+        self.inner.on_error(err);
+    }
+}
+
+/// Wraps a [`ConnectionHandle`] so that every response carries a
+/// `Server-Timing` header with the handler's processing duration.
+pub fn server_timing_service<H: ConnectionHandle>(handle: H) -> ServerTimingService<H> {
+    ServerTimingService {
+        inner: handle,
+        metric: "app",
+    }
+}
+
+#[cfg_attr(all(coverage_nightly, test), coverage(off))]
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use super::server_timing_service;
+    use crate::body::IncomingBody;
+    use crate::svc::{function_service, ConnectionHandle};
+
+    fn parse_dur(value: &http::HeaderValue, metric: &str) -> f64 {
+        let value = value.to_str().unwrap();
+        let dur = value
+            .strip_prefix(metric)
+            .and_then(|v| v.strip_prefix(";dur="))
+            .unwrap_or_else(|| panic!("unexpected header: {value}"));
+        dur.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn server_timing() {
+        let service = server_timing_service(function_service(|_| async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<_, Infallible>(
+                http::Response::builder()
+                    .header("server-timing", "db;dur=1.0")
+                    .body(http_body_util::Full::new(Bytes::from_static(b"hello")))
+                    .unwrap(),
+            )
+        }));
+
+        let req = http::Request::builder().uri("/").body(IncomingBody::empty()).unwrap();
+        let res = service.on_request(req).await.unwrap();
+
+        let values = res.headers().get_all("server-timing").iter().collect::<Vec<_>>();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0], "db;dur=1.0");
+
+        let dur = parse_dur(values[1], "app");
+        assert!((50.0..5000.0).contains(&dur), "{dur}");
+    }
+
+    #[tokio::test]
+    async fn custom_metric() {
+        let service = server_timing_service(function_service(|_| async {
+            Ok::<_, Infallible>(http::Response::new(http_body_util::Empty::<Bytes>::new()))
+        }))
+        .with_metric("handler");
+
+        let req = http::Request::builder().uri("/").body(IncomingBody::empty()).unwrap();
+        let res = service.on_request(req).await.unwrap();
+
+        let dur = parse_dur(&res.headers()["server-timing"], "handler");
+        assert!(dur >= 0.0, "{dur}");
+    }
+}
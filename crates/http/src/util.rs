@@ -1,22 +1,24 @@
 #![cfg_attr(not(any(feature = "http1", feature = "http2", feature = "http3")), allow(dead_code))]
 
-pub struct AbortOnDrop<T>(Option<tokio::task::JoinHandle<T>>);
+pub struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
 
 impl<T> AbortOnDrop<T> {
     pub fn new(handle: tokio::task::JoinHandle<T>) -> Self {
-        Self(Some(handle))
+        Self(handle)
     }
+}
+
+impl<T> std::future::Future for AbortOnDrop<T> {
+    type Output = Result<T, tokio::task::JoinError>;
 
-    pub fn disarm(mut self) -> tokio::task::JoinHandle<T> {
-        self.0.take().expect("disarmed twice")
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
+        std::pin::Pin::new(&mut self.0).poll(cx)
     }
 }
 
 impl<T> Drop for AbortOnDrop<T> {
     fn drop(&mut self) {
-        if let Some(handle) = self.0.take() {
-            handle.abort();
-        }
+        self.0.abort();
     }
 }
 
@@ -39,7 +39,10 @@ mod metrics_impl;
 ///
 /// - `crate_path`: The `scuffle_metrics` crate path.
 /// - `builder`: The builder to use for the metric.
-/// - `unit`: The unit of the metric.
+/// - `unit`: The unit of the metric. Full names (e.g. `milliseconds`), UCUM
+///   units (e.g. `ms`, `By`) and annotations (e.g. `{request}`) are accepted
+///   and passed to opentelemetry as written, a unit which is not listed in
+///   `scuffle_metrics::units` produces a compiler warning.
 /// - `rename`: The name of the metric.
 ///
 /// Function Arguments Attributes:
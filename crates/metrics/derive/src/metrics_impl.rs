@@ -392,7 +392,15 @@ fn metric_function(
         };
 
         let unit = if let Some(unit) = &options.unit {
+            // There is no way to emit a warning from a proc-macro on stable, so the
+            // unit is checked by a const expression which selects a deprecated
+            // function if the unit is not known.
+            let check = quote::quote_spanned! { unit.span() =>
+                #crate_path::units::UnitCheck::<{ #crate_path::units::is_known(#unit) }>.check();
+            };
+
             quote::quote! {
+                #check
                 builder = builder.with_unit(#unit);
             }
         } else {
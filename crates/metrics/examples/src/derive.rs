@@ -1,7 +1,7 @@
 use example::Kind;
 use opentelemetry::KeyValue;
 use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
-use opentelemetry_sdk::{runtime, Resource};
+use opentelemetry_sdk::runtime;
 
 #[scuffle_metrics::metrics]
 mod example {
@@ -23,7 +23,7 @@ async fn main() {
     let exporter = opentelemetry_stdout::MetricExporterBuilder::default().build();
 
     let provider = SdkMeterProvider::builder()
-        .with_resource(Resource::new_with_defaults(vec![KeyValue::new(
+        .with_resource(scuffle_metrics::resource::resource(vec![KeyValue::new(
             "service.name",
             env!("CARGO_BIN_NAME"),
         )]))
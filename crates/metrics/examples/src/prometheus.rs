@@ -1,7 +1,6 @@
 use example::Kind;
 use opentelemetry::KeyValue;
 use opentelemetry_sdk::metrics::SdkMeterProvider;
-use opentelemetry_sdk::Resource;
 
 #[scuffle_metrics::metrics]
 mod example {
@@ -29,7 +28,7 @@ async fn main() {
     registry.register_collector(exporter.collector());
 
     let provider = SdkMeterProvider::builder()
-        .with_resource(Resource::new_with_defaults(vec![KeyValue::new(
+        .with_resource(scuffle_metrics::resource::resource(vec![KeyValue::new(
             "service.name",
             env!("CARGO_BIN_NAME"),
         )]))
@@ -217,8 +217,19 @@ impl<'a, T: IsCollector> Collector<'a, T> {
     pub fn inner(&self) -> &'a T {
         self.collector
     }
+
+    /// Returns the attributes with an additional `outcome` attribute.
+    fn outcome_attributes(&self, outcome: &'static str) -> Vec<KeyValue> {
+        let mut attributes = Vec::with_capacity(self.attributes.len() + 1);
+        attributes.extend(self.attributes.iter().cloned());
+        attributes.push(KeyValue::new(OUTCOME_KEY, outcome));
+        attributes
+    }
 }
 
+/// The attribute key used by [`Collector::ok`] and [`Collector::err`].
+pub const OUTCOME_KEY: &str = "outcome";
+
 macro_rules! impl_counter {
     ($t:ty) => {
         impl<'a> Collector<'a, opentelemetry::metrics::Counter<$t>> {
@@ -232,6 +243,36 @@ macro_rules! impl_counter {
             pub fn incr_by(&self, value: $t) {
                 self.collector.add(value, &self.attributes);
             }
+
+            /// Increments the counter by one with an `outcome="ok"`
+            /// attribute.
+            ///
+            /// Together with [`err`](Self::err) this lets a single metric
+            /// track both successes and failures, the total is the sum of
+            /// both series.
+            pub fn ok(&self) {
+                self.collector.add(<$t as Number>::ONE, &self.outcome_attributes("ok"));
+            }
+
+            /// Increments the counter by one with an `outcome="error"`
+            /// attribute.
+            ///
+            /// See [`ok`](Self::ok).
+            pub fn err(&self) {
+                self.collector
+                    .add(<$t as Number>::ONE, &self.outcome_attributes("error"));
+            }
+
+            /// Increments the counter with the outcome of the given result
+            /// and returns it unchanged.
+            pub fn result<R, E>(&self, result: Result<R, E>) -> Result<R, E> {
+                match &result {
+                    Ok(_) => self.ok(),
+                    Err(_) => self.err(),
+                }
+
+                result
+            }
         }
     };
 }
@@ -290,9 +331,119 @@ macro_rules! impl_updowncounter {
             pub fn decr_by(&self, value: $t) {
                 self.collector.add(-value, &self.attributes);
             }
+
+            /// Increments the counter by one and returns a guard which
+            /// decrements it again when dropped.
+            ///
+            /// This is the way to track how many operations are in flight,
+            /// the counter is decremented on every exit path, including early
+            /// returns and panics.
+            ///
+            /// ```rust,ignore
+            /// let _guard = metrics::in_flight().track_inflight();
+            /// ```
+            pub fn track_inflight(&self) -> InFlightGuard<'a, $t> {
+                self.incr();
+                InFlightGuard {
+                    counter: self.collector,
+                    attributes: self.attributes.clone(),
+                    value: <$t as Number>::ONE,
+                }
+            }
         }
     };
 }
 
 impl_updowncounter!(i64);
 impl_updowncounter!(f64);
+
+/// A guard returned by [`Collector::track_inflight`] which decrements the
+/// counter when dropped.
+#[must_use = "the counter is decremented as soon as the guard is dropped"]
+pub struct InFlightGuard<'a, T: Copy + std::ops::Neg<Output = T>> {
+    counter: &'a opentelemetry::metrics::UpDownCounter<T>,
+    attributes: Vec<KeyValue>,
+    value: T,
+}
+
+impl<T: Copy + std::ops::Neg<Output = T>> Drop for InFlightGuard<'_, T> {
+    fn drop(&mut self) {
+        self.counter.add(-self.value, &self.attributes);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+
+    use super::{Collector, CounterU64, UpDownCounterI64, OUTCOME_KEY};
+    use crate::testing::TestReader;
+
+    #[test]
+    fn counter_outcome() {
+        let reader = TestReader::new();
+        let provider = SdkMeterProvider::builder().with_reader(reader.clone()).build();
+        let counter: CounterU64 = provider.meter("test").u64_counter("testing_requests").build();
+        let request = |kind: &'static str| Collector::new(vec![KeyValue::new("kind", kind)], &counter);
+
+        request("http").ok();
+        request("http").ok();
+        request("http").err();
+        assert_eq!(request("grpc").result(Err::<(), _>("failed")), Err("failed"));
+
+        let snapshot = reader.snapshot().unwrap();
+        let get = |kind: &'static str, outcome: &'static str| {
+            snapshot.get(
+                "testing_requests",
+                &[KeyValue::new("kind", kind), KeyValue::new(OUTCOME_KEY, outcome)],
+            )
+        };
+
+        assert_eq!(get("http", "ok"), Some(2.0));
+        assert_eq!(get("http", "error"), Some(1.0));
+        assert_eq!(get("grpc", "ok"), None);
+        assert_eq!(get("grpc", "error"), Some(1.0));
+        assert_eq!(snapshot.get("testing_requests", &[KeyValue::new("kind", "http")]), None);
+    }
+
+    #[test]
+    fn updowncounter_track_inflight() {
+        let reader = TestReader::new();
+        let provider = SdkMeterProvider::builder().with_reader(reader.clone()).build();
+        let counter: UpDownCounterI64 = provider.meter("test").i64_up_down_counter("testing_in_flight").build();
+        let in_flight = || Collector::new(vec![KeyValue::new("kind", "http")], &counter);
+        let get = || {
+            reader
+                .snapshot()
+                .unwrap()
+                .get("testing_in_flight", &[KeyValue::new("kind", "http")])
+        };
+
+        let first = in_flight().track_inflight();
+        assert_eq!(get(), Some(1.0));
+
+        let collector = in_flight();
+        let second = collector.track_inflight();
+        let third = collector.track_inflight();
+        assert_eq!(get(), Some(3.0));
+
+        drop(second);
+        assert_eq!(get(), Some(2.0));
+
+        // The guard is dropped when the operation panics.
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = in_flight().track_inflight();
+            assert_eq!(get(), Some(3.0));
+            panic!("request failed");
+        }))
+        .unwrap_err();
+        assert_eq!(get(), Some(2.0));
+
+        drop(first);
+        drop(third);
+        assert_eq!(get(), Some(0.0));
+    }
+}
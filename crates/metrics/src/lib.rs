@@ -25,6 +25,10 @@
 //!
 //! // Increment the counter
 //! example::request(example::Kind::Http).incr();
+//!
+//! // Increment the counter with an `outcome` attribute of `ok` or `error`
+//! example::request(example::Kind::Grpc).ok();
+//! example::request(example::Kind::Grpc).err();
 //! ```
 //!
 //! For details see [`metrics!`](metrics).
@@ -51,6 +55,33 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "prometheus")))]
 pub mod prometheus;
 
+/// A push exporter which sends metrics to an OTLP collector.
+#[cfg(feature = "otlp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "otlp")))]
+pub mod otlp;
+
+/// StatsD (DogStatsD) push exporter.
+#[cfg(feature = "statsd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "statsd")))]
+pub mod statsd;
+
+/// Helpers for asserting on metric values in tests.
+#[cfg(any(test, feature = "test-util"))]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+pub mod testing;
+
+/// Helpers for building the resource metrics are reported with.
+pub mod resource;
+
+/// Views to rename metrics, limit their attributes or change their
+/// aggregation.
+#[cfg(feature = "views")]
+#[cfg_attr(docsrs, doc(cfg(feature = "views")))]
+pub mod view;
+
+/// The metric units known to the [`metrics`] macro and the exporters.
+pub mod units;
+
 #[doc(hidden)]
 pub mod value;
 
@@ -86,6 +117,66 @@ mod tests {
         });
     }
 
+    #[test]
+    fn metrics_unit_alias() {
+        let output = postcompile::compile! {
+            #[scuffle_metrics::metrics]
+            mod example {
+                use scuffle_metrics::HistogramU64;
+
+                #[metrics(unit = "ms")]
+                pub fn latency() -> HistogramU64;
+            }
+        };
+
+        assert_eq!(output.status, postcompile::ExitStatus::Success, "{output}");
+        assert!(output.stdout.contains(r#"with_unit("ms")"#), "{output}");
+        assert!(!output.stderr.contains("unrecognized metric unit"), "{output}");
+    }
+
+    #[test]
+    fn metrics_unit_binary() {
+        let output = postcompile::compile! {
+            #[scuffle_metrics::metrics]
+            mod example {
+                use scuffle_metrics::GaugeU64;
+
+                #[metrics(unit = "KiBy")]
+                pub fn kibibytes() -> GaugeU64;
+
+                #[metrics(unit = "MiBy")]
+                pub fn mebibytes() -> GaugeU64;
+
+                #[metrics(unit = "GiBy")]
+                pub fn gibibytes() -> GaugeU64;
+            }
+        };
+
+        assert_eq!(output.status, postcompile::ExitStatus::Success, "{output}");
+        assert!(output.stdout.contains(r#"with_unit("KiBy")"#), "{output}");
+        assert!(output.stdout.contains(r#"with_unit("MiBy")"#), "{output}");
+        assert!(output.stdout.contains(r#"with_unit("GiBy")"#), "{output}");
+        assert!(!output.stderr.contains("unrecognized metric unit"), "{output}");
+    }
+
+    #[test]
+    fn metrics_unit_unrecognized() {
+        let output = postcompile::compile! {
+            #[scuffle_metrics::metrics]
+            mod example {
+                use scuffle_metrics::CounterU64;
+
+                #[metrics(unit = "requsts")]
+                pub fn request() -> CounterU64;
+            }
+        };
+
+        assert_eq!(output.status, postcompile::ExitStatus::Success, "{output}");
+        assert!(output.stdout.contains(r#"with_unit("requsts")"#), "{output}");
+        assert!(output.stderr.contains("unrecognized metric unit"), "{output}");
+        assert!(output.stderr.contains(r#"unit = "requsts""#), "{output}");
+    }
+
     #[test]
     fn opentelemetry() {
         #[derive(Debug, Clone)]
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::metrics::{MetricResult, PeriodicReader, Temporality};
+
+/// Builder for an OTLP push exporter.
+///
+/// The built reader periodically pushes all metrics to an OTLP collector over
+/// HTTP (protobuf encoding). It implements the
+/// [`opentelemetry_sdk::metrics::reader::MetricReader`](https://docs.rs/opentelemetry_sdk/0.27.0/opentelemetry_sdk/metrics/reader/trait.MetricReader.html)
+/// trait and therefore can be passed to a
+/// [`opentelemetry_sdk::metrics::SdkMeterProvider`](https://docs.rs/opentelemetry_sdk/0.27.0/opentelemetry_sdk/metrics/struct.SdkMeterProvider.html).
+///
+/// The reader spawns its export task on the tokio runtime, so
+/// [`build`](OtlpExporterBuilder::build) must be called from within one.
+///
+/// Any option which is not set falls back to the standard
+/// `OTEL_EXPORTER_OTLP_*` environment variables and then to the OTLP defaults.
+#[derive(Debug, Default)]
+pub struct OtlpExporterBuilder {
+    endpoint: Option<String>,
+    headers: HashMap<String, String>,
+    timeout: Option<Duration>,
+    interval: Option<Duration>,
+    temporality: Temporality,
+}
+
+impl OtlpExporterBuilder {
+    /// Set the full url metrics are pushed to, e.g.
+    /// `http://localhost:4318/v1/metrics`.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Add a header which is sent with every export request.
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the maximum time a single export request may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set how often metrics are pushed to the collector.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Set the reader temporality.
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Build the OTLP reader.
+    pub fn build(self) -> MetricResult<PeriodicReader> {
+        let mut exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_temporality(self.temporality);
+
+        if let Some(endpoint) = self.endpoint {
+            exporter = exporter.with_endpoint(endpoint);
+        }
+
+        if let Some(timeout) = self.timeout {
+            exporter = exporter.with_timeout(timeout);
+        }
+
+        if !self.headers.is_empty() {
+            exporter = exporter.with_headers(self.headers);
+        }
+
+        let mut reader = PeriodicReader::builder(exporter.build()?, opentelemetry_sdk::runtime::Tokio);
+
+        if let Some(interval) = self.interval {
+            reader = reader.with_interval(interval);
+        }
+
+        Ok(reader.build())
+    }
+}
+
+/// Returns a new [`OtlpExporterBuilder`] to configure an OTLP push exporter.
+pub fn exporter() -> OtlpExporterBuilder {
+    OtlpExporterBuilder::default()
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Accepts a single OTLP/HTTP export request and returns the raw request.
+    async fn mock_collector(listener: tokio::net::TcpListener) -> Vec<u8> {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n != 0, "connection closed before the request was complete");
+            request.extend_from_slice(&buf[..n]);
+
+            let Some(header_end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+                continue;
+            };
+
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+            let content_length = headers
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map(|len| len.trim().parse::<usize>().unwrap())
+                .unwrap_or_default();
+
+            if request.len() >= header_end + 4 + content_length {
+                break;
+            }
+        }
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        request
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn otlp_export() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let collector = tokio::spawn(mock_collector(listener));
+
+        let reader = super::exporter()
+            .with_endpoint(format!("http://{addr}/v1/metrics"))
+            .with_header("x-test", "scuffle")
+            .build()
+            .unwrap();
+
+        let provider = SdkMeterProvider::builder().with_reader(reader).build();
+        let counter = provider.meter("test").u64_counter("otlp_test_counter").build();
+        counter.add(7, &[]);
+
+        // The periodic reader blocks on flush, so it has to run outside the async context.
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let request = collector.await.unwrap();
+        let request_str = String::from_utf8_lossy(&request);
+
+        assert!(request_str.starts_with("POST /v1/metrics HTTP/1.1\r\n"), "{request_str}");
+        assert!(request_str.to_lowercase().contains("x-test: scuffle"), "{request_str}");
+        assert!(
+            request_str.to_lowercase().contains("content-type: application/x-protobuf"),
+            "{request_str}"
+        );
+        assert!(request_str.contains("otlp_test_counter"), "{request_str}");
+    }
+}
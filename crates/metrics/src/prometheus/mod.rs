@@ -10,6 +10,8 @@ use prometheus_client::encoding::{EncodeCounterValue, EncodeGaugeValue};
 use prometheus_client::metrics::MetricType;
 use prometheus_client::registry::Unit;
 
+mod text;
+
 /// A Prometheus exporter for OpenTelemetry metrics.
 ///
 /// Responsible for encoding OpenTelemetry metrics into Prometheus format.
@@ -23,10 +25,20 @@ use prometheus_client::registry::Unit;
 /// that can be registered with a
 /// [`prometheus_client::registry::Registry`](https://docs.rs/prometheus-client/0.22.3/prometheus_client/registry/struct.Registry.html)
 /// to provide metrics to Prometheus.
+///
+/// The unit of a metric is added as a suffix to its name, a histogram with
+/// the unit `ms` is exported as `<name>_ms`. Annotations such as `{request}`
+/// are dropped, see
+/// [`with_full_unit_names`](PrometheusExporterBuilder::with_full_unit_names)
+/// to export UCUM units by their full name instead.
 #[derive(Debug, Clone)]
 pub struct PrometheusExporter {
     reader: Arc<ManualReader>,
-    prometheus_full_utf8: bool,
+    escaping: NameEscaping,
+    /// See [`PrometheusExporterBuilder::with_histogram_quantiles`].
+    quantiles: Arc<[f64]>,
+    /// See [`PrometheusExporterBuilder::with_full_unit_names`].
+    full_unit_names: bool,
 }
 
 impl PrometheusExporter {
@@ -37,9 +49,42 @@ impl PrometheusExporter {
 
     /// Returns a [`prometheus_client::collector::Collector`] that can be registered
     /// with a [`prometheus_client::registry::Registry`] to provide metrics to Prometheus.
+    ///
+    /// The text encoder of `prometheus_client` cannot quote names, so the
+    /// collector always uses [`NameEscaping::Legacy`]. Use
+    /// [`encode_text`](Self::encode_text) for UTF-8 names.
     pub fn collector(&self) -> Box<dyn prometheus_client::collector::Collector> {
         Box::new(self.clone())
     }
+
+    /// Collects all current metrics into an owned [`ResourceMetrics`].
+    ///
+    /// This is useful for push-based exporters, where you want to drive the
+    /// collection yourself (e.g. on an interval) rather than waiting for
+    /// Prometheus to scrape the [`collector`](PrometheusExporter::collector).
+    pub fn snapshot(&self) -> opentelemetry_sdk::metrics::MetricResult<ResourceMetrics> {
+        let mut metrics = ResourceMetrics {
+            resource: Resource::empty(),
+            scope_metrics: vec![],
+        };
+
+        self.reader.collect(&mut metrics)?;
+
+        Ok(metrics)
+    }
+
+    /// Returns the unit suffix of a metric, `None` for metrics without a unit
+    /// and annotations (e.g. `{request}`) which are dropped as required by the
+    /// OpenTelemetry Prometheus compatibility specification.
+    fn unit<'a>(&self, unit: &'a str) -> Option<&'a str> {
+        if unit.is_empty() || crate::units::is_annotation(unit) {
+            None
+        } else if self.full_unit_names {
+            Some(crate::units::full_name(unit).unwrap_or(unit))
+        } else {
+            Some(unit)
+        }
+    }
 }
 
 impl MetricReader for PrometheusExporter {
@@ -67,11 +112,49 @@ impl MetricReader for PrometheusExporter {
     }
 }
 
+/// How metric names and label keys are escaped by the [`PrometheusExporter`].
+///
+/// The same policy is applied to metric names (including the unit suffix
+/// appended to them) and to label keys, so the `# HELP`, `# TYPE` and `# UNIT`
+/// lines always refer to the same name as the samples.
+///
+/// Only [`PrometheusExporter::encode_text`] supports [`NameEscaping::Utf8`],
+/// the [`collector`](PrometheusExporter::collector) always escapes names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameEscaping {
+    /// Replace every character which is not valid in the classic Prometheus
+    /// format (`[a-zA-Z0-9_:]`) with `_`, and prefix names starting with a
+    /// digit with `_`.
+    ///
+    /// For example the metric `http.requests` is exported as `http_requests`.
+    #[default]
+    Legacy,
+    /// Keep names and label keys as they are.
+    ///
+    /// Names and label keys which are not valid in the classic format are
+    /// quoted as described by the UTF-8 exposition format, the metric
+    /// `http.requests` is exported as `{"http.requests_total"}`. Only use this
+    /// if the scraper supports UTF-8 names, such metrics are otherwise
+    /// rejected.
+    Utf8,
+}
+
+impl NameEscaping {
+    fn escape(self, name: &str) -> Cow<'_, str> {
+        match self {
+            NameEscaping::Legacy => escape_key(name),
+            NameEscaping::Utf8 => Cow::Borrowed(name),
+        }
+    }
+}
+
 /// Builder for [`PrometheusExporter`].
 #[derive(Default)]
 pub struct PrometheusExporterBuilder {
     reader: ManualReaderBuilder,
-    prometheus_full_utf8: bool,
+    escaping: NameEscaping,
+    quantiles: Vec<f64>,
+    full_unit_names: bool,
 }
 
 impl PrometheusExporterBuilder {
@@ -81,12 +164,75 @@ impl PrometheusExporterBuilder {
         self
     }
 
-    /// Allow full UTF-8 labels in Prometheus.
+    /// Allow full UTF-8 names and labels in Prometheus.
     ///
     /// This is disabled by default however if you are using a newer version of
-    /// Prometheus that supports full UTF-8 labels you may enable this feature.
-    pub fn with_prometheus_full_utf8(mut self, prometheus_full_utf8: bool) -> Self {
-        self.prometheus_full_utf8 = prometheus_full_utf8;
+    /// Prometheus that supports full UTF-8 names you may enable this feature.
+    ///
+    /// This is the same as setting [`NameEscaping::Utf8`] (or
+    /// [`NameEscaping::Legacy`] when `false`) with
+    /// [`with_name_escaping`](Self::with_name_escaping).
+    pub fn with_prometheus_full_utf8(self, prometheus_full_utf8: bool) -> Self {
+        self.with_name_escaping(if prometheus_full_utf8 {
+            NameEscaping::Utf8
+        } else {
+            NameEscaping::Legacy
+        })
+    }
+
+    /// Set how metric names and label keys are escaped, defaults to
+    /// [`NameEscaping::Legacy`].
+    pub fn with_name_escaping(mut self, escaping: NameEscaping) -> Self {
+        self.escaping = escaping;
+        self
+    }
+
+    /// Export units by their full name instead of as written, e.g. a histogram
+    /// with the unit `ms` is exported as `<name>_milliseconds` rather than
+    /// `<name>_ms`. See [`units`](crate::units) for the known units, unknown
+    /// units are exported as written.
+    ///
+    /// This is disabled by default since it renames existing series, which
+    /// breaks dashboards and alerts querying them by their old name.
+    pub fn with_full_unit_names(mut self, full_unit_names: bool) -> Self {
+        self.full_unit_names = full_unit_names;
+        self
+    }
+
+    /// Also export the given quantiles (e.g. `[0.5, 0.9, 0.99]`) of every
+    /// histogram, for dashboards which expect summary-style quantiles.
+    ///
+    /// For a histogram `request_duration` the quantiles are exported as a
+    /// gauge named `request_duration_quantile` with a `quantile` label, next
+    /// to the regular histogram. The unit stays in front of the suffix, with
+    /// the unit `seconds` the gauge is named `request_duration_seconds_quantile`.
+    /// The Prometheus client does not support the summary metric type, so
+    /// this is the closest equivalent.
+    ///
+    /// The quantiles are approximations computed from the bucket counts, in
+    /// the same way as PromQL's `histogram_quantile`: the bucket containing the
+    /// quantile is found and the value is linearly interpolated between its
+    /// bounds, assuming the values are spread evenly within the bucket. This
+    /// means:
+    ///
+    /// - The error is bounded by the width of the bucket, so choose bucket
+    ///   boundaries which are narrow around the quantiles you care about.
+    /// - Quantiles falling into the first bucket are interpolated from the
+    ///   recorded minimum (or `0`), quantiles falling into the overflow bucket
+    ///   above the last boundary are interpolated up to the recorded maximum.
+    /// - With cumulative temporality (the default) the quantiles cover all
+    ///   values recorded since the start of the process rather than a recent
+    ///   window, unlike a classic Prometheus summary.
+    /// - Quantiles of a histogram without any recorded values are not exported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a quantile is not within `0.0..=1.0`.
+    pub fn with_histogram_quantiles(mut self, quantiles: impl IntoIterator<Item = f64>) -> Self {
+        self.quantiles = quantiles
+            .into_iter()
+            .inspect(|quantile| assert!((0.0..=1.0).contains(quantile), "quantile {quantile} is not within 0..=1"))
+            .collect();
         self
     }
 
@@ -94,7 +240,9 @@ impl PrometheusExporterBuilder {
     pub fn build(self) -> PrometheusExporter {
         PrometheusExporter {
             reader: Arc::new(self.reader.build()),
-            prometheus_full_utf8: self.prometheus_full_utf8,
+            escaping: self.escaping,
+            quantiles: self.quantiles.into(),
+            full_unit_names: self.full_unit_names,
         }
     }
 }
@@ -197,6 +345,30 @@ impl EncodeCounterValue for RawNumber {
     }
 }
 
+impl std::fmt::Display for RawNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            RawNumber::U64(value) => value.fmt(f),
+            RawNumber::I64(value) => value.fmt(f),
+            RawNumber::F64(value) => f.write_str(&text::format_float(value)),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::U32(value) => value.fmt(f),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::U16(value) => value.fmt(f),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::U8(value) => value.fmt(f),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::I32(value) => value.fmt(f),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::I16(value) => value.fmt(f),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::I8(value) => value.fmt(f),
+            #[cfg(feature = "extended-numbers")]
+            RawNumber::F32(value) => f.write_str(&text::format_float(value as f64)),
+        }
+    }
+}
+
 macro_rules! impl_raw_number {
     ($t:ty, $variant:ident) => {
         impl From<$t> for RawNumber {
@@ -258,6 +430,113 @@ where
         }
     }
 
+    fn is_histogram(&self) -> bool {
+        matches!(self, KnownMetricT::Histogram(_))
+    }
+
+    /// Encodes the quantiles of each data point of a histogram, see
+    /// [`PrometheusExporterBuilder::with_histogram_quantiles`].
+    fn encode_quantiles(
+        &self,
+        mut encoder: prometheus_client::encoding::MetricEncoder,
+        labels: KeyValueEncoder<'a>,
+        quantiles: &[f64],
+    ) -> Result<(), std::fmt::Error> {
+        let KnownMetricT::Histogram(histogram) = self else {
+            return Ok(());
+        };
+
+        for data_point in &histogram.data_points {
+            let min = data_point.min.map(|min| RawNumber::from(min).as_f64());
+            let max = data_point.max.map(|max| RawNumber::from(max).as_f64());
+
+            for &quantile in quantiles {
+                let Some(value) = bucket_quantile(quantile, &data_point.bounds, &data_point.bucket_counts, min, max) else {
+                    continue;
+                };
+
+                encoder
+                    .encode_family(&labels.with_attrs(Some(&data_point.attributes)).with_quantile(quantile))?
+                    .encode_gauge(&value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`encode_quantiles`](Self::encode_quantiles) but for
+    /// [`PrometheusExporter::encode_text`].
+    fn encode_text_quantiles(
+        &self,
+        encoder: &mut text::TextEncoder,
+        name: &str,
+        labels: KeyValueEncoder<'a>,
+        quantiles: &[f64],
+    ) -> Result<(), std::fmt::Error> {
+        let KnownMetricT::Histogram(histogram) = self else {
+            return Ok(());
+        };
+
+        for data_point in &histogram.data_points {
+            let min = data_point.min.map(|min| RawNumber::from(min).as_f64());
+            let max = data_point.max.map(|max| RawNumber::from(max).as_f64());
+
+            for &quantile in quantiles {
+                let Some(value) = bucket_quantile(quantile, &data_point.bounds, &data_point.bucket_counts, min, max) else {
+                    continue;
+                };
+
+                let labels = labels.with_attrs(Some(&data_point.attributes)).with_quantile(quantile);
+                encoder.sample(name, "", &labels, None, text::format_float(value))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Like [`encode`](Self::encode) but for
+    /// [`PrometheusExporter::encode_text`].
+    fn encode_text(
+        &self,
+        encoder: &mut text::TextEncoder,
+        name: &str,
+        labels: KeyValueEncoder<'a>,
+    ) -> Result<(), std::fmt::Error> {
+        match self {
+            KnownMetricT::Gauge(gauge) => {
+                for data_point in &gauge.data_points {
+                    let labels = labels.with_attrs(Some(&data_point.attributes));
+                    encoder.sample(name, "", &labels, None, RawNumber::from(data_point.value))?;
+                }
+            }
+            KnownMetricT::Sum(sum) => {
+                let suffix = if sum.is_monotonic { "_total" } else { "" };
+                for data_point in &sum.data_points {
+                    let labels = labels.with_attrs(Some(&data_point.attributes));
+                    encoder.sample(name, suffix, &labels, None, RawNumber::from(data_point.value))?;
+                }
+            }
+            KnownMetricT::Histogram(histogram) => {
+                for data_point in &histogram.data_points {
+                    let labels = labels.with_attrs(Some(&data_point.attributes));
+
+                    let mut cumulative = 0;
+                    for (bound, count) in data_point.bounds.iter().zip(&data_point.bucket_counts) {
+                        cumulative += count;
+                        let le = text::format_float(*bound);
+                        encoder.sample(name, "_bucket", &labels, Some(("le", &le)), cumulative)?;
+                    }
+
+                    encoder.sample(name, "_bucket", &labels, Some(("le", "+Inf")), data_point.count)?;
+                    encoder.sample(name, "_sum", &labels, None, RawNumber::from(data_point.sum))?;
+                    encoder.sample(name, "_count", &labels, None, data_point.count)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     fn encode(
         &self,
         mut encoder: prometheus_client::encoding::MetricEncoder,
@@ -329,6 +608,50 @@ enum KnownMetric<'a> {
     F32(KnownMetricT<'a, f32>),
 }
 
+/// Estimates the quantile `q` of a histogram by linear interpolation within
+/// the bucket containing it, like PromQL's `histogram_quantile`.
+///
+/// `bucket_counts` has one more entry than `bounds`, for the values above the
+/// last bound. The recorded `min` and `max` narrow the first and last buckets,
+/// which otherwise have no lower or upper bound. Returns `None` if the
+/// histogram is empty.
+fn bucket_quantile(q: f64, bounds: &[f64], bucket_counts: &[u64], min: Option<f64>, max: Option<f64>) -> Option<f64> {
+    let count: u64 = bucket_counts.iter().sum();
+    if count == 0 {
+        return None;
+    }
+
+    let rank = q * count as f64;
+    let mut cumulative = 0;
+    for (i, &bucket_count) in bucket_counts.iter().enumerate() {
+        let below = cumulative;
+        cumulative += bucket_count;
+        if bucket_count == 0 || (cumulative as f64) < rank {
+            continue;
+        }
+
+        let upper = match (bounds.get(i), max) {
+            (Some(&bound), Some(max)) => bound.min(max),
+            (Some(&bound), None) => bound,
+            // The overflow bucket, without a recorded maximum the best guess is
+            // the last bound.
+            (None, max) => max.or(bounds.last().copied())?,
+        };
+
+        let lower = match (i.checked_sub(1).and_then(|i| bounds.get(i)), min) {
+            (Some(&bound), Some(min)) => bound.max(min),
+            (Some(&bound), None) => bound,
+            (None, Some(min)) => min,
+            (None, None) => upper.min(0.0),
+        };
+
+        let fraction = (rank - below as f64) / bucket_count as f64;
+        return Some(lower + (upper - lower) * fraction.clamp(0.0, 1.0));
+    }
+
+    max
+}
+
 impl<'a> KnownMetric<'a> {
     fn from_any(any: &'a dyn std::any::Any) -> Option<Self> {
         macro_rules! try_decode {
@@ -407,21 +730,123 @@ impl<'a> KnownMetric<'a> {
             KnownMetric::F32(metric) => metric.encode(encoder, labels),
         }
     }
+
+    fn is_histogram(&self) -> bool {
+        match self {
+            KnownMetric::U64(metric) => metric.is_histogram(),
+            KnownMetric::I64(metric) => metric.is_histogram(),
+            KnownMetric::F64(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U32(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I32(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U16(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I16(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U8(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I8(metric) => metric.is_histogram(),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::F32(metric) => metric.is_histogram(),
+        }
+    }
+
+    fn encode_quantiles(
+        &self,
+        encoder: prometheus_client::encoding::MetricEncoder,
+        labels: KeyValueEncoder<'a>,
+        quantiles: &[f64],
+    ) -> Result<(), std::fmt::Error> {
+        match self {
+            KnownMetric::U64(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            KnownMetric::I64(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            KnownMetric::F64(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U32(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I32(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U16(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I16(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U8(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I8(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::F32(metric) => metric.encode_quantiles(encoder, labels, quantiles),
+        }
+    }
+
+    fn encode_text(
+        &self,
+        encoder: &mut text::TextEncoder,
+        name: &str,
+        labels: KeyValueEncoder<'a>,
+    ) -> Result<(), std::fmt::Error> {
+        match self {
+            KnownMetric::U64(metric) => metric.encode_text(encoder, name, labels),
+            KnownMetric::I64(metric) => metric.encode_text(encoder, name, labels),
+            KnownMetric::F64(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U32(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I32(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U16(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I16(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U8(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I8(metric) => metric.encode_text(encoder, name, labels),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::F32(metric) => metric.encode_text(encoder, name, labels),
+        }
+    }
+
+    fn encode_text_quantiles(
+        &self,
+        encoder: &mut text::TextEncoder,
+        name: &str,
+        labels: KeyValueEncoder<'a>,
+        quantiles: &[f64],
+    ) -> Result<(), std::fmt::Error> {
+        match self {
+            KnownMetric::U64(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            KnownMetric::I64(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            KnownMetric::F64(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U32(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I32(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U16(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I16(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::U8(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::I8(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+            #[cfg(feature = "extended-numbers")]
+            KnownMetric::F32(metric) => metric.encode_text_quantiles(encoder, name, labels, quantiles),
+        }
+    }
 }
 
 impl prometheus_client::collector::Collector for PrometheusExporter {
     fn encode(&self, mut encoder: prometheus_client::encoding::DescriptorEncoder) -> Result<(), std::fmt::Error> {
-        let mut metrics = ResourceMetrics {
-            resource: Resource::empty(),
-            scope_metrics: vec![],
+        let metrics = match self.snapshot() {
+            Ok(metrics) => metrics,
+            Err(err) => {
+                otel_error!(name: "prometheus_collector_collect_error", error = err.to_string());
+                return Err(std::fmt::Error);
+            }
         };
 
-        if let Err(err) = self.reader.collect(&mut metrics) {
-            otel_error!(name: "prometheus_collector_collect_error", error = err.to_string());
-            return Err(std::fmt::Error);
-        }
-
-        let labels = KeyValueEncoder::new(self.prometheus_full_utf8);
+        let labels = KeyValueEncoder::new();
 
         encoder
             .encode_descriptor("target", "Information about the target", None, MetricType::Info)?
@@ -434,21 +859,29 @@ impl prometheus_client::collector::Collector for PrometheusExporter {
                     continue;
                 };
 
-                let unit = if metric.unit.is_empty() {
-                    None
-                } else {
-                    Some(Unit::Other(metric.unit.to_string()))
-                };
+                let unit = self.unit(&metric.unit).map(|unit| Unit::Other(escape_key(unit).into_owned()));
+
+                let name = escape_key(&metric.name);
 
                 known_metric.encode(
-                    encoder.encode_descriptor(
-                        &metric.name,
-                        &metric.description,
-                        unit.as_ref(),
-                        known_metric.metric_type(),
-                    )?,
+                    encoder.encode_descriptor(&name, &metric.description, unit.as_ref(), known_metric.metric_type())?,
                     labels.with_scope(Some(&scope_metrics.scope)),
                 )?;
+
+                if !self.quantiles.is_empty() && known_metric.is_histogram() {
+                    // The unit is already part of the name, repeating it as the
+                    // unit of the family would append it a second time.
+                    let name = match &unit {
+                        Some(unit) => format!("{name}_{}_quantile", unit.as_str()),
+                        None => format!("{name}_quantile"),
+                    };
+
+                    known_metric.encode_quantiles(
+                        encoder.encode_descriptor(&name, &metric.description, None, MetricType::Gauge)?,
+                        labels.with_scope(Some(&scope_metrics.scope)),
+                        &self.quantiles,
+                    )?;
+                }
             }
         }
 
@@ -472,19 +905,36 @@ struct KeyValueEncoder<'a> {
     resource: Option<&'a Resource>,
     scope: Option<&'a InstrumentationScope>,
     attrs: Option<&'a [KeyValue]>,
-    prometheus_full_utf8: bool,
+    quantile: Option<f64>,
 }
 
 impl<'a> KeyValueEncoder<'a> {
-    fn new(prometheus_full_utf8: bool) -> Self {
+    fn new() -> Self {
         Self {
             resource: None,
             scope: None,
             attrs: None,
-            prometheus_full_utf8,
+            quantile: None,
         }
     }
 
+    /// Returns every label, with the keys as they are.
+    fn labels(&self) -> impl Iterator<Item = (&'a str, Cow<'a, str>)> {
+        let resource = self
+            .resource
+            .into_iter()
+            .flat_map(|resource| resource.iter().map(|(key, value)| (key.as_str(), value.as_str())));
+        let scope = self.scope.into_iter().flat_map(scope_to_iter);
+        let attrs = self
+            .attrs
+            .into_iter()
+            .flatten()
+            .map(|kv| (kv.key.as_str(), kv.value.as_str()));
+        let quantile = self.quantile.map(|quantile| ("quantile", Cow::Owned(quantile.to_string())));
+
+        resource.chain(scope).chain(attrs).chain(quantile)
+    }
+
     pub fn with_resource(self, resource: Option<&'a Resource>) -> Self {
         Self { resource, ..self }
     }
@@ -496,6 +946,13 @@ impl<'a> KeyValueEncoder<'a> {
     pub fn with_attrs(self, attrs: Option<&'a [KeyValue]>) -> Self {
         Self { attrs, ..self }
     }
+
+    pub fn with_quantile(self, quantile: f64) -> Self {
+        Self {
+            quantile: Some(quantile),
+            ..self
+        }
+    }
 }
 
 fn escape_key(s: &str) -> Cow<'_, str> {
@@ -537,51 +994,288 @@ impl prometheus_client::encoding::EncodeLabelSet for KeyValueEncoder<'_> {
     fn encode(&self, mut encoder: prometheus_client::encoding::LabelSetEncoder) -> Result<(), std::fmt::Error> {
         use std::fmt::Write;
 
-        fn write_kv(
-            encoder: &mut prometheus_client::encoding::LabelSetEncoder,
-            key: &str,
-            value: &str,
-            prometheus_full_utf8: bool,
-        ) -> Result<(), std::fmt::Error> {
+        // `prometheus_client` cannot quote label keys, so they are always
+        // escaped. See: https://github.com/prometheus/client_rust/issues/251
+        for (key, value) in self.labels() {
             let mut label = encoder.encode_label();
             let mut key_encoder = label.encode_label_key()?;
-            if prometheus_full_utf8 {
-                // TODO(troy): I am not sure if this is correct.
-                // See: https://github.com/prometheus/client_rust/issues/251
-                write!(&mut key_encoder, "{}", key)?;
-            } else {
-                write!(&mut key_encoder, "{}", escape_key(key))?;
-            }
+            write!(&mut key_encoder, "{}", escape_key(key))?;
 
             let mut value_encoder = key_encoder.encode_label_value()?;
             write!(&mut value_encoder, "{}", value)?;
 
-            value_encoder.finish()
+            value_encoder.finish()?;
         }
 
-        if let Some(resource) = self.resource {
-            for (key, value) in resource.iter() {
-                write_kv(&mut encoder, key.as_str(), value.as_str().as_ref(), self.prometheus_full_utf8)?;
-            }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::data::Sum;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+
+    use super::{bucket_quantile, exporter, NameEscaping};
+
+    #[test]
+    fn snapshot() {
+        let exporter = exporter().build();
+        let provider = SdkMeterProvider::builder().with_reader(exporter.clone()).build();
+
+        let counter = provider.meter("test").u64_counter("requests").build();
+        counter.add(3, &[]);
+
+        let snapshot = exporter.snapshot().expect("snapshot");
+        let metric = snapshot
+            .scope_metrics
+            .iter()
+            .flat_map(|scope| &scope.metrics)
+            .find(|metric| metric.name == "requests")
+            .expect("requests metric");
+
+        let sum = metric.data.as_any().downcast_ref::<Sum<u64>>().expect("u64 sum");
+        assert_eq!(sum.data_points.len(), 1);
+        assert_eq!(sum.data_points[0].value, 3);
+    }
+
+    #[test]
+    fn name_escaping() {
+        let encode = |escaping| {
+            let exporter = exporter().with_name_escaping(escaping).build();
+            let provider = SdkMeterProvider::builder().with_reader(exporter.clone()).build();
+
+            let counter = provider
+                .meter("test")
+                .u64_counter("http.requests")
+                .with_unit("requests")
+                .build();
+            counter.add(1, &[opentelemetry::KeyValue::new("http.method", "GET")]);
+
+            let mut registry = prometheus_client::registry::Registry::default();
+            registry.register_collector(exporter.collector());
+
+            let mut collector = String::new();
+            prometheus_client::encoding::text::encode(&mut collector, &registry).expect("encode");
+
+            let mut text = String::new();
+            exporter.encode_text(&mut text).expect("encode text");
+
+            (collector, text)
+        };
+
+        let (collector, legacy) = encode(NameEscaping::Legacy);
+        for legacy in [&collector, &legacy] {
+            assert!(legacy.contains("# HELP http_requests_requests \n"), "{legacy}");
+            assert!(legacy.contains("# TYPE http_requests_requests counter\n"), "{legacy}");
+            assert!(legacy.contains("# UNIT http_requests_requests requests\n"), "{legacy}");
+            assert!(legacy.contains("\nhttp_requests_requests_total{"), "{legacy}");
+            assert!(legacy.contains("http_method=\"GET\""), "{legacy}");
+            assert!(!legacy.contains("http.requests"), "{legacy}");
+            assert!(legacy.ends_with("# EOF\n"), "{legacy}");
+        }
+
+        // The collector cannot quote names and always escapes them.
+        let (utf8_collector, utf8) = encode(NameEscaping::Utf8);
+        assert!(utf8_collector.contains("\nhttp_requests_requests_total{"), "{utf8_collector}");
+        assert!(!utf8_collector.contains("http.requests"), "{utf8_collector}");
+
+        assert!(utf8.contains("# HELP \"http.requests_requests\" \n"), "{utf8}");
+        assert!(utf8.contains("# TYPE \"http.requests_requests\" counter\n"), "{utf8}");
+        assert!(utf8.contains("# UNIT \"http.requests_requests\" requests\n"), "{utf8}");
+        assert!(utf8.contains("\n{\"http.requests_requests_total\","), "{utf8}");
+        assert!(utf8.contains(",\"http.method\"=\"GET\"} 1\n"), "{utf8}");
+        assert!(utf8.contains("\"otel.scope.name\"=\"test\""), "{utf8}");
+        assert!(utf8.ends_with("# EOF\n"), "{utf8}");
+
+        assert_eq!(
+            exporter().with_prometheus_full_utf8(true).build().escaping,
+            NameEscaping::Utf8
+        );
+    }
+
+    #[test]
+    fn encode_text_histogram() {
+        let exporter = exporter().with_histogram_quantiles([0.5]).build();
+        let provider = SdkMeterProvider::builder().with_reader(exporter.clone()).build();
+
+        let histogram = provider
+            .meter("test")
+            .f64_histogram("latency")
+            .with_unit("seconds")
+            .with_boundaries(vec![0.5, 1.0])
+            .build();
+        for value in [0.25, 0.75, 2.0] {
+            histogram.record(value, &[]);
         }
 
-        if let Some(scope) = self.scope {
-            for (key, value) in scope_to_iter(scope) {
-                write_kv(&mut encoder, key, value.as_ref(), self.prometheus_full_utf8)?;
+        let mut text = String::new();
+        exporter.encode_text(&mut text).expect("encode text");
+
+        assert!(text.contains("# TYPE latency_seconds histogram\n"), "{text}");
+        assert!(
+            text.contains("\nlatency_seconds_bucket{otel_scope_name=\"test\",le=\"0.5\"} 1\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("\nlatency_seconds_bucket{otel_scope_name=\"test\",le=\"1.0\"} 2\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("\nlatency_seconds_bucket{otel_scope_name=\"test\",le=\"+Inf\"} 3\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("\nlatency_seconds_sum{otel_scope_name=\"test\"} 3.0\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("\nlatency_seconds_count{otel_scope_name=\"test\"} 3\n"),
+            "{text}"
+        );
+        assert!(text.contains("# TYPE latency_seconds_quantile gauge\n"), "{text}");
+        assert!(
+            text.contains("\nlatency_seconds_quantile{otel_scope_name=\"test\",quantile=\"0.5\"} 0.75\n"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn unit_suffix() {
+        let encode = |full_unit_names, unit| {
+            let exporter = exporter().with_full_unit_names(full_unit_names).build();
+            let provider = SdkMeterProvider::builder().with_reader(exporter.clone()).build();
+            provider
+                .meter("test")
+                .u64_histogram("latency")
+                .with_unit(unit)
+                .build()
+                .record(1, &[]);
+
+            let mut registry = prometheus_client::registry::Registry::default();
+            registry.register_collector(exporter.collector());
+
+            let mut collector = String::new();
+            prometheus_client::encoding::text::encode(&mut collector, &registry).expect("encode");
+
+            let mut text = String::new();
+            exporter.encode_text(&mut text).expect("encode text");
+
+            (collector, text)
+        };
+
+        for (full_unit_names, unit, expected) in [
+            (false, "ms", Some("ms")),
+            (false, "furlongs", Some("furlongs")),
+            (true, "ms", Some("milliseconds")),
+            (true, "By", Some("bytes")),
+            (true, "KiBy", Some("kibibytes")),
+            (true, "1", Some("ratio")),
+            (true, "request", Some("requests")),
+            (true, "furlongs", Some("furlongs")),
+            (false, "{request}", None),
+            (true, "{request}", None),
+        ] {
+            let (collector, text) = encode(full_unit_names, unit);
+            for output in [collector, text] {
+                match expected {
+                    Some(expected) => {
+                        assert!(output.contains(&format!("# TYPE latency_{expected} histogram\n")), "{output}");
+                        assert!(
+                            output.contains(&format!("# UNIT latency_{expected} {expected}\n")),
+                            "{output}"
+                        );
+                    }
+                    None => {
+                        assert!(output.contains("# TYPE latency histogram\n"), "{output}");
+                        assert!(!output.contains("# UNIT"), "{output}");
+                    }
+                }
             }
         }
+    }
 
-        if let Some(attrs) = self.attrs {
-            for kv in attrs {
-                write_kv(
-                    &mut encoder,
-                    kv.key.as_str(),
-                    kv.value.as_str().as_ref(),
-                    self.prometheus_full_utf8,
-                )?;
+    #[test]
+    fn histogram_quantiles() {
+        let encode = |quantiles: &[f64]| {
+            let exporter = exporter().with_histogram_quantiles(quantiles.iter().copied()).build();
+            let provider = SdkMeterProvider::builder().with_reader(exporter.clone()).build();
+
+            let histogram = provider
+                .meter("test")
+                .u64_histogram("latency")
+                .with_unit("seconds")
+                .with_boundaries((1..=10).map(|i| i as f64 * 10.0).collect())
+                .build();
+            for value in 1..=100 {
+                histogram.record(value, &[]);
             }
+
+            let mut registry = prometheus_client::registry::Registry::default();
+            registry.register_collector(exporter.collector());
+
+            let mut output = String::new();
+            prometheus_client::encoding::text::encode(&mut output, &registry).expect("encode");
+            output
+        };
+
+        let output = encode(&[0.5, 0.9, 0.99]);
+        assert!(output.contains("# TYPE latency_seconds histogram\n"), "{output}");
+        assert!(output.contains("# TYPE latency_seconds_quantile gauge\n"), "{output}");
+        assert!(!output.contains("# UNIT latency_seconds_quantile"), "{output}");
+        assert!(!output.contains("latency_quantile"), "{output}");
+
+        let quantile = |q: &str| -> f64 {
+            let label = format!("quantile=\"{q}\"}} ");
+            let line = output
+                .lines()
+                .find(|line| line.starts_with("latency_seconds_quantile{") && line.contains(&label))
+                .unwrap_or_else(|| panic!("missing quantile {q}: {output}"));
+            line.rsplit(' ').next().unwrap().parse().unwrap()
+        };
+
+        // Each quantile lies within the bucket holding it, and with evenly spread
+        // values the interpolation is exact.
+        for (q, lower, upper, expected) in [
+            ("0.5", 40.0, 50.0, 50.0),
+            ("0.9", 80.0, 90.0, 90.0),
+            ("0.99", 90.0, 100.0, 99.0),
+        ] {
+            let value = quantile(q);
+            assert!((lower..=upper).contains(&value), "p{q} = {value}");
+            assert!((value - expected).abs() < 1e-9, "p{q} = {value}");
         }
 
-        Ok(())
+        // Without the option only the histogram is exported.
+        let output = encode(&[]);
+        assert!(output.contains("# TYPE latency_seconds histogram\n"), "{output}");
+        assert!(!output.contains("quantile"), "{output}");
+    }
+
+    #[test]
+    fn bucket_quantile_edges() {
+        let bounds = [10.0, 20.0];
+
+        assert_eq!(bucket_quantile(0.5, &bounds, &[0, 0, 0], None, None), None);
+
+        // The first bucket is interpolated from the minimum, or from zero.
+        assert_eq!(bucket_quantile(0.5, &bounds, &[2, 0, 0], Some(4.0), Some(8.0)), Some(6.0));
+        assert_eq!(bucket_quantile(0.5, &bounds, &[2, 0, 0], None, None), Some(5.0));
+
+        // The overflow bucket is interpolated up to the maximum, or stops at the
+        // last bound.
+        assert_eq!(bucket_quantile(1.0, &bounds, &[0, 0, 2], Some(30.0), Some(40.0)), Some(40.0));
+        assert_eq!(bucket_quantile(1.0, &bounds, &[0, 0, 2], None, None), Some(20.0));
+
+        // Empty buckets are skipped.
+        assert_eq!(bucket_quantile(0.0, &bounds, &[0, 4, 0], None, None), Some(10.0));
+    }
+
+    #[test]
+    #[should_panic = "quantile 1.5 is not within 0..=1"]
+    fn histogram_quantiles_invalid() {
+        let _ = exporter().with_histogram_quantiles([1.5]);
     }
 }
@@ -0,0 +1,211 @@
+//! The text format written by [`PrometheusExporter::encode_text`].
+//!
+//! `prometheus_client` always writes the metric name in front of the labels,
+//! which cannot express names that have to be quoted. The UTF-8 exposition
+//! format moves those names inside the braces, e.g.
+//! `{"http.requests_total","http.method"="GET"} 1`, so this writes the
+//! format itself.
+
+use std::fmt::{self, Write};
+
+use prometheus_client::metrics::MetricType;
+
+use super::{escape_key, KeyValueEncoder, NameEscaping, PrometheusExporter};
+
+/// Returns `true` if the name is valid in the classic Prometheus format and
+/// does not have to be quoted. Label keys cannot contain colons.
+fn is_legacy_name(name: &str, allow_colon: bool) -> bool {
+    !name.is_empty()
+        && name
+            .char_indices()
+            .all(|(i, c)| c.is_ascii_alphabetic() || c == '_' || (allow_colon && c == ':') || (i > 0 && c.is_ascii_digit()))
+}
+
+/// Writes a quoted string, escaping backslashes, double quotes and newlines.
+fn write_quoted(writer: &mut dyn Write, value: &str) -> fmt::Result {
+    writer.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '\\' => writer.write_str("\\\\")?,
+            '"' => writer.write_str("\\\"")?,
+            '\n' => writer.write_str("\\n")?,
+            c => writer.write_char(c)?,
+        }
+    }
+    writer.write_char('"')
+}
+
+/// Formats a float the way Prometheus parses it.
+pub(super) fn format_float(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_owned()
+    } else if value.is_infinite() {
+        if value.is_sign_positive() { "+Inf" } else { "-Inf" }.to_owned()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+pub(super) struct TextEncoder<'w> {
+    writer: &'w mut dyn Write,
+    escaping: NameEscaping,
+}
+
+impl<'w> TextEncoder<'w> {
+    pub(super) fn new(writer: &'w mut dyn Write, escaping: NameEscaping) -> Self {
+        Self { writer, escaping }
+    }
+
+    /// Returns the name of a metric family, which includes the unit.
+    pub(super) fn family_name(&self, name: &str, unit: Option<&str>) -> String {
+        let name = self.escaping.escape(name);
+        match unit {
+            Some(unit) => format!("{name}_{}", self.escaping.escape(unit)),
+            None => name.into_owned(),
+        }
+    }
+
+    /// Writes a metric name, quoted if it is not a legacy name.
+    fn write_name(&mut self, name: &str) -> fmt::Result {
+        if is_legacy_name(name, true) {
+            self.writer.write_str(name)
+        } else {
+            write_quoted(self.writer, name)
+        }
+    }
+
+    fn write_label(&mut self, key: &str, value: &str) -> fmt::Result {
+        let key = match self.escaping {
+            NameEscaping::Legacy => escape_key(key),
+            NameEscaping::Utf8 => key.into(),
+        };
+
+        if is_legacy_name(&key, false) {
+            self.writer.write_str(&key)?;
+        } else {
+            write_quoted(self.writer, &key)?;
+        }
+
+        self.writer.write_char('=')?;
+        write_quoted(self.writer, value)
+    }
+
+    /// Writes the `# HELP`, `# TYPE` and `# UNIT` lines of a metric family.
+    pub(super) fn descriptor(&mut self, name: &str, help: &str, unit: Option<&str>, metric_type: MetricType) -> fmt::Result {
+        self.writer.write_str("# HELP ")?;
+        self.write_name(name)?;
+        self.writer.write_char(' ')?;
+        self.writer.write_str(&help.replace('\\', "\\\\").replace('\n', "\\n"))?;
+        self.writer.write_char('\n')?;
+
+        self.writer.write_str("# TYPE ")?;
+        self.write_name(name)?;
+        writeln!(self.writer, " {}", metric_type.as_str())?;
+
+        if let Some(unit) = unit {
+            self.writer.write_str("# UNIT ")?;
+            self.write_name(name)?;
+            writeln!(self.writer, " {}", self.escaping.escape(unit))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a single sample of the family `name`, `extra` is an additional
+    /// label such as the `le` of histogram buckets.
+    pub(super) fn sample(
+        &mut self,
+        name: &str,
+        suffix: &str,
+        labels: &KeyValueEncoder<'_>,
+        extra: Option<(&str, &str)>,
+        value: impl fmt::Display,
+    ) -> fmt::Result {
+        let name = format!("{name}{suffix}");
+        let mut labels = labels
+            .labels()
+            .chain(extra.map(|(key, value)| (key, value.into())))
+            .peekable();
+
+        if is_legacy_name(&name, true) {
+            self.writer.write_str(&name)?;
+            if labels.peek().is_some() {
+                self.writer.write_char('{')?;
+                for (i, (key, value)) in labels.enumerate() {
+                    if i > 0 {
+                        self.writer.write_char(',')?;
+                    }
+                    self.write_label(key, &value)?;
+                }
+                self.writer.write_char('}')?;
+            }
+        } else {
+            self.writer.write_char('{')?;
+            write_quoted(self.writer, &name)?;
+            for (key, value) in labels {
+                self.writer.write_char(',')?;
+                self.write_label(key, &value)?;
+            }
+            self.writer.write_char('}')?;
+        }
+
+        writeln!(self.writer, " {value}")
+    }
+
+    pub(super) fn eof(&mut self) -> fmt::Result {
+        self.writer.write_str("# EOF\n")
+    }
+}
+
+impl PrometheusExporter {
+    /// Encodes all current metrics in the OpenMetrics text format.
+    ///
+    /// Unlike the [`collector`](Self::collector) this honours
+    /// [`NameEscaping::Utf8`]: names and label keys which are not valid in
+    /// the classic format are quoted, and quoted metric names are moved inside
+    /// the braces as described by the UTF-8 exposition format, e.g.
+    /// `{"http.requests_total","http.method"="GET"} 1`. Serve it with the
+    /// `escaping=allow-utf-8` content type parameter so that Prometheus does
+    /// not reject it.
+    ///
+    /// With [`NameEscaping::Legacy`] names and label keys are escaped in the
+    /// same way as by the [`collector`](Self::collector).
+    pub fn encode_text(&self, writer: &mut impl Write) -> fmt::Result {
+        let metrics = match self.snapshot() {
+            Ok(metrics) => metrics,
+            Err(err) => {
+                opentelemetry::otel_error!(name: "prometheus_text_collect_error", error = err.to_string());
+                return Err(fmt::Error);
+            }
+        };
+
+        let mut encoder = TextEncoder::new(writer, self.escaping);
+        let labels = KeyValueEncoder::new();
+
+        encoder.descriptor("target", "Information about the target", None, MetricType::Info)?;
+        encoder.sample("target", "_info", &labels.with_resource(Some(&metrics.resource)), None, 1)?;
+
+        for scope_metrics in &metrics.scope_metrics {
+            for metric in &scope_metrics.metrics {
+                let Some(known_metric) = super::KnownMetric::from_any(metric.data.as_any()) else {
+                    continue;
+                };
+
+                let unit = self.unit(&metric.unit);
+                let name = encoder.family_name(&metric.name, unit);
+                let labels = labels.with_scope(Some(&scope_metrics.scope));
+
+                encoder.descriptor(&name, &metric.description, unit, known_metric.metric_type())?;
+                known_metric.encode_text(&mut encoder, &name, labels)?;
+
+                if !self.quantiles.is_empty() && known_metric.is_histogram() {
+                    let name = format!("{name}_quantile");
+                    encoder.descriptor(&name, &metric.description, None, MetricType::Gauge)?;
+                    known_metric.encode_text_quantiles(&mut encoder, &name, labels, &self.quantiles)?;
+                }
+            }
+        }
+
+        encoder.eof()
+    }
+}
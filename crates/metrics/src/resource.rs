@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_sdk::resource::{
+    EnvResourceDetector, ResourceDetector, SdkProvidedResourceDetector, TelemetryResourceDetector,
+};
+use opentelemetry_sdk::Resource;
+
+const OTEL_SERVICE_NAME: &str = "OTEL_SERVICE_NAME";
+
+/// Builds the [`Resource`] to pass to
+/// [`SdkMeterProvider::builder().with_resource`](opentelemetry_sdk::metrics::MeterProviderBuilder::with_resource).
+///
+/// The resource contains, in order of increasing precedence:
+/// - the SDK provided attributes (`telemetry.sdk.*` and `service.name`, which
+///   respects `OTEL_SERVICE_NAME`),
+/// - the attributes from the `OTEL_RESOURCE_ATTRIBUTES` environment variable,
+///   see [`env_attributes`],
+/// - the given `attributes`.
+///
+/// This allows deployment tooling to inject attributes such as
+/// `service.namespace` or `deployment.environment` without any code changes.
+pub fn resource(attributes: impl IntoIterator<Item = KeyValue>) -> Resource {
+    let defaults = Resource::from_detectors(
+        Duration::ZERO,
+        vec![Box::new(SdkProvidedResourceDetector), Box::new(TelemetryResourceDetector)],
+    );
+
+    let mut resource = defaults.merge(&Resource::new(env_attributes()));
+
+    // `OTEL_SERVICE_NAME` takes precedence over a `service.name` set in
+    // `OTEL_RESOURCE_ATTRIBUTES`.
+    if let Some(service_name) = std::env::var(OTEL_SERVICE_NAME).ok().filter(|name| !name.is_empty()) {
+        resource = resource.merge(&Resource::new([KeyValue::new("service.name", service_name)]));
+    }
+
+    resource.merge(&Resource::new(attributes))
+}
+
+/// Returns the attributes from the `OTEL_RESOURCE_ATTRIBUTES` environment
+/// variable.
+///
+/// The variable is read by the SDK's [`EnvResourceDetector`], in addition the
+/// values are percent-decoded so that they can include characters such as `,`
+/// or `=`. Entries with an invalid encoding are skipped.
+pub fn env_attributes() -> Vec<KeyValue> {
+    decode_attributes(&EnvResourceDetector::new().detect(Duration::ZERO))
+}
+
+fn decode_attributes(resource: &Resource) -> Vec<KeyValue> {
+    resource
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => Value::from(percent_decode(value.as_str())?),
+                value => value.clone(),
+            };
+
+            Some(KeyValue::new(key.clone(), value))
+        })
+        .collect()
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::{Key, KeyValue, Value};
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::Resource;
+
+    use super::decode_attributes;
+    use crate::testing::TestReader;
+
+    #[test]
+    fn decode() {
+        let resource = Resource::new([
+            KeyValue::new("service.namespace", "media"),
+            KeyValue::new("note", "a%2Cb%3Dc"),
+            KeyValue::new("bad", "%zz"),
+            KeyValue::new("truncated", "%2"),
+        ]);
+
+        let mut attributes = decode_attributes(&resource);
+        attributes.sort_by(|a, b| a.key.cmp(&b.key));
+        assert_eq!(
+            attributes,
+            vec![KeyValue::new("note", "a,b=c"), KeyValue::new("service.namespace", "media"),]
+        );
+    }
+
+    #[test]
+    fn env_resource_attributes() {
+        // This is the only test which sets the variable.
+        std::env::set_var("OTEL_RESOURCE_ATTRIBUTES", "deployment.environment=staging,note=a%2Cb");
+
+        let reader = TestReader::new();
+        let provider = SdkMeterProvider::builder()
+            .with_resource(super::resource([KeyValue::new("service.version", "1.0.0")]))
+            .with_reader(reader.clone())
+            .build();
+
+        let mut metrics = opentelemetry_sdk::metrics::data::ResourceMetrics {
+            resource: opentelemetry_sdk::Resource::empty(),
+            scope_metrics: Vec::new(),
+        };
+        opentelemetry_sdk::metrics::reader::MetricReader::collect(&reader, &mut metrics).unwrap();
+
+        std::env::remove_var("OTEL_RESOURCE_ATTRIBUTES");
+        drop(provider);
+
+        assert_eq!(
+            metrics.resource.get(Key::from_static_str("deployment.environment")),
+            Some(Value::from("staging"))
+        );
+        assert_eq!(metrics.resource.get(Key::from_static_str("note")), Some(Value::from("a,b")));
+        assert_eq!(
+            metrics.resource.get(Key::from_static_str("service.version")),
+            Some(Value::from("1.0.0"))
+        );
+        assert_eq!(
+            metrics.resource.get(Key::from_static_str("telemetry.sdk.language")),
+            Some(Value::from("rust"))
+        );
+    }
+}
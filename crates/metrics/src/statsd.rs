@@ -0,0 +1,366 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use opentelemetry::{otel_warn, KeyValue};
+use opentelemetry_sdk::metrics::data::{Gauge, Histogram, ResourceMetrics, Sum};
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+use opentelemetry_sdk::metrics::{MetricError, MetricResult, PeriodicReader, Temporality};
+
+/// The default maximum size of a single UDP packet.
+///
+/// This is the size recommended by Datadog so that packets are not
+/// fragmented on a typical network with an MTU of 1500 bytes.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 1432;
+
+/// Builder for a StatsD push exporter.
+///
+/// The built reader periodically sends all metrics to a StatsD server (such as
+/// the Datadog agent) over UDP, encoded in the DogStatsD line protocol. It
+/// implements the
+/// [`opentelemetry_sdk::metrics::reader::MetricReader`](https://docs.rs/opentelemetry_sdk/0.27.0/opentelemetry_sdk/metrics/reader/trait.MetricReader.html)
+/// trait and therefore can be passed to a
+/// [`opentelemetry_sdk::metrics::SdkMeterProvider`](https://docs.rs/opentelemetry_sdk/0.27.0/opentelemetry_sdk/metrics/struct.SdkMeterProvider.html).
+///
+/// Metrics are mapped as follows, with their attributes sent as tags:
+/// - Sums with delta temporality (counters by default) are sent as counts
+///   (`|c`).
+/// - Sums with cumulative temporality (up-down counters by default) and
+///   gauges are sent as gauges (`|g`).
+/// - Histograms are sent as a `.count` and a `.sum`, following the rules for
+///   sums, and a `.min` and `.max` gauge when they are recorded.
+///
+/// The reader spawns its export task on the tokio runtime, so
+/// [`build`](StatsdExporterBuilder::build) must be called from within one.
+#[derive(Debug)]
+pub struct StatsdExporterBuilder {
+    address: Cow<'static, str>,
+    prefix: Option<String>,
+    interval: Option<Duration>,
+    max_packet_size: usize,
+    temporality: Temporality,
+}
+
+impl Default for StatsdExporterBuilder {
+    fn default() -> Self {
+        Self {
+            address: Cow::Borrowed("127.0.0.1:8125"),
+            prefix: None,
+            interval: None,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            temporality: Temporality::Delta,
+        }
+    }
+}
+
+impl StatsdExporterBuilder {
+    /// Set the address of the StatsD server.
+    ///
+    /// Defaults to `127.0.0.1:8125`.
+    pub fn with_address(mut self, address: impl Into<Cow<'static, str>>) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    /// Set a prefix which is prepended to every metric name, separated by a
+    /// `.`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Set how often metrics are sent to the server.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Set the maximum size of a single UDP packet, lines are batched into
+    /// packets up to this size.
+    ///
+    /// Defaults to [`DEFAULT_MAX_PACKET_SIZE`].
+    pub fn with_max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    /// Set the reader temporality.
+    ///
+    /// Defaults to [`Temporality::Delta`], which is what StatsD expects for
+    /// counts.
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Build the StatsD reader.
+    pub fn build(self) -> MetricResult<PeriodicReader> {
+        let address = self
+            .address
+            .to_socket_addrs()
+            .map_err(|err| MetricError::Config(format!("invalid statsd address: {err}")))?
+            .next()
+            .ok_or_else(|| MetricError::Config(format!("statsd address {} did not resolve", self.address)))?;
+
+        let exporter = StatsdExporter::new(address, self.prefix, self.max_packet_size, self.temporality)?;
+
+        let mut reader = PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio);
+
+        if let Some(interval) = self.interval {
+            reader = reader.with_interval(interval);
+        }
+
+        Ok(reader.build())
+    }
+}
+
+/// Returns a new [`StatsdExporterBuilder`] to configure a StatsD push exporter.
+pub fn exporter() -> StatsdExporterBuilder {
+    StatsdExporterBuilder::default()
+}
+
+#[derive(Debug)]
+struct StatsdExporter {
+    socket: UdpSocket,
+    prefix: Option<String>,
+    max_packet_size: usize,
+    temporality: Temporality,
+}
+
+impl StatsdExporter {
+    fn new(
+        address: SocketAddr,
+        prefix: Option<String>,
+        max_packet_size: usize,
+        temporality: Temporality,
+    ) -> MetricResult<Self> {
+        let bind: SocketAddr = if address.is_ipv4() {
+            (std::net::Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+
+        let socket = UdpSocket::bind(bind).map_err(|err| MetricError::Other(err.to_string()))?;
+        socket.connect(address).map_err(|err| MetricError::Other(err.to_string()))?;
+        socket
+            .set_nonblocking(true)
+            .map_err(|err| MetricError::Other(err.to_string()))?;
+
+        Ok(Self {
+            socket,
+            prefix,
+            max_packet_size,
+            temporality,
+        })
+    }
+
+    fn send(&self, lines: &[String]) -> MetricResult<()> {
+        let mut packet = String::with_capacity(self.max_packet_size);
+        for line in lines {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > self.max_packet_size {
+                self.send_packet(&packet)?;
+                packet.clear();
+            }
+
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+
+            packet.push_str(line);
+        }
+
+        if !packet.is_empty() {
+            self.send_packet(&packet)?;
+        }
+
+        Ok(())
+    }
+
+    fn send_packet(&self, packet: &str) -> MetricResult<()> {
+        match self.socket.send(packet.as_bytes()) {
+            Ok(_) => Ok(()),
+            // StatsD is lossy by design, a full socket buffer drops the packet.
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                otel_warn!(name: "statsd_exporter_packet_dropped");
+                Ok(())
+            }
+            Err(err) => Err(MetricError::Other(err.to_string())),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl PushMetricExporter for StatsdExporter {
+    async fn export(&self, metrics: &mut ResourceMetrics) -> MetricResult<()> {
+        let mut lines = Vec::new();
+
+        for scope_metrics in &metrics.scope_metrics {
+            for metric in &scope_metrics.metrics {
+                let name = match &self.prefix {
+                    Some(prefix) => format!("{}.{}", sanitize(prefix), sanitize(&metric.name)),
+                    None => sanitize(&metric.name).into_owned(),
+                };
+
+                if !encode_metric(&mut lines, &name, metric.data.as_any()) {
+                    otel_warn!(name: "statsd_exporter_unknown_metric_type", metric_name = metric.name.as_ref());
+                }
+            }
+        }
+
+        self.send(&lines)
+    }
+
+    async fn force_flush(&self) -> MetricResult<()> {
+        Ok(())
+    }
+
+    fn shutdown(&self) -> MetricResult<()> {
+        Ok(())
+    }
+
+    fn temporality(&self) -> Temporality {
+        self.temporality
+    }
+}
+
+/// Encodes a metric into StatsD lines, returns false if the metric type is
+/// not supported.
+fn encode_metric(lines: &mut Vec<String>, name: &str, any: &dyn std::any::Any) -> bool {
+    macro_rules! try_encode {
+        ($($t:ty),*) => {
+            $(
+                if let Some(gauge) = any.downcast_ref::<Gauge<$t>>() {
+                    for data_point in &gauge.data_points {
+                        lines.push(line(name, data_point.value, "g", &data_point.attributes));
+                    }
+                    return true;
+                }
+
+                if let Some(sum) = any.downcast_ref::<Sum<$t>>() {
+                    let kind = sum_kind(sum.temporality);
+                    for data_point in &sum.data_points {
+                        lines.push(line(name, data_point.value, kind, &data_point.attributes));
+                    }
+                    return true;
+                }
+
+                if let Some(histogram) = any.downcast_ref::<Histogram<$t>>() {
+                    let kind = sum_kind(histogram.temporality);
+                    for data_point in &histogram.data_points {
+                        let attrs = &data_point.attributes;
+                        lines.push(line(&format!("{name}.count"), data_point.count, kind, attrs));
+                        lines.push(line(&format!("{name}.sum"), data_point.sum, kind, attrs));
+                        if let Some(min) = data_point.min {
+                            lines.push(line(&format!("{name}.min"), min, "g", attrs));
+                        }
+                        if let Some(max) = data_point.max {
+                            lines.push(line(&format!("{name}.max"), max, "g", attrs));
+                        }
+                    }
+                    return true;
+                }
+            )*
+        };
+    }
+
+    try_encode!(u64, i64, f64);
+    #[cfg(feature = "extended-numbers")]
+    try_encode!(u32, u16, u8, i32, i16, i8, f32);
+
+    false
+}
+
+/// Deltas are sent as counts, cumulative values as gauges.
+fn sum_kind(temporality: Temporality) -> &'static str {
+    match temporality {
+        Temporality::Cumulative => "g",
+        _ => "c",
+    }
+}
+
+fn line(name: &str, value: impl std::fmt::Display, kind: &str, attributes: &[KeyValue]) -> String {
+    let mut line = format!("{name}:{value}|{kind}");
+
+    for (i, kv) in attributes.iter().enumerate() {
+        line.push(if i == 0 { '|' } else { ',' });
+        if i == 0 {
+            line.push('#');
+        }
+
+        let _ = write!(line, "{}:{}", sanitize(kv.key.as_str()), sanitize(&kv.value.as_str()));
+    }
+
+    line
+}
+
+/// Replaces the characters which have a meaning in the StatsD protocol.
+fn sanitize(s: &str) -> Cow<'_, str> {
+    const RESERVED: [char; 6] = [':', '|', '@', '#', ',', '\n'];
+
+    if s.contains(RESERVED) {
+        Cow::Owned(s.replace(RESERVED, "_"))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+
+    #[test]
+    fn line() {
+        assert_eq!(super::line("requests", 7, "c", &[]), "requests:7|c");
+        assert_eq!(
+            super::line(
+                "requests",
+                1.5,
+                "g",
+                &[KeyValue::new("method", "get"), KeyValue::new("path", "/a:b")]
+            ),
+            "requests:1.5|g|#method:get,path:/a_b"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn statsd_export() {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        let reader = super::exporter()
+            .with_address(addr.to_string())
+            .with_prefix("scuffle")
+            .build()
+            .unwrap();
+
+        let provider = SdkMeterProvider::builder().with_reader(reader).build();
+        let meter = provider.meter("test");
+        let counter = meter.u64_counter("statsd_test_counter").build();
+        counter.add(7, &[KeyValue::new("method", "get")]);
+        let gauge = meter.i64_up_down_counter("statsd_test_gauge").build();
+        gauge.add(-3, &[]);
+
+        // The periodic reader blocks on flush, so it has to run outside the async context.
+        tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut buf = [0; 1500];
+        let n = tokio::time::timeout(std::time::Duration::from_secs(5), socket.recv(&mut buf))
+            .await
+            .expect("no packet received")
+            .unwrap();
+        let packet = std::str::from_utf8(&buf[..n]).unwrap();
+        let lines = packet.lines().collect::<Vec<_>>();
+
+        assert!(lines.contains(&"scuffle.statsd_test_counter:7|c|#method:get"), "{packet}");
+        assert!(lines.contains(&"scuffle.statsd_test_gauge:-3|g"), "{packet}");
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::metrics::data::{Gauge, Histogram, ResourceMetrics, Sum};
+use opentelemetry_sdk::metrics::reader::MetricReader;
+use opentelemetry_sdk::metrics::{InstrumentKind, ManualReader, MetricResult, Pipeline, Temporality};
+use opentelemetry_sdk::Resource;
+
+/// A metric reader which can take [`MetricsSnapshot`]s on demand.
+///
+/// Counters are cumulative for the lifetime of the meter provider, so
+/// asserting on absolute values makes tests depend on everything else that
+/// recorded to the same metric. Taking a snapshot before and after the code
+/// under test and asserting on the [`delta`](MetricsSnapshot::delta) avoids
+/// this.
+///
+/// The reader is cheap to clone, all clones read from the same pipeline.
+#[derive(Debug, Clone)]
+pub struct TestReader(Arc<ManualReader>);
+
+impl Default for TestReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestReader {
+    /// Creates a new reader.
+    pub fn new() -> Self {
+        Self(Arc::new(ManualReader::builder().build()))
+    }
+
+    /// Collects the current value of every metric.
+    pub fn snapshot(&self) -> MetricResult<MetricsSnapshot> {
+        let mut metrics = ResourceMetrics {
+            resource: Resource::empty(),
+            scope_metrics: Vec::new(),
+        };
+
+        self.0.collect(&mut metrics)?;
+
+        Ok(MetricsSnapshot::from_resource_metrics(&metrics))
+    }
+}
+
+impl MetricReader for TestReader {
+    fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+        self.0.register_pipeline(pipeline)
+    }
+
+    fn collect(&self, rm: &mut ResourceMetrics) -> MetricResult<()> {
+        self.0.collect(rm)
+    }
+
+    fn force_flush(&self) -> MetricResult<()> {
+        self.0.force_flush()
+    }
+
+    fn shutdown(&self) -> MetricResult<()> {
+        self.0.shutdown()
+    }
+
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.0.temporality(kind)
+    }
+}
+
+type SeriesKey = (String, Vec<(String, String)>);
+
+/// The values of every metric series at a point in time.
+///
+/// Sums and gauges are stored as their value, histograms as the number of
+/// recorded values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    values: HashMap<SeriesKey, f64>,
+}
+
+fn series_key<'a>(name: &str, attributes: impl IntoIterator<Item = &'a KeyValue>) -> SeriesKey {
+    let mut attributes = attributes
+        .into_iter()
+        .map(|kv| (kv.key.to_string(), kv.value.as_str().into_owned()))
+        .collect::<Vec<_>>();
+    attributes.sort();
+    (name.to_owned(), attributes)
+}
+
+impl MetricsSnapshot {
+    fn from_resource_metrics(metrics: &ResourceMetrics) -> Self {
+        let mut values = HashMap::new();
+
+        macro_rules! collect {
+            ($data:expr, $name:expr, [$($ty:ty),*]) => {
+                $(
+                    if let Some(sum) = $data.downcast_ref::<Sum<$ty>>() {
+                        for point in &sum.data_points {
+                            values.insert(series_key($name, &point.attributes), point.value as f64);
+                        }
+                    } else if let Some(gauge) = $data.downcast_ref::<Gauge<$ty>>() {
+                        for point in &gauge.data_points {
+                            values.insert(series_key($name, &point.attributes), point.value as f64);
+                        }
+                    } else if let Some(histogram) = $data.downcast_ref::<Histogram<$ty>>() {
+                        for point in &histogram.data_points {
+                            values.insert(series_key($name, &point.attributes), point.count as f64);
+                        }
+                    }
+                )*
+            };
+        }
+
+        for scope in &metrics.scope_metrics {
+            for metric in &scope.metrics {
+                let data = metric.data.as_any();
+                collect!(data, &metric.name, [u64, i64, f64]);
+            }
+        }
+
+        Self { values }
+    }
+
+    /// Returns the value of the series with the given metric name and
+    /// attributes, the order of the attributes does not matter.
+    pub fn get(&self, name: &str, attributes: &[KeyValue]) -> Option<f64> {
+        self.values.get(&series_key(name, attributes)).copied()
+    }
+
+    /// Returns how much every series changed since `previous`.
+    ///
+    /// Series which did not exist in `previous` are treated as if they were
+    /// zero.
+    pub fn delta(&self, previous: &MetricsSnapshot) -> MetricsSnapshot {
+        MetricsSnapshot {
+            values: self
+                .values
+                .iter()
+                .map(|(key, value)| (key.clone(), value - previous.values.get(key).copied().unwrap_or_default()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+
+    use super::TestReader;
+
+    #[test]
+    fn snapshot_delta() {
+        let reader = TestReader::new();
+        let provider = SdkMeterProvider::builder().with_reader(reader.clone()).build();
+        let counter = provider.meter("test").u64_counter("testing_counter").build();
+        let attributes = [KeyValue::new("kind", "http")];
+
+        counter.add(3, &attributes);
+
+        let before = reader.snapshot().unwrap();
+        assert_eq!(before.get("testing_counter", &attributes), Some(3.0));
+
+        counter.add(2, &attributes);
+        counter.add(1, &[KeyValue::new("kind", "grpc")]);
+
+        let delta = reader.snapshot().unwrap().delta(&before);
+        assert_eq!(delta.get("testing_counter", &attributes), Some(2.0));
+        assert_eq!(delta.get("testing_counter", &[KeyValue::new("kind", "grpc")]), Some(1.0));
+        assert_eq!(delta.get("testing_counter", &[]), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! Units can be written by their full name (e.g. `milliseconds`), its
+//! singular form (e.g. `millisecond`) or one of its UCUM aliases (e.g. `ms`),
+//! as used by opentelemetry. UCUM annotations (e.g. `{request}`) are
+//! explicitly custom units and are always accepted.
+//!
+//! The [`metrics`](crate::metrics) macro warns about units which are not
+//! known, exporters which need full names (such as the
+//! [`PrometheusExporter`](crate::prometheus::PrometheusExporter)) use
+//! [`full_name`](crate::units::full_name) to look them up.
+
+/// The known units, by their full name, together with their aliases.
+const UNITS: &[(&str, &[&str])] = &[
+    ("seconds", &["s", "sec", "secs"]),
+    ("milliseconds", &["ms", "msec"]),
+    ("microseconds", &["us", "\u{3bc}s", "usec"]),
+    ("nanoseconds", &["ns", "nsec"]),
+    ("minutes", &["min"]),
+    ("hours", &["h"]),
+    ("days", &["d"]),
+    ("bytes", &["B", "By"]),
+    ("kilobytes", &["kB", "KB", "KBy"]),
+    ("megabytes", &["MB", "MBy"]),
+    ("gigabytes", &["GB", "GBy"]),
+    ("kibibytes", &["KiB", "KiBy"]),
+    ("mebibytes", &["MiB", "MiBy"]),
+    ("gibibytes", &["GiB", "GiBy"]),
+    ("bits", &["bit"]),
+    ("percent", &["%"]),
+    ("ratio", &["1"]),
+    ("hertz", &["Hz"]),
+    ("celsius", &["Cel"]),
+    ("requests", &[]),
+    ("responses", &[]),
+    ("errors", &[]),
+    ("connections", &[]),
+    ("packets", &[]),
+    ("frames", &[]),
+    ("messages", &[]),
+    ("events", &[]),
+    ("operations", &[]),
+    ("items", &[]),
+    ("tasks", &[]),
+    ("threads", &[]),
+];
+
+/// Returns `true` if the unit is a UCUM annotation, such as `{request}`.
+pub const fn is_annotation(unit: &str) -> bool {
+    let unit = unit.as_bytes();
+    unit.len() >= 2 && unit[0] == b'{' && unit[unit.len() - 1] == b'}'
+}
+
+/// Returns `true` if the unit is known or an annotation.
+pub const fn is_known(unit: &str) -> bool {
+    is_annotation(unit) || find(unit).is_some()
+}
+
+/// Returns the full name of a known unit, e.g. `milliseconds` for `ms`.
+///
+/// Annotations and unknown units return `None`.
+pub fn full_name(unit: &str) -> Option<&'static str> {
+    find(unit)
+}
+
+const fn find(unit: &str) -> Option<&'static str> {
+    let mut i = 0;
+    while i < UNITS.len() {
+        let (name, aliases) = UNITS[i];
+        if str_eq(name, unit) || is_singular(name, unit) {
+            return Some(name);
+        }
+
+        let mut j = 0;
+        while j < aliases.len() {
+            if str_eq(aliases[j], unit) {
+                return Some(name);
+            }
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+/// Returns `true` if `unit` is `name` without its trailing `s`.
+const fn is_singular(name: &str, unit: &str) -> bool {
+    let (name, unit) = (name.as_bytes(), unit.as_bytes());
+    name.len() == unit.len() + 1 && name[unit.len()] == b's' && bytes_eq(name, unit, unit.len())
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && bytes_eq(a.as_bytes(), b.as_bytes(), a.len())
+}
+
+const fn bytes_eq(a: &[u8], b: &[u8], len: usize) -> bool {
+    let mut i = 0;
+    while i < len {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+#[doc(hidden)]
+/// A compiler trick used by the [`metrics`](crate::metrics) macro to warn
+/// about unknown units, as there is no way to emit a warning from a
+/// proc-macro on stable.
+pub struct UnitCheck<const KNOWN: bool>;
+
+impl UnitCheck<true> {
+    #[inline(always)]
+    pub const fn check(self) {}
+}
+
+impl UnitCheck<false> {
+    #[deprecated(note = "unrecognized metric unit, see `scuffle_metrics::units` for the known units")]
+    #[inline(always)]
+    pub const fn check(self) {}
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::{full_name, is_known};
+
+    #[test]
+    fn units() {
+        assert_eq!(full_name("ms"), Some("milliseconds"));
+        assert_eq!(full_name("By"), Some("bytes"));
+        assert_eq!(full_name("second"), Some("seconds"));
+        assert_eq!(full_name("requests"), Some("requests"));
+        assert_eq!(full_name("{request}"), None);
+        assert_eq!(full_name("requsts"), None);
+        assert_eq!(full_name(""), None);
+
+        assert!(is_known("{request}"));
+        assert!(is_known("\u{3bc}s"));
+        assert!(!is_known("{}x"));
+        assert!(!is_known("requsts"));
+    }
+}
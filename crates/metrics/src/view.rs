@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use opentelemetry::Key;
+use opentelemetry_sdk::metrics::{Aggregation, Instrument, Stream, View};
+
+/// A view which changes how a single metric is reported, registered with
+/// [`SdkMeterProvider::builder().with_view`](opentelemetry_sdk::metrics::MeterProviderBuilder::with_view).
+///
+/// Views are applied when the instrument is created, so they let you rename a
+/// metric, limit its attributes or change its aggregation without touching the
+/// code which records it. This is the usual way to get rid of a high
+/// cardinality attribute.
+///
+/// Metrics which no view matches are reported unchanged.
+///
+/// ```rust
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+/// use scuffle_metrics::view::MetricView;
+///
+/// let provider = SdkMeterProvider::builder()
+///     // Only keep the `method` attribute, dropping e.g. the request path.
+///     .with_view(MetricView::new("http_requests").allow_attributes(["method"]))
+///     .with_view(MetricView::new("db_queries").rename("database_queries"))
+///     .build();
+/// # drop(provider);
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct MetricView {
+    name: Cow<'static, str>,
+    rename: Option<Cow<'static, str>>,
+    description: Option<Cow<'static, str>>,
+    allowed_attributes: Option<Arc<HashSet<Key>>>,
+    aggregation: Option<Aggregation>,
+}
+
+impl MetricView {
+    /// Creates a view matching the metric with the given name, which does not
+    /// change anything yet.
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            name: name.into(),
+            rename: None,
+            description: None,
+            allowed_attributes: None,
+            aggregation: None,
+        }
+    }
+
+    /// Reports the metric under a different name.
+    pub fn rename(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.rename = Some(name.into());
+        self
+    }
+
+    /// Replaces the description of the metric.
+    pub fn description(mut self, description: impl Into<Cow<'static, str>>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Only keeps the attributes with the given keys, all other attributes
+    /// are dropped and the series which only differed by them are merged.
+    ///
+    /// OpenTelemetry only supports allow-lists, so to drop a single attribute
+    /// list all the other attributes of the metric.
+    pub fn allow_attributes<K: Into<Key>>(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.allowed_attributes = Some(Arc::new(keys.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Changes the aggregation of the metric, e.g. to use different histogram
+    /// buckets or [`Aggregation::Drop`] to not report it at all.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = Some(aggregation);
+        self
+    }
+}
+
+impl View for MetricView {
+    fn match_inst(&self, inst: &Instrument) -> Option<Stream> {
+        if inst.name != self.name {
+            return None;
+        }
+
+        let mut stream = Stream::new()
+            .name(self.rename.clone().unwrap_or_else(|| inst.name.clone()))
+            .description(self.description.clone().unwrap_or_else(|| inst.description.clone()))
+            .unit(inst.unit.clone());
+
+        stream.allowed_attribute_keys = self.allowed_attributes.clone();
+        stream.aggregation = self.aggregation.clone();
+
+        Some(stream)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::metrics::{Aggregation, SdkMeterProvider};
+
+    use super::MetricView;
+    use crate::testing::TestReader;
+
+    #[test]
+    fn allow_attributes() {
+        let reader = TestReader::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader.clone())
+            .with_view(MetricView::new("requests").allow_attributes(["method"]))
+            .build();
+        let meter = provider.meter("test");
+        let requests = meter.u64_counter("requests").build();
+        let other = meter.u64_counter("other").build();
+
+        requests.add(1, &[KeyValue::new("method", "GET"), KeyValue::new("path", "/a")]);
+        requests.add(2, &[KeyValue::new("method", "GET"), KeyValue::new("path", "/b")]);
+        other.add(1, &[KeyValue::new("method", "GET"), KeyValue::new("path", "/a")]);
+
+        let snapshot = reader.snapshot().unwrap();
+        assert_eq!(snapshot.get("requests", &[KeyValue::new("method", "GET")]), Some(3.0));
+        assert_eq!(
+            snapshot.get("requests", &[KeyValue::new("method", "GET"), KeyValue::new("path", "/a")]),
+            None
+        );
+
+        // Other metrics keep all their attributes.
+        assert_eq!(
+            snapshot.get("other", &[KeyValue::new("method", "GET"), KeyValue::new("path", "/a")]),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn rename_and_drop() {
+        let reader = TestReader::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader.clone())
+            .with_view(MetricView::new("queries").rename("database_queries"))
+            .with_view(MetricView::new("noisy").aggregation(Aggregation::Drop))
+            .build();
+        let meter = provider.meter("test");
+
+        meter.u64_counter("queries").build().add(1, &[]);
+        meter.u64_counter("noisy").build().add(1, &[]);
+
+        let snapshot = reader.snapshot().unwrap();
+        assert_eq!(snapshot.get("database_queries", &[]), Some(1.0));
+        assert_eq!(snapshot.get("queries", &[]), None);
+        assert_eq!(snapshot.get("noisy", &[]), None);
+    }
+}
@@ -5,6 +5,9 @@
 /// `Into` for the underlying type. As well as a custom `Debug` impl for human
 /// readable output.
 ///
+/// A `Display` impl is not generated by default, use [`display_enum!`] to opt
+/// into one.
+///
 /// # Examples
 ///
 /// ```rust
@@ -34,16 +37,28 @@ macro_rules! nutype_enum {
 
         impl ::std::fmt::Debug for $name {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self.variant_name() {
+                    Some(variant) => write!(f, "{}::{}", stringify!($name), variant),
+                    None => write!(f, "{}({:?})", stringify!($name), self.0),
+                }
+            }
+        }
+
+        impl $name {
+            /// Returns the name of the variant, or `None` if the value does
+            /// not match any known variant.
+            ///
+            /// Used by the generated `Debug` impl and by [`display_enum!`].
+            #[doc(hidden)]
+            pub(crate) fn variant_name(&self) -> ::std::option::Option<&'static str> {
                 match self {
                     $(
-                        &$name::$variant => write!(f, "{}::{}", stringify!($name), stringify!($variant)),
+                        &$name::$variant => ::std::option::Option::Some(stringify!($variant)),
                     )*
-                    _ => write!(f, "{}({:?})", stringify!($name), self.0),
+                    _ => ::std::option::Option::None,
                 }
             }
-        }
 
-        impl $name {
             $(
                 $(#[$variant_attr])*
                 #[allow(non_upper_case_globals)]
@@ -138,3 +153,38 @@ macro_rules! bitwise_enum {
         }
     };
 }
+
+/// Helper macro to implement `Display` for a nutype enum.
+///
+/// Known values are displayed as the variant name, unknown values are
+/// displayed as the underlying value.
+///
+/// # Examples
+///
+/// ```rust
+/// # use nutype_enum::{nutype_enum, display_enum};
+/// nutype_enum! {
+///     pub enum AacPacketType(u8) {
+///         SeqHdr = 0x0,
+///         Raw = 0x1,
+///     }
+/// }
+///
+/// display_enum!(AacPacketType);
+///
+/// assert_eq!(AacPacketType::Raw.to_string(), "Raw");
+/// assert_eq!(AacPacketType(0x2).to_string(), "2");
+/// ```
+#[macro_export]
+macro_rules! display_enum {
+    ($name:ident) => {
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self.variant_name() {
+                    Some(variant) => f.write_str(variant),
+                    None => ::std::fmt::Display::fmt(&self.0, f),
+                }
+            }
+        }
+    };
+}
@@ -27,7 +27,7 @@ pub struct Dependencies {
 
 impl Dependencies {
     pub fn new(config: &Config) -> Result<Self, Errored> {
-        let mut build = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+        let mut build = crate::tool(config, "CARGO", "cargo");
 
         let target_dir = if config.target_dir.ends_with(TARGET) {
             config.target_dir.parent().unwrap()
@@ -141,9 +141,23 @@ impl std::fmt::Display for CompileOutput {
     }
 }
 
-fn rustc(config: &Config, tmp_file: &Path) -> Command {
-    let mut program = Command::new(std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into()));
-    program.env("RUSTC_BOOTSTRAP", "1");
+/// Creates a command for the given tool (`rustc` or `cargo`), taking the
+/// configured toolchain into account.
+fn tool(config: &Config, env: &str, name: &str) -> Command {
+    match &config.toolchain {
+        Some(toolchain) => {
+            // The `+toolchain` syntax is only understood by the rustup proxy, so we cannot
+            // use the `RUSTC` / `CARGO` env vars which point at a specific toolchain.
+            let mut program = Command::new(name);
+            program.arg(format!("+{toolchain}"));
+            program
+        }
+        None => Command::new(std::env::var_os(env).unwrap_or_else(|| name.into())),
+    }
+}
+
+fn rustc(config: &Config, tmp_file: &Path, rustc_bootstrap: bool) -> Command {
+    let mut program = tool(config, "RUSTC", "rustc");
     let rust_flags = std::env::var_os("RUSTFLAGS");
 
     if let Some(rust_flags) = &rust_flags {
@@ -159,6 +173,11 @@ fn rustc(config: &Config, tmp_file: &Path) -> Command {
     program.arg(config.function_name.split("::").last().unwrap_or("unnamed"));
     program.arg(tmp_file);
     program.envs(std::env::vars());
+    if rustc_bootstrap {
+        program.env("RUSTC_BOOTSTRAP", "1");
+    } else {
+        program.env_remove("RUSTC_BOOTSTRAP");
+    }
 
     program.stderr(std::process::Stdio::piped());
     program.stdout(std::process::Stdio::piped());
@@ -187,18 +206,31 @@ pub fn compile_custom(tokens: &str, config: &Config) -> Result<CompileOutput, Er
 
     let dependencies = Dependencies::new(config)?;
 
-    let mut program = rustc(config, &tmp_file);
+    let (mut status, stdout, stderr, syn_file) = {
+        // `-Zunpretty=expanded` is an unstable flag, so the expansion always
+        // needs `RUSTC_BOOTSTRAP`. It only prints the expanded code, whether the
+        // code compiles without it is checked by the compile step below.
+        let mut program = rustc(config, &tmp_file, true);
 
-    dependencies.apply(&mut program);
-    // The first invoke is used to get the macro expanded code.
-    program.arg("-Zunpretty=expanded");
+        dependencies.apply(&mut program);
+        // The first invoke is used to get the macro expanded code.
+        program.arg("-Zunpretty=expanded");
 
-    let output = program.output().unwrap();
+        let output = program.output().unwrap();
 
-    let stdout = String::from_utf8(output.stdout).unwrap();
-    let syn_file = syn::parse_file(&stdout);
-    #[cfg(feature = "prettyplease")]
-    let stdout = syn_file.as_ref().map(prettyplease::unparse).unwrap_or(stdout);
+        let status = if output.status.success() {
+            ExitStatus::Success
+        } else {
+            ExitStatus::Failure(output.status.code().unwrap_or(-1))
+        };
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let syn_file = syn::parse_file(&stdout);
+        #[cfg(feature = "prettyplease")]
+        let stdout = syn_file.as_ref().map(prettyplease::unparse).unwrap_or(stdout);
+
+        (status, stdout, String::from_utf8(output.stderr).unwrap(), syn_file)
+    };
 
     let mut crate_type = "lib";
 
@@ -214,14 +246,8 @@ pub fn compile_custom(tokens: &str, config: &Config) -> Result<CompileOutput, Er
         }
     };
 
-    let mut status = if output.status.success() {
-        ExitStatus::Success
-    } else {
-        ExitStatus::Failure(output.status.code().unwrap_or(-1))
-    };
-
     let stderr = if status == ExitStatus::Success {
-        let mut program = rustc(config, &tmp_file);
+        let mut program = rustc(config, &tmp_file, config.rustc_bootstrap);
         dependencies.apply(&mut program);
         program.arg("--emit=llvm-ir");
         program.arg(format!("--crate-type={crate_type}"));
@@ -235,7 +261,7 @@ pub fn compile_custom(tokens: &str, config: &Config) -> Result<CompileOutput, Er
         };
         String::from_utf8(comp_output.stderr).unwrap()
     } else {
-        String::from_utf8(output.stderr).unwrap()
+        stderr
     };
 
     let stderr = stderr.replace(tmp_file.as_os_str().to_string_lossy().as_ref(), "<postcompile>");
@@ -258,6 +284,23 @@ pub struct Config {
     pub tmp_dir: Cow<'static, Path>,
     /// The name of the function to compile.
     pub function_name: Cow<'static, str>,
+    /// Set `RUSTC_BOOTSTRAP=1` when compiling the code (enabled by default).
+    ///
+    /// This unlocks every nightly feature which can hide the fact that the
+    /// code would not compile on stable, disable it to compile the code as a
+    /// stable compiler would.
+    ///
+    /// The expansion step always sets `RUSTC_BOOTSTRAP=1` since
+    /// `-Zunpretty=expanded` is an unstable flag, so
+    /// [`CompileOutput::stdout`] contains the expanded code either way.
+    pub rustc_bootstrap: bool,
+    /// The rustup toolchain to use (e.g. `nightly-2025-01-01`), invoked as
+    /// `rustc +toolchain`.
+    ///
+    /// This requires `rustc` and `cargo` to be the rustup proxies. The
+    /// dependencies are built with the same toolchain, which means they will
+    /// not share the build cache with the toolchain running the tests.
+    pub toolchain: Option<Cow<'static, str>>,
 }
 
 #[macro_export]
@@ -303,6 +346,8 @@ macro_rules! _config {
             tmp_dir: ::std::borrow::Cow::Borrowed($crate::build_dir()),
             target_dir: ::std::borrow::Cow::Borrowed($crate::target_dir()),
             function_name: ::std::borrow::Cow::Borrowed($crate::_function_name!()),
+            rustc_bootstrap: true,
+            toolchain: None,
         }
     }};
 }
@@ -388,7 +433,7 @@ macro_rules! try_compile_str {
 mod tests {
     use insta::assert_snapshot;
 
-    use crate::ExitStatus;
+    use crate::{compile_custom, tool, Config, ExitStatus};
 
     #[test]
     fn compile_success() {
@@ -448,4 +493,50 @@ mod tests {
         assert!(out.stdout.is_empty());
         assert!(!out.stderr.is_empty());
     }
+
+    /// Returns true if the configured `rustc` accepts unstable flags without
+    /// `RUSTC_BOOTSTRAP`.
+    fn is_nightly(config: &Config) -> bool {
+        let Ok(output) = tool(config, "RUSTC", "rustc").arg("-vV").output() else {
+            return false;
+        };
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.strip_prefix("release: "))
+            .is_some_and(|release| release.contains("nightly") || release.contains("dev"))
+    }
+
+    #[test]
+    fn compile_without_bootstrap() {
+        let mut config = crate::_config!();
+        config.rustc_bootstrap = false;
+
+        let out = compile_custom(
+            stringify! {
+                #[derive(Debug, Clone, Copy)]
+                pub struct Test(pub u32);
+
+                pub const TEST: Test = Test(1);
+            },
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(out.status, ExitStatus::Success);
+        assert!(out.stderr.is_empty(), "{}", out.stderr);
+        assert!(out.stdout.contains("impl ::core::clone::Clone for Test"), "{}", out.stdout);
+        assert!(out.stdout.contains("pub const TEST: Test = Test(1);"), "{}", out.stdout);
+
+        let out = compile_custom("#![feature(never_type)] pub type Never = !;", &config).unwrap();
+        assert!(out.stdout.contains("pub type Never = !;"), "{}", out.stdout);
+
+        if is_nightly(&config) {
+            assert_eq!(out.status, ExitStatus::Success);
+        } else {
+            // Nightly features are not unlocked by the bootstrap hack anymore.
+            assert_eq!(out.status, ExitStatus::Failure(1));
+            assert!(out.stderr.contains("E0554"), "{}", out.stderr);
+        }
+    }
 }
@@ -8,11 +8,12 @@ struct Config {
 }
 
 fn main() {
-    let config = scuffle_settings::parse_settings::<Config>(scuffle_settings::Options {
-        cli: Some(scuffle_settings::cli!()),
-        default_config_file: Some("config"),
-        env_prefix: Some("APP"),
-    });
+    let config = scuffle_settings::parse_settings::<Config>(
+        scuffle_settings::Options::new()
+            .with_cli(scuffle_settings::cli!())
+            .with_default_config_file("config")
+            .with_env_prefix("APP"),
+    );
 
     println!("{:#?}", config);
 }
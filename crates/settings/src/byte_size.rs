@@ -0,0 +1,190 @@
+//! Human-friendly byte sizes like `10MB` or `2GiB`.
+//!
+//! Use this module with `#[serde(with = "scuffle_settings::byte_size")]` on a
+//! `u64` field:
+//!
+//! ```rust
+//! #[derive(serde::Deserialize)]
+//! struct MyConfig {
+//!     #[serde(with = "scuffle_settings::byte_size")]
+//!     max_size: u64,
+//! }
+//! ```
+//!
+//! A byte size is a number followed by an optional unit, optionally separated
+//! by whitespace, e.g. `512`, `10MB`, `1.5 GiB`. Decimal units (`kB`, `MB`,
+//! `GB`, `TB`, `PB`) are powers of 1000, binary units (`KiB`, `MiB`, `GiB`,
+//! `TiB`, `PiB`) are powers of 1024 and `B` is a single byte. Units are case
+//! insensitive. A plain integer (not a string) is read as a number of bytes.
+//!
+//! Sizes are serialized with the largest binary unit they are a multiple of,
+//! e.g. `2GiB`, or in bytes, e.g. `1500B`.
+
+use crate::units::{scale, split_number};
+
+const UNITS: &[(&str, u128)] = &[
+    ("b", 1),
+    ("kb", 1000),
+    ("mb", 1000 * 1000),
+    ("gb", 1000 * 1000 * 1000),
+    ("tb", 1000 * 1000 * 1000 * 1000),
+    ("pb", 1000 * 1000 * 1000 * 1000 * 1000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+    ("pib", 1 << 50),
+];
+
+/// The units used when formatting, from the largest to the smallest.
+const FORMAT_UNITS: &[(&str, u64)] = &[
+    ("PiB", 1 << 50),
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+];
+
+/// An error that can occur when parsing a byte size.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseByteSizeError {
+    #[error("empty byte size")]
+    Empty,
+    #[error("invalid number in byte size: {0:?}")]
+    InvalidNumber(String),
+    #[error("unknown unit in byte size: {0:?}")]
+    UnknownUnit(String),
+    #[error("byte size is too large")]
+    Overflow,
+}
+
+/// Parses a human-friendly byte size like `2GiB` into a number of bytes.
+pub fn parse(value: &str) -> Result<u64, ParseByteSizeError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ParseByteSizeError::Empty);
+    }
+
+    let (number, unit) = split_number(value);
+    if number.is_empty() {
+        return Err(ParseByteSizeError::InvalidNumber(value.to_owned()));
+    }
+
+    let multiplier = if unit.is_empty() {
+        1
+    } else {
+        UNITS
+            .iter()
+            .find_map(|&(name, multiplier)| name.eq_ignore_ascii_case(unit).then_some(multiplier))
+            .ok_or_else(|| ParseByteSizeError::UnknownUnit(unit.to_owned()))?
+    };
+
+    let bytes = scale(number, multiplier).ok_or_else(|| ParseByteSizeError::InvalidNumber(number.to_owned()))?;
+    u64::try_from(bytes).map_err(|_| ParseByteSizeError::Overflow)
+}
+
+/// Formats a number of bytes like `2GiB`, the inverse of [`parse`].
+pub fn format(bytes: u64) -> String {
+    FORMAT_UNITS
+        .iter()
+        .find(|&&(_, multiplier)| bytes != 0 && bytes.is_multiple_of(multiplier))
+        .map(|&(unit, multiplier)| format!("{}{unit}", bytes / multiplier))
+        .unwrap_or_else(|| format!("{bytes}B"))
+}
+
+/// Serializes a number of bytes as a string like `2GiB`.
+pub fn serialize<S: serde::Serializer>(bytes: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(*bytes))
+}
+
+/// Deserializes a number of bytes from a string like `2GiB` or an integer.
+pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    struct Visitor;
+
+    impl serde::de::Visitor<'_> for Visitor {
+        type Value = u64;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a byte size like \"10MB\" or a number of bytes")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<u64, E> {
+            parse(value).map_err(E::custom)
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<u64, E> {
+            u64::try_from(value).map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+        }
+    }
+
+    deserializer.deserialize_any(Visitor)
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use super::{format, parse, ParseByteSizeError};
+    use crate::{parse_settings, Options};
+
+    #[test]
+    fn parse_byte_sizes() {
+        assert_eq!(parse("2GiB"), Ok(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse("10MB"), Ok(10_000_000));
+        assert_eq!(parse("512"), Ok(512));
+        assert_eq!(parse("1.5 KiB"), Ok(1536));
+        assert_eq!(parse("4kb"), Ok(4000));
+        assert_eq!(parse("1B"), Ok(1));
+        assert_eq!(parse("16PiB"), Ok(16 << 50));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(" "), Err(ParseByteSizeError::Empty));
+        assert_eq!(parse("MB"), Err(ParseByteSizeError::InvalidNumber("MB".to_owned())));
+        assert_eq!(parse("1.2.3MB"), Err(ParseByteSizeError::InvalidNumber("1.2.3".to_owned())));
+        assert_eq!(parse("10XB"), Err(ParseByteSizeError::UnknownUnit("XB".to_owned())));
+        assert_eq!(
+            parse("10 MB extra"),
+            Err(ParseByteSizeError::UnknownUnit("MB extra".to_owned()))
+        );
+        assert_eq!(parse("20000PiB"), Err(ParseByteSizeError::Overflow));
+        assert_eq!(parse("10XB").unwrap_err().to_string(), "unknown unit in byte size: \"XB\"");
+    }
+
+    #[test]
+    fn format_byte_sizes() {
+        assert_eq!(format(2 * 1024 * 1024 * 1024), "2GiB");
+        assert_eq!(format(1536), "1536B");
+        assert_eq!(format(0), "0B");
+        assert_eq!(parse(&format(3 << 20)), Ok(3 << 20));
+    }
+
+    #[test]
+    fn deserialize() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Settings {
+            #[serde(with = "crate::byte_size")]
+            max_size: u64,
+        }
+
+        let options = Options {
+            env_prefix: Some("SETTINGS_BYTE_SIZE_TEST"),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_BYTE_SIZE_TEST_MAX_SIZE", "2GiB");
+        let settings = parse_settings::<Settings>(options).expect("failed to parse settings");
+        assert_eq!(settings.max_size, 2 * 1024 * 1024 * 1024);
+
+        let options = Options {
+            env_prefix: Some("SETTINGS_BYTE_SIZE_ERROR_TEST"),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_BYTE_SIZE_ERROR_TEST_MAX_SIZE", "lots");
+        let err = parse_settings::<Settings>(options).expect_err("expected error");
+        assert!(err.to_string().contains("invalid number in byte size"), "{err}");
+    }
+}
@@ -0,0 +1,210 @@
+//! Human-friendly durations like `30s` or `1h30m`.
+//!
+//! Use this module with `#[serde(with = "scuffle_settings::duration")]` on a
+//! [`Duration`] field:
+//!
+//! ```rust
+//! # use std::time::Duration;
+//! #[derive(serde::Deserialize)]
+//! struct MyConfig {
+//!     #[serde(with = "scuffle_settings::duration")]
+//!     timeout: Duration,
+//! }
+//! ```
+//!
+//! A duration is one or more numbers each followed by a unit, optionally
+//! separated by whitespace, e.g. `1h30m`, `1.5s` or `2m 30s`. The supported
+//! units are `ns`, `us` (or `µs`), `ms`, `s`, `m`, `h` and `d`. A plain
+//! integer (not a string) is read as a number of seconds.
+//!
+//! Durations are serialized in the same format, e.g. `1h30m`.
+
+use std::time::Duration;
+
+use crate::units::{scale, split_number};
+
+const UNITS: &[(&str, u128)] = &[
+    ("d", 24 * 60 * 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ns", 1),
+];
+
+/// An error that can occur when parsing a duration.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseDurationError {
+    #[error("empty duration")]
+    Empty,
+    #[error("invalid number in duration: {0:?}")]
+    InvalidNumber(String),
+    #[error("missing unit in duration, expected one of ns, us, ms, s, m, h or d")]
+    MissingUnit,
+    #[error("unknown unit in duration: {0:?}")]
+    UnknownUnit(String),
+    #[error("duration is too large")]
+    Overflow,
+}
+
+/// Parses a human-friendly duration like `1h30m`.
+pub fn parse(value: &str) -> Result<Duration, ParseDurationError> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return Err(ParseDurationError::Empty);
+    }
+
+    let mut nanos = 0u128;
+    while !rest.is_empty() {
+        let (number, tail) = split_number(rest);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+
+        if number.is_empty() {
+            return Err(ParseDurationError::InvalidNumber(
+                rest.split_whitespace().next().unwrap_or(rest).to_owned(),
+            ));
+        }
+
+        if unit.is_empty() {
+            return Err(ParseDurationError::MissingUnit);
+        }
+
+        let multiplier = UNITS
+            .iter()
+            .find_map(|&(name, multiplier)| (name == unit).then_some(multiplier))
+            .ok_or_else(|| ParseDurationError::UnknownUnit(unit.to_owned()))?;
+
+        let part = scale(number, multiplier).ok_or_else(|| ParseDurationError::InvalidNumber(number.to_owned()))?;
+        nanos = nanos.checked_add(part).ok_or(ParseDurationError::Overflow)?;
+        rest = tail.trim_start();
+    }
+
+    let secs = u64::try_from(nanos / 1_000_000_000).map_err(|_| ParseDurationError::Overflow)?;
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+/// Formats a duration like `1h30m`, the inverse of [`parse`].
+pub fn format(duration: Duration) -> String {
+    let mut nanos = duration.as_nanos();
+    if nanos == 0 {
+        return "0s".to_owned();
+    }
+
+    let mut out = String::new();
+    for &(unit, multiplier) in UNITS.iter().filter(|(unit, _)| *unit != "µs") {
+        if nanos >= multiplier {
+            out.push_str(&(nanos / multiplier).to_string());
+            out.push_str(unit);
+            nanos %= multiplier;
+        }
+    }
+
+    out
+}
+
+/// Serializes a duration as a string like `1h30m`.
+pub fn serialize<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(*duration))
+}
+
+/// Deserializes a duration from a string like `1h30m` or an integer number
+/// of seconds.
+pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    struct Visitor;
+
+    impl serde::de::Visitor<'_> for Visitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a duration like \"1h30m\" or a number of seconds")
+        }
+
+        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Duration, E> {
+            parse(value).map_err(E::custom)
+        }
+
+        fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Duration, E> {
+            Ok(Duration::from_secs(value))
+        }
+
+        fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Duration, E> {
+            u64::try_from(value)
+                .map(Duration::from_secs)
+                .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+        }
+    }
+
+    deserializer.deserialize_any(Visitor)
+}
+
+#[cfg(test)]
+#[cfg_attr(all(test, coverage_nightly), coverage(off))]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format, parse, ParseDurationError};
+    use crate::{parse_settings, Options};
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse(" 2m 30s "), Ok(Duration::from_secs(150)));
+        assert_eq!(parse("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse("1d2h3m4s5ms6us7ns"), Ok(Duration::new(93784, 5_006_007)));
+        assert_eq!(parse("250µs"), Ok(Duration::from_micros(250)));
+        assert_eq!(parse("0s"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(""), Err(ParseDurationError::Empty));
+        assert_eq!(parse("10"), Err(ParseDurationError::MissingUnit));
+        assert_eq!(parse("1h30"), Err(ParseDurationError::MissingUnit));
+        assert_eq!(parse("5x"), Err(ParseDurationError::UnknownUnit("x".to_owned())));
+        assert_eq!(parse("abc"), Err(ParseDurationError::InvalidNumber("abc".to_owned())));
+        assert_eq!(parse("1..5s"), Err(ParseDurationError::InvalidNumber("1..5".to_owned())));
+        assert_eq!(parse("99999999999999999999999d"), Err(ParseDurationError::Overflow));
+        assert_eq!(parse("5x").unwrap_err().to_string(), "unknown unit in duration: \"x\"");
+    }
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format(Duration::from_secs(90 * 60)), "1h30m");
+        assert_eq!(format(Duration::ZERO), "0s");
+        assert_eq!(format(Duration::new(93784, 5_006_007)), "1d2h3m4s5ms6us7ns");
+
+        let duration = Duration::new(12345, 678);
+        assert_eq!(parse(&format(duration)), Ok(duration));
+    }
+
+    #[test]
+    fn deserialize() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Settings {
+            #[serde(with = "crate::duration")]
+            timeout: Duration,
+        }
+
+        let options = Options {
+            env_prefix: Some("SETTINGS_DURATION_TEST"),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_DURATION_TEST_TIMEOUT", "1h30m");
+        let settings = parse_settings::<Settings>(options).expect("failed to parse settings");
+        assert_eq!(settings.timeout, Duration::from_secs(90 * 60));
+
+        let options = Options {
+            env_prefix: Some("SETTINGS_DURATION_ERROR_TEST"),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_DURATION_ERROR_TEST_TIMEOUT", "soon");
+        let err = parse_settings::<Settings>(options).expect_err("expected error");
+        assert!(err.to_string().contains("invalid number in duration"), "{err}");
+    }
+}
@@ -48,10 +48,7 @@
 //! }
 //!
 //! // Parsing options
-//! let options = scuffle_settings::Options {
-//!     env_prefix: Some("MY_APP"),
-//!     ..Default::default()
-//! };
+//! let options = scuffle_settings::Options::new().with_env_prefix("MY_APP");
 //! // Parse the settings
 //! let settings: MyConfig = scuffle_settings::parse_settings(options)?;
 //! # Ok(())
@@ -62,6 +59,23 @@
 //!
 //! See [`Options`] for more information on how to customize parsing.
 //!
+//! ## Durations and Byte Sizes
+//!
+//! Values like `timeout = "30s"` or `max_size = "10MB"` can be parsed into a
+//! [`Duration`](std::time::Duration) or a number of bytes with the
+//! [`duration`] and [`byte_size`] serde helpers:
+//!
+//! ```rust
+//! # use std::time::Duration;
+//! #[derive(serde::Deserialize)]
+//! struct MyConfig {
+//!     #[serde(with = "scuffle_settings::duration")]
+//!     timeout: Duration,
+//!     #[serde(with = "scuffle_settings::byte_size")]
+//!     max_size: u64,
+//! }
+//! ```
+//!
 //! ## Templates
 //!
 //! If the `templates` feature is enabled, the parser will attempt to render
@@ -78,6 +92,27 @@
 //!
 //! Use `${{` and `}}` for variables, `{%` and `%}` for blocks and `{#` and `#}` for comments.
 //!
+//! ## Remote Config
+//!
+//! If the `remote` feature is enabled, a config can be fetched over HTTP(S)
+//! by setting [`Options::remote`]. It is parsed like a config file and by
+//! default merged before all other sources, so files, environment variables
+//! and overrides can still change single values.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "remote")]
+//! # fn test() -> Result<(), scuffle_settings::SettingsError> {
+//! # #[derive(serde::Deserialize)]
+//! # struct MyConfig {}
+//! let options = scuffle_settings::Options::new().with_remote(scuffle_settings::RemoteSource {
+//!     policy: scuffle_settings::RemotePolicy::FailOpen,
+//!     ..scuffle_settings::RemoteSource::new("https://config.example.com/app.toml")
+//! });
+//! let settings: MyConfig = scuffle_settings::parse_settings(options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Command Line Interface
 //!
 //! The following options are available for the CLI:
@@ -89,18 +124,27 @@
 //!
 //!   Provide an override for a configuration value, in the format `KEY=VALUE`.
 //!
+//! To add flags of your own, pass a [`clap::Command`] as [`Options::command`]
+//! and use [`parse_settings_with_matches`] to get the [`clap::ArgMatches`]
+//! alongside the settings.
+//!
 //! ## Feature Flags
 //!
 //! - `full`: Enables all of the following features
 //! - `templates`: Enables template support
 //!
 //!   See [Templates](#templates) above.
+//! - `remote`: Enables loading config from a URL
+//!
+//!   See [Remote Config](#remote-config) above.
 //! - `bootstrap`: Enables the `bootstrap!` macro
 //!
 //!   See [`bootstrap!`] and [With `scuffle_bootstrap`](#with-scuffle_bootstrap) above.
 //! - `cli`: Enables the CLI
 //!
 //!   See [Command Line Interface](#command-line-interface) above.
+//! - `tracing`: Logs a remote config which failed to load with
+//!   [`RemotePolicy::FailOpen`]
 //! - `all-formats`: Enables all of the following formats
 //!
 //! ### Format Feature Flags
@@ -131,9 +175,16 @@ use std::path::Path;
 
 use config::FileStoredFormat;
 
+pub mod byte_size;
+pub mod duration;
 mod options;
+#[cfg(feature = "remote")]
+mod remote;
+mod units;
 
 pub use options::*;
+#[cfg(feature = "remote")]
+pub use remote::{RemotePolicy, RemoteSource};
 
 #[derive(Debug, Clone, Copy)]
 struct FormatWrapper;
@@ -176,7 +227,7 @@ impl config::Format for FormatWrapper {
     ) -> Result<config::Map<String, config::Value>, Box<dyn std::error::Error + Send + Sync>> {
         let uri_ext = uri.and_then(|s| Path::new(s.as_str()).extension()).and_then(|s| s.to_str());
 
-        let mut formats: Vec<config::FileFormat> = vec![
+        let formats: Vec<config::FileFormat> = vec![
             #[cfg(feature = "toml")]
             config::FileFormat::Toml,
             #[cfg(feature = "json")]
@@ -191,8 +242,17 @@ impl config::Format for FormatWrapper {
             config::FileFormat::Ron,
         ];
 
+        // A known extension decides the format, trying the others could silently
+        // misinterpret the file (e.g. a broken TOML file which happens to be valid INI).
         if let Some(uri_ext) = uri_ext {
-            formats.sort_by_key(|f| if f.file_extensions().contains(&uri_ext) { 0 } else { 1 });
+            if let Some(format) = formats.iter().find(|f| f.file_extensions().contains(&uri_ext)) {
+                return format.parse(uri, template_text(text, format)?.as_ref()).map_err(|err| {
+                    Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Failed to parse file {:?} as {}: {}", uri, uri_ext, err),
+                    )) as Box<dyn std::error::Error + Send + Sync>
+                });
+            }
         }
 
         for format in formats {
@@ -231,30 +291,81 @@ impl config::FileStoredFormat for FormatWrapper {
 
 /// An error that can occur when parsing settings.
 #[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum SettingsError {
     #[error(transparent)]
     Config(#[from] config::ConfigError),
     #[cfg(feature = "cli")]
     #[error(transparent)]
     Clap(#[from] clap::Error),
+    #[error("validation: {0}")]
+    Validation(String),
+    #[cfg(feature = "remote")]
+    #[error("remote config {url}: {source}")]
+    Remote {
+        url: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// A trait for settings which need to be validated after they have been
+/// deserialized.
+///
+/// This is useful for cross-field validation that cannot be expressed with
+/// serde attributes alone, such as requiring a certificate path when TLS is
+/// enabled.
+///
+/// See [`parse_settings_validated`].
+pub trait Validate {
+    /// Validate the settings, returning a message describing the problem if
+    /// they are invalid.
+    fn validate(&self) -> Result<(), String>;
 }
 
 /// Parse settings using the given options.
 ///
 /// Refer to the [`Options`] struct for more information on how to customize parsing.
 pub fn parse_settings<T: serde::de::DeserializeOwned>(options: Options) -> Result<T, SettingsError> {
-    let mut config = config::Config::builder();
+    parse(options).map(|(settings, _)| settings)
+}
 
+/// Parse settings using the given options and return the matched command
+/// line arguments alongside them.
+///
+/// This is the same as [`parse_settings`], but allows the application to read
+/// the flags it added with [`Options::command`]. The matches are `None` if
+/// [`Options::cli`] is not set.
+#[cfg(feature = "cli")]
+pub fn parse_settings_with_matches<T: serde::de::DeserializeOwned>(
+    options: Options,
+) -> Result<(T, Option<clap::ArgMatches>), SettingsError> {
+    parse(options)
+}
+
+#[cfg(feature = "cli")]
+type Matches = Option<clap::ArgMatches>;
+#[cfg(not(feature = "cli"))]
+type Matches = ();
+
+fn parse<T: serde::de::DeserializeOwned>(options: Options) -> Result<(T, Matches), SettingsError> {
+    #[allow(unused_mut)]
+    let mut files: Vec<config::File<config::FileSourceFile, FormatWrapper>> = Vec::new();
     #[allow(unused_mut)]
-    let mut added_files = false;
+    let mut overrides = config::Config::builder();
+    #[allow(unused_mut, clippy::let_unit_value)]
+    let mut matches = Matches::default();
 
     #[cfg(feature = "cli")]
     if let Some(cli) = options.cli {
-        let command = clap::Command::new(cli.name)
-            .version(cli.version)
-            .about(cli.about)
-            .author(cli.author)
-            .bin_name(cli.name)
+        let command = options.command.unwrap_or_else(|| {
+            clap::Command::new(cli.name)
+                .version(cli.version)
+                .about(cli.about)
+                .author(cli.author)
+                .bin_name(cli.name)
+        });
+
+        let command = command
             .arg(
                 clap::Arg::new("config")
                     .short('c')
@@ -272,17 +383,14 @@ pub fn parse_settings<T: serde::de::DeserializeOwned>(options: Options) -> Resul
                     .action(clap::ArgAction::Append),
             );
 
-        let matches = command.get_matches_from(cli.argv);
+        let cli_matches = command.get_matches_from(cli.argv);
 
-        if let Some(config_files) = matches.get_many::<String>("config") {
-            for path in config_files {
-                config = config.add_source(config::File::new(path, FormatWrapper));
-                added_files = true;
-            }
+        if let Some(config_files) = cli_matches.get_many::<String>("config") {
+            files.extend(config_files.map(|path| config::File::new(path, FormatWrapper)));
         }
 
-        if let Some(overrides) = matches.get_many::<String>("overrides") {
-            for ov in overrides {
+        if let Some(ov) = cli_matches.get_many::<String>("overrides") {
+            for ov in ov {
                 let (key, value) = ov.split_once('=').ok_or_else(|| {
                     clap::Error::raw(
                         clap::error::ErrorKind::InvalidValue,
@@ -290,28 +398,59 @@ pub fn parse_settings<T: serde::de::DeserializeOwned>(options: Options) -> Resul
                     )
                 })?;
 
-                config = config.set_override(key, value)?;
+                overrides = overrides.set_override(key, value)?;
             }
         }
+
+        matches = Some(cli_matches);
     }
 
-    if !added_files {
-        if let Some(default_config_file) = options.default_config_file {
-            config = config.add_source(config::File::new(default_config_file, FormatWrapper).required(false));
+    let overrides = overrides.build()?;
+
+    let mut config = config::Config::builder();
+
+    for kind in options.source_order {
+        match kind {
+            SourceKind::Remote => {
+                #[cfg(feature = "remote")]
+                if let Some(remote) = options.remote.as_ref().map(RemoteSource::load).transpose()?.flatten() {
+                    config = config.add_source(remote);
+                }
+            }
+            SourceKind::DefaultFile => {
+                if let Some(default_config_file) = options.default_config_file.filter(|_| files.is_empty()) {
+                    config = config.add_source(config::File::new(default_config_file, FormatWrapper).required(false));
+                }
+            }
+            SourceKind::Files => config = config.add_source(files.clone()),
+            SourceKind::Env => {
+                if let Some(env_prefix) = options.env_prefix {
+                    config = config.add_source(config::Environment::with_prefix(env_prefix));
+                }
+            }
+            SourceKind::Overrides => config = config.add_source(overrides.clone()),
         }
     }
 
-    if let Some(env_prefix) = options.env_prefix {
-        config = config.add_source(config::Environment::with_prefix(env_prefix));
-    }
+    Ok((config.build()?.try_deserialize()?, matches))
+}
 
-    Ok(config.build()?.try_deserialize()?)
+/// Parse settings using the given options and then validate them.
+///
+/// This is the same as [`parse_settings`] but calls [`Validate::validate`] on
+/// the deserialized settings, turning a failure into a
+/// [`SettingsError::Validation`].
+pub fn parse_settings_validated<T: serde::de::DeserializeOwned + Validate>(options: Options) -> Result<T, SettingsError> {
+    let settings: T = parse_settings(options)?;
+    settings.validate().map_err(SettingsError::Validation)?;
+    Ok(settings)
 }
 
 #[doc(hidden)]
 #[cfg(feature = "bootstrap")]
 pub mod macros {
-    pub use {anyhow, scuffle_bootstrap};
+    pub use anyhow;
+    pub use scuffle_bootstrap;
 }
 
 /// This macro can be used to integrate with the [`scuffle_bootstrap`] ecosystem.
@@ -319,6 +458,9 @@ pub mod macros {
 /// This macro will implement the [`scuffle_bootstrap::config::ConfigParser`] trait for the given type.
 /// The generated implementation uses the [`parse_settings`] function to parse the settings.
 ///
+/// If the type implements [`Validate`], use `bootstrap!(MySettings, validate)`
+/// to use [`parse_settings_validated`] instead.
+///
 /// ## Example
 ///
 /// ```rust
@@ -330,14 +472,21 @@ pub mod macros {
 #[cfg(feature = "bootstrap")]
 #[macro_export]
 macro_rules! bootstrap {
+    ($ty:ty,validate) => {
+        impl $crate::macros::scuffle_bootstrap::config::ConfigParser for $ty {
+            async fn parse() -> $crate::macros::anyhow::Result<Self> {
+                $crate::macros::anyhow::Context::context(
+                    $crate::parse_settings_validated($crate::Options::new().with_cli($crate::cli!())),
+                    "config",
+                )
+            }
+        }
+    };
     ($ty:ty) => {
         impl $crate::macros::scuffle_bootstrap::config::ConfigParser for $ty {
             async fn parse() -> $crate::macros::anyhow::Result<Self> {
                 $crate::macros::anyhow::Context::context(
-                    $crate::parse_settings($crate::Options {
-                        cli: Some($crate::cli!()),
-                        ..::std::default::Default::default()
-                    }),
+                    $crate::parse_settings($crate::Options::new().with_cli($crate::cli!())),
                     "config",
                 )
             }
@@ -348,9 +497,11 @@ macro_rules! bootstrap {
 #[cfg(test)]
 #[cfg_attr(all(test, coverage_nightly), coverage(off))]
 mod tests {
+    use crate::{parse_settings, parse_settings_validated, Options, SourceKind, Validate};
     #[cfg(feature = "cli")]
-    use crate::Cli;
-    use crate::{parse_settings, Options};
+    use crate::{parse_settings_with_matches, Cli};
+    #[cfg(feature = "remote")]
+    use crate::{RemotePolicy, RemoteSource};
 
     #[derive(Debug, serde::Deserialize)]
     struct TestSettings {
@@ -358,6 +509,22 @@ mod tests {
         key: String,
     }
 
+    #[derive(Debug, serde::Deserialize)]
+    struct TlsSettings {
+        tls_enabled: bool,
+        cert_path: Option<String>,
+    }
+
+    impl Validate for TlsSettings {
+        fn validate(&self) -> Result<(), String> {
+            if self.tls_enabled && self.cert_path.is_none() {
+                return Err("cert_path is required when tls is enabled".to_string());
+            }
+
+            Ok(())
+        }
+    }
+
     #[test]
     fn parse_empty() {
         let err = parse_settings::<TestSettings>(Options::default()).expect_err("expected error");
@@ -365,6 +532,36 @@ mod tests {
         assert_eq!(err.to_string(), "missing field `key`");
     }
 
+    #[test]
+    fn validation_error() {
+        let options = Options {
+            env_prefix: Some("SETTINGS_VALIDATION_ERROR_TEST"),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_VALIDATION_ERROR_TEST_TLS_ENABLED", "true");
+        let err = parse_settings_validated::<TlsSettings>(options).expect_err("expected error");
+
+        if let crate::SettingsError::Validation(message) = &err {
+            assert_eq!(message, "cert_path is required when tls is enabled");
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+        assert_eq!(err.to_string(), "validation: cert_path is required when tls is enabled");
+    }
+
+    #[test]
+    fn validation_ok() {
+        let options = Options {
+            env_prefix: Some("SETTINGS_VALIDATION_OK_TEST"),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_VALIDATION_OK_TEST_TLS_ENABLED", "false");
+        let settings = parse_settings_validated::<TlsSettings>(options).expect("failed to parse settings");
+
+        assert!(!settings.tls_enabled);
+        assert!(settings.cert_path.is_none());
+    }
+
     #[test]
     #[cfg(feature = "cli")]
     fn parse_cli() {
@@ -405,6 +602,39 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "cli")]
+    fn custom_command() {
+        let command =
+            clap::Command::new("custom").arg(clap::Arg::new("migrate").long("migrate").action(clap::ArgAction::SetTrue));
+
+        let options = Options {
+            cli: Some(Cli {
+                name: "test",
+                version: "0.1.0",
+                about: "test",
+                author: "test",
+                argv: vec![
+                    "test".to_string(),
+                    "--migrate".to_string(),
+                    "-o".to_string(),
+                    "key=value".to_string(),
+                ],
+            }),
+            command: Some(command),
+            ..Default::default()
+        };
+        let (settings, matches): (TestSettings, _) = parse_settings_with_matches(options).expect("failed to parse settings");
+        let matches = matches.expect("expected matches");
+
+        assert_eq!(settings.key, "value");
+        assert!(matches.get_flag("migrate"));
+        assert_eq!(
+            matches.get_many::<String>("overrides").unwrap().collect::<Vec<_>>(),
+            vec!["key=value"]
+        );
+    }
+
     #[test]
     #[cfg(feature = "cli")]
     fn parse_file() {
@@ -449,6 +679,35 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(all(feature = "cli", feature = "toml", feature = "ini"))]
+    fn file_extension_decides_format() {
+        let options = Options {
+            cli: Some(Cli {
+                name: "test",
+                version: "0.1.0",
+                about: "test",
+                author: "test",
+                argv: vec!["test".to_string(), "-c".to_string(), "assets/invalid.toml".to_string()],
+            }),
+            ..Default::default()
+        };
+        // The file is valid INI, but it must only be parsed as TOML.
+        let err = parse_settings::<TestSettings>(options).expect_err("expected error");
+
+        if let crate::SettingsError::Config(config::ConfigError::FileParse { uri: Some(uri), cause }) = err {
+            assert_eq!(uri, "assets/invalid.toml");
+            assert!(
+                cause
+                    .to_string()
+                    .starts_with("Failed to parse file Some(\"assets/invalid.toml\") as toml: TOML parse error"),
+                "unexpected cause: {cause}"
+            );
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+    }
+
     #[test]
     #[cfg(feature = "cli")]
     fn parse_env() {
@@ -489,6 +748,120 @@ mod tests {
         assert_eq!(settings.key, "value");
     }
 
+    #[test]
+    #[cfg(feature = "cli")]
+    fn env_over_overrides() {
+        let options = Options {
+            cli: Some(Cli {
+                name: "test",
+                version: "0.1.0",
+                about: "test",
+                author: "test",
+                argv: vec!["test".to_string(), "-o".to_string(), "key=value".to_string()],
+            }),
+            env_prefix: Some("SETTINGS_ENV_OVER_OVERRIDES_TEST"),
+            source_order: vec![
+                SourceKind::DefaultFile,
+                SourceKind::Files,
+                SourceKind::Overrides,
+                SourceKind::Env,
+            ],
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_ENV_OVER_OVERRIDES_TEST_KEY", "envvalue");
+        let settings: TestSettings = parse_settings(options).expect("failed to parse settings");
+
+        assert_eq!(settings.key, "envvalue");
+    }
+
+    #[test]
+    fn source_order_skips_missing() {
+        let options = Options {
+            env_prefix: Some("SETTINGS_SOURCE_ORDER_SKIPS_MISSING_TEST"),
+            source_order: vec![SourceKind::DefaultFile],
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_SOURCE_ORDER_SKIPS_MISSING_TEST_KEY", "envvalue");
+        let err = parse_settings::<TestSettings>(options).expect_err("expected error");
+
+        assert_eq!(err.to_string(), "missing field `key`");
+    }
+
+    /// Serves a single HTTP request with the given response body.
+    #[cfg(all(feature = "remote", feature = "toml"))]
+    fn serve_once(body: &'static str) -> String {
+        use std::io::{BufRead, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        });
+
+        format!("http://{addr}")
+    }
+
+    #[test]
+    #[cfg(all(feature = "remote", feature = "toml"))]
+    fn remote() {
+        #[derive(Debug, serde::Deserialize)]
+        struct RemoteSettings {
+            key: String,
+            other: String,
+        }
+
+        let url = serve_once("key = \"remotevalue\"\nother = \"remotevalue\"\n");
+        let options = Options {
+            env_prefix: Some("SETTINGS_REMOTE_TEST"),
+            remote: Some(RemoteSource::new(format!("{url}/config.toml"))),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_REMOTE_TEST_OTHER", "envvalue");
+        let settings: RemoteSettings = parse_settings(options).expect("failed to parse settings");
+
+        assert_eq!(settings.key, "remotevalue");
+        assert_eq!(settings.other, "envvalue");
+    }
+
+    #[test]
+    #[cfg(feature = "remote")]
+    fn remote_policy() {
+        // Nothing listens on the port once the listener is dropped.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let options = |policy| Options {
+            env_prefix: Some("SETTINGS_REMOTE_POLICY_TEST"),
+            remote: Some(RemoteSource {
+                policy,
+                ..RemoteSource::new(format!("http://{addr}/config.toml"))
+            }),
+            ..Default::default()
+        };
+        std::env::set_var("SETTINGS_REMOTE_POLICY_TEST_KEY", "envvalue");
+
+        let err = parse_settings::<TestSettings>(options(RemotePolicy::FailClosed)).expect_err("expected error");
+        if let crate::SettingsError::Remote { url, .. } = &err {
+            assert_eq!(url, &format!("http://{addr}/config.toml"));
+        } else {
+            panic!("unexpected error: {}", err);
+        }
+
+        let settings = parse_settings::<TestSettings>(options(RemotePolicy::FailOpen)).expect("failed to parse settings");
+        assert_eq!(settings.key, "envvalue");
+    }
+
     #[test]
     #[cfg(all(feature = "templates", feature = "cli"))]
     fn templates() {
@@ -1,15 +1,48 @@
 /// Options to customize parsing
+///
+/// Create them with a struct literal and `..Default::default()`, or with
+/// [`Options::new`] and the `with_*` methods.
+///
+/// ```rust
+/// # use scuffle_settings::{Options, SourceKind};
+/// // Let the environment win over the `--override` arguments.
+/// let options = Options {
+///     env_prefix: Some("APP"),
+///     source_order: vec![SourceKind::DefaultFile, SourceKind::Files, SourceKind::Overrides, SourceKind::Env],
+///     ..Default::default()
+/// };
+/// ```
 #[derive(Debug, Clone)]
 pub struct Options {
     /// The CLI options
     #[cfg(feature = "cli")]
     pub cli: Option<Cli>,
+    /// A pre-built command to which the settings arguments are added
+    ///
+    /// This allows the application to define its own flags next to `--config`
+    /// and `--override`. Use [`parse_settings_with_matches`](crate::parse_settings_with_matches)
+    /// to read them. When set, the name, version, about and author of the
+    /// [`Cli`] are ignored in favour of the ones on the command.
+    ///
+    /// Only used if [`Options::cli`] is set.
+    #[cfg(feature = "cli")]
+    pub command: Option<clap::Command>,
     /// The default config file name (loaded if no other files are specified)
     pub default_config_file: Option<&'static str>,
     /// Environment variables prefix
     ///
     /// A setting called `foo` would be read from the environment as `APP_FOO` where `APP` is the prefix.
     pub env_prefix: Option<&'static str>,
+    /// A config fetched over HTTP(S), merged at [`SourceKind::Remote`]
+    #[cfg(feature = "remote")]
+    pub remote: Option<crate::RemoteSource>,
+    /// The order in which sources are merged
+    ///
+    /// Sources later in the list take precedence over earlier ones. A source
+    /// which is not in the list is not loaded at all.
+    ///
+    /// Defaults to [`SourceKind::DEFAULT_ORDER`].
+    pub source_order: Vec<SourceKind>,
 }
 
 impl Default for Options {
@@ -17,12 +50,94 @@ impl Default for Options {
         Self {
             #[cfg(feature = "cli")]
             cli: None,
+            #[cfg(feature = "cli")]
+            command: None,
             default_config_file: Some("config"),
             env_prefix: Some("APP"),
+            #[cfg(feature = "remote")]
+            remote: None,
+            source_order: SourceKind::DEFAULT_ORDER.to_vec(),
         }
     }
 }
 
+impl Options {
+    /// Creates the default options, which read the `config` file and
+    /// environment variables prefixed with `APP` without a CLI.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets [`Options::cli`]
+    #[cfg(feature = "cli")]
+    pub fn with_cli(mut self, cli: impl Into<Option<Cli>>) -> Self {
+        self.cli = cli.into();
+        self
+    }
+
+    /// Sets [`Options::command`]
+    #[cfg(feature = "cli")]
+    pub fn with_command(mut self, command: impl Into<Option<clap::Command>>) -> Self {
+        self.command = command.into();
+        self
+    }
+
+    /// Sets [`Options::default_config_file`]
+    pub fn with_default_config_file(mut self, default_config_file: impl Into<Option<&'static str>>) -> Self {
+        self.default_config_file = default_config_file.into();
+        self
+    }
+
+    /// Sets [`Options::env_prefix`]
+    pub fn with_env_prefix(mut self, env_prefix: impl Into<Option<&'static str>>) -> Self {
+        self.env_prefix = env_prefix.into();
+        self
+    }
+
+    /// Sets [`Options::remote`]
+    #[cfg(feature = "remote")]
+    pub fn with_remote(mut self, remote: impl Into<Option<crate::RemoteSource>>) -> Self {
+        self.remote = remote.into();
+        self
+    }
+
+    /// Sets [`Options::source_order`]
+    pub fn with_source_order(mut self, source_order: impl Into<Vec<SourceKind>>) -> Self {
+        self.source_order = source_order.into();
+        self
+    }
+}
+
+/// A source settings can be loaded from
+///
+/// See [`Options::source_order`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    /// The remote config, only loaded with the `remote` feature if [`Options::remote`] is set
+    Remote,
+    /// The default config file, only loaded if no files were given on the command line
+    DefaultFile,
+    /// The config files given on the command line with `--config`
+    Files,
+    /// The environment variables
+    Env,
+    /// The overrides given on the command line with `--override`
+    Overrides,
+}
+
+impl SourceKind {
+    /// The default source order, the remote config is overridden by files,
+    /// which are overridden by the environment which is in turn overridden by
+    /// command line overrides.
+    pub const DEFAULT_ORDER: [SourceKind; 5] = [
+        SourceKind::Remote,
+        SourceKind::DefaultFile,
+        SourceKind::Files,
+        SourceKind::Env,
+        SourceKind::Overrides,
+    ];
+}
+
 /// A struct used to define how the CLI should be generated
 ///
 /// See the [`cli!`](crate::cli) macro for a more convenient way to initialize this struct.
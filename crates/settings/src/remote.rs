@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use crate::{FormatWrapper, SettingsError};
+
+/// A config fetched over HTTP(S) when the settings are parsed
+///
+/// The response body is parsed like a config file, the format is picked from
+/// the extension of the URL path (e.g. `https://example.com/app.toml`). If the
+/// path has no known extension all enabled formats are tried.
+///
+/// See [`Options::remote`](crate::Options::remote).
+#[derive(Debug, Clone)]
+pub struct RemoteSource {
+    /// The URL to fetch the config from
+    pub url: String,
+    /// How long the whole request, including reading the body, may take
+    pub timeout: Duration,
+    /// What to do if the config cannot be fetched or parsed
+    pub policy: RemotePolicy,
+}
+
+impl RemoteSource {
+    /// Creates a source for the given URL with a timeout of 10 seconds which
+    /// fails closed.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: Duration::from_secs(10),
+            policy: RemotePolicy::default(),
+        }
+    }
+
+    /// Fetches and parses the config, returns `None` if that failed and the
+    /// policy is [`RemotePolicy::FailOpen`].
+    pub(crate) fn load(&self) -> Result<Option<RemoteConfig>, SettingsError> {
+        match self.fetch() {
+            Ok(map) => Ok(Some(RemoteConfig(map))),
+            Err(_err) if self.policy == RemotePolicy::FailOpen => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    "failed to load the remote config from {}, continuing without it: {}",
+                    self.url,
+                    _err
+                );
+                Ok(None)
+            }
+            Err(source) => Err(SettingsError::Remote {
+                url: self.url.clone(),
+                source,
+            }),
+        }
+    }
+
+    fn fetch(&self) -> Result<config::Map<String, config::Value>, Box<dyn std::error::Error + Send + Sync>> {
+        let url = reqwest::Url::parse(&self.url)?;
+        let path = url.path().to_owned();
+        let timeout = self.timeout;
+
+        // The blocking client panics when it is used inside of an async runtime,
+        // which is where the `bootstrap!` macro parses the settings.
+        let text = std::thread::spawn(move || {
+            reqwest::blocking::Client::builder()
+                .timeout(timeout)
+                .build()?
+                .get(url)
+                .send()?
+                .error_for_status()?
+                .text()
+        })
+        .join()
+        .map_err(|_| "remote config request panicked")??;
+
+        config::Format::parse(&FormatWrapper, Some(&path), &text)
+    }
+}
+
+/// What to do if a [`RemoteSource`] cannot be fetched or parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RemotePolicy {
+    /// Fail parsing the settings with [`SettingsError::Remote`]
+    #[default]
+    FailClosed,
+    /// Continue without the remote config, the other sources are still loaded
+    ///
+    /// The error is otherwise dropped, with the `tracing` feature it is
+    /// logged as a warning.
+    FailOpen,
+}
+
+/// A fetched remote config, merged like any other source
+#[derive(Debug, Clone)]
+pub(crate) struct RemoteConfig(config::Map<String, config::Value>);
+
+impl config::Source for RemoteConfig {
+    fn clone_into_box(&self) -> Box<dyn config::Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<config::Map<String, config::Value>, config::ConfigError> {
+        Ok(self.0.clone())
+    }
+}
@@ -0,0 +1,39 @@
+/// Splits a value like `1.5GiB` or `30s` into its number and unit, skipping
+/// whitespace between them.
+pub(crate) fn split_number(value: &str) -> (&str, &str) {
+    let end = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(end);
+    (number, unit.trim_start())
+}
+
+/// Parses a decimal number like `1.5` and multiplies it with `multiplier`,
+/// digits which are too precise for the multiplier are truncated.
+///
+/// Returns `None` if the number is malformed or the result overflows.
+pub(crate) fn scale(number: &str, multiplier: u128) -> Option<u128> {
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if integer.is_empty() && fraction.is_empty() {
+        return None;
+    }
+
+    if !integer.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let integer = if integer.is_empty() {
+        0
+    } else {
+        integer.parse::<u128>().ok()?
+    };
+
+    // Further digits are worth less than one nanosecond or byte with any unit,
+    // ignoring them keeps the multiplication from overflowing.
+    let fraction = &fraction[..fraction.len().min(18)];
+    let fraction = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>().ok()? * multiplier / 10u128.pow(fraction.len() as u32)
+    };
+
+    integer.checked_mul(multiplier)?.checked_add(fraction)
+}
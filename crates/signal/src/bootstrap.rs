@@ -1,8 +1,8 @@
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use scuffle_bootstrap::global::Global;
 use scuffle_bootstrap::service::Service;
-use scuffle_context::ContextFutExt;
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct SignalSvc;
@@ -19,6 +19,35 @@ pub trait SignalConfig: Global {
         Some(std::time::Duration::from_secs(30))
     }
 
+    /// The number of signals, including the one which started the shutdown,
+    /// which make the process exit immediately through
+    /// [`SignalConfig::force_exit`] when received within
+    /// [`SignalConfig::force_exit_timeout`] of each other.
+    ///
+    /// Values below 2 behave like 2. Defaults to `None`, where the second
+    /// signal calls [`SignalConfig::on_force_shutdown`] instead.
+    fn force_exit_after(&self) -> Option<usize> {
+        None
+    }
+
+    /// The window in which the signals counted by
+    /// [`SignalConfig::force_exit_after`] have to be received, measured from
+    /// the first signal of the window. A signal after the window starts a new
+    /// one.
+    fn force_exit_timeout(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    /// Exits the process, called once [`SignalConfig::force_exit_after`]
+    /// signals have been received.
+    ///
+    /// Defaults to exiting with the conventional status of `128` plus the
+    /// signal number. If this returns, [`SignalConfig::on_force_shutdown`]
+    /// is called with the signal.
+    fn force_exit(&self, signal: tokio::signal::unix::SignalKind) {
+        std::process::exit(128 + signal.as_raw_value())
+    }
+
     fn on_shutdown(self: &Arc<Self>) -> impl std::future::Future<Output = anyhow::Result<()>> + Send {
         std::future::ready(Ok(()))
     }
@@ -44,17 +73,42 @@ impl<Global: SignalConfig> Service<Global> for SignalSvc {
 
     async fn run(self, global: Arc<Global>, ctx: scuffle_context::Context) -> anyhow::Result<()> {
         let timeout = global.timeout();
+        let force_exit_after = global.force_exit_after().map(|count| count.max(2));
+        let force_exit_timeout = global.force_exit_timeout();
 
         let signals = global.signals();
         let mut handler = crate::SignalHandler::with_signals(signals);
 
         // Wait for a signal, or for the context to be done.
-        handler.recv().with_context(&ctx).await;
+        let signal = handler.recv_or_ctx(&ctx).await;
+
+        // The start of the force exit window and the signals received within it.
+        let mut window = (Instant::now(), usize::from(signal.is_some()));
+
         global.on_shutdown().await?;
         drop(ctx);
 
+        let force_shutdown = async {
+            loop {
+                let signal = handler.recv().await;
+                let Some(force_exit_after) = force_exit_after else {
+                    return signal;
+                };
+
+                if window.0.elapsed() > force_exit_timeout {
+                    window = (Instant::now(), 0);
+                }
+
+                window.1 += 1;
+                if window.1 >= force_exit_after {
+                    global.force_exit(signal);
+                    return signal;
+                }
+            }
+        };
+
         tokio::select! {
-            signal = handler.recv() => {
+            signal = force_shutdown => {
                 global.on_force_shutdown(Some(signal)).await?;
             },
             _ = scuffle_context::Handler::global().shutdown() => {}
@@ -77,6 +131,7 @@ impl<Global: SignalConfig> Service<Global> for SignalSvc {
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Arc;
 
     use scuffle_bootstrap::global::GlobalWithoutConfig;
@@ -270,4 +325,72 @@ mod tests {
             .await
             .is_ok());
     }
+
+    #[derive(Default)]
+    struct ForceExitTestGlobal {
+        exited: AtomicBool,
+    }
+
+    impl GlobalWithoutConfig for ForceExitTestGlobal {
+        fn init() -> impl std::future::Future<Output = anyhow::Result<Arc<Self>>> + Send {
+            std::future::ready(Ok(Arc::default()))
+        }
+    }
+
+    impl SignalConfig for ForceExitTestGlobal {
+        fn timeout(&self) -> Option<std::time::Duration> {
+            None
+        }
+
+        fn force_exit_after(&self) -> Option<usize> {
+            Some(3)
+        }
+
+        fn force_exit(&self, signal: SignalKind) {
+            assert_eq!(signal, SignalKind::interrupt());
+            self.exited.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn bootstrap_service_force_exit() {
+        let (ctx, handler) = scuffle_context::Context::new();
+
+        // Block the global context
+        let _global_ctx = scuffle_context::Context::global();
+
+        let svc = SignalSvc;
+        let global = ForceExitTestGlobal::init().await.unwrap();
+        let mut result = tokio::spawn(svc.run(global.clone(), ctx));
+
+        // Wait for the service to start
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+
+        raise_signal(SignalKind::interrupt());
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        raise_signal(SignalKind::interrupt());
+
+        // Two signals are not enough to exit.
+        assert!((&mut result)
+            .with_timeout(tokio::time::Duration::from_millis(50))
+            .await
+            .is_err());
+        assert!(!global.exited.load(Ordering::SeqCst));
+
+        raise_signal(SignalKind::interrupt());
+
+        match result.with_timeout(tokio::time::Duration::from_millis(100)).await {
+            Ok(Ok(Err(e))) => {
+                assert_eq!(e.to_string(), "received signal, shutting down immediately: SignalKind(2)");
+            }
+            _ => panic!("unexpected result"),
+        }
+        assert!(global.exited.load(Ordering::SeqCst));
+
+        assert!(handler
+            .shutdown()
+            .with_timeout(tokio::time::Duration::from_millis(100))
+            .await
+            .is_ok());
+    }
 }
@@ -78,6 +78,9 @@ pub use bootstrap::{SignalConfig, SignalSvc};
 /// After a signal is received you can poll the handler again to wait for
 /// another signal. Dropping the handle will cancel the signal subscription
 ///
+/// The handler is also a [`Stream`](futures_core::Stream) of the signals it
+/// receives, which never ends.
+///
 /// # Example
 ///
 /// ```rust
@@ -170,6 +173,19 @@ impl SignalHandler {
         self
     }
 
+    /// Remove a signal from the handler.
+    ///
+    /// Returns `true` if the signal was in the handler. Dropping the signal
+    /// cancels its subscription, so the signal is no longer received until it
+    /// is added again. The process still does not get the default behavior of
+    /// the signal back, once tokio has installed a handler for a signal it is
+    /// never removed.
+    pub fn remove_signal(&mut self, kind: SignalKind) -> bool {
+        let len = self.signals.len();
+        self.signals.retain(|(k, _)| k != &kind);
+        self.signals.len() != len
+    }
+
     /// Wait for a signal to be received.
     /// This is equivilant to calling (&mut handler).await, but is more
     /// ergonomic if you want to not take ownership of the handler.
@@ -177,6 +193,28 @@ impl SignalHandler {
         self.await
     }
 
+    /// Wait for a signal to be received, and return how many times it was
+    /// received since the handler was last polled.
+    ///
+    /// The count is at least 1. Tokio already coalesces signals which arrive
+    /// before the handler is woken, so this counts the notifications which
+    /// were pending and not the raw number of signals delivered to the
+    /// process. See [`SignalHandler::poll_recv_count`].
+    pub async fn recv_count(&mut self) -> (SignalKind, usize) {
+        std::future::poll_fn(|cx| self.poll_recv_count(cx)).await
+    }
+
+    /// Wait for a signal to be received, or for the context to be cancelled.
+    ///
+    /// Returns `Some(kind)` if a signal was received first, or `None` if the
+    /// context was cancelled before any signal arrived.
+    #[cfg(feature = "context")]
+    pub async fn recv_or_ctx(&mut self, ctx: &scuffle_context::Context) -> Option<SignalKind> {
+        use scuffle_context::ContextFutExt;
+
+        self.recv().with_context(ctx).await
+    }
+
     /// Poll for a signal to be received.
     /// Does not require pinning the handler.
     pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<SignalKind> {
@@ -188,6 +226,27 @@ impl SignalHandler {
 
         Poll::Pending
     }
+
+    /// Poll for a signal to be received, and drain all pending notifications
+    /// of that signal to count them.
+    ///
+    /// Like [`SignalHandler::poll_recv`], the first signal which is ready in
+    /// the order the signals were added wins. Only that signal is drained,
+    /// the others are still received by the next poll.
+    pub fn poll_recv_count(&mut self, cx: &mut Context<'_>) -> Poll<(SignalKind, usize)> {
+        for (kind, signal) in self.signals.iter_mut() {
+            if signal.poll_recv(cx).is_ready() {
+                let mut count = 1;
+                while signal.poll_recv(cx).is_ready() {
+                    count += 1;
+                }
+
+                return Poll::Ready((*kind, count));
+            }
+        }
+
+        Poll::Pending
+    }
 }
 
 impl std::future::Future for SignalHandler {
@@ -198,6 +257,38 @@ impl std::future::Future for SignalHandler {
     }
 }
 
+/// Receives the signals until the stream is dropped, the stream never ends.
+///
+/// Combined with [`ContextStreamExt`](https://docs.rs/scuffle-context/latest/scuffle_context/trait.ContextStreamExt.html)
+/// this handles signals until the context is cancelled.
+///
+/// ```rust
+/// use futures::StreamExt;
+/// use scuffle_context::ContextStreamExt;
+/// use scuffle_signal::SignalHandler;
+/// use tokio::signal::unix::SignalKind;
+///
+/// # tokio_test::block_on(async {
+/// let (ctx, handler) = scuffle_context::Context::new();
+/// # handler.cancel();
+///
+/// SignalHandler::new()
+///     .with_signal(SignalKind::hangup())
+///     .with_context(ctx)
+///     .for_each(|signal| async move {
+///         println!("reloading config after {signal:?}");
+///     })
+///     .await;
+/// # });
+/// ```
+impl futures_core::Stream for SignalHandler {
+    type Item = SignalKind;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx).map(Some)
+    }
+}
+
 #[cfg(test)]
 #[cfg_attr(all(coverage_nightly, test), coverage(off))]
 mod tests {
@@ -263,6 +354,63 @@ mod tests {
         assert_eq!(recv, SignalKind::user_defined2(), "expected SIGUSR2");
     }
 
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn remove_signal() {
+        let mut handler = SignalHandler::new()
+            .with_signal(SignalKind::user_defined1())
+            .with_signal(SignalKind::user_defined2());
+
+        assert!(handler.remove_signal(SignalKind::user_defined1()));
+        assert!(!handler.remove_signal(SignalKind::user_defined1()));
+
+        raise_signal(SignalKind::user_defined1());
+
+        // Expected to timeout
+        assert!(handler.recv().with_timeout(Duration::from_millis(50)).await.is_err());
+
+        // The signal can be added again.
+        handler.add_signal(SignalKind::user_defined1());
+        raise_signal(SignalKind::user_defined1());
+
+        let recv = handler.recv().with_timeout(Duration::from_millis(5)).await.unwrap();
+
+        assert_eq!(recv, SignalKind::user_defined1(), "expected SIGUSR1");
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn recv_count() {
+        let mut handler = SignalHandler::new()
+            .with_signal(SignalKind::user_defined1())
+            .with_signal(SignalKind::user_defined2());
+
+        raise_signal(SignalKind::user_defined1());
+        raise_signal(SignalKind::user_defined1());
+
+        let (recv, count) = handler.recv_count().with_timeout(Duration::from_millis(5)).await.unwrap();
+
+        assert_eq!(recv, SignalKind::user_defined1(), "expected SIGUSR1");
+        assert!(count >= 1, "expected at least one signal, got {count}");
+
+        // All the pending signals were drained.
+        assert!(handler.recv_count().with_timeout(Duration::from_millis(5)).await.is_err());
+    }
+
+    #[cfg(not(valgrind))] // test is time-sensitive
+    #[tokio::test]
+    async fn stream() {
+        use futures::StreamExt;
+
+        let mut handler = SignalHandler::new().with_signal(SignalKind::user_defined2());
+
+        raise_signal(SignalKind::user_defined2());
+
+        let recv = handler.next().with_timeout(Duration::from_millis(5)).await.unwrap();
+
+        assert_eq!(recv, Some(SignalKind::user_defined2()), "expected SIGUSR2");
+    }
+
     #[cfg(not(valgrind))] // test is time-sensitive
     #[tokio::test]
     async fn no_signals() {
@@ -271,4 +419,40 @@ mod tests {
         // Expected to timeout
         assert!(handler.recv().with_timeout(Duration::from_millis(50)).await.is_err());
     }
+
+    #[cfg(all(feature = "context", not(valgrind)))] // test is time-sensitive
+    #[tokio::test]
+    async fn recv_or_ctx_signal() {
+        let mut handler = SignalHandler::new().with_signal(SignalKind::user_defined1());
+        let ctx_handler = scuffle_context::Handler::new();
+        let ctx = ctx_handler.context();
+
+        raise_signal(SignalKind::user_defined1());
+
+        let recv = handler
+            .recv_or_ctx(&ctx)
+            .with_timeout(Duration::from_millis(5))
+            .await
+            .unwrap();
+
+        assert_eq!(recv, Some(SignalKind::user_defined1()), "expected SIGUSR1");
+    }
+
+    #[cfg(all(feature = "context", not(valgrind)))] // test is time-sensitive
+    #[tokio::test]
+    async fn recv_or_ctx_cancel() {
+        let mut handler = SignalHandler::new().with_signal(SignalKind::user_defined2());
+        let ctx_handler = scuffle_context::Handler::new();
+        let ctx = ctx_handler.context();
+
+        ctx_handler.cancel();
+
+        let recv = handler
+            .recv_or_ctx(&ctx)
+            .with_timeout(Duration::from_millis(5))
+            .await
+            .unwrap();
+
+        assert_eq!(recv, None, "expected context cancellation");
+    }
 }
@@ -0,0 +1,6 @@
+use scuffle_metrics::MetricEnum;
+#[derive(MetricEnum)]
+pub enum Kind {
+    Http,
+    Grpc,
+}
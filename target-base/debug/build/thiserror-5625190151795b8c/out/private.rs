@@ -0,0 +1,5 @@
+#[doc(hidden)]
+pub mod __private21 {
+    #[doc(hidden)]
+    pub use crate::private::*;
+}